@@ -1,9 +1,10 @@
 //! CLI subcommand implementations.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::clean_path;
 use crate::db::Db;
+use crate::query::Query;
 use crate::scanner;
 
 pub fn track(db: &Db, path: &Path) {
@@ -83,6 +84,7 @@ pub fn status(db: &Db) {
     println!("files:   {} ({} dirs)", stats.total_files, stats.total_dirs);
     println!("hashed:  {}/{}", stats.hashed, stats.total_files);
     println!("exif:    {}/{}", stats.with_exif, stats.total_files);
+    println!("thumbs:  {}/{}", stats.with_thumb, stats.total_files);
     println!("failed:  {}", stats.failed);
     println!("tracked: {}", tracked.len());
     for (p, recursive, watched) in &tracked {
@@ -96,12 +98,13 @@ pub fn status(db: &Db) {
     }
 }
 
-pub fn worker(db: &Db) {
+pub fn worker(db: &Db, full_hash: bool) {
     use std::sync::atomic::Ordering;
 
     println!("Running jobs (turbo mode)...");
     let mut engine = crate::jobs::JobEngine::start(db.clone());
     engine.stats.turbo.store(true, Ordering::Relaxed);
+    engine.stats.full_hash.store(full_hash, Ordering::Relaxed);
 
     // Poll until no more work
     loop {
@@ -124,3 +127,385 @@ pub fn worker(db: &Db) {
         eprint!("\r  {} ok, {} failed, {} active...", done, failed, active);
     }
 }
+
+pub fn search(db: &Db, query: &str) {
+    let q = match Query::parse(query) {
+        Ok(q) => q,
+        Err(e) => {
+            eprintln!("lv search: {}", e);
+            return;
+        }
+    };
+    let files = db.query_files(&q);
+    for f in &files {
+        println!("{}", f.path);
+    }
+    eprintln!("{} files", files.len());
+}
+
+/// Resolve a CLI path argument to its library file id.
+fn lookup_file(db: &Db, cmd: &str, path: &Path) -> Option<i64> {
+    let abs = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let abs_str = clean_path(&abs.to_string_lossy());
+    match db.file_lookup(&abs_str) {
+        Some((id, _, _)) => Some(id),
+        None => {
+            eprintln!("lv {}: {}: not in library", cmd, abs_str);
+            None
+        }
+    }
+}
+
+/// `lv tag add|remove NAME PATH...`
+pub fn tag_set(db: &Db, name: &str, paths: &[PathBuf], on: bool) {
+    let cmd = if on { "tag add" } else { "tag remove" };
+    let name = name.trim();
+    if name.is_empty() {
+        eprintln!("lv {}: empty tag name", cmd);
+        return;
+    }
+    // Files already in the wanted state are left out, so undo doesn't flip them
+    let found: Vec<(i64, &PathBuf)> = paths
+        .iter()
+        .filter_map(|p| lookup_file(db, cmd, p).map(|id| (id, p)))
+        .filter(|(id, _)| db.file_tags(*id).iter().any(|t| t == name) != on)
+        .collect();
+    if found.is_empty() {
+        eprintln!("0 files");
+        return;
+    }
+    let change = crate::undo::Change::Tag {
+        tag: name.to_string(),
+        on,
+        files: found.iter().map(|(id, _)| *id).collect(),
+    };
+    let sign = if on { "+" } else { "-" };
+    let label = match found.len() {
+        1 => format!("{}{}", sign, name),
+        n => format!("{}{} ×{}", sign, name, n),
+    };
+    match crate::undo::run(db, &label, change) {
+        Ok(_) => {
+            for (_, path) in &found {
+                println!("{}{} {}", sign, name, path.display());
+            }
+            eprintln!("{} files", found.len());
+        }
+        Err(e) => eprintln!("lv {}: {}", cmd, e),
+    }
+}
+
+/// `lv tag list [PATH]` — tags on one file, or every tag with its file count.
+pub fn tag_list(db: &Db, path: Option<&Path>) {
+    if let Some(path) = path {
+        if let Some(id) = lookup_file(db, "tag list", path) {
+            for tag in db.file_tags(id) {
+                println!("{}", tag);
+            }
+        }
+        return;
+    }
+    let slots = db.slot_tags();
+    for (name, count) in db.all_tags() {
+        let key = match slots.iter().find(|(_, t)| *t == name) {
+            Some((slot, _)) => format!("  [{}]", slot),
+            None if name == "like" => "  [9]".to_string(),
+            None => String::new(),
+        };
+        println!("{:>6}  {}{}", count, name, key);
+    }
+}
+
+/// `lv tag slot [N NAME]` — list or rename the digit-key tags.
+pub fn tag_slot(db: &Db, slot: Option<u8>, name: Option<&str>) {
+    match (slot, name) {
+        (Some(slot), Some(name)) => match db.set_slot_tag(slot, name) {
+            Ok(()) => println!("{} → {}", slot, name.trim()),
+            Err(e) => eprintln!("lv tag slot: {}", e),
+        },
+        (Some(slot), None) => println!("{}", db.slot_tag(slot)),
+        _ => {
+            for (slot, name) in db.slot_tags() {
+                println!("{}  {}", slot, name);
+            }
+            println!("9  like");
+        }
+    }
+}
+
+/// `lv dest [N [DIR] [--copy] [--clear]]` — list or bind the Shift+digit
+/// destination directories.
+pub fn dest(db: &Db, slot: Option<u8>, path: Option<&Path>, copy: bool, clear: bool) {
+    let show = |n: u8, d: &crate::db::DestSlot| {
+        let mode = if d.copy { "  (copy)" } else { "" };
+        println!("{}  {}{}", n, d.path, mode);
+    };
+    match (slot, path) {
+        (Some(n), Some(path)) => {
+            let path = crate::selection::expand_home(&path.to_string_lossy());
+            let path = std::path::absolute(&path).unwrap_or(path);
+            let d = crate::db::DestSlot {
+                path: clean_path(&path.to_string_lossy()),
+                copy,
+            };
+            match db.set_dest_slot(n, Some(&d)) {
+                Ok(()) => show(n, &d),
+                Err(e) => eprintln!("lv dest: {}", e),
+            }
+        }
+        (Some(n), None) if clear => {
+            if let Err(e) = db.set_dest_slot(n, None) {
+                eprintln!("lv dest: {}", e);
+            }
+        }
+        (Some(n), None) => match db.dest_slot(n) {
+            Some(d) => show(n, &d),
+            None => eprintln!("lv dest: Shift+{} has no destination", n),
+        },
+        (None, _) => {
+            let slots = db.dest_slots();
+            if slots.is_empty() {
+                println!("No destinations. Bind one with: lv dest N DIR [--copy]");
+            }
+            for (n, d) in &slots {
+                show(*n, d);
+            }
+        }
+    }
+}
+
+/// `lv undo [--redo] [--list]` — step the undo journal the viewer shares.
+pub fn undo(db: &Db, redo: bool, list: bool) {
+    if list {
+        for e in db.journal(20) {
+            let mark = if e.undone { "undone" } else { "" };
+            println!("{}  {:<6}  {}", e.created_at, mark, e.label);
+        }
+        return;
+    }
+    let result = if redo {
+        crate::undo::redo(db)
+    } else {
+        crate::undo::undo(db)
+    };
+    match result {
+        Ok(step) => println!("{}: {}", if redo { "redo" } else { "undo" }, step.label),
+        Err(e) => eprintln!("lv undo: {}", e),
+    }
+}
+
+/// `lv config check` — list problems in the config file; exits 1 if any.
+pub fn config_check() {
+    let path = crate::config::path();
+    let (_, errors) = crate::config::load();
+    if errors.is_empty() {
+        if path.exists() {
+            println!("{}: ok", path.display());
+        } else {
+            println!("{}: not found, using defaults", path.display());
+        }
+        return;
+    }
+    for e in &errors {
+        eprintln!("{}", e);
+    }
+    std::process::exit(1);
+}
+
+/// `lv dupes` — duplicate groups with sizes, biggest savings first.
+pub fn dupes(db: &Db) {
+    use crate::statusbar::format_size;
+
+    let unconfirmed: usize = crate::dupes::candidates(db)
+        .iter()
+        .filter(|(hash, _)| hash.starts_with("fp:"))
+        .map(|(_, files)| files.iter().filter(|f| f.full_hash.is_none()).count())
+        .sum();
+    if unconfirmed > 0 {
+        eprintln!(
+            "Confirming {} fingerprinted files with a full hash...",
+            unconfirmed
+        );
+    }
+    let groups = crate::dupes::find_all(db);
+    let mut files = 0usize;
+    let mut reclaimable = 0i64;
+    for g in &groups {
+        println!(
+            "{} × {}  ({} reclaimable)",
+            g.files.len(),
+            format_size(g.size),
+            format_size(g.reclaimable())
+        );
+        for f in &g.files {
+            println!("  {}", f.path);
+        }
+        files += g.files.len();
+        reclaimable += g.reclaimable();
+    }
+    println!(
+        "{} groups, {} files, {} reclaimable",
+        groups.len(),
+        files,
+        format_size(reclaimable)
+    );
+}
+
+/// `lv verify [PATH]` — re-hash files and report those whose bytes changed
+/// although their size and mtime did not (bit-rot).
+pub fn verify(db: &Db, path: Option<&Path>) {
+    use crate::jobs::Verdict;
+
+    let prefix = path.map(|p| {
+        clean_path(
+            &p.canonicalize()
+                .unwrap_or_else(|_| p.to_path_buf())
+                .to_string_lossy(),
+        )
+    });
+    let files = db.stored_hashes(prefix.as_deref());
+    let (mut ok, mut corrupt, mut modified, mut missing, mut errors) = (0, 0, 0, 0, 0);
+    let mut fingerprint_only = 0usize;
+    for (i, f) in files.iter().enumerate() {
+        eprint!("\r  {}/{}...", i + 1, files.len());
+        let verdict = crate::jobs::verify_file(f);
+        if verdict != Verdict::Missing && f.full_hash.is_none() && f.hash.starts_with("fp:") {
+            fingerprint_only += 1;
+        }
+        match verdict {
+            Verdict::Ok => ok += 1,
+            Verdict::Corrupt => {
+                corrupt += 1;
+                eprint!("\r");
+                println!("CORRUPT   {}", f.path);
+            }
+            Verdict::Modified => {
+                modified += 1;
+                eprint!("\r");
+                println!("modified  {}", f.path);
+            }
+            Verdict::Missing => {
+                missing += 1;
+                eprint!("\r");
+                println!("missing   {}", f.path);
+            }
+            Verdict::Error(e) => {
+                errors += 1;
+                eprint!("\r");
+                println!("error     {}: {}", f.path, e);
+            }
+        }
+    }
+    eprint!("\r");
+    println!(
+        "{} checked: {} ok, {} corrupt, {} modified, {} missing, {} errors",
+        files.len(),
+        ok,
+        corrupt,
+        modified,
+        missing,
+        errors
+    );
+    if fingerprint_only > 0 {
+        println!(
+            "{} large files were only checked by fingerprint; run `lv worker --full-hash` for a full check",
+            fingerprint_only
+        );
+    }
+}
+
+/// `lv thumbs [--rebuild] [--gc]` — thumbnail store usage; `--rebuild`
+/// regenerates every thumbnail now, `--gc` prunes orphans.
+pub fn thumbs(db: &Db, rebuild: bool, gc: bool) {
+    use crate::statusbar::format_size;
+
+    let root = crate::thumbs::dir();
+    if gc {
+        let (removed, freed) = crate::thumbs::gc(&root, &db.live_hashes());
+        println!(
+            "Removed {} orphaned thumbnails ({})",
+            removed,
+            format_size(freed as i64)
+        );
+    }
+    if rebuild {
+        crate::thumbs::gc(&root, &Default::default());
+        db.thumbs_reset();
+        let (mut ok, mut failed) = (0, 0);
+        while let Some((id, path)) = db.next_missing_thumb() {
+            eprint!("\r  {} ok, {} failed...", ok, failed);
+            match crate::jobs::process_thumb(db, id, &path, &root) {
+                Ok(()) => ok += 1,
+                Err(e) => {
+                    failed += 1;
+                    db.record_job_fail(id, "thumb", &e);
+                    eprint!("\r");
+                    println!("error     {}: {}", path, e);
+                }
+            }
+        }
+        eprint!("\r");
+        println!("Rebuilt {} thumbnails, {} failed", ok, failed);
+    }
+    let (count, bytes) = crate::thumbs::usage(&root);
+    println!(
+        "{}: {} thumbnails, {}",
+        clean_path(&root.to_string_lossy()),
+        count,
+        format_size(bytes as i64)
+    );
+}
+
+/// `lv similar FILE` — near-duplicates by perceptual hash, closest first.
+pub fn similar(db: &Db, path: &Path, distance: u32) {
+    let Some(id) = lookup_file(db, "similar", path) else {
+        return;
+    };
+    let file_path = db.file_path_by_id(id).unwrap_or_default();
+    let phash = match crate::jobs::phash_of(db, id, &file_path) {
+        Ok(h) => h,
+        Err(e) => {
+            eprintln!("lv similar: {}: {}", path.display(), e);
+            return;
+        }
+    };
+    let mut count = 0usize;
+    for (f, d) in db.similar_files(phash, distance) {
+        if f.id != id {
+            println!("{:>2}  {}", d, f.path);
+            count += 1;
+        }
+    }
+    eprintln!("{} similar files", count);
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_add_then_undo_keeps_existing_tag() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg"], Some("t"));
+        let paths = vec![tmp.path().join("a.jpg"), tmp.path().join("b.jpg")];
+
+        // Already tagged everywhere: nothing to journal
+        tag_set(&db, "t", &paths, true);
+        assert!(db.journal(10).is_empty());
+
+        db.set_tag(files[1].id, "t", false).unwrap();
+        tag_set(&db, "t", &paths, true);
+        assert_eq!(db.journal(10)[0].label, "+t");
+        crate::undo::undo(&db).unwrap();
+        assert_eq!(db.file_tags(files[0].id), vec!["t".to_string()]);
+        assert!(db.file_tags(files[1].id).is_empty());
+
+        // Undoing a remove doesn't add the tag to files that never had it
+        tag_set(&db, "t", &paths, false);
+        assert_eq!(db.journal(10)[0].label, "-t");
+        crate::undo::undo(&db).unwrap();
+        assert_eq!(db.file_tags(files[0].id), vec!["t".to_string()]);
+        assert!(db.file_tags(files[1].id).is_empty());
+    }
+}
@@ -0,0 +1,859 @@
+//! User configuration: `config.toml` in the project config dir (or
+//! `$LV_CONFIG`), covering key bindings, video, cache and slideshow
+//! tunables and the overlay theme.
+//!
+//! Every setting has a default, so the file only needs what differs. A
+//! binding is a chord like `ctrl+shift+z`, or a sequence of chords separated
+//! by spaces (`g t`). Listing an action replaces its default keys, and keys
+//! it takes are dropped from the defaults of other actions. Bad entries are
+//! reported and skipped, so a typo never stops the viewer from starting.
+
+use std::path::PathBuf;
+
+use sdl2::keyboard::{Keycode, Mod};
+
+use crate::slideshow;
+use crate::statusbar::Theme;
+
+// ── Actions ─────────────────────────────────────────────────────────────
+
+/// Something a key can do in the viewer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Next,
+    Prev,
+    NextDir,
+    PrevDir,
+    Random,
+    Newest,
+    RandomFav,
+    LatestFav,
+    Grid,
+    Filmstrip,
+    Tree,
+    Flatten,
+    Open,
+    Back,
+    Quit,
+    Select,
+    SelectRange,
+    SelectAll,
+    InvertSelection,
+    Like,
+    Tags,
+    Search,
+    Similar,
+    Dupes,
+    KeepDupe,
+    Trash,
+    Restore,
+    Undo,
+    Redo,
+    Export,
+    Rename,
+    CopyPath,
+    Refresh,
+    Sort,
+    SortBack,
+    Slideshow,
+    SlideshowMode,
+    SlideshowPause,
+    SlideshowFaster,
+    SlideshowSlower,
+    Fullscreen,
+    Info,
+    InfoPageUp,
+    InfoPageDown,
+    InfoTop,
+    InfoBottom,
+    Turbo,
+    Pause,
+    SeekBack,
+    SeekForward,
+    VolumeUp,
+    VolumeDown,
+    Report,
+    /// Toggle the tag on digit key 2-8.
+    Tag(u8),
+    /// Show collection 0-9 (0: temporary, 9: liked).
+    Collection(u8),
+    /// Send to destination slot 1-9.
+    Dest(u8),
+}
+
+/// Actions with a fixed name, with their default keys.
+const NAMED: &[(&str, Action, &[&str])] = &[
+    ("next", Action::Next, &["j"]),
+    ("prev", Action::Prev, &["k"]),
+    ("next-dir", Action::NextDir, &["l"]),
+    ("prev-dir", Action::PrevDir, &["h"]),
+    ("random", Action::Random, &["u"]),
+    ("newest", Action::Newest, &["n"]),
+    ("random-fav", Action::RandomFav, &["m"]),
+    ("latest-fav", Action::LatestFav, &["b"]),
+    ("grid", Action::Grid, &["g"]),
+    ("filmstrip", Action::Filmstrip, &["v"]),
+    ("tree", Action::Tree, &["tab"]),
+    ("flatten", Action::Flatten, &["shift+f"]),
+    ("open", Action::Open, &["return", "kp_enter"]),
+    ("back", Action::Back, &["escape"]),
+    ("quit", Action::Quit, &["q"]),
+    ("select", Action::Select, &["a"]),
+    ("select-range", Action::SelectRange, &["shift+a"]),
+    ("select-all", Action::SelectAll, &["ctrl+a"]),
+    ("invert-selection", Action::InvertSelection, &["ctrl+i"]),
+    ("like", Action::Like, &["y", "9", "kp9"]),
+    ("tags", Action::Tags, &["t"]),
+    ("search", Action::Search, &["/"]),
+    ("similar", Action::Similar, &["s"]),
+    ("dupes", Action::Dupes, &["d"]),
+    ("keep-dupe", Action::KeepDupe, &["x"]),
+    ("trash", Action::Trash, &["delete"]),
+    ("restore", Action::Restore, &["z"]),
+    ("undo", Action::Undo, &["ctrl+z"]),
+    ("redo", Action::Redo, &["ctrl+shift+z"]),
+    ("export", Action::Export, &["e"]),
+    ("rename", Action::Rename, &["f2"]),
+    ("copy-path", Action::CopyPath, &["c"]),
+    ("refresh", Action::Refresh, &["r"]),
+    ("sort", Action::Sort, &["o"]),
+    ("sort-back", Action::SortBack, &["shift+o"]),
+    ("slideshow", Action::Slideshow, &["f5"]),
+    ("slideshow-mode", Action::SlideshowMode, &["shift+f5"]),
+    ("slideshow-pause", Action::SlideshowPause, &["shift+space"]),
+    ("slideshow-faster", Action::SlideshowFaster, &["["]),
+    ("slideshow-slower", Action::SlideshowSlower, &["]"]),
+    ("fullscreen", Action::Fullscreen, &["f"]),
+    ("info", Action::Info, &["i"]),
+    ("info-page-up", Action::InfoPageUp, &["pageup"]),
+    ("info-page-down", Action::InfoPageDown, &["pagedown"]),
+    ("info-top", Action::InfoTop, &["home"]),
+    ("info-bottom", Action::InfoBottom, &["end"]),
+    ("turbo", Action::Turbo, &["-"]),
+    ("pause", Action::Pause, &["space"]),
+    ("seek-back", Action::SeekBack, &["left"]),
+    ("seek-forward", Action::SeekForward, &["right"]),
+    ("volume-up", Action::VolumeUp, &["up"]),
+    ("volume-down", Action::VolumeDown, &["down"]),
+    ("report", Action::Report, &["p"]),
+];
+
+impl Action {
+    /// Every action in the order `lv config dump` lists them.
+    fn all() -> Vec<Action> {
+        let mut all: Vec<Action> = NAMED.iter().map(|(_, a, _)| *a).collect();
+        all.extend((2..=8).map(Action::Tag));
+        all.extend((0..=9).map(Action::Collection));
+        all.extend((1..=9).map(Action::Dest));
+        all
+    }
+
+    /// Name in `[keys]`: `next-dir`, `tag-3`, `collection-0`, `dest-1`.
+    pub fn name(self) -> String {
+        match self {
+            Action::Tag(n) => format!("tag-{}", n),
+            Action::Collection(n) => format!("collection-{}", n),
+            Action::Dest(n) => format!("dest-{}", n),
+            _ => NAMED
+                .iter()
+                .find(|(_, a, _)| *a == self)
+                .map(|(name, _, _)| name.to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        if let Some((_, a, _)) = NAMED.iter().find(|(n, _, _)| *n == name) {
+            return Some(*a);
+        }
+        let (kind, n) = name.rsplit_once('-')?;
+        let n: u8 = n.parse().ok()?;
+        match kind {
+            "tag" if (2..=8).contains(&n) => Some(Action::Tag(n)),
+            "collection" if n <= 9 => Some(Action::Collection(n)),
+            "dest" if (1..=9).contains(&n) => Some(Action::Dest(n)),
+            _ => None,
+        }
+    }
+
+    /// Keys bound to this action when the config doesn't say.
+    fn default_keys(self) -> Vec<String> {
+        match self {
+            Action::Tag(n) => vec![n.to_string(), format!("kp{}", n)],
+            Action::Collection(n) => vec![format!("ctrl+{}", n), format!("ctrl+kp{}", n)],
+            Action::Dest(n) => vec![format!("shift+{}", n)],
+            _ => NAMED
+                .iter()
+                .find(|(_, a, _)| *a == self)
+                .map(|(_, _, keys)| keys.iter().map(|k| k.to_string()).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// ── Keys ────────────────────────────────────────────────────────────────
+
+/// Key names besides single printable characters. The first name for a key
+/// is the one `dump` prints.
+const KEY_NAMES: &[(&str, Keycode)] = &[
+    ("escape", Keycode::ESCAPE),
+    ("esc", Keycode::ESCAPE),
+    ("return", Keycode::RETURN),
+    ("enter", Keycode::RETURN),
+    ("kp_enter", Keycode::KP_ENTER),
+    ("space", Keycode::SPACE),
+    ("tab", Keycode::TAB),
+    ("backspace", Keycode::BACKSPACE),
+    ("delete", Keycode::DELETE),
+    ("insert", Keycode::INSERT),
+    ("home", Keycode::HOME),
+    ("end", Keycode::END),
+    ("pageup", Keycode::PAGEUP),
+    ("pagedown", Keycode::PAGEDOWN),
+    ("up", Keycode::UP),
+    ("down", Keycode::DOWN),
+    ("left", Keycode::LEFT),
+    ("right", Keycode::RIGHT),
+    ("f1", Keycode::F1),
+    ("f2", Keycode::F2),
+    ("f3", Keycode::F3),
+    ("f4", Keycode::F4),
+    ("f5", Keycode::F5),
+    ("f6", Keycode::F6),
+    ("f7", Keycode::F7),
+    ("f8", Keycode::F8),
+    ("f9", Keycode::F9),
+    ("f10", Keycode::F10),
+    ("f11", Keycode::F11),
+    ("f12", Keycode::F12),
+    ("kp0", Keycode::KP_0),
+    ("kp1", Keycode::KP_1),
+    ("kp2", Keycode::KP_2),
+    ("kp3", Keycode::KP_3),
+    ("kp4", Keycode::KP_4),
+    ("kp5", Keycode::KP_5),
+    ("kp6", Keycode::KP_6),
+    ("kp7", Keycode::KP_7),
+    ("kp8", Keycode::KP_8),
+    ("kp9", Keycode::KP_9),
+    ("kp_plus", Keycode::KP_PLUS),
+    ("kp_minus", Keycode::KP_MINUS),
+];
+
+/// One key press with the modifiers held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chord {
+    key: Keycode,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+}
+
+impl Chord {
+    pub fn new(key: Keycode, keymod: Mod) -> Self {
+        Chord {
+            key,
+            ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+            shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+            alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+        }
+    }
+
+    /// Parse `ctrl+shift+z`, `alt+left`, `/`. Case-insensitive.
+    fn parse(text: &str) -> Result<Chord, String> {
+        let bad = || format!("bad key \"{}\"", text);
+        let mut rest = text.to_lowercase();
+        let (mut ctrl, mut shift, mut alt) = (false, false, false);
+        loop {
+            let flag = if rest.starts_with("ctrl+") {
+                &mut ctrl
+            } else if rest.starts_with("shift+") {
+                &mut shift
+            } else if rest.starts_with("alt+") {
+                &mut alt
+            } else {
+                break;
+            };
+            *flag = true;
+            rest = rest[rest.find('+').unwrap() + 1..].to_string();
+        }
+        let key = if let Some((_, k)) = KEY_NAMES.iter().find(|(n, _)| *n == rest) {
+            *k
+        } else {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_graphic() => {
+                    Keycode::from_i32(c as i32).ok_or_else(bad)?
+                }
+                _ => return Err(bad()),
+            }
+        };
+        Ok(Chord {
+            key,
+            ctrl,
+            shift,
+            alt,
+        })
+    }
+
+    fn unshifted(self) -> Chord {
+        Chord {
+            shift: false,
+            ..self
+        }
+    }
+}
+
+impl std::fmt::Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (on, name) in [
+            (self.ctrl, "ctrl+"),
+            (self.shift, "shift+"),
+            (self.alt, "alt+"),
+        ] {
+            if on {
+                f.write_str(name)?;
+            }
+        }
+        match KEY_NAMES.iter().find(|(_, k)| *k == self.key) {
+            Some((name, _)) => f.write_str(name),
+            None => match char::from_u32(self.key.into_i32() as u32) {
+                Some(c) if c.is_ascii_graphic() => write!(f, "{}", c),
+                _ => write!(f, "#{}", self.key.into_i32()),
+            },
+        }
+    }
+}
+
+/// Parse a binding: chords separated by spaces.
+fn parse_sequence(text: &str) -> Result<Vec<Chord>, String> {
+    let seq = text
+        .split_whitespace()
+        .map(Chord::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    if seq.is_empty() {
+        return Err("empty key".into());
+    }
+    Ok(seq)
+}
+
+fn sequence_text(seq: &[Chord]) -> String {
+    seq.iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Two bindings clash when one is the other or starts it: the shorter one
+/// would always fire first.
+fn clashes(a: &[Chord], b: &[Chord]) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// Key bindings, with the chords typed so far of an unfinished sequence.
+pub struct Keymap {
+    bindings: Vec<(Vec<Chord>, Action)>,
+    pending: Vec<Chord>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::build(&[], &mut Vec::new())
+    }
+}
+
+impl Keymap {
+    /// The defaults with `user` bindings on top. Clashes between user
+    /// bindings are reported in `errors`; the first one wins.
+    fn build(user: &[(Action, Vec<Vec<Chord>>)], errors: &mut Vec<String>) -> Keymap {
+        let mut bindings: Vec<(Vec<Chord>, Action)> = Vec::new();
+        for (action, seqs) in user {
+            for seq in seqs {
+                match bindings.iter().find(|(s, _)| clashes(s, seq)) {
+                    Some((s, other)) => errors.push(format!(
+                        "[keys] {}: \"{}\" clashes with \"{}\" ({})",
+                        action.name(),
+                        sequence_text(seq),
+                        sequence_text(s),
+                        other.name()
+                    )),
+                    None => bindings.push((seq.clone(), *action)),
+                }
+            }
+        }
+        for action in Action::all() {
+            if user.iter().any(|(a, _)| *a == action) {
+                continue;
+            }
+            for key in action.default_keys() {
+                let seq = parse_sequence(&key).expect("default key");
+                if !bindings.iter().any(|(s, _)| clashes(s, &seq)) {
+                    bindings.push((seq, action));
+                }
+            }
+        }
+        Keymap {
+            bindings,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Keys bound to `action`, as written in the config.
+    fn keys_for(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(s, _)| sequence_text(s))
+            .collect()
+    }
+
+    /// Feed one key press. Returns the action it completes, if any. A press
+    /// that starts (or continues) a longer binding returns `None` and is
+    /// remembered; one that breaks a sequence starts over on its own. A
+    /// shifted key with no binding of its own acts like the plain key.
+    pub fn feed(&mut self, chord: Chord) -> Option<Action> {
+        let prefix = std::mem::take(&mut self.pending);
+        for c in [chord, chord.unshifted()] {
+            let mut seq = prefix.clone();
+            seq.push(c);
+            if let Some((_, a)) = self.bindings.iter().find(|(s, _)| *s == seq) {
+                return Some(*a);
+            }
+            if self.bindings.iter().any(|(s, _)| s.starts_with(&seq)) {
+                self.pending = seq;
+                return None;
+            }
+        }
+        if prefix.is_empty() {
+            None
+        } else {
+            self.feed(chord)
+        }
+    }
+}
+
+// ── Config ──────────────────────────────────────────────────────────────
+
+pub struct Config {
+    pub keys: Keymap,
+    /// Seconds Left / Right seek in a video.
+    pub seek_back: f64,
+    pub seek_forward: f64,
+    /// Volume change per Up / Down, in percent.
+    pub volume_step: i64,
+    /// Files decoded ahead on each side of the cursor (and shown in the
+    /// filmstrip).
+    pub preload: usize,
+    /// Full-size images kept on the GPU.
+    pub textures: usize,
+    /// Seconds a slideshow image stays up.
+    pub slideshow_interval: f64,
+    pub slideshow_mode: slideshow::Mode,
+    pub theme: Theme,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            keys: Keymap::default(),
+            seek_back: 5.0,
+            seek_forward: 15.0,
+            volume_step: 5,
+            preload: 10,
+            textures: 20,
+            slideshow_interval: 5.0,
+            slideshow_mode: slideshow::Mode::Sequential,
+            theme: Theme::DEFAULT,
+        }
+    }
+}
+
+/// Theme colours by their name in `[theme]`.
+fn theme_colours(theme: &mut Theme) -> [(&'static str, &mut [f32; 4]); 11] {
+    [
+        ("background", &mut theme.background),
+        ("text", &mut theme.text),
+        ("dim", &mut theme.dim),
+        ("bright", &mut theme.bright),
+        ("accent", &mut theme.accent),
+        ("error", &mut theme.error),
+        ("error_dim", &mut theme.error_dim),
+        ("label", &mut theme.label),
+        ("value", &mut theme.value),
+        ("header", &mut theme.header),
+        ("selected", &mut theme.selected),
+    ]
+}
+
+/// Parse `#rrggbb` or `#rrggbbaa`.
+fn parse_colour(text: &str) -> Option<[f32; 4]> {
+    let hex = text.strip_prefix('#')?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return None;
+    }
+    let mut c = [1.0; 4];
+    for (i, v) in c.iter_mut().enumerate().take(hex.len() / 2) {
+        *v = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()? as f32 / 255.0;
+    }
+    Some(c)
+}
+
+fn colour_text(c: [f32; 4]) -> String {
+    let b = c.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8);
+    if b[3] == 255 {
+        format!("#{:02x}{:02x}{:02x}", b[0], b[1], b[2])
+    } else {
+        format!("#{:02x}{:02x}{:02x}{:02x}", b[0], b[1], b[2], b[3])
+    }
+}
+
+/// A number in `min..=max` from `[section] key`.
+fn number(section: &str, key: &str, v: &toml::Value, min: f64, max: f64) -> Result<f64, String> {
+    let n = match v {
+        toml::Value::Integer(i) => *i as f64,
+        toml::Value::Float(f) => *f,
+        _ => f64::NAN,
+    };
+    if (min..=max).contains(&n) {
+        Ok(n)
+    } else {
+        Err(format!(
+            "[{}] {}: expected a number from {} to {}",
+            section, key, min, max
+        ))
+    }
+}
+
+impl Config {
+    /// Parse config text. Returns the config with every valid setting
+    /// applied, and one message per problem.
+    pub fn parse(text: &str) -> (Config, Vec<String>) {
+        let mut config = Config::default();
+        let mut errors = Vec::new();
+        let table: toml::Table = match text.parse() {
+            Ok(t) => t,
+            Err(e) => {
+                errors.push(e.to_string().trim_end().to_string());
+                return (config, errors);
+            }
+        };
+        for (section, value) in &table {
+            let Some(entries) = value.as_table() else {
+                errors.push(format!("{}: expected a [section]", section));
+                continue;
+            };
+            match section.as_str() {
+                "keys" => config.keys = parse_keys(entries, &mut errors),
+                "video" | "cache" | "slideshow" => {
+                    for (key, v) in entries {
+                        let result = match (section.as_str(), key.as_str()) {
+                            ("video", "seek_back") => {
+                                number(section, key, v, 0.1, 3600.0).map(|n| config.seek_back = n)
+                            }
+                            ("video", "seek_forward") => number(section, key, v, 0.1, 3600.0)
+                                .map(|n| config.seek_forward = n),
+                            ("video", "volume_step") => number(section, key, v, 1.0, 100.0)
+                                .map(|n| config.volume_step = n as i64),
+                            ("cache", "preload") => number(section, key, v, 0.0, 100.0)
+                                .map(|n| config.preload = n as usize),
+                            ("cache", "textures") => number(section, key, v, 1.0, 1000.0)
+                                .map(|n| config.textures = n as usize),
+                            ("slideshow", "interval") => number(section, key, v, 0.5, 3600.0)
+                                .map(|n| config.slideshow_interval = n),
+                            ("slideshow", "mode") => v
+                                .as_str()
+                                .and_then(slideshow::Mode::from_name)
+                                .map(|m| config.slideshow_mode = m)
+                                .ok_or_else(|| {
+                                    "[slideshow] mode: expected \"sequential\", \"shuffle\" or \"random\""
+                                        .to_string()
+                                }),
+                            _ => Err(format!("[{}] {}: unknown setting", section, key)),
+                        };
+                        if let Err(e) = result {
+                            errors.push(e);
+                        }
+                    }
+                }
+                "theme" => {
+                    for (key, v) in entries {
+                        let mut colours = theme_colours(&mut config.theme);
+                        let Some((_, slot)) = colours.iter_mut().find(|(n, _)| n == key) else {
+                            errors.push(format!("[theme] {}: unknown colour", key));
+                            continue;
+                        };
+                        match v.as_str().and_then(parse_colour) {
+                            Some(c) => **slot = c,
+                            None => errors.push(format!(
+                                "[theme] {}: expected \"#rrggbb\" or \"#rrggbbaa\"",
+                                key
+                            )),
+                        }
+                    }
+                }
+                _ => errors.push(format!("[{}]: unknown section", section)),
+            }
+        }
+        (config, errors)
+    }
+
+    /// The effective config as TOML, every setting included.
+    pub fn dump(&self) -> String {
+        let quote = |s: &str| toml::Value::String(s.to_string()).to_string();
+        let mut out = String::from("[keys]\n");
+        for action in Action::all() {
+            let keys: Vec<String> = self
+                .keys
+                .keys_for(action)
+                .iter()
+                .map(|k| quote(k))
+                .collect();
+            let value = match keys.len() {
+                1 => keys[0].clone(),
+                _ => format!("[{}]", keys.join(", ")),
+            };
+            out.push_str(&format!("{} = {}\n", action.name(), value));
+        }
+        out.push_str(&format!(
+            "\n[video]\nseek_back = {}\nseek_forward = {}\nvolume_step = {}\n",
+            self.seek_back, self.seek_forward, self.volume_step
+        ));
+        out.push_str(&format!(
+            "\n[cache]\npreload = {}\ntextures = {}\n",
+            self.preload, self.textures
+        ));
+        out.push_str(&format!(
+            "\n[slideshow]\ninterval = {}\nmode = {}\n",
+            self.slideshow_interval,
+            quote(self.slideshow_mode.name())
+        ));
+        out.push_str("\n[theme]\n");
+        let mut theme = self.theme;
+        for (name, c) in theme_colours(&mut theme) {
+            out.push_str(&format!("{} = {}\n", name, quote(&colour_text(*c))));
+        }
+        out
+    }
+}
+
+/// `[keys]`: `action = "key"` or `action = ["key", ...]`.
+fn parse_keys(entries: &toml::Table, errors: &mut Vec<String>) -> Keymap {
+    let mut user = Vec::new();
+    for (name, v) in entries {
+        let Some(action) = Action::from_name(name) else {
+            errors.push(format!("[keys] {}: unknown action", name));
+            continue;
+        };
+        let texts: Vec<Option<&str>> = match v {
+            toml::Value::String(s) => vec![Some(s.as_str())],
+            toml::Value::Array(a) => a.iter().map(|k| k.as_str()).collect(),
+            _ => vec![None],
+        };
+        let mut seqs = Vec::new();
+        for text in texts {
+            match text.map(parse_sequence) {
+                Some(Ok(seq)) => seqs.push(seq),
+                Some(Err(e)) => errors.push(format!("[keys] {}: {}", name, e)),
+                None => errors.push(format!("[keys] {}: expected a key or list of keys", name)),
+            }
+        }
+        // An entry with no usable key keeps the defaults; `[]` unbinds
+        if seqs.is_empty() && !matches!(v, toml::Value::Array(a) if a.is_empty()) {
+            continue;
+        }
+        user.push((action, seqs));
+    }
+    Keymap::build(&user, errors)
+}
+
+/// Where the config file lives.
+pub fn path() -> PathBuf {
+    if let Ok(p) = std::env::var("LV_CONFIG") {
+        return PathBuf::from(p);
+    }
+    if let Some(dirs) = directories::ProjectDirs::from("dev", "lv", "lv") {
+        dirs.config_dir().join("config.toml")
+    } else {
+        PathBuf::from("config.toml")
+    }
+}
+
+/// Load the config file. A missing file means all defaults; problems come
+/// back as messages prefixed with the file path.
+pub fn load() -> (Config, Vec<String>) {
+    let path = path();
+    let text = match std::fs::read_to_string(&path) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (Config::default(), vec![]),
+        Err(e) => {
+            return (
+                Config::default(),
+                vec![format!("{}: {}", path.display(), e)],
+            )
+        }
+    };
+    let (config, errors) = Config::parse(&text);
+    let errors = errors
+        .into_iter()
+        .map(|e| format!("{}: {}", path.display(), e))
+        .collect();
+    (config, errors)
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(text: &str) -> Chord {
+        Chord::parse(text).unwrap()
+    }
+
+    #[test]
+    fn chords_parse_and_print() {
+        let c = chord("Ctrl+Shift+Z");
+        assert!(c.ctrl && c.shift && !c.alt);
+        assert_eq!(c.key, Keycode::Z);
+        assert_eq!(c.to_string(), "ctrl+shift+z");
+        assert_eq!(chord("ctrl++").key, Keycode::PLUS);
+        assert_eq!(chord("/").key, Keycode::SLASH);
+        assert_eq!(chord("esc").to_string(), "escape");
+        assert_eq!(chord("kp_enter").key, Keycode::KP_ENTER);
+        assert!(Chord::parse("ctrl+").is_err());
+        assert!(Chord::parse("hyper+x").is_err());
+        assert!(parse_sequence("  ").is_err());
+        assert_eq!(parse_sequence("g  t").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn defaults_cover_every_action() {
+        let keys = Keymap::default();
+        for action in Action::all() {
+            assert!(!keys.keys_for(action).is_empty(), "{:?}", action);
+            assert_eq!(Action::from_name(&action.name()), Some(action));
+        }
+        assert_eq!(Action::from_name("tag-9"), None);
+        assert_eq!(Action::from_name("dest-0"), None);
+    }
+
+    #[test]
+    fn feed_modifiers_and_shift_fallback() {
+        let mut keys = Keymap::default();
+        assert_eq!(keys.feed(chord("ctrl+z")), Some(Action::Undo));
+        assert_eq!(keys.feed(chord("ctrl+shift+z")), Some(Action::Redo));
+        assert_eq!(keys.feed(chord("z")), Some(Action::Restore));
+        assert_eq!(keys.feed(chord("shift+a")), Some(Action::SelectRange));
+        // No binding of its own: acts like `j`
+        assert_eq!(keys.feed(chord("shift+j")), Some(Action::Next));
+        assert_eq!(keys.feed(chord("ctrl+j")), None);
+        assert_eq!(keys.feed(chord("shift+3")), Some(Action::Dest(3)));
+        assert_eq!(keys.feed(chord("kp3")), Some(Action::Tag(3)));
+        assert_eq!(keys.feed(chord("ctrl+0")), Some(Action::Collection(0)));
+    }
+
+    #[test]
+    fn sequences_wait_for_the_rest() {
+        let (mut config, errors) = Config::parse("[keys]\ntags = \"g t\"\ngrid = \"g g\"\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let keys = &mut config.keys;
+        assert_eq!(keys.feed(chord("g")), None);
+        assert_eq!(keys.pending.len(), 1);
+        assert_eq!(keys.feed(chord("t")), Some(Action::Tags));
+        assert_eq!(keys.feed(chord("g")), None);
+        assert_eq!(keys.feed(chord("g")), Some(Action::Grid));
+        // A key that breaks the sequence counts on its own
+        assert_eq!(keys.feed(chord("g")), None);
+        assert_eq!(keys.feed(chord("j")), Some(Action::Next));
+        assert!(keys.pending.is_empty());
+        // `t` no longer opens tags on its own
+        assert_eq!(keys.feed(chord("t")), None);
+    }
+
+    #[test]
+    fn user_keys_replace_defaults() {
+        let text = "[keys]\ntrash = [\"d\", \"delete\"]\nrestore = []\nnext = \"space\"\n";
+        let (mut config, errors) = Config::parse(text);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let keys = &mut config.keys;
+        assert_eq!(keys.feed(chord("d")), Some(Action::Trash));
+        // `dupes` lost `d` and `pause` lost space; `restore` is unbound
+        assert!(keys.keys_for(Action::Dupes).is_empty());
+        assert!(keys.keys_for(Action::Restore).is_empty());
+        assert_eq!(keys.feed(chord("space")), Some(Action::Next));
+        assert_eq!(keys.feed(chord("z")), None);
+    }
+
+    #[test]
+    fn problems_are_reported_and_skipped() {
+        let text = r##"
+[keys]
+nxt = "j"
+prev = "ctrl+nope"
+grid = 3
+search = "x"
+keep-dupe = "x"
+
+[video]
+seek_back = 2.5
+volume_step = 0
+speed = 2
+
+[slideshow]
+interval = 3
+mode = "loop"
+
+[theme]
+accent = "#00ff00"
+dim = "grey"
+glow = "#fff"
+
+[colours]
+x = 1
+"##;
+        let (config, errors) = Config::parse(text);
+        assert_eq!(config.seek_back, 2.5);
+        assert_eq!(config.volume_step, 5);
+        assert_eq!(config.slideshow_interval, 3.0);
+        assert_eq!(config.theme.accent, [0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(config.theme.dim, Theme::DEFAULT.dim);
+        let expect = [
+            "[keys] nxt: unknown action",
+            "[keys] prev: bad key \"ctrl+nope\"",
+            "[keys] grid: expected a key",
+            "clashes with \"x\"",
+            "[video] volume_step: expected a number from 1 to 100",
+            "[video] speed: unknown setting",
+            "[slideshow] mode: expected",
+            "[theme] dim: expected",
+            "[theme] glow: unknown colour",
+            "[colours]: unknown section",
+        ];
+        for e in expect {
+            assert!(errors.iter().any(|m| m.contains(e)), "{}: {:?}", e, errors);
+        }
+        assert_eq!(errors.len(), expect.len(), "{:?}", errors);
+
+        let (_, errors) = Config::parse("[keys\n");
+        assert!(errors[0].contains("line 1"), "{:?}", errors);
+    }
+
+    #[test]
+    fn dump_round_trips() {
+        let text = "[keys]\nundo = [\"ctrl+z\", \"u\"]\n[cache]\npreload = 4\n[slideshow]\nmode = \"shuffle\"\n[theme]\nbackground = \"#10203040\"\n";
+        let (config, errors) = Config::parse(text);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let dump = config.dump();
+        assert!(dump.contains("undo = [\"ctrl+z\", \"u\"]\n"), "{}", dump);
+        assert!(dump.contains("random = []\n"), "{}", dump);
+        assert!(dump.contains("search = \"/\"\n"), "{}", dump);
+        assert!(dump.contains("background = \"#10203040\"\n"), "{}", dump);
+
+        let (again, errors) = Config::parse(&dump);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(again.dump(), dump);
+        assert_eq!(again.preload, 4);
+        assert_eq!(again.slideshow_mode, slideshow::Mode::Shuffle);
+        assert_eq!(Config::default().dump(), Config::parse("").0.dump());
+    }
+}
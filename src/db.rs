@@ -9,6 +9,9 @@ use std::sync::{Arc, Mutex};
 #[derive(Clone)]
 pub struct Db(Arc<Mutex<Connection>>);
 
+/// Undo entries kept in the journal.
+const JOURNAL_KEEP: i64 = 500;
+
 #[derive(Clone)]
 pub struct FileEntry {
     pub id: i64,
@@ -22,15 +25,205 @@ pub struct FileEntry {
     pub temporary: bool,
 }
 
+/// Directory a Shift+digit key sends files to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DestSlot {
+    pub path: String,
+    /// Copy instead of move.
+    pub copy: bool,
+}
+
+/// A file moved or renamed from `from` to `to`, keeping its row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rename {
+    pub file_id: i64,
+    pub from: String,
+    pub to: String,
+}
+
+/// One undoable action in the `journal` table. `forward` and `inverse` are
+/// the JSON changes that redo and undo it (see `undo.rs`).
+#[derive(Clone, Debug)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub label: String,
+    pub forward: String,
+    pub inverse: String,
+    pub undone: bool,
+    pub created_at: String,
+}
+
+/// Order of a directory or collection listing. `o` cycles through them;
+/// the choice is remembered per directory and per collection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Path with digit runs compared as numbers (`img2` before `img10`).
+    #[default]
+    Natural,
+    /// Path, byte by byte.
+    Path,
+    /// Most recently modified first.
+    Newest,
+    /// Largest first.
+    Size,
+    /// Most pixels first.
+    Pixels,
+    /// Most viewed first.
+    Views,
+    /// Most recently liked first, then the rest.
+    Liked,
+    /// By camera or AI model name, files without one last.
+    Model,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 8] = [
+        SortOrder::Natural,
+        SortOrder::Path,
+        SortOrder::Newest,
+        SortOrder::Size,
+        SortOrder::Pixels,
+        SortOrder::Views,
+        SortOrder::Liked,
+        SortOrder::Model,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortOrder::Natural => "natural",
+            SortOrder::Path => "name",
+            SortOrder::Newest => "newest",
+            SortOrder::Size => "size",
+            SortOrder::Pixels => "pixels",
+            SortOrder::Views => "views",
+            SortOrder::Liked => "liked",
+            SortOrder::Model => "model",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SortOrder> {
+        SortOrder::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    /// The next order in `ALL`, wrapping around; `back` steps the other way.
+    pub fn cycle(self, back: bool) -> SortOrder {
+        let i = SortOrder::ALL.iter().position(|s| *s == self).unwrap_or(0);
+        let n = SortOrder::ALL.len();
+        SortOrder::ALL[if back { (i + n - 1) % n } else { (i + 1) % n }]
+    }
+
+    /// `ORDER BY` terms over `files f LEFT JOIN meta m`. Ties go by path,
+    /// in natural order.
+    fn order_by(self) -> &'static str {
+        match self {
+            SortOrder::Natural => "f.path COLLATE NATSORT",
+            SortOrder::Path => "f.path",
+            // As instants: rows from older watcher builds hold epoch seconds
+            SortOrder::Newest => "unixepoch(f.modified_at, 'auto') DESC, f.path COLLATE NATSORT",
+            SortOrder::Size => "f.size DESC, f.path COLLATE NATSORT",
+            SortOrder::Pixels => "COALESCE(m.width * m.height, 0) DESC, f.path COLLATE NATSORT",
+            SortOrder::Views => {
+                "(SELECT COUNT(*) FROM history h WHERE h.file_id = f.id AND h.action = 'view')
+                 DESC, f.path COLLATE NATSORT"
+            }
+            SortOrder::Liked => {
+                "COALESCE(
+                     (SELECT mt.created_at FROM meta_tags mt JOIN tags t ON t.id = mt.tag_id
+                      WHERE mt.meta_id = f.meta_id AND t.name = 'like'),
+                     (SELECT pt.created_at FROM pending_tags pt JOIN tags t ON t.id = pt.tag_id
+                      WHERE pt.file_id = f.id AND t.name = 'like')) DESC, f.path COLLATE NATSORT"
+            }
+            // Camera model, else the AI model (`pnginfo` ends in "model: <name>")
+            SortOrder::Model => {
+                "COALESCE(json_extract(m.exif_json, '$.model'),
+                          CASE WHEN instr(m.pnginfo, 'model: ') > 0
+                               THEN trim(substr(m.pnginfo, instr(m.pnginfo, 'model: ') + 7))
+                          END) COLLATE NOCASE NULLS LAST, f.path COLLATE NATSORT"
+            }
+        }
+    }
+}
+
+/// Compare strings the way people read them: runs of digits by value
+/// (`frame_9` before `frame_10`), everything else case-insensitively.
+/// Strings that only differ in case or zero padding fall back to plain
+/// order, so the order is total. Registered as the `NATSORT` collation.
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    let (mut x, mut y) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let ord = match (x.peek().copied(), y.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(c), Some(d)) if c.is_ascii_digit() && d.is_ascii_digit() => {
+                let (m, n) = (digit_run(&mut x), digit_run(&mut y));
+                let (m, n) = (m.trim_start_matches('0'), n.trim_start_matches('0'));
+                m.len().cmp(&n.len()).then_with(|| m.cmp(n))
+            }
+            (Some(c), Some(d)) => {
+                x.next();
+                y.next();
+                c.to_lowercase().cmp(d.to_lowercase())
+            }
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+}
+
+fn digit_run(it: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = it.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    digits
+}
+
+/// Add the `NATSORT` collation ([`natural_cmp`]) to a connection.
+fn register_collations(conn: &Connection) {
+    if let Err(e) = conn.create_collation("NATSORT", natural_cmp) {
+        eprintln!("db: natural collation unavailable: {}", e);
+    }
+}
+
+/// File, like and hashing counts of one directory, for the tree panel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirCounts {
+    pub dir: String,
+    pub files: i64,
+    pub liked: i64,
+    pub hashed: i64,
+}
+
 /// Aggregate stats for the info sidebar.
 pub struct CollectionStats {
     pub total_files: i64,
     pub total_dirs: i64,
     pub hashed: i64,
     pub with_exif: i64,
+    pub with_thumb: i64,
     pub failed: i64,
 }
 
+/// One member of a candidate duplicate group (`dupe_candidates`).
+pub struct DupeFile {
+    pub entry: FileEntry,
+    pub size: Option<i64>,
+    /// Full-content SHA-512, when a fingerprinted file has been confirmed.
+    pub full_hash: Option<String>,
+}
+
+/// What a file looked like when it was last hashed, for `lv verify`.
+pub struct StoredHash {
+    pub path: String,
+    pub size: Option<i64>,
+    pub modified_at: Option<String>,
+    pub hash: String,
+    pub full_hash: Option<String>,
+}
+
 /// Extended metadata for the info sidebar.
 pub struct FileMeta {
     pub filename: String,
@@ -45,8 +238,10 @@ pub struct FileMeta {
     pub duration_ms: Option<i64>,
     pub bitrate: Option<i64>,
     pub codecs: Option<String>,
+    pub gop_frames: Option<i64>,
     pub tags: Vec<String>,
     pub pnginfo: Option<String>,
+    pub exif_json: Option<String>,
 }
 
 impl Db {
@@ -54,9 +249,40 @@ impl Db {
     pub fn open_memory() -> Self {
         let conn = Connection::open_in_memory().expect("failed to open in-memory db");
         conn.execute_batch("PRAGMA foreign_keys = ON;").ok();
+        register_collations(&conn);
         Db(Arc::new(Mutex::new(conn)))
     }
 
+    /// A library with `names` as real files in a fresh temp dir (canonical
+    /// path), each hashed and, with `tag`, tagged with it.
+    #[cfg(test)]
+    pub fn open_with_files(
+        names: &[&str],
+        tag: Option<&str>,
+    ) -> (Self, tempfile::TempDir, Vec<FileEntry>) {
+        let base = std::fs::canonicalize(std::env::temp_dir()).unwrap();
+        let tmp = tempfile::tempdir_in(base).unwrap();
+        let dir = tmp.path().to_string_lossy().to_string();
+        let db = Self::open_memory();
+        db.ensure_schema();
+        for name in names {
+            let p = tmp.path().join(name);
+            std::fs::write(&p, name).unwrap();
+            let md = std::fs::metadata(&p).unwrap();
+            let mtime = crate::scanner::mtime_stamp(&md);
+            let size = Some(md.len() as i64);
+            let id = db
+                .file_insert(&p.to_string_lossy(), &dir, name, size, mtime.as_deref())
+                .unwrap();
+            db.file_set_hash_meta(id, &format!("hash_{}", name));
+            if let Some(tag) = tag {
+                db.set_tag(id, tag, true).unwrap();
+            }
+        }
+        let files = db.files_by_dir(&dir, SortOrder::Path);
+        (db, tmp, files)
+    }
+
     pub fn open_default() -> Self {
         Self::open_path(&default_db_path())
     }
@@ -71,6 +297,7 @@ impl Db {
         let conn = Connection::open(path).expect("failed to open lv.db");
         conn.execute_batch("PRAGMA journal_mode = WAL;").ok();
         conn.execute_batch("PRAGMA foreign_keys = ON;").ok();
+        register_collations(&conn);
         Db(Arc::new(Mutex::new(conn)))
     }
 
@@ -79,6 +306,10 @@ impl Db {
     }
 
     pub fn ensure_schema(&self) {
+        let had_meta_tags = self
+            .conn()
+            .prepare("SELECT meta_id FROM meta_tags LIMIT 0")
+            .is_ok();
         self.conn()
             .execute_batch(
                 "CREATE TABLE IF NOT EXISTS files (
@@ -121,8 +352,47 @@ impl Db {
                     recursive     INTEGER NOT NULL DEFAULT 1,
                     created_at    TEXT DEFAULT (datetime('now'))
                 );
+                CREATE TABLE IF NOT EXISTS tags (
+                    id            INTEGER PRIMARY KEY,
+                    name          TEXT NOT NULL UNIQUE
+                );
+                CREATE TABLE IF NOT EXISTS meta_tags (
+                    meta_id       INTEGER NOT NULL REFERENCES meta(id),
+                    tag_id        INTEGER NOT NULL REFERENCES tags(id),
+                    created_at    TEXT DEFAULT (datetime('now')),
+                    PRIMARY KEY (meta_id, tag_id)
+                );
+                CREATE TABLE IF NOT EXISTS pending_tags (
+                    file_id       INTEGER NOT NULL REFERENCES files(id),
+                    tag_id        INTEGER NOT NULL REFERENCES tags(id),
+                    created_at    TEXT DEFAULT (datetime('now')),
+                    PRIMARY KEY (file_id, tag_id)
+                );
+                CREATE TABLE IF NOT EXISTS tag_slots (
+                    slot          INTEGER PRIMARY KEY,
+                    tag_id        INTEGER NOT NULL REFERENCES tags(id)
+                );
+                CREATE TABLE IF NOT EXISTS dest_slots (
+                    slot          INTEGER PRIMARY KEY,
+                    path          TEXT NOT NULL,
+                    copy          INTEGER NOT NULL DEFAULT 0
+                );
+                CREATE TABLE IF NOT EXISTS journal (
+                    id            INTEGER PRIMARY KEY,
+                    label         TEXT NOT NULL,
+                    forward       TEXT NOT NULL,
+                    inverse       TEXT NOT NULL,
+                    undone        INTEGER NOT NULL DEFAULT 0,
+                    created_at    TEXT DEFAULT (datetime('now'))
+                );
+                CREATE TABLE IF NOT EXISTS listing_sorts (
+                    listing       TEXT PRIMARY KEY,
+                    sort          TEXT NOT NULL
+                );
                 CREATE INDEX IF NOT EXISTS idx_files_dir ON files(dir);
-                CREATE INDEX IF NOT EXISTS idx_files_path ON files(path);",
+                CREATE INDEX IF NOT EXISTS idx_files_path ON files(path);
+                CREATE INDEX IF NOT EXISTS idx_files_meta ON files(meta_id);
+                CREATE INDEX IF NOT EXISTS idx_meta_tags_tag ON meta_tags(tag_id);",
             )
             .expect("schema creation failed");
 
@@ -134,6 +404,24 @@ impl Db {
             db.execute_batch("ALTER TABLE files ADD COLUMN temporary INTEGER NOT NULL DEFAULT 0;")
                 .ok();
         }
+        // Add full_hash column if missing (full-content hash of fp: files)
+        let has_full: bool = db.prepare("SELECT full_hash FROM files LIMIT 0").is_ok();
+        if !has_full {
+            db.execute_batch("ALTER TABLE files ADD COLUMN full_hash TEXT;")
+                .ok();
+        }
+        // Add phash column if missing (perceptual hash, Layer::PHash)
+        let has_phash: bool = db.prepare("SELECT phash FROM meta LIMIT 0").is_ok();
+        if !has_phash {
+            db.execute_batch("ALTER TABLE meta ADD COLUMN phash INTEGER;")
+                .ok();
+        }
+        // Add gop_frames column if missing (keyframe interval, Layer::VideoProbe)
+        let has_gop: bool = db.prepare("SELECT gop_frames FROM meta LIMIT 0").is_ok();
+        if !has_gop {
+            db.execute_batch("ALTER TABLE meta ADD COLUMN gop_frames INTEGER;")
+                .ok();
+        }
         // Migrate old watched table → directories
         let has_old: bool = db.prepare("SELECT path FROM watched LIMIT 0").is_ok();
         if has_old {
@@ -144,6 +432,32 @@ impl Db {
             )
             .ok();
         }
+        // The watcher used to store mtimes as epoch seconds; use the scanner's
+        // ISO form everywhere so date terms, sorting and verify agree
+        db.execute_batch(
+            "UPDATE files
+             SET modified_at = strftime('%Y-%m-%dT%H:%M:%SZ', CAST(modified_at AS INTEGER), 'unixepoch')
+             WHERE modified_at != '' AND modified_at NOT GLOB '*[^0-9]*';",
+        )
+        .ok();
+        // Copy JSON meta.tags into tags/meta_tags the first time they exist.
+        // meta.tags stays and is kept in sync so older builds can still read it.
+        if !had_meta_tags {
+            db.execute_batch(
+                "INSERT OR IGNORE INTO tags (name)
+                 SELECT DISTINCT j.value
+                 FROM meta m,
+                      json_each(CASE WHEN json_valid(m.tags) THEN m.tags ELSE '[]' END) j
+                 WHERE j.type = 'text' AND j.value != '';
+                 INSERT OR IGNORE INTO meta_tags (meta_id, tag_id)
+                 SELECT m.id, t.id
+                 FROM meta m,
+                      json_each(CASE WHEN json_valid(m.tags) THEN m.tags ELSE '[]' END) j
+                 JOIN tags t ON t.name = j.value
+                 WHERE j.type = 'text';",
+            )
+            .ok();
+        }
     }
 
     // ── Directories (track / watch) ────────────────────────────────────
@@ -230,6 +544,8 @@ impl Db {
                 .ok();
             db.execute("DELETE FROM job_fails WHERE file_id = ?1", [file_id])
                 .ok();
+            db.execute("DELETE FROM pending_tags WHERE file_id = ?1", [file_id])
+                .ok();
             db.execute("DELETE FROM files WHERE id = ?1", [file_id])
                 .ok();
         }
@@ -242,6 +558,8 @@ impl Db {
             .ok();
         db.execute("DELETE FROM job_fails WHERE file_id = ?1", [file_id])
             .ok();
+        db.execute("DELETE FROM pending_tags WHERE file_id = ?1", [file_id])
+            .ok();
         match db.execute("DELETE FROM files WHERE id = ?1", [file_id]) {
             Ok(n) => {
                 if n == 0 {
@@ -305,45 +623,228 @@ impl Db {
             .collect()
     }
 
-    // ── Collections (tag-based) ──────────────────────────────────────────
+    // ── Tags ────────────────────────────────────────────────────────────
 
-    /// Toggle collection tag (c2-c8) on a file. Returns new state.
-    pub fn toggle_collection(&self, file_id: i64, collection: u8) -> bool {
-        let tag = collection_tag(collection);
+    /// Add (`on`) or remove a named tag on a file.
+    pub fn set_tag(&self, file_id: i64, name: &str, on: bool) -> Result<(), String> {
         let db = self.conn();
-        let meta_id: Option<i64> = db
-            .query_row("SELECT meta_id FROM files WHERE id = ?1", [file_id], |r| {
-                r.get(0)
-            })
+        let target = tag_target(&db, file_id)?;
+        set_target_tag(&db, target, name, on)
+    }
+
+    /// All tag names with the number of files carrying them, most used first.
+    /// Unused names (e.g. a renamed slot's old tag) are included with 0.
+    pub fn all_tags(&self) -> Vec<(String, i64)> {
+        let db = self.conn();
+        let mut stmt = db
+            .prepare(
+                "SELECT t.name,
+                        (SELECT COUNT(*) FROM meta_tags mt
+                         JOIN files f ON f.meta_id = mt.meta_id
+                         WHERE mt.tag_id = t.id)
+                        + (SELECT COUNT(*) FROM pending_tags pt WHERE pt.tag_id = t.id) AS n
+                 FROM tags t
+                 ORDER BY n DESC, t.name",
+            )
+            .unwrap();
+        stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
+    /// Tag bound to a digit key: 9 is always `like`, 2-8 default to `c2`..`c8`
+    /// until renamed with `set_slot_tag`.
+    pub fn slot_tag(&self, slot: u8) -> String {
+        if !(2..=8).contains(&slot) {
+            return collection_tag(slot);
+        }
+        self.conn()
+            .query_row(
+                "SELECT t.name FROM tag_slots s JOIN tags t ON t.id = s.tag_id
+                 WHERE s.slot = ?1",
+                [slot],
+                |r| r.get(0),
+            )
+            .unwrap_or_else(|_| collection_tag(slot))
+    }
+
+    /// Tag names for digit keys 2-8.
+    pub fn slot_tags(&self) -> Vec<(u8, String)> {
+        (2..=8).map(|slot| (slot, self.slot_tag(slot))).collect()
+    }
+
+    /// Rename digit key `slot` (2-8) to `name`. If no tag called `name` exists
+    /// yet, the slot's current tag is renamed so its files carry over;
+    /// otherwise the key is pointed at the existing tag.
+    pub fn set_slot_tag(&self, slot: u8, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if !(2..=8).contains(&slot) {
+            return Err(format!("key {} can't be renamed (use 2-8)", slot));
+        }
+        if name.is_empty() {
+            return Err("empty tag name".into());
+        }
+        if name == "like" {
+            return Err("'like' is reserved for key 9".into());
+        }
+        let old = self.slot_tag(slot);
+        if old == name {
+            return Ok(());
+        }
+        if let Some((other, _)) = self
+            .slot_tags()
+            .into_iter()
+            .find(|(s, t)| *s != slot && t == name)
+        {
+            return Err(format!("'{}' is already on key {}", name, other));
+        }
+        let db = self.conn();
+        let exists = tag_id(&db, name).is_some();
+        match tag_id(&db, &old) {
+            Some(old_id) if !exists => {
+                db.execute(
+                    "UPDATE tags SET name = ?1 WHERE id = ?2",
+                    rusqlite::params![name, old_id],
+                )
+                .map_err(|e| e.to_string())?;
+                let metas: Vec<i64> = db
+                    .prepare("SELECT meta_id FROM meta_tags WHERE tag_id = ?1")
+                    .and_then(|mut stmt| {
+                        stmt.query_map([old_id], |r| r.get(0))?
+                            .collect::<rusqlite::Result<_>>()
+                    })
+                    .unwrap_or_default();
+                for meta_id in metas {
+                    sync_legacy_tags(&db, meta_id);
+                }
+            }
+            _ => {
+                db.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [name])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        db.execute(
+            "INSERT OR REPLACE INTO tag_slots (slot, tag_id)
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            rusqlite::params![slot, name],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Destination bound to Shift+`slot` (1-9), if any.
+    pub fn dest_slot(&self, slot: u8) -> Option<DestSlot> {
+        self.conn()
+            .query_row(
+                "SELECT path, copy FROM dest_slots WHERE slot = ?1",
+                [slot],
+                |r| {
+                    Ok(DestSlot {
+                        path: r.get(0)?,
+                        copy: r.get::<_, i32>(1)? != 0,
+                    })
+                },
+            )
             .ok()
-            .flatten();
-        let meta_id = match meta_id {
-            Some(id) => id,
-            None => return false,
-        };
-        let tags_str: String = db
-            .query_row("SELECT tags FROM meta WHERE id = ?1", [meta_id], |r| {
-                r.get(0)
-            })
-            .unwrap_or_else(|_| "[]".into());
-        let mut tags: Vec<String> = serde_json::from_str(&tags_str).unwrap_or_default();
+    }
+
+    /// All configured destination slots, by key.
+    pub fn dest_slots(&self) -> Vec<(u8, DestSlot)> {
+        let db = self.conn();
+        let mut stmt = db
+            .prepare("SELECT slot, path, copy FROM dest_slots ORDER BY slot")
+            .unwrap();
+        stmt.query_map([], |r| {
+            Ok((
+                r.get(0)?,
+                DestSlot {
+                    path: r.get(1)?,
+                    copy: r.get::<_, i32>(2)? != 0,
+                },
+            ))
+        })
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect()
+    }
 
-        let now_in = if tags.contains(&tag) {
-            tags.retain(|t| t != &tag);
-            false
+    /// Bind Shift+`slot` (1-9) to a directory, or unbind it with `None`.
+    pub fn set_dest_slot(&self, slot: u8, dest: Option<&DestSlot>) -> Result<(), String> {
+        if !(1..=9).contains(&slot) {
+            return Err(format!("no destination key {} (use 1-9)", slot));
+        }
+        let db = self.conn();
+        match dest {
+            Some(d) if d.path.trim().is_empty() => Err("empty destination path".into()),
+            Some(d) => db
+                .execute(
+                    "INSERT OR REPLACE INTO dest_slots (slot, path, copy) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![slot, d.path.trim(), d.copy as i32],
+                )
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            None => db
+                .execute("DELETE FROM dest_slots WHERE slot = ?1", [slot])
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Remembered sort order of a listing: a directory path, or
+    /// `collection:N`.
+    pub fn sort_for(&self, listing: &str) -> SortOrder {
+        self.conn()
+            .query_row(
+                "SELECT sort FROM listing_sorts WHERE listing = ?1",
+                [listing],
+                |r| r.get::<_, String>(0),
+            )
+            .ok()
+            .and_then(|name| SortOrder::from_name(&name))
+            .unwrap_or_default()
+    }
+
+    /// Remember `sort` for a listing. The default order is not stored.
+    pub fn set_sort_for(&self, listing: &str, sort: SortOrder) -> Result<(), String> {
+        let db = self.conn();
+        let result = if sort == SortOrder::default() {
+            db.execute("DELETE FROM listing_sorts WHERE listing = ?1", [listing])
         } else {
-            tags.push(tag);
-            true
+            db.execute(
+                "INSERT OR REPLACE INTO listing_sorts (listing, sort) VALUES (?1, ?2)",
+                [listing, sort.name()],
+            )
         };
-        let json = serde_json::to_string(&tags).unwrap_or_else(|_| "[]".into());
-        db.execute(
-            "UPDATE meta SET tags = ?1 WHERE id = ?2",
-            rusqlite::params![json, meta_id],
-        )
-        .ok();
-        now_in
+        result.map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    /// Tag names on a file, in the order they were applied.
+    pub fn file_tags(&self, file_id: i64) -> Vec<String> {
+        let db = self.conn();
+        let mut stmt = db
+            .prepare(
+                "SELECT name FROM (
+                     SELECT t.name, mt.created_at AS at FROM files f
+                     JOIN meta_tags mt ON mt.meta_id = f.meta_id
+                     JOIN tags t ON t.id = mt.tag_id
+                     WHERE f.id = ?1
+                     UNION
+                     SELECT t.name, pt.created_at FROM pending_tags pt
+                     JOIN tags t ON t.id = pt.tag_id
+                     WHERE pt.file_id = ?1)
+                 GROUP BY name
+                 ORDER BY MIN(at), name",
+            )
+            .unwrap();
+        stmt.query_map([file_id], |r| r.get(0))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
     }
 
+    // ── Collections (tag-based) ──────────────────────────────────────────
+
     /// Check if file belongs to a collection.
     #[allow(dead_code)]
     pub fn file_in_collection(&self, file_id: i64, collection: u8) -> bool {
@@ -366,22 +867,12 @@ impl Db {
                 )
                 .map(|t| t != 0)
                 .unwrap_or(false),
-            9 => self
-                .conn()
-                .query_row(
-                    "SELECT 1 FROM files f JOIN meta m ON f.meta_id = m.id
-                         WHERE f.id = ?1 AND m.tags LIKE '%\"like\"%'",
-                    [file_id],
-                    |_| Ok(true),
-                )
-                .unwrap_or(false),
-            2..=8 => {
-                let pattern = format!("%\"{}\"%%", collection_tag(collection));
+            2..=9 => {
+                let tag = self.slot_tag(collection);
                 self.conn()
                     .query_row(
-                        "SELECT 1 FROM files f JOIN meta m ON f.meta_id = m.id
-                         WHERE f.id = ?1 AND m.tags LIKE ?2",
-                        rusqlite::params![file_id, pattern],
+                        &format!("SELECT 1 FROM files f WHERE f.id = ?1 AND {}", TAGGED_2),
+                        rusqlite::params![file_id, tag],
                         |_| Ok(true),
                     )
                     .unwrap_or(false)
@@ -390,48 +881,26 @@ impl Db {
         }
     }
 
-    /// Get files for a collection.
+    /// Get files for a collection, in `sort` order.
     /// Collection 0 = all non-temporary. 1 = temporary.
-    /// 2-8 = tag c2-c8. 9 = tag like.
-    pub fn files_by_collection(&self, collection: u8) -> Vec<FileEntry> {
-        let db = self.conn();
-        let (sql, param): (&str, Option<String>) = match collection {
-            0 => (
-                "SELECT f.id, f.path, f.dir, f.filename, f.meta_id,
-                        (COALESCE(m.tags, '[]') LIKE '%\"like\"%'), f.temporary
-                 FROM files f LEFT JOIN meta m ON f.meta_id = m.id
-                 WHERE f.temporary = 0
-                 ORDER BY f.path",
-                None,
-            ),
-            1 => (
-                "SELECT f.id, f.path, f.dir, f.filename, f.meta_id,
-                        (COALESCE(m.tags, '[]') LIKE '%\"like\"%'), f.temporary
-                 FROM files f LEFT JOIN meta m ON f.meta_id = m.id
-                 WHERE f.temporary = 1
-                 ORDER BY f.path",
-                None,
-            ),
-            9 => (
-                "SELECT f.id, f.path, f.dir, f.filename, f.meta_id, 1, f.temporary
-                 FROM files f JOIN meta m ON f.meta_id = m.id
-                 WHERE m.tags LIKE '%\"like\"%'
-                 ORDER BY f.path",
-                None,
-            ),
-            c @ 2..=8 => (
-                "SELECT f.id, f.path, f.dir, f.filename, f.meta_id,
-                        (COALESCE(m.tags, '[]') LIKE '%\"like\"%'), f.temporary
-                 FROM files f JOIN meta m ON f.meta_id = m.id
-                 WHERE m.tags LIKE ?1
-                 ORDER BY f.path",
-                Some(format!("%\"{}\"%%", collection_tag(c))),
-            ),
-            _ => return vec![],
+    /// 2-8 = tag on that digit key (`slot_tag`). 9 = tag like.
+    pub fn files_by_collection(&self, collection: u8, sort: SortOrder) -> Vec<FileEntry> {
+        let Some(filter) = collection_filter(collection) else {
+            return vec![];
         };
-        let mut stmt = db.prepare(sql).unwrap();
-        let rows = if let Some(ref p) = param {
-            stmt.query_map([p.as_str()], row_to_entry)
+        let sql = format!(
+            "SELECT {} FROM files f LEFT JOIN meta m ON f.meta_id = m.id
+             WHERE {}
+             ORDER BY {}",
+            ENTRY_COLS,
+            filter,
+            sort.order_by()
+        );
+        let tag = self.slot_tag(collection);
+        let db = self.conn();
+        let mut stmt = db.prepare(&sql).unwrap();
+        let rows = if collection >= 2 {
+            stmt.query_map([tag], row_to_entry)
         } else {
             stmt.query_map([], row_to_entry)
         };
@@ -440,80 +909,39 @@ impl Db {
 
     /// Random file within a collection.
     pub fn random_in_collection(&self, collection: u8) -> Option<FileEntry> {
+        let filter = collection_filter(collection)?;
+        let sql = format!(
+            "SELECT {} FROM files f LEFT JOIN meta m ON f.meta_id = m.id
+             WHERE {}
+             ORDER BY RANDOM() LIMIT 1",
+            ENTRY_COLS, filter
+        );
+        let tag = self.slot_tag(collection);
         let db = self.conn();
-        match collection {
-            0 => db
-                .query_row(
-                    "SELECT f.id, f.path, f.dir, f.filename, f.meta_id,
-                        (COALESCE(m.tags, '[]') LIKE '%\"like\"%'), f.temporary
-                 FROM files f LEFT JOIN meta m ON f.meta_id = m.id
-                 WHERE f.temporary = 0
-                 ORDER BY RANDOM() LIMIT 1",
-                    [],
-                    row_to_entry,
-                )
-                .ok(),
-            1 => db
-                .query_row(
-                    "SELECT f.id, f.path, f.dir, f.filename, f.meta_id,
-                        (COALESCE(m.tags, '[]') LIKE '%\"like\"%'), f.temporary
-                 FROM files f LEFT JOIN meta m ON f.meta_id = m.id
-                 WHERE f.temporary = 1
-                 ORDER BY RANDOM() LIMIT 1",
-                    [],
-                    row_to_entry,
-                )
-                .ok(),
-            9 => db
-                .query_row(
-                    "SELECT f.id, f.path, f.dir, f.filename, f.meta_id, 1, f.temporary
-                 FROM files f JOIN meta m ON f.meta_id = m.id
-                 WHERE m.tags LIKE '%\"like\"%'
-                 ORDER BY RANDOM() LIMIT 1",
-                    [],
-                    row_to_entry,
-                )
-                .ok(),
-            c @ 2..=8 => {
-                let pattern = format!("%\"{}\"%%", collection_tag(c));
-                db.query_row(
-                    "SELECT f.id, f.path, f.dir, f.filename, f.meta_id,
-                            (COALESCE(m.tags, '[]') LIKE '%\"like\"%'), f.temporary
-                     FROM files f JOIN meta m ON f.meta_id = m.id
-                     WHERE m.tags LIKE ?1
-                     ORDER BY RANDOM() LIMIT 1",
-                    [&pattern],
-                    row_to_entry,
-                )
-                .ok()
-            }
-            _ => None,
+        if collection >= 2 {
+            db.query_row(&sql, [tag], row_to_entry).ok()
+        } else {
+            db.query_row(&sql, [], row_to_entry).ok()
         }
     }
 
     /// Count files + total size for a collection.
     #[allow(dead_code)]
     pub fn collection_count_size(&self, collection: u8) -> (i64, i64) {
-        let db = self.conn();
-        let (sql, param): (&str, Option<String>) = match collection {
-            0 => ("SELECT COUNT(*), COALESCE(SUM(size),0) FROM files WHERE temporary = 0", None),
-            1 => ("SELECT COUNT(*), COALESCE(SUM(size),0) FROM files WHERE temporary = 1", None),
-            9 => (
-                "SELECT COUNT(*), COALESCE(SUM(f.size),0) FROM files f JOIN meta m ON f.meta_id = m.id WHERE m.tags LIKE '%\"like\"%'",
-                None,
-            ),
-            c @ 2..=8 => (
-                "SELECT COUNT(*), COALESCE(SUM(f.size),0) FROM files f JOIN meta m ON f.meta_id = m.id WHERE m.tags LIKE ?1",
-                Some(format!("%\"{}\"%%", collection_tag(c))),
-            ),
-            _ => return (0, 0),
+        let Some(filter) = collection_filter(collection) else {
+            return (0, 0);
         };
-        if let Some(ref p) = param {
-            db.query_row(sql, [p.as_str()], |r| Ok((r.get(0)?, r.get(1)?)))
-                .unwrap_or((0, 0))
+        let sql = format!(
+            "SELECT COUNT(*), COALESCE(SUM(f.size),0) FROM files f WHERE {}",
+            filter
+        );
+        let tag = self.slot_tag(collection);
+        let db = self.conn();
+        let count = |r: &rusqlite::Row| Ok((r.get(0)?, r.get(1)?));
+        if collection >= 2 {
+            db.query_row(&sql, [tag], count).unwrap_or((0, 0))
         } else {
-            db.query_row(sql, [], |r| Ok((r.get(0)?, r.get(1)?)))
-                .unwrap_or((0, 0))
+            db.query_row(&sql, [], count).unwrap_or((0, 0))
         }
     }
 
@@ -553,75 +981,259 @@ impl Db {
         Some(db.last_insert_rowid())
     }
 
+    /// Point the rows of `moves` at their new paths and run `on_disk` (the
+    /// renames) with the library locked, all or none. Ids are kept, so hash,
+    /// tags and history follow. The watcher waits on the lock, so it finds
+    /// the rows already at their new paths when it sees the renames.
+    pub fn relocate_files(
+        &self,
+        moves: &[Rename],
+        on_disk: impl FnOnce() -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut db = self.conn();
+        let tx = db.transaction().map_err(|e| e.to_string())?;
+        repoint(&tx, moves)?;
+        on_disk()?;
+        tx.commit().map_err(|e| e.to_string())
+    }
+
     pub fn file_update_meta(&self, file_id: i64, size: Option<i64>, modified_at: Option<&str>) {
-        self.conn()
-            .execute(
-                "UPDATE files SET size = ?1, modified_at = ?2, hash_sha512 = NULL, meta_id = NULL WHERE id = ?3",
+        let db = self.conn();
+        // Content changed: keep the file's tags pending until it is re-hashed
+        db.execute(
+            "INSERT OR IGNORE INTO pending_tags (file_id, tag_id, created_at)
+             SELECT f.id, mt.tag_id, mt.created_at FROM files f
+             JOIN meta_tags mt ON mt.meta_id = f.meta_id
+             WHERE f.id = ?1",
+            [file_id],
+        )
+        .ok();
+        db.execute(
+                "UPDATE files SET size = ?1, modified_at = ?2, hash_sha512 = NULL, meta_id = NULL, full_hash = NULL WHERE id = ?3",
                 rusqlite::params![size, modified_at, file_id],
             )
             .ok();
     }
 
-    // ── Directory listing ───────────────────────────────────────────────
+    // ── Undo journal ────────────────────────────────────────────────────
 
-    pub fn dirs(&self) -> Vec<String> {
+    /// Journal a new action. Anything undone is dropped (redo starts over),
+    /// and only the newest `JOURNAL_KEEP` entries are kept.
+    pub fn journal_push(&self, label: &str, forward: &str, inverse: &str) -> Result<i64, String> {
         let db = self.conn();
-        let mut stmt = db
-            .prepare("SELECT DISTINCT dir FROM files ORDER BY dir")
-            .unwrap();
-        stmt.query_map([], |r| r.get(0))
-            .unwrap()
-            .filter_map(|r| r.ok())
-            .collect()
+        db.execute("DELETE FROM journal WHERE undone = 1", [])
+            .map_err(|e| e.to_string())?;
+        db.execute(
+            "INSERT INTO journal (label, forward, inverse) VALUES (?1, ?2, ?3)",
+            rusqlite::params![label, forward, inverse],
+        )
+        .map_err(|e| e.to_string())?;
+        let id = db.last_insert_rowid();
+        db.execute("DELETE FROM journal WHERE id <= ?1", [id - JOURNAL_KEEP])
+            .ok();
+        Ok(id)
     }
 
-    pub fn first_dir(&self) -> Option<String> {
-        self.conn()
-            .query_row("SELECT dir FROM files ORDER BY dir LIMIT 1", [], |r| {
-                r.get(0)
-            })
-            .ok()
+    /// The entry to undo next (`undone = false`: newest done one) or to redo
+    /// next (`undone = true`: oldest undone one).
+    pub fn journal_next(&self, undone: bool) -> Option<JournalEntry> {
+        let sql = if undone {
+            "SELECT id, label, forward, inverse, undone, created_at FROM journal
+             WHERE undone = 1 ORDER BY id LIMIT 1"
+        } else {
+            "SELECT id, label, forward, inverse, undone, created_at FROM journal
+             WHERE undone = 0 ORDER BY id DESC LIMIT 1"
+        };
+        self.conn().query_row(sql, [], row_to_journal).ok()
     }
 
-    // ── File queries ────────────────────────────────────────────────────
+    /// Mark an entry undone or redone, storing the changes that now redo
+    /// and undo it.
+    pub fn journal_update(
+        &self,
+        id: i64,
+        undone: bool,
+        forward: &str,
+        inverse: &str,
+    ) -> Result<(), String> {
+        self.conn()
+            .execute(
+                "UPDATE journal SET undone = ?1, forward = ?2, inverse = ?3 WHERE id = ?4",
+                rusqlite::params![undone as i32, forward, inverse, id],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
 
-    pub fn files_by_dir(&self, dir: &str) -> Vec<FileEntry> {
+    /// Newest entries first.
+    pub fn journal(&self, limit: usize) -> Vec<JournalEntry> {
         let db = self.conn();
         let mut stmt = db
             .prepare(
-                "SELECT f.id, f.path, f.dir, f.filename, f.meta_id,
-                        (COALESCE(m.tags, '[]') LIKE '%\"like\"%'), f.temporary
-                 FROM files f LEFT JOIN meta m ON f.meta_id = m.id
-                 WHERE f.dir = ?1
-                 ORDER BY f.path",
+                "SELECT id, label, forward, inverse, undone, created_at FROM journal
+                 ORDER BY id DESC LIMIT ?1",
             )
             .unwrap();
-        stmt.query_map([dir], row_to_entry)
+        stmt.query_map([limit as i64], row_to_journal)
             .unwrap()
             .filter_map(|r| r.ok())
             .collect()
     }
 
-    pub fn navigate_dir(&self, current_dir: &str, delta: i32) -> Option<String> {
-        let dirs = self.dirs();
-        if dirs.is_empty() {
-            return None;
-        }
-        let cur_idx = dirs.iter().position(|d| d == current_dir).unwrap_or(0);
-        let new_idx = (cur_idx as i64 + delta as i64).clamp(0, dirs.len() as i64 - 1) as usize;
-        if new_idx == cur_idx {
-            return None;
-        }
-        Some(dirs[new_idx].clone())
-    }
+    // ── Directory listing ───────────────────────────────────────────────
 
-    pub fn random_file(&self) -> Option<FileEntry> {
-        self.conn()
-            .query_row(
-                "SELECT f.id, f.path, f.dir, f.filename, f.meta_id,
-                        (COALESCE(m.tags, '[]') LIKE '%\"like\"%'), f.temporary
-                 FROM files f LEFT JOIN meta m ON f.meta_id = m.id
-                 ORDER BY RANDOM() LIMIT 1",
+    pub fn dirs(&self) -> Vec<String> {
+        let db = self.conn();
+        let mut stmt = db
+            .prepare("SELECT DISTINCT dir FROM files ORDER BY dir COLLATE NATSORT")
+            .unwrap();
+        stmt.query_map([], |r| r.get(0))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
+    /// Counts for every directory with files, in natural order.
+    pub fn dir_counts(&self) -> Vec<DirCounts> {
+        let db = self.conn();
+        let mut stmt = db
+            .prepare(&format!(
+                "SELECT f.dir, COUNT(*), SUM({}), COUNT(f.hash_sha512)
+                 FROM files f GROUP BY f.dir ORDER BY f.dir COLLATE NATSORT",
+                TAGGED_1
+            ))
+            .unwrap();
+        stmt.query_map(["like"], |r| {
+            Ok(DirCounts {
+                dir: r.get(0)?,
+                files: r.get(1)?,
+                liked: r.get(2)?,
+                hashed: r.get(3)?,
+            })
+        })
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect()
+    }
+
+    pub fn first_dir(&self) -> Option<String> {
+        self.conn()
+            .query_row(
+                "SELECT dir FROM files ORDER BY dir COLLATE NATSORT LIMIT 1",
+                [],
+                |r| r.get(0),
+            )
+            .ok()
+    }
+
+    // ── File queries ────────────────────────────────────────────────────
+
+    /// Files directly in `dir`, in `sort` order.
+    pub fn files_by_dir(&self, dir: &str, sort: SortOrder) -> Vec<FileEntry> {
+        let db = self.conn();
+        let mut stmt = db
+            .prepare(&format!(
+                "SELECT {} FROM files f LEFT JOIN meta m ON f.meta_id = m.id
+                     WHERE f.dir = ?1
+                     ORDER BY {}",
+                ENTRY_COLS,
+                sort.order_by()
+            ))
+            .unwrap();
+        stmt.query_map([dir], row_to_entry)
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
+    /// Files in `dir` and every directory below it, in `sort` order.
+    pub fn files_under(&self, dir: &str, sort: SortOrder) -> Vec<FileEntry> {
+        let prefix = format!(
+            "{}{}",
+            dir.trim_end_matches(std::path::MAIN_SEPARATOR),
+            std::path::MAIN_SEPARATOR
+        );
+        let db = self.conn();
+        let mut stmt = db
+            .prepare(&format!(
+                "SELECT {} FROM files f LEFT JOIN meta m ON f.meta_id = m.id
+                     WHERE f.dir = ?1 OR substr(f.dir, 1, length(?2)) = ?2
+                     ORDER BY {}",
+                ENTRY_COLS,
+                sort.order_by()
+            ))
+            .unwrap();
+        stmt.query_map([dir, &prefix], row_to_entry)
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
+    /// Files with the given ids that are still in the library, by path.
+    pub fn files_by_ids(&self, ids: &[i64]) -> Vec<FileEntry> {
+        if ids.is_empty() {
+            return Vec::new();
+        }
+        let db = self.conn();
+        let mut stmt = db
+            .prepare(&format!(
+                "SELECT {} FROM files f LEFT JOIN meta m ON f.meta_id = m.id
+                     WHERE f.id IN (SELECT value FROM json_each(?1))
+                     ORDER BY f.path",
+                ENTRY_COLS
+            ))
+            .unwrap();
+        let json = serde_json::to_string(ids).unwrap_or_default();
+        stmt.query_map([json], row_to_entry)
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
+    /// Files matching a search query (see `query.rs`), across the whole library.
+    pub fn query_files(&self, query: &crate::query::Query) -> Vec<FileEntry> {
+        let (where_sql, params) = query.to_sql();
+        let sql = format!(
+            "SELECT {} FROM files f LEFT JOIN meta m ON f.meta_id = m.id
+             WHERE {}
+             ORDER BY f.path",
+            ENTRY_COLS, where_sql
+        );
+        let db = self.conn();
+        let mut stmt = match db.prepare(&sql) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("db: query_files failed: {}", e);
+                return Vec::new();
+            }
+        };
+        stmt.query_map(rusqlite::params_from_iter(params), row_to_entry)
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
+    pub fn navigate_dir(&self, current_dir: &str, delta: i32) -> Option<String> {
+        let dirs = self.dirs();
+        if dirs.is_empty() {
+            return None;
+        }
+        let cur_idx = dirs.iter().position(|d| d == current_dir).unwrap_or(0);
+        let new_idx = (cur_idx as i64 + delta as i64).clamp(0, dirs.len() as i64 - 1) as usize;
+        if new_idx == cur_idx {
+            return None;
+        }
+        Some(dirs[new_idx].clone())
+    }
+
+    pub fn random_file(&self) -> Option<FileEntry> {
+        self.conn()
+            .query_row(
+                &format!(
+                    "SELECT {} FROM files f LEFT JOIN meta m ON f.meta_id = m.id
+                     ORDER BY RANDOM() LIMIT 1",
+                    ENTRY_COLS
+                ),
                 [],
                 row_to_entry,
             )
@@ -631,10 +1243,11 @@ impl Db {
     pub fn newest_file(&self) -> Option<FileEntry> {
         self.conn()
             .query_row(
-                "SELECT f.id, f.path, f.dir, f.filename, f.meta_id,
-                        (COALESCE(m.tags, '[]') LIKE '%\"like\"%'), f.temporary
-                 FROM files f LEFT JOIN meta m ON f.meta_id = m.id
-                 ORDER BY f.modified_at DESC LIMIT 1",
+                &format!(
+                    "SELECT {} FROM files f LEFT JOIN meta m ON f.meta_id = m.id
+                     ORDER BY unixepoch(f.modified_at, 'auto') DESC LIMIT 1",
+                    ENTRY_COLS
+                ),
                 [],
                 row_to_entry,
             )
@@ -644,11 +1257,13 @@ impl Db {
     pub fn random_fav(&self) -> Option<FileEntry> {
         self.conn()
             .query_row(
-                "SELECT f.id, f.path, f.dir, f.filename, f.meta_id, 1, f.temporary
-                 FROM files f JOIN meta m ON f.meta_id = m.id
-                 WHERE m.tags LIKE '%\"like\"%'
-                 ORDER BY RANDOM() LIMIT 1",
-                [],
+                &format!(
+                    "SELECT {} FROM files f LEFT JOIN meta m ON f.meta_id = m.id
+                     WHERE {}
+                     ORDER BY RANDOM() LIMIT 1",
+                    ENTRY_COLS, TAGGED_1
+                ),
+                ["like"],
                 row_to_entry,
             )
             .ok()
@@ -657,66 +1272,103 @@ impl Db {
     pub fn latest_fav(&self) -> Option<FileEntry> {
         self.conn()
             .query_row(
-                "SELECT f.id, f.path, f.dir, f.filename, f.meta_id, 1, f.temporary
-                 FROM files f JOIN meta m ON f.meta_id = m.id
-                 JOIN history h ON h.file_id = f.id AND h.action = 'like'
-                 WHERE m.tags LIKE '%\"like\"%'
-                 ORDER BY h.id DESC LIMIT 1",
-                [],
+                &format!(
+                    "SELECT {} FROM files f LEFT JOIN meta m ON f.meta_id = m.id
+                     JOIN history h ON h.file_id = f.id AND h.action = 'like'
+                     WHERE {}
+                     ORDER BY h.id DESC LIMIT 1",
+                    ENTRY_COLS, TAGGED_1
+                ),
+                ["like"],
                 row_to_entry,
             )
             .ok()
     }
 
-    // ── Mutations ───────────────────────────────────────────────────────
+    // ── Duplicates ──────────────────────────────────────────────────────
 
-    pub fn toggle_like(&self, file_id: i64) -> bool {
+    /// Files sharing a `meta` row with at least one other file, grouped by
+    /// content hash (in path order). `fp:` groups still need confirming.
+    pub fn dupe_candidates(&self) -> Vec<(String, Vec<DupeFile>)> {
         let db = self.conn();
-        let meta_id: Option<i64> = db
-            .query_row("SELECT meta_id FROM files WHERE id = ?1", [file_id], |r| {
-                r.get(0)
+        let sql = format!(
+            "SELECT {ENTRY_COLS}, f.size, f.full_hash, m.hash_sha512
+             FROM files f JOIN meta m ON m.id = f.meta_id
+             WHERE f.meta_id IN (SELECT meta_id FROM files WHERE meta_id IS NOT NULL
+                                 GROUP BY meta_id HAVING COUNT(*) > 1)
+             ORDER BY f.meta_id, f.path"
+        );
+        let mut stmt = db.prepare(&sql).unwrap();
+        let rows = stmt
+            .query_map([], |r| {
+                Ok((
+                    r.get::<_, String>(9)?,
+                    DupeFile {
+                        entry: row_to_entry(r)?,
+                        size: r.get(7)?,
+                        full_hash: r.get(8)?,
+                    },
+                ))
             })
-            .ok()
-            .flatten();
-
-        let meta_id = match meta_id {
-            Some(id) => id,
-            None => return false,
-        };
+            .unwrap()
+            .filter_map(|r| r.ok());
+        let mut groups: Vec<(String, Vec<DupeFile>)> = Vec::new();
+        for (hash, file) in rows {
+            match groups.last_mut() {
+                Some((h, members)) if *h == hash => members.push(file),
+                _ => groups.push((hash, vec![file])),
+            }
+        }
+        groups
+    }
 
-        let tags_str: String = db
-            .query_row("SELECT tags FROM meta WHERE id = ?1", [meta_id], |r| {
-                r.get(0)
-            })
-            .unwrap_or_else(|_| "[]".into());
-        let mut tags: Vec<String> = serde_json::from_str(&tags_str).unwrap_or_default();
+    // ── Similar images ──────────────────────────────────────────────────
 
-        let liked = if tags.contains(&"like".to_string()) {
-            tags.retain(|t| t != "like");
-            db.execute(
-                "INSERT INTO history (file_id, action) VALUES (?1, 'unlike')",
-                [file_id],
-            )
-            .ok();
-            false
-        } else {
-            tags.push("like".to_string());
-            db.execute(
-                "INSERT INTO history (file_id, action) VALUES (?1, 'like')",
+    pub fn file_phash(&self, file_id: i64) -> Option<u64> {
+        self.conn()
+            .query_row(
+                "SELECT m.phash FROM files f JOIN meta m ON m.id = f.meta_id WHERE f.id = ?1",
                 [file_id],
+                |r| r.get::<_, Option<i64>>(0),
             )
-            .ok();
-            true
-        };
+            .ok()
+            .flatten()
+            .map(|h| h as u64)
+    }
 
-        let json = serde_json::to_string(&tags).unwrap_or_else(|_| "[]".into());
+    /// Files whose perceptual hash is within `max_distance` bits (Hamming
+    /// distance) of `phash`, closest first.
+    pub fn similar_files(&self, phash: u64, max_distance: u32) -> Vec<(FileEntry, u32)> {
+        let db = self.conn();
+        let sql = format!(
+            "SELECT {ENTRY_COLS}, m.phash FROM files f JOIN meta m ON m.id = f.meta_id
+             WHERE m.phash IS NOT NULL ORDER BY f.path"
+        );
+        let mut stmt = db.prepare(&sql).unwrap();
+        let mut found: Vec<(FileEntry, u32)> = stmt
+            .query_map([], |r| Ok((row_to_entry(r)?, r.get::<_, i64>(7)?)))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .map(|(f, h)| (f, (h as u64 ^ phash).count_ones()))
+            .filter(|(_, d)| *d <= max_distance)
+            .collect();
+        found.sort_by_key(|(_, d)| *d);
+        found
+    }
+
+    // ── Mutations ───────────────────────────────────────────────────────
+
+    /// Set or clear `like` and record it in history.
+    pub fn set_like(&self, file_id: i64, liked: bool) -> Result<(), String> {
+        let db = self.conn();
+        let target = tag_target(&db, file_id)?;
+        set_target_tag(&db, target, "like", liked)?;
         db.execute(
-            "UPDATE meta SET tags = ?1 WHERE id = ?2",
-            rusqlite::params![json, meta_id],
+            "INSERT INTO history (file_id, action) VALUES (?1, ?2)",
+            rusqlite::params![file_id, if liked { "like" } else { "unlike" }],
         )
         .ok();
-
-        liked
+        Ok(())
     }
 
     pub fn record_view(&self, file_id: i64) {
@@ -731,36 +1383,39 @@ impl Db {
     // ── Metadata ─────────────────────────────────────────────────────────
 
     pub fn get_file_metadata(&self, file_id: i64) -> Option<FileMeta> {
-        let db = self.conn();
-        db.query_row(
-            "SELECT f.filename, f.path, f.dir, f.size, f.modified_at, f.hash_sha512,
+        let mut meta = self
+            .conn()
+            .query_row(
+                "SELECT f.filename, f.path, f.dir, f.size, f.modified_at, f.hash_sha512,
                     m.width, m.height, m.format, m.duration_ms, m.bitrate, m.codecs,
-                    COALESCE(m.tags, '[]'), m.pnginfo
+                    m.pnginfo, m.exif_json, m.gop_frames
              FROM files f LEFT JOIN meta m ON f.meta_id = m.id
              WHERE f.id = ?1",
-            [file_id],
-            |row| {
-                let tags_str: String = row.get(12)?;
-                let tags: Vec<String> = serde_json::from_str(&tags_str).unwrap_or_default();
-                Ok(FileMeta {
-                    filename: row.get(0)?,
-                    path: row.get(1)?,
-                    dir: row.get(2)?,
-                    size: row.get(3)?,
-                    modified_at: row.get(4)?,
-                    hash_sha512: row.get(5)?,
-                    width: row.get(6)?,
-                    height: row.get(7)?,
-                    format: row.get(8)?,
-                    duration_ms: row.get(9)?,
-                    bitrate: row.get(10)?,
-                    codecs: row.get(11)?,
-                    tags,
-                    pnginfo: row.get(13)?,
-                })
-            },
-        )
-        .ok()
+                [file_id],
+                |row| {
+                    Ok(FileMeta {
+                        filename: row.get(0)?,
+                        path: row.get(1)?,
+                        dir: row.get(2)?,
+                        size: row.get(3)?,
+                        modified_at: row.get(4)?,
+                        hash_sha512: row.get(5)?,
+                        width: row.get(6)?,
+                        height: row.get(7)?,
+                        format: row.get(8)?,
+                        duration_ms: row.get(9)?,
+                        bitrate: row.get(10)?,
+                        codecs: row.get(11)?,
+                        tags: Vec::new(),
+                        pnginfo: row.get(12)?,
+                        exif_json: row.get(13)?,
+                        gop_frames: row.get(14)?,
+                    })
+                },
+            )
+            .ok()?;
+        meta.tags = self.file_tags(file_id);
+        Some(meta)
     }
 
     // ── Status ──────────────────────────────────────────────────────────
@@ -806,17 +1461,32 @@ impl Db {
             .ok()
     }
 
+    /// Fingerprinted (`fp:`) file still waiting for its full-content hash.
+    pub fn next_missing_full_hash(&self) -> Option<(i64, String)> {
+        self.conn()
+            .query_row(
+                "SELECT f.id, f.path FROM files f
+                 WHERE f.hash_sha512 LIKE 'fp:%' AND f.full_hash IS NULL
+                 AND f.id NOT IN (SELECT file_id FROM job_fails WHERE layer = 'full_hash')
+                 ORDER BY RANDOM() LIMIT 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .ok()
+    }
+
     pub fn next_missing_exif(&self) -> Option<(i64, String)> {
         self.conn()
             .query_row(
                 "SELECT f.id, f.path FROM files f
                  JOIN meta m ON f.meta_id = m.id
-                 WHERE m.width IS NULL
+                 WHERE (m.width IS NULL OR m.exif_json IS NULL)
                  AND f.id NOT IN (SELECT file_id FROM job_fails WHERE layer = 'exif')
                  AND (LOWER(f.path) LIKE '%.jpg' OR LOWER(f.path) LIKE '%.jpeg'
                    OR LOWER(f.path) LIKE '%.png' OR LOWER(f.path) LIKE '%.webp'
                    OR LOWER(f.path) LIKE '%.gif' OR LOWER(f.path) LIKE '%.bmp'
-                   OR LOWER(f.path) LIKE '%.tiff')
+                   OR LOWER(f.path) LIKE '%.tiff' OR LOWER(f.path) LIKE '%.tif'
+                   OR LOWER(f.path) LIKE '%.heic' OR LOWER(f.path) LIKE '%.heif')
                  ORDER BY RANDOM() LIMIT 1",
                 [],
                 |r| Ok((r.get(0)?, r.get(1)?)),
@@ -841,9 +1511,57 @@ impl Db {
                 rusqlite::params![hash, meta_id, file_id],
             )
             .ok();
+            // Tags applied before the file was hashed move onto its content
+            let merged = db
+                .execute(
+                    "INSERT OR IGNORE INTO meta_tags (meta_id, tag_id, created_at)
+                     SELECT ?1, tag_id, created_at FROM pending_tags WHERE file_id = ?2",
+                    rusqlite::params![meta_id, file_id],
+                )
+                .unwrap_or(0);
+            db.execute("DELETE FROM pending_tags WHERE file_id = ?1", [file_id])
+                .ok();
+            if merged > 0 {
+                sync_legacy_tags(&db, meta_id);
+            }
         }
     }
 
+    pub fn file_set_full_hash(&self, file_id: i64, hash: &str) {
+        self.conn()
+            .execute(
+                "UPDATE files SET full_hash = ?1 WHERE id = ?2",
+                rusqlite::params![hash, file_id],
+            )
+            .ok();
+    }
+
+    /// Hashed files, optionally only those in or under `dir_prefix`, by path.
+    pub fn stored_hashes(&self, dir_prefix: Option<&str>) -> Vec<StoredHash> {
+        let db = self.conn();
+        let mut stmt = db
+            .prepare(
+                "SELECT path, size, modified_at, hash_sha512, full_hash FROM files
+                 WHERE hash_sha512 IS NOT NULL
+                 AND (?1 IS NULL OR dir = ?1 OR dir LIKE ?2 ESCAPE '\\')
+                 ORDER BY path",
+            )
+            .unwrap();
+        let like = dir_prefix.map(|p| format!("{}/%", crate::query::like_escape(p)));
+        stmt.query_map(rusqlite::params![dir_prefix, like], |r| {
+            Ok(StoredHash {
+                path: r.get(0)?,
+                size: r.get(1)?,
+                modified_at: r.get(2)?,
+                hash: r.get(3)?,
+                full_hash: r.get(4)?,
+            })
+        })
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect()
+    }
+
     pub fn meta_set_dimensions(&self, file_id: i64, w: u32, h: u32, format: &str) {
         let db = self.conn();
         let meta_id: Option<i64> = db
@@ -878,6 +1596,178 @@ impl Db {
         }
     }
 
+    /// Parsed camera metadata (`exif::Exif::to_json`); `{}` when the file
+    /// has none, so it isn't parsed again.
+    pub fn meta_set_exif_json(&self, file_id: i64, json: &str) {
+        let db = self.conn();
+        let meta_id: Option<i64> = db
+            .query_row("SELECT meta_id FROM files WHERE id = ?1", [file_id], |r| {
+                r.get(0)
+            })
+            .ok()
+            .flatten();
+        if let Some(mid) = meta_id {
+            db.execute(
+                "UPDATE meta SET exif_json = ?1 WHERE id = ?2",
+                rusqlite::params![json, mid],
+            )
+            .ok();
+        }
+    }
+
+    /// Container facts from `probe::probe`. Width/height are only
+    /// overwritten when the container states them.
+    pub fn meta_set_video(&self, file_id: i64, info: &crate::probe::VideoInfo) {
+        let db = self.conn();
+        let meta_id: Option<i64> = db
+            .query_row("SELECT meta_id FROM files WHERE id = ?1", [file_id], |r| {
+                r.get(0)
+            })
+            .ok()
+            .flatten();
+        if let Some(mid) = meta_id {
+            db.execute(
+                "UPDATE meta SET duration_ms = ?1, bitrate = ?2, codecs = ?3, gop_frames = ?4,
+                     format = ?5, width = COALESCE(?6, width), height = COALESCE(?7, height)
+                 WHERE id = ?8",
+                rusqlite::params![
+                    info.duration_ms,
+                    info.bitrate,
+                    (!info.codecs.is_empty()).then(|| info.codecs.join(", ")),
+                    info.gop_frames,
+                    info.format,
+                    info.width,
+                    info.height,
+                    mid
+                ],
+            )
+            .ok();
+        }
+    }
+
+    pub fn next_missing_video_probe(&self) -> Option<(i64, String)> {
+        self.conn()
+            .query_row(
+                "SELECT f.id, f.path FROM files f
+                 JOIN meta m ON f.meta_id = m.id
+                 WHERE m.duration_ms IS NULL
+                 AND f.id NOT IN (SELECT file_id FROM job_fails WHERE layer = 'video_probe')
+                 AND (LOWER(f.path) LIKE '%.mp4' OR LOWER(f.path) LIKE '%.m4v'
+                   OR LOWER(f.path) LIKE '%.mov' OR LOWER(f.path) LIKE '%.mkv'
+                   OR LOWER(f.path) LIKE '%.webm')
+                 ORDER BY RANDOM() LIMIT 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .ok()
+    }
+
+    pub fn next_missing_thumb(&self) -> Option<(i64, String)> {
+        self.conn()
+            .query_row(
+                "SELECT f.id, f.path FROM files f
+                 JOIN meta m ON f.meta_id = m.id
+                 WHERE COALESCE(m.thumb_ready, 0) = 0
+                 AND f.id NOT IN (SELECT file_id FROM job_fails WHERE layer = 'thumb')
+                 AND (LOWER(f.path) LIKE '%.jpg' OR LOWER(f.path) LIKE '%.jpeg'
+                   OR LOWER(f.path) LIKE '%.png' OR LOWER(f.path) LIKE '%.webp'
+                   OR LOWER(f.path) LIKE '%.gif' OR LOWER(f.path) LIKE '%.bmp'
+                   OR LOWER(f.path) LIKE '%.tiff' OR LOWER(f.path) LIKE '%.tif'
+                   OR LOWER(f.path) LIKE '%.mp4' OR LOWER(f.path) LIKE '%.m4v'
+                   OR LOWER(f.path) LIKE '%.mov' OR LOWER(f.path) LIKE '%.mkv'
+                   OR LOWER(f.path) LIKE '%.webm' OR LOWER(f.path) LIKE '%.avi'
+                   OR LOWER(f.path) LIKE '%.wmv' OR LOWER(f.path) LIKE '%.flv'
+                   OR LOWER(f.path) LIKE '%.3gp')
+                 ORDER BY RANDOM() LIMIT 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .ok()
+    }
+
+    /// Content hash of a file, the key of its thumbnail.
+    pub fn file_hash(&self, file_id: i64) -> Option<String> {
+        self.conn()
+            .query_row(
+                "SELECT m.hash_sha512 FROM files f JOIN meta m ON m.id = f.meta_id
+                 WHERE f.id = ?1",
+                [file_id],
+                |r| r.get(0),
+            )
+            .ok()
+    }
+
+    pub fn meta_set_thumb_ready(&self, file_id: i64) {
+        self.conn()
+            .execute(
+                "UPDATE meta SET thumb_ready = 1
+                 WHERE id = (SELECT meta_id FROM files WHERE id = ?1)",
+                [file_id],
+            )
+            .ok();
+    }
+
+    /// Mark every thumbnail as missing and forget past thumbnail failures,
+    /// so the Thumb layer regenerates them all.
+    pub fn thumbs_reset(&self) {
+        self.conn()
+            .execute_batch(
+                "UPDATE meta SET thumb_ready = 0;
+                 DELETE FROM job_fails WHERE layer = 'thumb';",
+            )
+            .ok();
+    }
+
+    /// Hashes still used by some file; thumbnails for any other hash are
+    /// orphans.
+    pub fn live_hashes(&self) -> std::collections::HashSet<String> {
+        let db = self.conn();
+        let Ok(mut stmt) = db
+            .prepare("SELECT DISTINCT m.hash_sha512 FROM files f JOIN meta m ON m.id = f.meta_id")
+        else {
+            return Default::default();
+        };
+        stmt.query_map([], |r| r.get(0))
+            .map(|rows| rows.flatten().collect())
+            .unwrap_or_default()
+    }
+
+    /// 64-bit perceptual hash, stored as its i64 bit pattern.
+    pub fn meta_set_phash(&self, file_id: i64, phash: u64) {
+        let db = self.conn();
+        let meta_id: Option<i64> = db
+            .query_row("SELECT meta_id FROM files WHERE id = ?1", [file_id], |r| {
+                r.get(0)
+            })
+            .ok()
+            .flatten();
+        if let Some(mid) = meta_id {
+            db.execute(
+                "UPDATE meta SET phash = ?1 WHERE id = ?2",
+                rusqlite::params![phash as i64, mid],
+            )
+            .ok();
+        }
+    }
+
+    pub fn next_missing_phash(&self) -> Option<(i64, String)> {
+        self.conn()
+            .query_row(
+                "SELECT f.id, f.path FROM files f
+                 JOIN meta m ON f.meta_id = m.id
+                 WHERE m.phash IS NULL
+                 AND f.id NOT IN (SELECT file_id FROM job_fails WHERE layer = 'phash')
+                 AND (LOWER(f.path) LIKE '%.jpg' OR LOWER(f.path) LIKE '%.jpeg'
+                   OR LOWER(f.path) LIKE '%.png' OR LOWER(f.path) LIKE '%.webp'
+                   OR LOWER(f.path) LIKE '%.gif' OR LOWER(f.path) LIKE '%.bmp'
+                   OR LOWER(f.path) LIKE '%.tiff')
+                 ORDER BY RANDOM() LIMIT 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .ok()
+    }
+
     pub fn next_missing_pnginfo(&self) -> Option<(i64, String)> {
         self.conn()
             .query_row(
@@ -925,6 +1815,13 @@ impl Db {
                     |r| r.get(0),
                 )
                 .unwrap_or(0),
+            with_thumb: db
+                .query_row(
+                    "SELECT COUNT(*) FROM files f JOIN meta m ON f.meta_id = m.id WHERE m.thumb_ready = 1",
+                    [],
+                    |r| r.get(0),
+                )
+                .unwrap_or(0),
             failed: db
                 .query_row("SELECT COUNT(*) FROM job_fails", [], |r| r.get(0))
                 .unwrap_or(0),
@@ -941,6 +1838,146 @@ impl Db {
     }
 }
 
+/// Column list read by `row_to_entry`; `liked` comes from the `like` tag.
+const ENTRY_COLS: &str = "f.id, f.path, f.dir, f.filename, f.meta_id,
+    (EXISTS (SELECT 1 FROM meta_tags mt JOIN tags t ON t.id = mt.tag_id
+             WHERE mt.meta_id = f.meta_id AND t.name = 'like')
+     OR EXISTS (SELECT 1 FROM pending_tags pt JOIN tags t ON t.id = pt.tag_id
+                WHERE pt.file_id = f.id AND t.name = 'like')),
+    f.temporary";
+
+/// `WHERE` fragment: file carries the tag bound to `?1` / `?2`, on its
+/// content or still pending.
+const TAGGED_1: &str = "(f.meta_id IN (SELECT mt.meta_id FROM meta_tags mt
+    JOIN tags t ON t.id = mt.tag_id WHERE t.name = ?1)
+    OR f.id IN (SELECT pt.file_id FROM pending_tags pt
+    JOIN tags t ON t.id = pt.tag_id WHERE t.name = ?1))";
+const TAGGED_2: &str = "(f.meta_id IN (SELECT mt.meta_id FROM meta_tags mt
+    JOIN tags t ON t.id = mt.tag_id WHERE t.name = ?2)
+    OR f.id IN (SELECT pt.file_id FROM pending_tags pt
+    JOIN tags t ON t.id = pt.tag_id WHERE t.name = ?2))";
+
+/// `WHERE` fragment for a collection; 2-9 bind the tag name as `?1`.
+fn collection_filter(collection: u8) -> Option<&'static str> {
+    match collection {
+        0 => Some("f.temporary = 0"),
+        1 => Some("f.temporary = 1"),
+        2..=9 => Some(TAGGED_1),
+        _ => None,
+    }
+}
+
+/// Where a file's tags are stored: on its content once hashed, otherwise
+/// in `pending_tags` keyed by file id.
+#[derive(Clone, Copy)]
+enum TagTarget {
+    Meta(i64),
+    Pending(i64),
+}
+
+fn tag_target(db: &Connection, file_id: i64) -> Result<TagTarget, String> {
+    match db.query_row("SELECT meta_id FROM files WHERE id = ?1", [file_id], |r| {
+        r.get::<_, Option<i64>>(0)
+    }) {
+        Ok(Some(meta_id)) => Ok(TagTarget::Meta(meta_id)),
+        Ok(None) => Ok(TagTarget::Pending(file_id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err("file no longer in library".into()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn tag_id(db: &Connection, name: &str) -> Option<i64> {
+    db.query_row("SELECT id FROM tags WHERE name = ?1", [name], |r| r.get(0))
+        .ok()
+}
+
+/// Add or remove a tag, creating the tag name on first use.
+fn set_target_tag(db: &Connection, target: TagTarget, name: &str, on: bool) -> Result<(), String> {
+    let (add, remove, id) = match target {
+        TagTarget::Meta(id) => (
+            "INSERT OR IGNORE INTO meta_tags (meta_id, tag_id)
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            "DELETE FROM meta_tags
+             WHERE meta_id = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+            id,
+        ),
+        TagTarget::Pending(id) => (
+            "INSERT OR IGNORE INTO pending_tags (file_id, tag_id)
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            "DELETE FROM pending_tags
+             WHERE file_id = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+            id,
+        ),
+    };
+    if on {
+        db.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [name])
+            .map_err(|e| e.to_string())?;
+    }
+    db.execute(if on { add } else { remove }, rusqlite::params![id, name])
+        .map_err(|e| e.to_string())?;
+    if let TagTarget::Meta(meta_id) = target {
+        sync_legacy_tags(db, meta_id);
+    }
+    Ok(())
+}
+
+/// Mirror a meta row's tags into the old JSON `meta.tags` column, which
+/// builds predating the tags table still read.
+fn sync_legacy_tags(db: &Connection, meta_id: i64) {
+    db.execute(
+        "UPDATE meta SET tags = (
+             SELECT COALESCE(json_group_array(name), '[]') FROM (
+                 SELECT t.name FROM meta_tags mt JOIN tags t ON t.id = mt.tag_id
+                 WHERE mt.meta_id = ?1
+                 ORDER BY mt.created_at, t.name))
+         WHERE id = ?1",
+        [meta_id],
+    )
+    .ok();
+}
+
+/// Set `path`, `dir` and `filename` of each moved file. Rows are parked on
+/// placeholder paths first, so files can swap names without tripping the
+/// unique index.
+fn repoint(db: &Connection, moves: &[Rename]) -> Result<(), String> {
+    for m in moves {
+        db.execute(
+            "UPDATE files SET path = ?1 WHERE id = ?2",
+            rusqlite::params![format!(":repoint:{}", m.file_id), m.file_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for m in moves {
+        let p = std::path::Path::new(&m.to);
+        let dir = p.parent().map(|d| d.to_string_lossy()).unwrap_or_default();
+        let filename = p
+            .file_name()
+            .map(|f| f.to_string_lossy())
+            .unwrap_or_default();
+        let n = db
+            .execute(
+                "UPDATE files SET path = ?1, dir = ?2, filename = ?3 WHERE id = ?4",
+                rusqlite::params![m.to, dir, filename, m.file_id],
+            )
+            .map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err(format!("file {} not in library", m.file_id));
+        }
+    }
+    Ok(())
+}
+
+fn row_to_journal(row: &rusqlite::Row) -> rusqlite::Result<JournalEntry> {
+    Ok(JournalEntry {
+        id: row.get(0)?,
+        label: row.get(1)?,
+        forward: row.get(2)?,
+        inverse: row.get(3)?,
+        undone: row.get::<_, i32>(4)? != 0,
+        created_at: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+    })
+}
+
 fn row_to_entry(row: &rusqlite::Row) -> rusqlite::Result<FileEntry> {
     Ok(FileEntry {
         id: row.get(0)?,
@@ -977,11 +2014,24 @@ fn default_db_path() -> PathBuf {
 mod tests {
     use super::*;
 
+    /// Flip `tag` on a file, as the tag keys do (`like` goes to history
+    /// too). Returns the new state.
+    fn toggle(db: &Db, file_id: i64, tag: &str) -> Result<bool, String> {
+        let on = !db.file_tags(file_id).iter().any(|t| t == tag);
+        if tag == "like" {
+            db.set_like(file_id, on)?;
+        } else {
+            db.set_tag(file_id, tag, on)?;
+        }
+        Ok(on)
+    }
+
     /// Create an in-memory Db with the minimal schema needed for tests.
     /// Mirrors production: foreign_keys ON, FK constraints on history/job_fails.
     fn test_db() -> Db {
         let conn = Connection::open_in_memory().unwrap();
         conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        register_collations(&conn);
         conn.execute_batch(
             "CREATE TABLE meta (
                  id INTEGER PRIMARY KEY,
@@ -996,7 +2046,9 @@ mod tests {
                  codecs TEXT,
                  tags TEXT DEFAULT '[]',
                  thumb_ready INTEGER DEFAULT 0,
-                 created_at TEXT DEFAULT (datetime('now'))
+                 created_at TEXT DEFAULT (datetime('now')),
+                 phash INTEGER,
+                 gop_frames INTEGER
              );
              CREATE TABLE files (
                  id INTEGER PRIMARY KEY,
@@ -1008,7 +2060,8 @@ mod tests {
                  hash_sha512 TEXT,
                  meta_id INTEGER REFERENCES meta(id),
                  created_at TEXT DEFAULT (datetime('now')),
-                 temporary INTEGER NOT NULL DEFAULT 0
+                 temporary INTEGER NOT NULL DEFAULT 0,
+                 full_hash TEXT
              );
              CREATE TABLE history (
                  id INTEGER PRIMARY KEY,
@@ -1029,6 +2082,43 @@ mod tests {
                  tracked INTEGER NOT NULL DEFAULT 1,
                  watched INTEGER NOT NULL DEFAULT 0,
                  recursive INTEGER NOT NULL DEFAULT 1
+             );
+             CREATE TABLE tags (
+                 id INTEGER PRIMARY KEY,
+                 name TEXT NOT NULL UNIQUE
+             );
+             CREATE TABLE meta_tags (
+                 meta_id INTEGER NOT NULL REFERENCES meta(id),
+                 tag_id INTEGER NOT NULL REFERENCES tags(id),
+                 created_at TEXT DEFAULT (datetime('now')),
+                 PRIMARY KEY (meta_id, tag_id)
+             );
+             CREATE TABLE pending_tags (
+                 file_id INTEGER NOT NULL REFERENCES files(id),
+                 tag_id INTEGER NOT NULL REFERENCES tags(id),
+                 created_at TEXT DEFAULT (datetime('now')),
+                 PRIMARY KEY (file_id, tag_id)
+             );
+             CREATE TABLE tag_slots (
+                 slot INTEGER PRIMARY KEY,
+                 tag_id INTEGER NOT NULL REFERENCES tags(id)
+             );
+             CREATE TABLE dest_slots (
+                 slot INTEGER PRIMARY KEY,
+                 path TEXT NOT NULL,
+                 copy INTEGER NOT NULL DEFAULT 0
+             );
+             CREATE TABLE journal (
+                 id INTEGER PRIMARY KEY,
+                 label TEXT NOT NULL,
+                 forward TEXT NOT NULL,
+                 inverse TEXT NOT NULL,
+                 undone INTEGER NOT NULL DEFAULT 0,
+                 created_at TEXT DEFAULT (datetime('now'))
+             );
+             CREATE TABLE listing_sorts (
+                 listing TEXT PRIMARY KEY,
+                 sort TEXT NOT NULL
              );",
         )
         .unwrap();
@@ -1067,16 +2157,145 @@ mod tests {
         insert_file(&db, 2, "/pics/a.jpg", "/pics", "a.jpg");
         insert_file(&db, 3, "/vids/c.mp4", "/vids", "c.mp4");
 
-        let files = db.files_by_dir("/pics");
+        let files = db.files_by_dir("/pics", SortOrder::Path);
         assert_eq!(files.len(), 2);
         assert_eq!(files[0].filename, "a.jpg"); // sorted by path
         assert_eq!(files[1].filename, "b.jpg");
 
-        let vids = db.files_by_dir("/vids");
+        let vids = db.files_by_dir("/vids", SortOrder::Path);
         assert_eq!(vids.len(), 1);
         assert_eq!(vids[0].filename, "c.mp4");
 
-        assert!(db.files_by_dir("/nonexistent").is_empty());
+        assert!(db.files_by_dir("/nonexistent", SortOrder::Path).is_empty());
+    }
+
+    #[test]
+    fn natural_cmp_orders_numbers_by_value() {
+        let mut names = vec![
+            "frame_10.png",
+            "Frame_9.png",
+            "frame_9.png",
+            "ComfyUI_00010_.png",
+            "ComfyUI_00009_.png",
+            "frame_09.png",
+            "a",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "a",
+                "ComfyUI_00009_.png",
+                "ComfyUI_00010_.png",
+                "Frame_9.png",
+                "frame_09.png",
+                "frame_9.png",
+                "frame_10.png",
+            ]
+        );
+        assert_eq!(natural_cmp("img2", "img10"), std::cmp::Ordering::Less);
+        assert_eq!(natural_cmp("x", "x"), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn newest_compares_instants() {
+        let db = test_db();
+        let stamps = ["2024-01-02T00:00:00Z", "1760000000", "2023-05-06T07:08:09Z"];
+        for (id, stamp) in (1..).zip(stamps) {
+            insert_file(
+                &db,
+                id,
+                &format!("/p/{}.jpg", id),
+                "/p",
+                &format!("{}.jpg", id),
+            );
+            db.conn()
+                .execute(
+                    "UPDATE files SET modified_at = ?1 WHERE id = ?2",
+                    rusqlite::params![stamp, id],
+                )
+                .unwrap();
+        }
+        let ids: Vec<i64> = db
+            .files_by_dir("/p", SortOrder::Newest)
+            .iter()
+            .map(|f| f.id)
+            .collect();
+        assert_eq!(ids, vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn sort_orders() {
+        let db = test_db();
+        for (id, name) in [(1, "img10.jpg"), (2, "img2.jpg"), (3, "img1.jpg")] {
+            insert_file(&db, id, &format!("/p/{}", name), "/p", name);
+        }
+        {
+            let conn = db.conn();
+            conn.execute_batch(
+                "UPDATE files SET size = 10 * id, modified_at = '2024-01-0' || (4 - id);
+                 UPDATE meta SET width = 100, height = 100 WHERE id = 2;
+                 UPDATE meta SET exif_json = '{\"model\":\"X-T5\"}' WHERE id = 3;
+                 UPDATE meta SET pnginfo = 'a cat' || char(10) || char(10) || 'model: flux'
+                     WHERE id = 1;",
+            )
+            .unwrap();
+        }
+        db.record_view(3);
+        db.record_view(3);
+        db.record_view(1);
+        db.set_like(2, true).unwrap();
+        db.set_like(1, true).unwrap();
+        db.conn()
+            .execute(
+                "UPDATE meta_tags SET created_at = '2020-01-01' WHERE meta_id = 2",
+                [],
+            )
+            .unwrap();
+
+        let ids = |sort| {
+            db.files_by_dir("/p", sort)
+                .iter()
+                .map(|f| f.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(SortOrder::Path), vec![3, 1, 2]);
+        assert_eq!(ids(SortOrder::Natural), vec![3, 2, 1]);
+        assert_eq!(ids(SortOrder::Newest), vec![1, 2, 3]);
+        assert_eq!(ids(SortOrder::Size), vec![3, 2, 1]);
+        assert_eq!(ids(SortOrder::Pixels), vec![2, 3, 1]);
+        assert_eq!(ids(SortOrder::Views), vec![3, 1, 2]);
+        assert_eq!(ids(SortOrder::Liked), vec![1, 2, 3]);
+        assert_eq!(ids(SortOrder::Model), vec![1, 3, 2]);
+
+        let liked = db.files_by_collection(9, SortOrder::Liked);
+        assert_eq!(liked.iter().map(|f| f.id).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn sort_is_remembered_per_listing() {
+        let db = test_db();
+        assert_eq!(db.sort_for("/p"), SortOrder::Natural);
+        db.set_sort_for("/p", SortOrder::Size).unwrap();
+        db.set_sort_for("collection:9", SortOrder::Liked).unwrap();
+        assert_eq!(db.sort_for("/p"), SortOrder::Size);
+        assert_eq!(db.sort_for("/q"), SortOrder::Natural);
+        assert_eq!(db.sort_for("collection:9"), SortOrder::Liked);
+        // Back to the default forgets it
+        db.set_sort_for("/p", SortOrder::Natural).unwrap();
+        assert_eq!(
+            db.conn()
+                .query_row("SELECT COUNT(*) FROM listing_sorts", [], |r| r
+                    .get::<_, i64>(0))
+                .unwrap(),
+            1
+        );
+
+        assert_eq!(SortOrder::Model.cycle(false), SortOrder::Natural);
+        assert_eq!(SortOrder::Natural.cycle(true), SortOrder::Model);
+        for s in SortOrder::ALL {
+            assert_eq!(SortOrder::from_name(s.name()), Some(s));
+        }
     }
 
     #[test]
@@ -1108,25 +2327,88 @@ mod tests {
         assert_eq!(db.navigate_dir("/a", -1), None); // at start
     }
 
+    #[test]
+    fn dirs_follow_natural_order() {
+        let db = test_db();
+        insert_file(&db, 1, "/r/shot10/a.jpg", "/r/shot10", "a.jpg");
+        insert_file(&db, 2, "/r/shot9/a.jpg", "/r/shot9", "a.jpg");
+        insert_file(&db, 3, "/r/shot9/frame_10.jpg", "/r/shot9", "frame_10.jpg");
+        insert_file(&db, 4, "/r/shot9/frame_9.jpg", "/r/shot9", "frame_9.jpg");
+
+        assert_eq!(db.dirs(), vec!["/r/shot9", "/r/shot10"]);
+        assert_eq!(db.first_dir().as_deref(), Some("/r/shot9"));
+        assert_eq!(
+            db.navigate_dir("/r/shot9", 1),
+            Some("/r/shot10".to_string())
+        );
+        let ids: Vec<i64> = db
+            .files_by_dir("/r/shot9", SortOrder::default())
+            .iter()
+            .map(|f| f.id)
+            .collect();
+        assert_eq!(ids, vec![2, 4, 3]);
+    }
+
+    #[test]
+    fn files_under_includes_subdirs_only() {
+        let db = test_db();
+        insert_file(&db, 1, "/p/sub10/a.jpg", "/p/sub10", "a.jpg");
+        insert_file(&db, 2, "/p/sub9/b.jpg", "/p/sub9", "b.jpg");
+        insert_file(&db, 3, "/p/c.jpg", "/p", "c.jpg");
+        insert_file(&db, 4, "/p_x/d.jpg", "/p_x", "d.jpg");
+        insert_file(&db, 5, "/px/e.jpg", "/px", "e.jpg");
+
+        let ids = |dir, sort| {
+            db.files_under(dir, sort)
+                .iter()
+                .map(|f| f.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids("/p", SortOrder::Natural), vec![3, 2, 1]);
+        assert_eq!(ids("/p", SortOrder::Path), vec![3, 1, 2]);
+        assert_eq!(ids("/p/sub9", SortOrder::Natural), vec![2]);
+        assert!(ids("/q", SortOrder::Natural).is_empty());
+    }
+
+    #[test]
+    fn dir_counts_per_directory() {
+        let db = test_db();
+        insert_file(&db, 1, "/r/b/1.jpg", "/r/b", "1.jpg");
+        insert_file(&db, 2, "/r/b/2.jpg", "/r/b", "2.jpg");
+        insert_file(&db, 3, "/r/a/3.jpg", "/r/a", "3.jpg");
+        db.file_set_hash_meta(1, "h1");
+        db.set_like(1, true).unwrap();
+        db.set_like(3, true).unwrap();
+
+        let counts = db.dir_counts();
+        let row = |dir: &str, files, liked, hashed| DirCounts {
+            dir: dir.into(),
+            files,
+            liked,
+            hashed,
+        };
+        assert_eq!(counts, vec![row("/r/a", 1, 1, 0), row("/r/b", 2, 1, 1)]);
+    }
+
     #[test]
     fn toggle_like() {
         let db = test_db();
         insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
 
         // Initially not liked
-        let files = db.files_by_dir("/a");
+        let files = db.files_by_dir("/a", SortOrder::Path);
         assert!(!files[0].liked);
 
         // Like it
-        let liked = db.toggle_like(1);
+        let liked = toggle(&db, 1, "like").unwrap();
         assert!(liked);
-        let files = db.files_by_dir("/a");
+        let files = db.files_by_dir("/a", SortOrder::Path);
         assert!(files[0].liked);
 
         // Unlike it
-        let liked = db.toggle_like(1);
+        let liked = toggle(&db, 1, "like").unwrap();
         assert!(!liked);
-        let files = db.files_by_dir("/a");
+        let files = db.files_by_dir("/a", SortOrder::Path);
         assert!(!files[0].liked);
     }
 
@@ -1300,17 +2582,17 @@ mod tests {
         insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
 
         // Default: not temporary
-        let f = db.files_by_dir("/a");
+        let f = db.files_by_dir("/a", SortOrder::Path);
         assert!(!f[0].temporary);
 
         // Set temporary
         db.set_temporary(1, true);
-        let f = db.files_by_dir("/a");
+        let f = db.files_by_dir("/a", SortOrder::Path);
         assert!(f[0].temporary);
 
         // Unset
         db.set_temporary(1, false);
-        let f = db.files_by_dir("/a");
+        let f = db.files_by_dir("/a", SortOrder::Path);
         assert!(!f[0].temporary);
     }
 
@@ -1323,7 +2605,7 @@ mod tests {
         insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
         db.set_temporary(2, true);
 
-        let c0 = db.files_by_collection(0);
+        let c0 = db.files_by_collection(0, SortOrder::Path);
         assert_eq!(c0.len(), 1);
         assert_eq!(c0[0].id, 1);
 
@@ -1338,7 +2620,7 @@ mod tests {
         insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
         db.set_temporary(2, true);
 
-        let c1 = db.files_by_collection(1);
+        let c1 = db.files_by_collection(1, SortOrder::Path);
         assert_eq!(c1.len(), 1);
         assert_eq!(c1[0].id, 2);
 
@@ -1351,9 +2633,9 @@ mod tests {
         let db = test_db();
         insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
         insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
-        db.toggle_like(1);
+        toggle(&db, 1, "like").unwrap();
 
-        let c9 = db.files_by_collection(9);
+        let c9 = db.files_by_collection(9, SortOrder::Path);
         assert_eq!(c9.len(), 1);
         assert_eq!(c9[0].id, 1);
 
@@ -1367,18 +2649,18 @@ mod tests {
         insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
 
         // Toggle c3 on
-        let on = db.toggle_collection(1, 3);
+        let on = toggle(&db, 1, &db.slot_tag(3)).unwrap();
         assert!(on);
         assert!(db.file_in_collection(1, 3));
 
-        let c3 = db.files_by_collection(3);
+        let c3 = db.files_by_collection(3, SortOrder::Path);
         assert_eq!(c3.len(), 1);
 
         // Toggle c3 off
-        let off = db.toggle_collection(1, 3);
+        let off = toggle(&db, 1, &db.slot_tag(3)).unwrap();
         assert!(!off);
         assert!(!db.file_in_collection(1, 3));
-        assert!(db.files_by_collection(3).is_empty());
+        assert!(db.files_by_collection(3, SortOrder::Path).is_empty());
     }
 
     #[test]
@@ -1386,15 +2668,15 @@ mod tests {
         let db = test_db();
         insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
 
-        db.toggle_collection(1, 2);
-        db.toggle_collection(1, 5);
+        toggle(&db, 1, &db.slot_tag(2)).unwrap();
+        toggle(&db, 1, &db.slot_tag(5)).unwrap();
 
         assert!(db.file_in_collection(1, 2));
         assert!(db.file_in_collection(1, 5));
         assert!(!db.file_in_collection(1, 3));
 
         // Removing c2 doesn't affect c5
-        db.toggle_collection(1, 2);
+        toggle(&db, 1, &db.slot_tag(2)).unwrap();
         assert!(!db.file_in_collection(1, 2));
         assert!(db.file_in_collection(1, 5));
     }
@@ -1418,7 +2700,7 @@ mod tests {
         assert_eq!(c1, 1);
 
         // Tag collection
-        db.toggle_collection(1, 4);
+        toggle(&db, 1, &db.slot_tag(4)).unwrap();
         let (c4, _) = db.collection_count_size(4);
         assert_eq!(c4, 1);
     }
@@ -1468,7 +2750,7 @@ mod tests {
     }
 
     #[test]
-    fn toggle_collection_on_file_without_meta_returns_false() {
+    fn toggle_collection_on_file_without_meta_is_pending() {
         let db = test_db();
         // Insert file without meta_id
         db.conn()
@@ -1477,8 +2759,17 @@ mod tests {
                 [],
             )
             .unwrap();
-        let result = db.toggle_collection(99, 3);
-        assert!(!result);
+        assert!(toggle(&db, 99, &db.slot_tag(3)).unwrap());
+        assert!(db.file_in_collection(99, 3));
+        assert_eq!(db.files_by_collection(3, SortOrder::Path).len(), 1);
+        assert!(!toggle(&db, 99, &db.slot_tag(3)).unwrap());
+        assert!(db.files_by_collection(3, SortOrder::Path).is_empty());
+    }
+
+    #[test]
+    fn toggle_collection_on_missing_file_is_error() {
+        let db = test_db();
+        assert!(toggle(&db, 99, &db.slot_tag(3)).is_err());
     }
 
     #[test]
@@ -1493,8 +2784,8 @@ mod tests {
     #[test]
     fn files_by_collection_invalid_returns_empty() {
         let db = test_db();
-        assert!(db.files_by_collection(10).is_empty());
-        assert!(db.files_by_collection(255).is_empty());
+        assert!(db.files_by_collection(10, SortOrder::Path).is_empty());
+        assert!(db.files_by_collection(255, SortOrder::Path).is_empty());
     }
 
     #[test]
@@ -1502,15 +2793,15 @@ mod tests {
         let db = test_db();
         insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
 
-        db.toggle_like(1);
-        db.toggle_collection(1, 4);
+        toggle(&db, 1, "like").unwrap();
+        toggle(&db, 1, &db.slot_tag(4)).unwrap();
 
         assert!(db.file_in_collection(1, 9)); // liked
         assert!(db.file_in_collection(1, 4)); // c4
         assert!(db.file_in_collection(1, 0)); // non-temporary
 
         // Unlike doesn't remove c4
-        db.toggle_like(1);
+        toggle(&db, 1, "like").unwrap();
         assert!(!db.file_in_collection(1, 9));
         assert!(db.file_in_collection(1, 4));
     }
@@ -1520,7 +2811,7 @@ mod tests {
         let db = test_db();
         insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
         db.set_temporary(1, true);
-        db.toggle_collection(1, 3);
+        toggle(&db, 1, &db.slot_tag(3)).unwrap();
 
         // Temporary file can still be in tag collections
         assert!(db.file_in_collection(1, 1)); // temporary
@@ -1551,15 +2842,15 @@ mod tests {
         // Accented
         insert_file(&db, 3, "/café/latté.jpg", "/café", "latté.jpg");
 
-        let files = db.files_by_dir("/📸");
+        let files = db.files_by_dir("/📸", SortOrder::Path);
         assert_eq!(files.len(), 1);
         assert_eq!(files[0].path, "/📸/photo.jpg");
 
-        let files = db.files_by_dir("/写真");
+        let files = db.files_by_dir("/写真", SortOrder::Path);
         assert_eq!(files.len(), 1);
         assert_eq!(files[0].filename, "img.png");
 
-        let files = db.files_by_dir("/café");
+        let files = db.files_by_dir("/café", SortOrder::Path);
         assert_eq!(files.len(), 1);
         assert_eq!(files[0].filename, "latté.jpg");
 
@@ -1589,13 +2880,13 @@ mod tests {
         insert_file(&db, 1, lossy_path, lossy_dir, "caf\u{FFFD}.jpg");
 
         // Lookup with the same lossy string succeeds
-        let files = db.files_by_dir(lossy_dir);
+        let files = db.files_by_dir(lossy_dir, SortOrder::Path);
         assert_eq!(files.len(), 1);
         assert_eq!(files[0].path, lossy_path);
         assert_eq!(files[0].filename, "caf\u{FFFD}.jpg");
 
         // Lookup with the "correct" UTF-8 does NOT match the lossy version
-        let files = db.files_by_dir("/pics_other");
+        let files = db.files_by_dir("/pics_other", SortOrder::Path);
         assert!(files.is_empty());
     }
 
@@ -1605,7 +2896,7 @@ mod tests {
         // A path stored with replacement char won't match the "intended" name
         insert_file(&db, 1, "/a/caf\u{FFFD}.jpg", "/a", "caf\u{FFFD}.jpg");
 
-        let files = db.files_by_dir("/a");
+        let files = db.files_by_dir("/a", SortOrder::Path);
         assert_eq!(files.len(), 1);
         // The stored filename contains the replacement char, not the original byte
         assert!(files[0].filename.contains('\u{FFFD}'));
@@ -1708,7 +2999,7 @@ mod tests {
         let db = test_db();
         insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
         insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
-        db.toggle_like(1);
+        toggle(&db, 1, "like").unwrap();
 
         for _ in 0..20 {
             let f = db.random_fav();
@@ -1729,8 +3020,8 @@ mod tests {
         let db = test_db();
         insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
         insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
-        db.toggle_like(1); // like 1 first
-        db.toggle_like(2); // like 2 second
+        toggle(&db, 1, "like").unwrap(); // like 1 first
+        toggle(&db, 2, "like").unwrap(); // like 2 second
 
         let latest = db.latest_fav().unwrap();
         assert_eq!(latest.id, 2); // most recently liked
@@ -1909,10 +3200,10 @@ mod tests {
         insert_file(&db, 2, "/d/b.jpg", "/d", "b.jpg");
         insert_file(&db, 3, "/d/c.jpg", "/d", "c.jpg");
 
-        assert_eq!(db.files_by_dir("/d").len(), 3);
+        assert_eq!(db.files_by_dir("/d", SortOrder::Path).len(), 3);
 
         db.remove_file_by_id(2);
-        let files = db.files_by_dir("/d");
+        let files = db.files_by_dir("/d", SortOrder::Path);
         assert_eq!(files.len(), 2);
         let names: Vec<&str> = files.iter().map(|f| f.filename.as_str()).collect();
         assert!(names.contains(&"a.jpg"));
@@ -1945,7 +3236,7 @@ mod tests {
         // Add view + like history
         db.record_view(1);
         db.record_view(1);
-        db.toggle_like(1);
+        toggle(&db, 1, "like").unwrap();
 
         // This would panic before the cascade fix
         db.remove_file_by_id(1);
@@ -1967,7 +3258,7 @@ mod tests {
         let db = test_db();
         insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
         db.record_view(1);
-        db.toggle_like(1);
+        toggle(&db, 1, "like").unwrap();
 
         db.remove_file_by_path("/a/1.jpg");
         assert_eq!(db.file_count(), 0);
@@ -2013,7 +3304,7 @@ mod tests {
         insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
 
         db.record_view(1);
-        db.toggle_like(1);
+        toggle(&db, 1, "like").unwrap();
         db.record_job_fail(1, "hash", "err");
         db.record_view(2);
 
@@ -2095,14 +3386,14 @@ mod tests {
                 path
             );
             assert!(
-                !db.files_by_dir(dir).is_empty(),
+                !db.files_by_dir(dir, SortOrder::Path).is_empty(),
                 "files_by_dir failed for: {}",
                 dir
             );
         }
 
         // Percent and underscore are SQL LIKE wildcards — files_by_dir uses = not LIKE
-        let pics = db.files_by_dir("/pics");
+        let pics = db.files_by_dir("/pics", SortOrder::Path);
         assert_eq!(pics.len(), 5, "all /pics files should be found");
     }
 
@@ -2117,7 +3408,7 @@ mod tests {
 
         insert_file(&db, 1, &path, dir, &filename);
         assert!(db.file_lookup(&path).is_some());
-        let files = db.files_by_dir(dir);
+        let files = db.files_by_dir(dir, SortOrder::Path);
         assert_eq!(files.len(), 1);
         assert_eq!(files[0].filename, filename);
     }
@@ -2135,7 +3426,7 @@ mod tests {
 
         db.file_insert(&path, &dir, "photo.jpg", Some(100), None);
         assert!(db.file_lookup(&path).is_some());
-        assert_eq!(db.files_by_dir(&dir).len(), 1);
+        assert_eq!(db.files_by_dir(&dir, SortOrder::Path).len(), 1);
     }
 
     #[test]
@@ -2146,12 +3437,15 @@ mod tests {
         insert_file(&db, 3, "/c/photo.jpg", "/c", "photo.jpg");
 
         assert_eq!(db.file_count(), 3);
-        assert_eq!(db.files_by_dir("/a").len(), 1);
-        assert_eq!(db.files_by_dir("/b").len(), 1);
-        assert_eq!(db.files_by_dir("/c").len(), 1);
+        assert_eq!(db.files_by_dir("/a", SortOrder::Path).len(), 1);
+        assert_eq!(db.files_by_dir("/b", SortOrder::Path).len(), 1);
+        assert_eq!(db.files_by_dir("/c", SortOrder::Path).len(), 1);
 
         // Each has unique path
-        assert_ne!(db.files_by_dir("/a")[0].id, db.files_by_dir("/b")[0].id);
+        assert_ne!(
+            db.files_by_dir("/a", SortOrder::Path)[0].id,
+            db.files_by_dir("/b", SortOrder::Path)[0].id
+        );
     }
 
     #[test]
@@ -2175,7 +3469,7 @@ mod tests {
             let db = Arc::clone(&db);
             handles.push(thread::spawn(move || {
                 for _ in 0..50 {
-                    let _ = db.files_by_dir("/t");
+                    let _ = db.files_by_dir("/t", SortOrder::Path);
                     let _ = db.file_count();
                     let _ = db.dirs();
                 }
@@ -2186,9 +3480,9 @@ mod tests {
         {
             let db = Arc::clone(&db);
             handles.push(thread::spawn(move || {
-                let files = db.files_by_dir("/t");
+                let files = db.files_by_dir("/t", SortOrder::Path);
                 for f in &files {
-                    db.toggle_like(f.id);
+                    toggle(&db, f.id, "like").unwrap();
                 }
             }));
         }
@@ -2197,7 +3491,7 @@ mod tests {
         {
             let db = Arc::clone(&db);
             handles.push(thread::spawn(move || {
-                let files = db.files_by_dir("/t");
+                let files = db.files_by_dir("/t", SortOrder::Path);
                 for f in &files {
                     db.record_view(f.id);
                 }
@@ -2211,7 +3505,7 @@ mod tests {
 
         // DB should still be consistent
         assert_eq!(db.file_count(), 20);
-        assert_eq!(db.files_by_dir("/t").len(), 20);
+        assert_eq!(db.files_by_dir("/t", SortOrder::Path).len(), 20);
     }
 
     #[test]
@@ -2232,7 +3526,7 @@ mod tests {
         let db2 = Arc::clone(&db);
 
         let deleter = thread::spawn(move || {
-            let files = db1.files_by_dir("/c");
+            let files = db1.files_by_dir("/c", SortOrder::Path);
             for f in files.iter().take(5) {
                 db1.remove_file_by_id(f.id);
             }
@@ -2240,7 +3534,7 @@ mod tests {
 
         let reader = thread::spawn(move || {
             for _ in 0..20 {
-                let files = db2.files_by_dir("/c");
+                let files = db2.files_by_dir("/c", SortOrder::Path);
                 // Should never panic, count should be between 5 and 10
                 assert!(files.len() <= 10);
             }
@@ -2249,7 +3543,7 @@ mod tests {
         deleter.join().unwrap();
         reader.join().unwrap();
 
-        assert_eq!(db.files_by_dir("/c").len(), 5);
+        assert_eq!(db.files_by_dir("/c", SortOrder::Path).len(), 5);
     }
 
     // ── Job worker pipeline integration ────────────────────────────────
@@ -2262,7 +3556,7 @@ mod tests {
         db.file_insert("/pics/photo.png", "/pics", "photo.png", Some(100), None);
         db.file_insert("/pics/video.mp4", "/pics", "video.mp4", Some(200), None);
         db.file_insert("/pics/broken.jpg", "/pics", "broken.jpg", Some(50), None);
-        let files = db.files_by_dir("/pics");
+        let files = db.files_by_dir("/pics", SortOrder::Path);
         let id_png = files.iter().find(|f| f.filename == "photo.png").unwrap().id;
         let id_mp4 = files.iter().find(|f| f.filename == "video.mp4").unwrap().id;
         let id_broken = files
@@ -2277,7 +3571,7 @@ mod tests {
 
         // Step 2: Hash the PNG
         db.file_set_hash_meta(id_png, "sha512_photo");
-        let files = db.files_by_dir("/pics");
+        let files = db.files_by_dir("/pics", SortOrder::Path);
         let f1 = files.iter().find(|f| f.id == id_png).unwrap();
         assert!(f1.meta_id.is_some(), "file should have meta_id after hash");
 
@@ -2298,26 +3592,109 @@ mod tests {
         let (eid, epath) = exif_job.unwrap();
         assert!(epath.ends_with(".png"), "should pick the PNG for exif");
 
-        // Step 5: Set dimensions
-        db.meta_set_dimensions(eid, 1920, 1080, "PNG");
-        let meta = db.get_file_metadata(eid).unwrap();
-        assert_eq!(meta.width, Some(1920));
-        assert_eq!(meta.height, Some(1080));
-        assert_eq!(meta.format, Some("PNG".into()));
+        // Step 5: Set dimensions
+        db.meta_set_dimensions(eid, 1920, 1080, "PNG");
+        let meta = db.get_file_metadata(eid).unwrap();
+        assert_eq!(meta.width, Some(1920));
+        assert_eq!(meta.height, Some(1080));
+        assert_eq!(meta.format, Some("PNG".into()));
+
+        // Step 6: next_missing_pnginfo for PNG files
+        let pnginfo_job = db.next_missing_pnginfo();
+        assert!(pnginfo_job.is_some());
+        let (pid, ppath) = pnginfo_job.unwrap();
+        assert!(ppath.ends_with(".png"));
+
+        // Step 7: Set pnginfo
+        db.meta_set_pnginfo(pid, "Steps: 20, Sampler: Euler");
+        let meta2 = db.get_file_metadata(pid).unwrap();
+        assert_eq!(meta2.pnginfo, Some("Steps: 20, Sampler: Euler".into()));
+
+        // No more pnginfo jobs
+        assert!(db.next_missing_pnginfo().is_none());
+    }
+
+    #[test]
+    fn video_probe_fills_meta() {
+        let db = test_db();
+        insert_file(&db, 1, "/v/a.mkv", "/v", "a.mkv");
+        insert_file(&db, 2, "/v/b.jpg", "/v", "b.jpg");
+        db.file_set_hash_meta(1, "hash_a");
+        db.file_set_hash_meta(2, "hash_b");
+        assert_eq!(db.next_missing_video_probe().map(|(id, _)| id), Some(1));
+
+        db.meta_set_video(
+            1,
+            &crate::probe::VideoInfo {
+                format: "Matroska",
+                width: Some(1920),
+                height: Some(1080),
+                duration_ms: Some(90_000),
+                bitrate: Some(4_000_000),
+                codecs: vec!["h264".into(), "aac".into()],
+                gop_frames: Some(48),
+            },
+        );
+        assert!(db.next_missing_video_probe().is_none());
+        let m = db.get_file_metadata(1).unwrap();
+        assert_eq!((m.width, m.height), (Some(1920), Some(1080)));
+        assert_eq!(m.duration_ms, Some(90_000));
+        assert_eq!(m.bitrate, Some(4_000_000));
+        assert_eq!(m.codecs.as_deref(), Some("h264, aac"));
+        assert_eq!(m.gop_frames, Some(48));
+        assert_eq!(m.format.as_deref(), Some("Matroska"));
+    }
+
+    #[test]
+    fn thumb_queue_and_reset() {
+        let db = test_db();
+        insert_file(&db, 1, "/p/a.jpg", "/p", "a.jpg");
+        insert_file(&db, 2, "/p/b.mkv", "/p", "b.mkv");
+        insert_file(&db, 3, "/p/c.txt", "/p", "c.txt");
+        db.conn()
+            .execute(
+                "INSERT INTO files (id, path, dir, filename) VALUES (4, '/p/d.png', '/p', 'd.png')",
+                [],
+            )
+            .unwrap();
+
+        let mut queued = Vec::new();
+        while let Some((id, _)) = db.next_missing_thumb() {
+            assert_eq!(db.file_hash(id), Some(format!("hash_{}", id)));
+            db.meta_set_thumb_ready(id);
+            queued.push(id);
+        }
+        queued.sort();
+        // Unhashed d.png waits for its hash; c.txt is no media
+        assert_eq!(queued, vec![1, 2]);
+        assert_eq!(db.collection_stats().with_thumb, 2);
+
+        db.record_job_fail(1, "thumb", "x");
+        db.thumbs_reset();
+        assert_eq!(db.collection_stats().with_thumb, 0);
+        assert!(db.next_missing_thumb().is_some());
 
-        // Step 6: next_missing_pnginfo for PNG files
-        let pnginfo_job = db.next_missing_pnginfo();
-        assert!(pnginfo_job.is_some());
-        let (pid, ppath) = pnginfo_job.unwrap();
-        assert!(ppath.ends_with(".png"));
+        let live = db.live_hashes();
+        assert_eq!(live.len(), 3);
+        assert!(live.contains("hash_2"));
+        assert!(db.file_hash(4).is_none());
+    }
 
-        // Step 7: Set pnginfo
-        db.meta_set_pnginfo(pid, "Steps: 20, Sampler: Euler");
-        let meta2 = db.get_file_metadata(pid).unwrap();
-        assert_eq!(meta2.pnginfo, Some("Steps: 20, Sampler: Euler".into()));
+    #[test]
+    fn exif_json_completes_exif_layer() {
+        let db = test_db();
+        insert_file(&db, 1, "/pics/a.heic", "/pics", "a.heic");
+        db.file_set_hash_meta(1, "hash_a");
+        assert_eq!(db.next_missing_exif().map(|(id, _)| id), Some(1));
 
-        // No more pnginfo jobs
-        assert!(db.next_missing_pnginfo().is_none());
+        // Dimensions alone (rows from before exif_json) still need EXIF
+        db.meta_set_dimensions(1, 4032, 3024, "HEIC");
+        assert!(db.next_missing_exif().is_some());
+
+        db.meta_set_exif_json(1, r#"{"model":"iPhone 15"}"#);
+        assert!(db.next_missing_exif().is_none());
+        let meta = db.get_file_metadata(1).unwrap();
+        assert_eq!(meta.exif_json.as_deref(), Some(r#"{"model":"iPhone 15"}"#));
     }
 
     #[test]
@@ -2358,12 +3735,12 @@ mod tests {
         db.file_set_hash_meta(1, "same_hash_512");
         db.file_set_hash_meta(2, "same_hash_512");
 
-        let f1 = db.files_by_dir("/a")[0].meta_id;
-        let f2 = db.files_by_dir("/b")[0].meta_id;
+        let f1 = db.files_by_dir("/a", SortOrder::Path)[0].meta_id;
+        let f2 = db.files_by_dir("/b", SortOrder::Path)[0].meta_id;
         assert_eq!(f1, f2, "same hash should share meta row");
 
         // Like on one should affect the shared meta
-        db.toggle_like(1);
+        toggle(&db, 1, "like").unwrap();
         let meta1 = db.get_file_metadata(1).unwrap();
         let meta2 = db.get_file_metadata(2).unwrap();
         assert!(meta1.tags.contains(&"like".to_string()));
@@ -2373,6 +3750,66 @@ mod tests {
         );
     }
 
+    // ── Full hash / verify ──────────────────────────────────────────────
+
+    #[test]
+    fn next_missing_full_hash_only_fingerprints() {
+        let db = test_db();
+        db.file_insert("/v/a.mp4", "/v", "a.mp4", None, None);
+        db.file_insert("/v/b.png", "/v", "b.png", None, None);
+        let ids: Vec<i64> = db
+            .files_by_dir("/v", SortOrder::Path)
+            .iter()
+            .map(|f| f.id)
+            .collect();
+        db.file_set_hash_meta(ids[0], "fp:aaa");
+        db.file_set_hash_meta(ids[1], "small");
+
+        assert_eq!(
+            db.next_missing_full_hash(),
+            Some((ids[0], "/v/a.mp4".into()))
+        );
+        db.record_job_fail(ids[0], "full_hash", "io error");
+        assert!(db.next_missing_full_hash().is_none());
+    }
+
+    #[test]
+    fn full_hash_cleared_when_content_changes() {
+        let db = test_db();
+        db.file_insert("/v/a.mp4", "/v", "a.mp4", Some(10), None);
+        let id = db.files_by_dir("/v", SortOrder::Path)[0].id;
+        db.file_set_hash_meta(id, "fp:aaa");
+        db.file_set_full_hash(id, "full");
+        assert_eq!(db.stored_hashes(None)[0].full_hash.as_deref(), Some("full"));
+
+        db.file_update_meta(id, Some(11), None);
+        db.file_set_hash_meta(id, "fp:bbb");
+        assert_eq!(db.stored_hashes(None)[0].full_hash, None);
+    }
+
+    #[test]
+    fn stored_hashes_filters_by_dir() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/x.jpg", "/a", "x.jpg");
+        insert_file(&db, 2, "/a/sub/y.jpg", "/a/sub", "y.jpg");
+        insert_file(&db, 3, "/a_b/z.jpg", "/a_b", "z.jpg");
+        db.file_insert("/a/unhashed.jpg", "/a", "unhashed.jpg", None, None);
+        db.conn()
+            .execute(
+                "UPDATE files SET hash_sha512 = 'h' || id WHERE meta_id IS NOT NULL",
+                [],
+            )
+            .unwrap();
+
+        assert_eq!(db.stored_hashes(None).len(), 3);
+        let under: Vec<String> = db
+            .stored_hashes(Some("/a"))
+            .into_iter()
+            .map(|s| s.path)
+            .collect();
+        assert_eq!(under, vec!["/a/sub/y.jpg", "/a/x.jpg"]);
+    }
+
     // ── Directory tracking lifecycle ─────────────────────────────────────
 
     #[test]
@@ -2465,20 +3902,137 @@ mod tests {
         insert_file(&db, 1, "/a/photo.jpg", "/a", "photo.jpg");
         db.file_set_hash_meta(1, "h1");
 
-        assert!(db.toggle_like(1)); // like
+        assert!(toggle(&db, 1, "like").unwrap()); // like
         assert!(db.file_in_collection(1, 9));
 
-        assert!(!db.toggle_like(1)); // unlike
+        assert!(!toggle(&db, 1, "like").unwrap()); // unlike
         assert!(!db.file_in_collection(1, 9));
     }
 
     #[test]
-    fn toggle_like_without_meta_returns_false() {
+    fn set_like_is_idempotent() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/photo.jpg", "/a", "photo.jpg");
+        db.set_like(1, true).unwrap();
+        db.set_like(1, true).unwrap();
+        assert!(db.files_by_dir("/a", SortOrder::Path)[0].liked);
+        db.set_like(1, false).unwrap();
+        assert!(!db.files_by_dir("/a", SortOrder::Path)[0].liked);
+    }
+
+    #[test]
+    fn files_by_ids_skips_missing() {
+        let db = test_db();
+        insert_file(&db, 1, "/b/2.jpg", "/b", "2.jpg");
+        insert_file(&db, 2, "/a/1.jpg", "/a", "1.jpg");
+        let found: Vec<i64> = db.files_by_ids(&[1, 2, 99]).iter().map(|f| f.id).collect();
+        assert_eq!(found, vec![2, 1]); // ordered by path
+        assert!(db.files_by_ids(&[]).is_empty());
+    }
+
+    #[test]
+    fn repoint_keeps_ids_and_allows_swaps() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/x.jpg", "/a", "x.jpg");
+        insert_file(&db, 2, "/a/y.jpg", "/a", "y.jpg");
+        db.set_tag(1, "keep", true).unwrap();
+        let mv = |id: i64, from: &str, to: &str| Rename {
+            file_id: id,
+            from: from.into(),
+            to: to.into(),
+        };
+        db.relocate_files(&[mv(1, "/a/x.jpg", "/b/x.jpg")], || Ok(()))
+            .unwrap();
+        let moved = db.files_by_dir("/b", SortOrder::Path);
+        assert_eq!((moved[0].id, moved[0].filename.as_str()), (1, "x.jpg"));
+        assert_eq!(db.file_tags(1), vec!["keep".to_string()]);
+
+        // Two files trading names
+        insert_file(&db, 3, "/b/z.jpg", "/b", "z.jpg");
+        let swap = [mv(1, "/b/x.jpg", "/b/z.jpg"), mv(3, "/b/z.jpg", "/b/x.jpg")];
+        db.relocate_files(&swap, || Ok(())).unwrap();
+        assert_eq!(db.files_by_ids(&[1])[0].path, "/b/z.jpg");
+        assert_eq!(db.files_by_ids(&[3])[0].path, "/b/x.jpg");
+
+        // A path already in the library is refused, and nothing changes
+        let taken = [mv(1, "/b/z.jpg", "/b/q.jpg"), mv(2, "/a/y.jpg", "/b/x.jpg")];
+        assert!(db.relocate_files(&taken, || Ok(())).is_err());
+        assert_eq!(db.files_by_ids(&[1])[0].path, "/b/z.jpg");
+        assert!(db
+            .relocate_files(&[mv(99, "/c/x.jpg", "/c/y.jpg")], || Ok(()))
+            .is_err());
+
+        // Failing on disk leaves the rows where they were
+        let err = db.relocate_files(&[mv(1, "/b/z.jpg", "/b/q.jpg")], || Err("busy".into()));
+        assert_eq!(err, Err("busy".to_string()));
+        assert_eq!(db.files_by_ids(&[1])[0].path, "/b/z.jpg");
+    }
+
+    #[test]
+    fn journal_undo_redo_order() {
+        let db = test_db();
+        assert!(db.journal_next(false).is_none());
+        let a = db.journal_push("a", "fa", "ia").unwrap();
+        let b = db.journal_push("b", "fb", "ib").unwrap();
+        assert_eq!(db.journal_next(false).unwrap().id, b);
+
+        db.journal_update(b, true, "fb2", "ib2").unwrap();
+        db.journal_update(a, true, "fa", "ia").unwrap();
+        assert!(db.journal_next(false).is_none());
+        // Redo goes oldest first
+        let next = db.journal_next(true).unwrap();
+        assert_eq!((next.id, next.label.as_str()), (a, "a"));
+        db.journal_update(a, false, "fa", "ia").unwrap();
+        assert_eq!(db.journal_next(true).unwrap().forward, "fb2");
+
+        // A new action drops what was undone
+        db.journal_push("c", "fc", "ic").unwrap();
+        assert!(db.journal_next(true).is_none());
+        let labels: Vec<String> = db.journal(10).into_iter().map(|e| e.label).collect();
+        assert_eq!(labels, vec!["c", "a"]);
+    }
+
+    #[test]
+    fn dest_slots_set_and_clear() {
+        let db = test_db();
+        assert!(db.dest_slot(1).is_none());
+        let keep = DestSlot {
+            path: "/pics/keep".into(),
+            copy: false,
+        };
+        let backup = DestSlot {
+            path: " /backup ".into(),
+            copy: true,
+        };
+        db.set_dest_slot(1, Some(&keep)).unwrap();
+        db.set_dest_slot(3, Some(&backup)).unwrap();
+        assert_eq!(db.dest_slot(1), Some(keep.clone()));
+        assert_eq!(db.dest_slot(3).unwrap().path, "/backup");
+        assert_eq!(
+            db.dest_slots().iter().map(|(s, _)| *s).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        db.set_dest_slot(1, None).unwrap();
+        assert!(db.dest_slot(1).is_none());
+
+        assert!(db.set_dest_slot(0, Some(&keep)).is_err());
+        assert!(db.set_dest_slot(10, Some(&keep)).is_err());
+        let empty = DestSlot {
+            path: "  ".into(),
+            copy: false,
+        };
+        assert!(db.set_dest_slot(2, Some(&empty)).is_err());
+    }
+
+    #[test]
+    fn toggle_like_without_meta_is_pending() {
         let db = test_db();
         // Use file_insert so file has no meta_id (insert_file helper auto-creates meta)
         db.file_insert("/a/photo.jpg", "/a", "photo.jpg", Some(100), None);
-        let files = db.files_by_dir("/a");
-        assert!(!db.toggle_like(files[0].id));
+        let files = db.files_by_dir("/a", SortOrder::Path);
+        assert!(toggle(&db, files[0].id, "like").unwrap());
+        assert!(db.files_by_dir("/a", SortOrder::Path)[0].liked);
+        assert_eq!(db.latest_fav().unwrap().id, files[0].id);
     }
 
     #[test]
@@ -2488,16 +4042,16 @@ mod tests {
         db.file_set_hash_meta(1, "h1");
 
         // Add to collection 3
-        assert!(db.toggle_collection(1, 3));
+        assert!(toggle(&db, 1, &db.slot_tag(3)).unwrap());
         assert!(db.file_in_collection(1, 3));
 
         // Add to collection 5 simultaneously
-        assert!(db.toggle_collection(1, 5));
+        assert!(toggle(&db, 1, &db.slot_tag(5)).unwrap());
         assert!(db.file_in_collection(1, 3));
         assert!(db.file_in_collection(1, 5));
 
         // Remove from collection 3
-        assert!(!db.toggle_collection(1, 3));
+        assert!(!toggle(&db, 1, &db.slot_tag(3)).unwrap());
         assert!(!db.file_in_collection(1, 3));
         assert!(db.file_in_collection(1, 5)); // 5 still there
     }
@@ -2515,7 +4069,7 @@ mod tests {
         assert_eq!(size, 300);
 
         // Mark one as temporary
-        let files = db.files_by_dir("/a");
+        let files = db.files_by_dir("/a", SortOrder::Path);
         db.set_temporary(files[2].id, true);
         let (count0, _) = db.collection_count_size(0);
         let (count1, _) = db.collection_count_size(1);
@@ -2583,8 +4137,8 @@ mod tests {
         assert!(db.latest_fav().is_none());
 
         // Like file 1, then file 2
-        db.toggle_like(1);
-        db.toggle_like(2);
+        toggle(&db, 1, "like").unwrap();
+        toggle(&db, 2, "like").unwrap();
 
         // random_fav should return one of them
         let fav = db.random_fav().unwrap();
@@ -2605,7 +4159,7 @@ mod tests {
             Some(5000),
             Some("2025-01-15"),
         );
-        let files = db.files_by_dir("/a");
+        let files = db.files_by_dir("/a", SortOrder::Path);
         let fid = files[0].id;
 
         db.file_set_hash_meta(fid, "sha512_abc");
@@ -2667,11 +4221,11 @@ mod tests {
 
         db.set_temporary(2, true);
 
-        let col0 = db.files_by_collection(0);
+        let col0 = db.files_by_collection(0, SortOrder::Path);
         assert_eq!(col0.len(), 1);
         assert_eq!(col0[0].filename, "keep.jpg");
 
-        let col1 = db.files_by_collection(1);
+        let col1 = db.files_by_collection(1, SortOrder::Path);
         assert_eq!(col1.len(), 1);
         assert_eq!(col1[0].filename, "temp.jpg");
     }
@@ -2709,6 +4263,452 @@ mod tests {
         }
     }
 
+    // ── Tags table ──────────────────────────────────────────────────────
+
+    #[test]
+    fn toggle_tag_creates_name_once() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
+        assert!(toggle(&db, 1, "sunset").unwrap());
+        assert!(toggle(&db, 2, "sunset").unwrap());
+        let n: i64 = db
+            .conn()
+            .query_row("SELECT COUNT(*) FROM tags WHERE name = 'sunset'", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(db.file_tags(1), vec!["sunset"]);
+        assert!(!toggle(&db, 1, "sunset").unwrap());
+        assert!(db.file_tags(1).is_empty());
+        assert_eq!(db.file_tags(2), vec!["sunset"]);
+    }
+
+    #[test]
+    fn tag_names_match_exactly() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
+        toggle(&db, 1, "c3x").unwrap();
+        toggle(&db, 2, "say \"hi\"").unwrap();
+        assert!(db.files_by_collection(3, SortOrder::Path).is_empty());
+        assert!(!db.file_in_collection(1, 3));
+        assert_eq!(db.file_tags(2), vec!["say \"hi\""]);
+    }
+
+    #[test]
+    fn toggle_tag_mirrors_legacy_json() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        toggle(&db, 1, &db.slot_tag(3)).unwrap();
+        toggle(&db, 1, "like").unwrap();
+        let json = |db: &Db| -> Vec<String> {
+            let s: String = db
+                .conn()
+                .query_row("SELECT tags FROM meta WHERE id = 1", [], |r| r.get(0))
+                .unwrap();
+            serde_json::from_str(&s).unwrap()
+        };
+        let mut tags = json(&db);
+        tags.sort();
+        assert_eq!(tags, vec!["c3", "like"]);
+        toggle(&db, 1, &db.slot_tag(3)).unwrap();
+        assert_eq!(json(&db), vec!["like"]);
+        toggle(&db, 1, "like").unwrap();
+        assert!(json(&db).is_empty());
+    }
+
+    #[test]
+    fn ensure_schema_normalizes_epoch_mtimes() {
+        let db = Db::open_memory();
+        db.ensure_schema();
+        db.file_insert("/a/1.jpg", "/a", "1.jpg", None, Some("1760000000"));
+        db.file_insert(
+            "/a/2.jpg",
+            "/a",
+            "2.jpg",
+            None,
+            Some("2024-01-02T03:04:05Z"),
+        );
+        db.ensure_schema();
+        let mtime = |p: &str| db.file_lookup(p).and_then(|(_, _, m)| m);
+        assert_eq!(mtime("/a/1.jpg").as_deref(), Some("2025-10-09T08:53:20Z"));
+        assert_eq!(mtime("/a/2.jpg").as_deref(), Some("2024-01-02T03:04:05Z"));
+    }
+
+    #[test]
+    fn ensure_schema_migrates_json_tags() {
+        // A database written before the tags table existed
+        let db = Db::open_memory();
+        db.conn()
+            .execute_batch(
+                "CREATE TABLE meta (
+                     id INTEGER PRIMARY KEY,
+                     hash_sha512 TEXT NOT NULL UNIQUE,
+                     width INTEGER, height INTEGER, format TEXT, exif_json TEXT,
+                     pnginfo TEXT, duration_ms INTEGER, bitrate INTEGER, codecs TEXT,
+                     tags TEXT DEFAULT '[]',
+                     thumb_ready INTEGER DEFAULT 0,
+                     created_at TEXT DEFAULT (datetime('now'))
+                 );
+                 CREATE TABLE files (
+                     id INTEGER PRIMARY KEY,
+                     path TEXT NOT NULL UNIQUE,
+                     dir TEXT NOT NULL,
+                     filename TEXT NOT NULL,
+                     size INTEGER, modified_at TEXT, hash_sha512 TEXT,
+                     meta_id INTEGER REFERENCES meta(id),
+                     created_at TEXT DEFAULT (datetime('now'))
+                 );
+                 INSERT INTO meta (id, hash_sha512, tags) VALUES
+                     (1, 'h1', '[\"like\",\"c3\"]'),
+                     (2, 'h2', '[\"c3\"]'),
+                     (3, 'h3', 'not json'),
+                     (4, 'h4', NULL);
+                 INSERT INTO files (id, path, dir, filename, meta_id) VALUES
+                     (1, '/a/1.jpg', '/a', '1.jpg', 1),
+                     (2, '/a/2.jpg', '/a', '2.jpg', 2),
+                     (3, '/a/3.jpg', '/a', '3.jpg', 3),
+                     (4, '/a/4.jpg', '/a', '4.jpg', 4);",
+            )
+            .unwrap();
+        db.ensure_schema();
+
+        let liked = db.files_by_collection(9, SortOrder::Path);
+        assert_eq!(liked.len(), 1);
+        assert!(liked[0].liked);
+        assert_eq!(db.files_by_collection(3, SortOrder::Path).len(), 2);
+        assert!(db.file_tags(3).is_empty());
+        assert!(db.file_tags(4).is_empty());
+
+        // Running again doesn't resurrect tags removed after the migration
+        toggle(&db, 2, &db.slot_tag(3)).unwrap();
+        db.ensure_schema();
+        assert_eq!(db.files_by_collection(3, SortOrder::Path).len(), 1);
+    }
+
+    #[test]
+    fn get_file_metadata_reads_tags_table() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        toggle(&db, 1, &db.slot_tag(5)).unwrap();
+        toggle(&db, 1, "like").unwrap();
+        let meta = db.get_file_metadata(1).unwrap();
+        let mut tags = meta.tags;
+        tags.sort();
+        assert_eq!(tags, vec!["c5", "like"]);
+    }
+
+    #[test]
+    fn set_tag_is_idempotent() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        db.set_tag(1, "keeper", true).unwrap();
+        db.set_tag(1, "keeper", true).unwrap();
+        assert_eq!(db.file_tags(1), vec!["keeper"]);
+        db.set_tag(1, "keeper", false).unwrap();
+        db.set_tag(1, "keeper", false).unwrap();
+        assert!(db.file_tags(1).is_empty());
+    }
+
+    #[test]
+    fn set_tag_on_missing_file_is_error() {
+        let db = test_db();
+        assert!(db.set_tag(42, "keeper", true).is_err());
+        assert!(toggle(&db, 42, "keeper").is_err());
+        assert!(toggle(&db, 42, "like").is_err());
+    }
+
+    #[test]
+    fn all_tags_counts_files() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
+        db.set_tag(1, "sky", true).unwrap();
+        db.set_tag(2, "sky", true).unwrap();
+        db.set_tag(2, "car", true).unwrap();
+        db.set_tag(2, "car", false).unwrap();
+        assert_eq!(
+            db.all_tags(),
+            vec![("sky".to_string(), 2), ("car".to_string(), 0)]
+        );
+    }
+
+    #[test]
+    fn slot_tags_default_names() {
+        let db = test_db();
+        assert_eq!(db.slot_tag(3), "c3");
+        assert_eq!(db.slot_tag(9), "like");
+        let slots = db.slot_tags();
+        assert_eq!(slots.len(), 7);
+        assert_eq!(slots[0], (2, "c2".to_string()));
+        assert_eq!(slots[6], (8, "c8".to_string()));
+    }
+
+    #[test]
+    fn rename_slot_keeps_tagged_files() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        toggle(&db, 1, &db.slot_tag(3)).unwrap();
+        db.set_slot_tag(3, "portfolio").unwrap();
+        assert_eq!(db.slot_tag(3), "portfolio");
+        assert_eq!(db.file_tags(1), vec!["portfolio"]);
+        assert!(db.file_in_collection(1, 3));
+        assert_eq!(db.files_by_collection(3, SortOrder::Path).len(), 1);
+        // Key 3 now toggles the renamed tag
+        assert!(!toggle(&db, 1, &db.slot_tag(3)).unwrap());
+        assert!(db.file_tags(1).is_empty());
+    }
+
+    #[test]
+    fn rename_slot_to_existing_tag_points_at_it() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
+        db.set_tag(1, "reject", true).unwrap();
+        toggle(&db, 2, &db.slot_tag(4)).unwrap();
+        db.set_slot_tag(4, "reject").unwrap();
+        let c4: Vec<i64> = db
+            .files_by_collection(4, SortOrder::Path)
+            .iter()
+            .map(|f| f.id)
+            .collect();
+        assert_eq!(c4, vec![1]);
+        // The old c4 tag is untouched
+        assert_eq!(db.file_tags(2), vec!["c4"]);
+    }
+
+    #[test]
+    fn rename_slot_rejects_bad_input() {
+        let db = test_db();
+        assert!(db.set_slot_tag(9, "x").is_err());
+        assert!(db.set_slot_tag(1, "x").is_err());
+        assert!(db.set_slot_tag(3, "  ").is_err());
+        assert!(db.set_slot_tag(3, "like").is_err());
+        db.set_slot_tag(3, "portfolio").unwrap();
+        assert!(db.set_slot_tag(5, "portfolio").is_err());
+        assert_eq!(db.slot_tag(5), "c5");
+    }
+
+    // ── Pending tags (file not hashed yet) ──────────────────────────────
+
+    #[test]
+    fn pending_tags_merge_on_hash() {
+        let db = test_db();
+        let id = db
+            .file_insert("/a/new.jpg", "/a", "new.jpg", Some(10), None)
+            .unwrap();
+        db.set_tag(id, "keeper", true).unwrap();
+        assert!(toggle(&db, id, "like").unwrap());
+        assert_eq!(db.file_tags(id), vec!["keeper", "like"]);
+
+        db.file_set_hash_meta(id, "h_new");
+        assert_eq!(db.file_tags(id), vec!["keeper", "like"]);
+        let pending: i64 = db
+            .conn()
+            .query_row("SELECT COUNT(*) FROM pending_tags", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(pending, 0);
+        let meta = db.get_file_metadata(id).unwrap();
+        assert!(meta.tags.contains(&"like".to_string()));
+        assert!(db.files_by_collection(9, SortOrder::Path)[0].liked);
+    }
+
+    #[test]
+    fn pending_tags_merge_into_existing_content_tags() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        toggle(&db, 1, &db.slot_tag(3)).unwrap();
+        // A copy of file 1, not hashed yet
+        let id = db
+            .file_insert("/b/1.jpg", "/b", "1.jpg", Some(10), None)
+            .unwrap();
+        toggle(&db, id, &db.slot_tag(4)).unwrap();
+        db.file_set_hash_meta(id, "hash_1");
+        let mut tags = db.file_tags(id);
+        tags.sort();
+        assert_eq!(tags, vec!["c3", "c4"]);
+        // Tags live on the content, so the original sees c4 too
+        assert!(db.file_in_collection(1, 4));
+    }
+
+    #[test]
+    fn file_update_meta_keeps_tags_pending() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        toggle(&db, 1, "like").unwrap();
+        db.file_update_meta(1, Some(20), Some("2026-01-01T00:00:00Z"));
+        assert!(db.files_by_dir("/a", SortOrder::Path)[0].liked);
+        db.file_set_hash_meta(1, "hash_edited");
+        assert_eq!(db.file_tags(1), vec!["like"]);
+    }
+
+    #[test]
+    fn remove_file_clears_pending_tags() {
+        let db = test_db();
+        let id = db
+            .file_insert("/a/new.jpg", "/a", "new.jpg", Some(10), None)
+            .unwrap();
+        db.set_tag(id, "keeper", true).unwrap();
+        db.remove_file_by_id(id);
+        let pending: i64 = db
+            .conn()
+            .query_row("SELECT COUNT(*) FROM pending_tags", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(pending, 0);
+        assert_eq!(db.file_count(), 0);
+    }
+
+    // ── Search queries ────────────────────────────────────────────────
+
+    fn search(db: &Db, q: &str) -> Vec<String> {
+        let query = crate::query::Query::parse(q).unwrap();
+        db.query_files(&query)
+            .into_iter()
+            .map(|f| f.filename)
+            .collect()
+    }
+
+    #[test]
+    fn query_files_empty_query_returns_all() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        insert_file(&db, 2, "/b/2.png", "/b", "2.png");
+        assert_eq!(search(&db, ""), vec!["1.jpg", "2.png"]);
+    }
+
+    #[test]
+    fn query_files_ext_and_liked() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        insert_file(&db, 2, "/a/2.PNG", "/a", "2.PNG");
+        insert_file(&db, 3, "/a/3.png", "/a", "3.png");
+        toggle(&db, 3, "like").unwrap();
+
+        assert_eq!(search(&db, "ext:png"), vec!["2.PNG", "3.png"]);
+        assert_eq!(search(&db, "ext:png liked"), vec!["3.png"]);
+        assert_eq!(search(&db, "ext:png -liked"), vec!["2.PNG"]);
+    }
+
+    #[test]
+    fn query_files_tag() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/1.jpg", "/a", "1.jpg");
+        insert_file(&db, 2, "/a/2.jpg", "/a", "2.jpg");
+        toggle(&db, 2, &db.slot_tag(3)).unwrap();
+        assert_eq!(search(&db, "tag:c3"), vec!["2.jpg"]);
+        assert!(search(&db, "tag:c4").is_empty());
+    }
+
+    #[test]
+    fn query_files_tag_pending() {
+        let db = test_db();
+        let id = db
+            .file_insert("/a/new.jpg", "/a", "new.jpg", None, None)
+            .unwrap();
+        db.set_tag(id, "draft", true).unwrap();
+        toggle(&db, id, "like").unwrap();
+        assert_eq!(search(&db, "tag:draft liked"), vec!["new.jpg"]);
+        assert!(search(&db, "-liked").is_empty());
+    }
+
+    #[test]
+    fn query_files_dimensions_and_size() {
+        let db = test_db();
+        db.file_insert("/a/big.png", "/a", "big.png", Some(5_000_000), None);
+        db.file_insert("/a/small.png", "/a", "small.png", Some(1000), None);
+        db.file_insert("/a/unhashed.png", "/a", "unhashed.png", None, None);
+        let files = db.files_by_dir("/a", SortOrder::Path);
+        let id = |name: &str| files.iter().find(|f| f.filename == name).unwrap().id;
+        db.file_set_hash_meta(id("big.png"), "h_big");
+        db.file_set_hash_meta(id("small.png"), "h_small");
+        db.meta_set_dimensions(id("big.png"), 4096, 2048, "PNG");
+        db.meta_set_dimensions(id("small.png"), 640, 480, "PNG");
+
+        assert_eq!(search(&db, "width>2000"), vec!["big.png"]);
+        assert_eq!(search(&db, "height<=480"), vec!["small.png"]);
+        assert_eq!(search(&db, "size>1M"), vec!["big.png"]);
+        // Files without meta don't match numeric terms, but do match their negation
+        assert_eq!(
+            search(&db, "-width>2000"),
+            vec!["small.png", "unhashed.png"]
+        );
+    }
+
+    #[test]
+    fn query_files_ai_model_and_prompt() {
+        let db = test_db();
+        insert_file(&db, 1, "/r/1.png", "/r", "1.png");
+        insert_file(&db, 2, "/r/2.png", "/r", "2.png");
+        db.meta_set_pnginfo(1, "a red car at night\n\nmodel: flux1-dev.safetensors");
+        db.meta_set_pnginfo(2, "a blue car\n\nmodel: sdxl_base");
+
+        assert_eq!(search(&db, "model:flux"), vec!["1.png"]);
+        assert_eq!(search(&db, r#"prompt:"red car""#), vec!["1.png"]);
+        assert_eq!(search(&db, "prompt:car"), vec!["1.png", "2.png"]);
+        // "flux" only appears in the model line, "red" only in the prompt
+        assert!(search(&db, "model:red").is_empty());
+    }
+
+    #[test]
+    fn query_files_dir_includes_subdirs() {
+        let db = test_db();
+        insert_file(&db, 1, "/renders/a.png", "/renders", "a.png");
+        insert_file(&db, 2, "/renders/x/b.png", "/renders/x", "b.png");
+        insert_file(&db, 3, "/renders2/c.png", "/renders2", "c.png");
+        assert_eq!(search(&db, "dir:/renders"), vec!["a.png", "b.png"]);
+        assert_eq!(search(&db, "dir:/renders/x/"), vec!["b.png"]);
+    }
+
+    #[test]
+    fn query_files_modified() {
+        let db = test_db();
+        db.file_insert(
+            "/a/old.jpg",
+            "/a",
+            "old.jpg",
+            None,
+            Some("2025-06-01T10:00:00Z"),
+        );
+        db.file_insert(
+            "/a/new.jpg",
+            "/a",
+            "new.jpg",
+            None,
+            Some("2026-02-01T10:00:00Z"),
+        );
+        db.file_insert(
+            "/a/day.jpg",
+            "/a",
+            "day.jpg",
+            None,
+            Some("2026-01-01T23:00:00Z"),
+        );
+
+        assert_eq!(search(&db, "modified>2026-01-01"), vec!["new.jpg"]);
+        assert_eq!(
+            search(&db, "modified>=2026-01-01"),
+            vec!["day.jpg", "new.jpg"]
+        );
+        assert_eq!(
+            search(&db, "modified<=2026-01-01"),
+            vec!["day.jpg", "old.jpg"]
+        );
+        assert_eq!(search(&db, "modified:2026-01-01"), vec!["day.jpg"]);
+    }
+
+    #[test]
+    fn query_files_name_is_literal() {
+        let db = test_db();
+        insert_file(&db, 1, "/a/100%.jpg", "/a", "100%.jpg");
+        insert_file(&db, 2, "/a/1000.jpg", "/a", "1000.jpg");
+        insert_file(&db, 3, "/a/a_b.jpg", "/a", "a_b.jpg");
+        insert_file(&db, 4, "/a/axb.jpg", "/a", "axb.jpg");
+        assert_eq!(search(&db, "100%"), vec!["100%.jpg"]);
+        assert_eq!(search(&db, "a_b"), vec!["a_b.jpg"]);
+    }
+
     // ── open_path creates parent directories ──────────────────────────
 
     #[test]
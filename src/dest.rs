@@ -0,0 +1,193 @@
+//! Destination slots: Shift+1-9 send the current file or selection to a
+//! bookmarked directory (`lv dest N DIR [--copy]`).
+//!
+//! A move renames the file and updates its `files` row in place, so the id,
+//! and with it tags, likes and history, stays the same. A copy is added as a
+//! new row linked to the original's hash, so it shares the original's tags.
+//! Names already taken in the destination get a number (`a.2.jpg`). Each
+//! send is one undo step.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::db::{Db, DestSlot, FileEntry, Rename};
+use crate::undo::Change;
+
+/// `dir/filename`, or the first `dir/name.N.ext` that doesn't exist and
+/// isn't `taken` by an earlier file of the batch.
+fn free_path(dir: &Path, filename: &str, taken: &HashSet<PathBuf>) -> PathBuf {
+    let mut to = dir.join(filename);
+    let mut n = 2;
+    while to.exists() || taken.contains(&to) {
+        to = dir.join(crate::trash::numbered(filename, n));
+        n += 1;
+    }
+    to
+}
+
+/// Move or copy `targets` into `slot`'s directory (created if needed) as
+/// one undoable step. Files already in it are left alone. Moves are all or
+/// none; copies stop at the first failure. Returns a summary for the
+/// status bar.
+pub fn send(db: &Db, slot: &DestSlot, targets: &[FileEntry]) -> Result<String, String> {
+    if targets.is_empty() {
+        return Err("nothing to send".into());
+    }
+    let dest = crate::selection::expand_home(&slot.path);
+    std::fs::create_dir_all(&dest).map_err(|e| format!("{}: {}", dest.display(), e))?;
+    let dest = std::fs::canonicalize(&dest).map_err(|e| format!("{}: {}", dest.display(), e))?;
+    let dir = crate::clean_path(&dest.to_string_lossy());
+
+    let (mut renamed, mut skipped) = (0, 0);
+    let mut taken = HashSet::new();
+    let mut moves = Vec::new();
+    for f in targets {
+        if f.dir == dir {
+            skipped += 1;
+            continue;
+        }
+        let to = free_path(&dest, &f.filename, &taken);
+        if to.file_name().is_some_and(|n| *n != *f.filename) {
+            renamed += 1;
+        }
+        moves.push(Rename {
+            file_id: f.id,
+            from: f.path.clone(),
+            to: crate::clean_path(&to.to_string_lossy()),
+        });
+        taken.insert(to);
+    }
+
+    let name = dest.file_name().unwrap_or_default().to_string_lossy();
+    let (verb, change) = if slot.copy {
+        let files = moves.into_iter().map(|m| (m.from, m.to)).collect();
+        ("copied", Change::Copy { files })
+    } else {
+        ("moved", Change::Relocate { files: moves })
+    };
+    let label = format!("{} ×{} → {}", verb, change.len(), name);
+    let sent = crate::undo::run(db, &label, change)?;
+
+    let mut msg = format!("{} {} to {}", sent, verb, dest.display());
+    if renamed > 0 {
+        msg.push_str(&format!(" ({} renamed)", renamed));
+    }
+    if skipped > 0 {
+        msg.push_str(&format!(" ({} already there)", skipped));
+    }
+    Ok(msg)
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::SortOrder;
+
+    fn slot(path: &Path, copy: bool) -> DestSlot {
+        DestSlot {
+            path: path.to_string_lossy().to_string(),
+            copy,
+        }
+    }
+
+    #[test]
+    fn move_updates_row_in_place() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg"], Some("t"));
+        let out = tmp.path().join("keep");
+        let msg = send(&db, &slot(&out, false), &files).unwrap();
+        assert!(msg.starts_with("2 moved"), "{}", msg);
+
+        let out = std::fs::canonicalize(&out).unwrap();
+        let moved = db.files_by_dir(&out.to_string_lossy(), SortOrder::Path);
+        assert_eq!(
+            moved.iter().map(|f| f.id).collect::<Vec<_>>(),
+            files.iter().map(|f| f.id).collect::<Vec<_>>()
+        );
+        assert_eq!(moved[0].path, out.join("a.jpg").to_string_lossy());
+        assert!(!Path::new(&files[0].path).exists());
+        assert_eq!(std::fs::read(out.join("a.jpg")).unwrap(), b"a.jpg");
+        assert!(db.files_by_dir(&files[0].dir, SortOrder::Path).is_empty());
+        assert_eq!(db.file_tags(files[0].id), vec!["t".to_string()]);
+
+        // Sending them again is a no-op
+        let msg = send(&db, &slot(&out, false), &moved).unwrap();
+        assert!(msg.contains("2 already there"), "{}", msg);
+
+        // One undo puts both back
+        let step = crate::undo::undo(&db).unwrap();
+        assert_eq!(step.label, "moved ×2 → keep");
+        assert_eq!(db.files_by_dir(&files[0].dir, SortOrder::Path).len(), 2);
+        assert!(Path::new(&files[1].path).exists());
+    }
+
+    #[test]
+    fn taken_names_are_numbered() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg"], Some("t"));
+        let out = tmp.path().join("keep");
+        std::fs::create_dir(&out).unwrap();
+        std::fs::write(out.join("a.jpg"), "other").unwrap();
+        let msg = send(&db, &slot(&out, false), &files).unwrap();
+        assert!(msg.contains("1 renamed"), "{}", msg);
+        assert_eq!(std::fs::read(out.join("a.jpg")).unwrap(), b"other");
+        assert_eq!(std::fs::read(out.join("a.2.jpg")).unwrap(), b"a.jpg");
+        let moved = &db.files_by_ids(&[files[0].id])[0];
+        assert_eq!(moved.filename, "a.2.jpg");
+
+        // Same name from two dirs in one batch
+        let other = tmp.path().join("other");
+        std::fs::create_dir(&other).unwrap();
+        std::fs::write(other.join("b.jpg"), "1").unwrap();
+        std::fs::write(tmp.path().join("b.jpg"), "2").unwrap();
+        for dir in [other.as_path(), tmp.path()] {
+            let dir = std::fs::canonicalize(dir).unwrap();
+            let d = dir.to_string_lossy();
+            db.file_insert(
+                &dir.join("b.jpg").to_string_lossy(),
+                &d,
+                "b.jpg",
+                None,
+                None,
+            );
+        }
+        let twins: Vec<FileEntry> = db
+            .files_by_ids(&(1..=10).collect::<Vec<_>>())
+            .into_iter()
+            .filter(|f| f.filename == "b.jpg")
+            .collect();
+        let msg = send(&db, &slot(&out, false), &twins).unwrap();
+        assert!(msg.starts_with("2 moved"), "{}", msg);
+        assert!(out.join("b.jpg").exists() && out.join("b.2.jpg").exists());
+    }
+
+    #[test]
+    fn copy_adds_row_sharing_tags() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg"], Some("t"));
+        let out = tmp.path().join("backup");
+        let msg = send(&db, &slot(&out, true), &files).unwrap();
+        assert!(msg.starts_with("1 copied"), "{}", msg);
+
+        // Original untouched
+        assert!(Path::new(&files[0].path).exists());
+        assert_eq!(db.files_by_dir(&files[0].dir, SortOrder::Path).len(), 1);
+
+        let out = std::fs::canonicalize(&out).unwrap();
+        let copies = db.files_by_dir(&out.to_string_lossy(), SortOrder::Path);
+        assert_eq!(copies.len(), 1);
+        assert_ne!(copies[0].id, files[0].id);
+        assert_eq!(db.file_hash(copies[0].id).as_deref(), Some("hash_a.jpg"));
+        assert_eq!(db.file_tags(copies[0].id), vec!["t".to_string()]);
+    }
+
+    #[test]
+    fn missing_source_stops_and_reports() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg"], Some("t"));
+        std::fs::remove_file(&files[0].path).unwrap();
+        let err = send(&db, &slot(&tmp.path().join("keep"), false), &files).unwrap_err();
+        assert!(err.starts_with("a.jpg: "), "{}", err);
+        // Row left where it was
+        assert_eq!(db.files_by_ids(&[files[0].id])[0].path, files[0].path);
+        assert!(send(&db, &slot(tmp.path(), false), &[]).is_err());
+    }
+}
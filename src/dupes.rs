@@ -0,0 +1,354 @@
+//! Duplicate finder: files whose content hash shares a `meta` row.
+//!
+//! Files up to 2 MB are hashed in full, so their groups are exact. Larger
+//! files only carry an `fp:` fingerprint (head + tail + size), so every
+//! member of an `fp:` group is hashed in full before the group is shown.
+//! The full hash is cached in `files.full_hash`.
+
+use std::collections::VecDeque;
+use std::path::Path;
+
+use crate::db::{Db, DupeFile, FileEntry};
+
+/// Files confirmed to have identical bytes.
+pub struct DupeGroup {
+    pub hash: String,
+    pub size: i64,
+    pub files: Vec<FileEntry>,
+}
+
+impl DupeGroup {
+    /// Bytes freed by keeping a single copy.
+    pub fn reclaimable(&self) -> i64 {
+        self.size * (self.files.len() as i64 - 1)
+    }
+}
+
+/// Unconfirmed candidate groups, biggest savings first.
+pub fn candidates(db: &Db) -> Vec<(String, Vec<DupeFile>)> {
+    let mut groups = db.dupe_candidates();
+    groups.sort_by_key(|(_, files)| {
+        let size = files.iter().filter_map(|f| f.size).max().unwrap_or(0);
+        std::cmp::Reverse(size * (files.len() as i64 - 1))
+    });
+    groups
+}
+
+/// Check a candidate group against the disk. Files that are gone are
+/// dropped; `fp:` members are split by full hash, computing and caching any
+/// that are missing. Returns the groups still holding two or more files.
+pub fn confirm(db: &Db, hash: String, files: Vec<DupeFile>) -> Vec<DupeGroup> {
+    let fingerprinted = hash.starts_with("fp:");
+    let mut groups: Vec<DupeGroup> = Vec::new();
+    for f in files {
+        let Ok(md) = std::fs::metadata(&f.entry.path) else {
+            continue;
+        };
+        let key = if !fingerprinted {
+            hash.clone()
+        } else if let Some(h) = f.full_hash {
+            h
+        } else {
+            match crate::jobs::full_sha512(&f.entry.path) {
+                Ok(h) => {
+                    db.file_set_full_hash(f.entry.id, &h);
+                    h
+                }
+                Err(e) => {
+                    eprintln!("dupes: {}: {}", f.entry.path, e);
+                    continue;
+                }
+            }
+        };
+        match groups.iter_mut().find(|g| g.hash == key) {
+            Some(g) => g.files.push(f.entry),
+            None => groups.push(DupeGroup {
+                hash: key,
+                size: md.len() as i64,
+                files: vec![f.entry],
+            }),
+        }
+    }
+    groups.retain(|g| g.files.len() > 1);
+    groups
+}
+
+/// Every confirmed duplicate group in the library, biggest savings first.
+pub fn find_all(db: &Db) -> Vec<DupeGroup> {
+    let mut groups: Vec<DupeGroup> = candidates(db)
+        .into_iter()
+        .flat_map(|(hash, files)| confirm(db, hash, files))
+        .collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.reclaimable()));
+    groups
+}
+
+/// Keep `keep_id` and move every other copy in `group` to the trash, removing
+/// it from the library, as one undo step. Returns how many files were
+/// trashed; stops at the first file that can't be trashed.
+pub fn keep_only(db: &Db, group: &DupeGroup, keep_id: i64) -> Result<usize, String> {
+    let keep = group
+        .files
+        .iter()
+        .find(|f| f.id == keep_id)
+        .ok_or("file is not in this group")?;
+    if !Path::new(&keep.path).exists() {
+        return Err(format!("{} is missing", keep.filename));
+    }
+    let paths: Vec<String> = group
+        .files
+        .iter()
+        .filter(|f| f.id != keep_id)
+        .map(|f| f.path.clone())
+        .collect();
+    let label = format!("keep {}, trash ×{}", keep.filename, paths.len());
+    crate::undo::run(db, &label, crate::undo::Change::Trash { paths })
+}
+
+// ── Viewer walk ─────────────────────────────────────────────────────────
+
+/// Steps through duplicate groups one at a time for the viewer. Candidates
+/// are confirmed as they are reached, so `fp:` files are only hashed in full
+/// when their group comes up.
+pub struct DupeWalker {
+    pending: VecDeque<(String, Vec<DupeFile>)>,
+    groups: Vec<DupeGroup>,
+    index: usize,
+}
+
+impl DupeWalker {
+    /// Start at the first confirmed group; `None` if there are no duplicates.
+    pub fn new(db: &Db) -> Option<Self> {
+        let mut walker = DupeWalker {
+            pending: candidates(db).into(),
+            groups: Vec::new(),
+            index: 0,
+        };
+        walker.fill(db, 0).then_some(walker)
+    }
+
+    /// Confirm candidates until `groups[index]` exists.
+    fn fill(&mut self, db: &Db, index: usize) -> bool {
+        while self.groups.len() <= index {
+            let Some((hash, files)) = self.pending.pop_front() else {
+                return false;
+            };
+            self.groups.extend(confirm(db, hash, files));
+        }
+        true
+    }
+
+    pub fn current(&self) -> &DupeGroup {
+        &self.groups[self.index]
+    }
+
+    pub fn next(&mut self, db: &Db) -> bool {
+        if self.fill(db, self.index + 1) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn prev(&mut self) -> bool {
+        if self.index > 0 {
+            self.index -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 1-based position and the group count so far; unconfirmed candidates
+    /// count as one group each, so the total can shrink while stepping.
+    pub fn position(&self) -> (usize, usize) {
+        (self.index + 1, self.groups.len() + self.pending.len())
+    }
+
+    /// Drop the current group (it has been dealt with) and move on to the
+    /// next one, or back to the previous at the end. `false` when none are left.
+    pub fn resolve(&mut self, db: &Db) -> bool {
+        self.groups.remove(self.index);
+        if self.fill(db, self.index) {
+            true
+        } else if self.index > 0 {
+            self.index -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Forget files of the current group that left the library (watcher or
+    /// refresh); resolves the group once fewer than two remain.
+    pub fn prune(&mut self, db: &Db) -> bool {
+        let group = &mut self.groups[self.index];
+        group.files.retain(|f| db.file_lookup(&f.path).is_some());
+        if group.files.len() < 2 {
+            self.resolve(db)
+        } else {
+            true
+        }
+    }
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Db {
+        let db = Db::open_memory();
+        db.ensure_schema();
+        db.ensure_jobs_schema();
+        db
+    }
+
+    /// Write `content` to `dir/name` and register it with `hash`.
+    fn add(db: &Db, dir: &Path, name: &str, content: &[u8], hash: &str) -> i64 {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let dir = dir.to_string_lossy().to_string();
+        let id = db
+            .file_insert(&path, &dir, name, Some(content.len() as i64), None)
+            .unwrap();
+        db.file_set_hash_meta(id, hash);
+        id
+    }
+
+    #[test]
+    fn exact_group_reports_reclaimable() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        add(&db, tmp.path(), "a.jpg", b"same", "h1");
+        add(&db, tmp.path(), "b.jpg", b"same", "h1");
+        add(&db, tmp.path(), "c.jpg", b"same", "h1");
+        add(&db, tmp.path(), "solo.jpg", b"other", "h2");
+
+        let groups = find_all(&db);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].files.len(), 3);
+        assert_eq!(groups[0].size, 4);
+        assert_eq!(groups[0].reclaimable(), 8);
+    }
+
+    #[test]
+    fn fingerprint_group_split_by_full_hash() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        let a = add(&db, tmp.path(), "a.mp4", b"head-AAA-tail", "fp:x");
+        let b = add(&db, tmp.path(), "b.mp4", b"head-AAA-tail", "fp:x");
+        add(&db, tmp.path(), "c.mp4", b"head-BBB-tail", "fp:x");
+
+        let groups = find_all(&db);
+        assert_eq!(groups.len(), 1, "c.mp4 only collides on the fingerprint");
+        let ids: Vec<i64> = groups[0].files.iter().map(|f| f.id).collect();
+        assert_eq!(ids, vec![a, b]);
+        assert_eq!(
+            groups[0].hash,
+            crate::jobs::full_sha512(&groups[0].files[0].path).unwrap()
+        );
+
+        // Full hashes are cached for the next run
+        let cached = db.dupe_candidates();
+        assert!(cached[0].1.iter().all(|f| f.full_hash.is_some()));
+    }
+
+    #[test]
+    fn fingerprint_collision_only_is_not_a_dupe() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        add(&db, tmp.path(), "a.mp4", b"one", "fp:x");
+        add(&db, tmp.path(), "b.mp4", b"two", "fp:x");
+        assert!(find_all(&db).is_empty());
+        assert!(DupeWalker::new(&db).is_none());
+    }
+
+    #[test]
+    fn missing_files_are_dropped() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        add(&db, tmp.path(), "a.jpg", b"same", "h1");
+        add(&db, tmp.path(), "b.jpg", b"same", "h1");
+        std::fs::remove_file(tmp.path().join("b.jpg")).unwrap();
+        assert!(find_all(&db).is_empty());
+    }
+
+    #[test]
+    fn groups_sorted_by_reclaimable() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        add(&db, tmp.path(), "s1.jpg", b"s", "small");
+        add(&db, tmp.path(), "s2.jpg", b"s", "small");
+        add(&db, tmp.path(), "b1.jpg", b"bigger", "big");
+        add(&db, tmp.path(), "b2.jpg", b"bigger", "big");
+
+        let groups = find_all(&db);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].hash, "big");
+        assert_eq!(groups[1].hash, "small");
+    }
+
+    #[test]
+    fn walker_steps_and_resolves() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        add(&db, tmp.path(), "b1.jpg", b"bigger", "big");
+        add(&db, tmp.path(), "b2.jpg", b"bigger", "big");
+        add(&db, tmp.path(), "s1.jpg", b"s", "small");
+        add(&db, tmp.path(), "s2.jpg", b"s", "small");
+
+        let mut w = DupeWalker::new(&db).unwrap();
+        assert_eq!(w.position(), (1, 2));
+        assert_eq!(w.current().hash, "big");
+        assert!(!w.prev());
+        assert!(w.next(&db));
+        assert_eq!(w.current().hash, "small");
+        assert!(!w.next(&db));
+
+        // Resolving the last group steps back to the previous one
+        assert!(w.resolve(&db));
+        assert_eq!(w.current().hash, "big");
+        assert_eq!(w.position(), (1, 1));
+        assert!(!w.resolve(&db));
+    }
+
+    #[test]
+    fn walker_prune_drops_removed_files() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        add(&db, tmp.path(), "a.jpg", b"same", "h1");
+        add(&db, tmp.path(), "b.jpg", b"same", "h1");
+        add(&db, tmp.path(), "c.jpg", b"same", "h1");
+
+        let mut w = DupeWalker::new(&db).unwrap();
+        let b = w.current().files[1].path.clone();
+        db.remove_file_by_path(&b);
+        assert!(w.prune(&db));
+        assert_eq!(w.current().files.len(), 2);
+
+        let a = w.current().files[0].path.clone();
+        db.remove_file_by_path(&a);
+        assert!(!w.prune(&db), "one copy left is no longer a group");
+    }
+
+    #[test]
+    fn keep_only_rejects_foreign_or_missing_keeper() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        add(&db, tmp.path(), "a.jpg", b"same", "h1");
+        add(&db, tmp.path(), "b.jpg", b"same", "h1");
+        let group = find_all(&db).remove(0);
+
+        assert!(keep_only(&db, &group, -1).is_err());
+        std::fs::remove_file(&group.files[0].path).unwrap();
+        assert!(keep_only(&db, &group, group.files[0].id).is_err());
+        assert!(
+            Path::new(&group.files[1].path).exists(),
+            "nothing trashed when the keeper is gone"
+        );
+    }
+}
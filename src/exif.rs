@@ -0,0 +1,858 @@
+//! EXIF / XMP extraction from JPEG, TIFF, PNG, WebP and HEIC files.
+//!
+//! Finds the TIFF-structured EXIF block in each container, walks IFD0, the
+//! Exif sub-IFD and the GPS IFD, and keeps the fields the info panel shows.
+//! An XMP packet, if present, fills in whatever EXIF left out.
+
+use serde_json::{json, Map, Value};
+
+/// Camera metadata kept in `meta.exif_json`. Missing fields stay `None` and
+/// are left out of the JSON.
+#[derive(Default, Debug, PartialEq)]
+pub struct Exif {
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub lens: Option<String>,
+    pub software: Option<String>,
+    /// Seconds.
+    pub exposure_time: Option<f64>,
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    /// Millimetres.
+    pub focal_length: Option<f64>,
+    pub focal_length_35mm: Option<u32>,
+    /// `YYYY-MM-DD hh:mm:ss`, camera local time.
+    pub taken_at: Option<String>,
+    /// EXIF orientation, 1-8 (1 = as stored).
+    pub orientation: Option<u16>,
+    /// Decimal degrees, south/west negative.
+    pub gps_lat: Option<f64>,
+    pub gps_lon: Option<f64>,
+    /// Metres above sea level.
+    pub gps_alt: Option<f64>,
+    /// Pixel size, when the container states it (HEIC `ispe`, EXIF tags).
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl Exif {
+    pub fn to_json(&self) -> String {
+        let mut m = Map::new();
+        let mut put = |k: &str, v: Option<Value>| {
+            if let Some(v) = v {
+                m.insert(k.into(), v);
+            }
+        };
+        put("make", self.make.as_ref().map(|v| json!(v)));
+        put("model", self.model.as_ref().map(|v| json!(v)));
+        put("lens", self.lens.as_ref().map(|v| json!(v)));
+        put("software", self.software.as_ref().map(|v| json!(v)));
+        put("exposure_time", self.exposure_time.map(|v| json!(v)));
+        put("f_number", self.f_number.map(|v| json!(v)));
+        put("iso", self.iso.map(|v| json!(v)));
+        put("focal_length", self.focal_length.map(|v| json!(v)));
+        put(
+            "focal_length_35mm",
+            self.focal_length_35mm.map(|v| json!(v)),
+        );
+        put("taken_at", self.taken_at.as_ref().map(|v| json!(v)));
+        put("orientation", self.orientation.map(|v| json!(v)));
+        if let (Some(lat), Some(lon)) = (self.gps_lat, self.gps_lon) {
+            let mut gps = json!({ "lat": lat, "lon": lon });
+            if let Some(alt) = self.gps_alt {
+                gps["alt"] = json!(alt);
+            }
+            put("gps", Some(gps));
+        }
+        Value::Object(m).to_string()
+    }
+}
+
+/// Read camera metadata from an image file. Files without any EXIF or XMP
+/// give an empty `Exif`, not an error.
+pub fn extract(path: &str) -> Result<Exif, String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    Ok(parse(&data))
+}
+
+/// Parse an in-memory image file of any supported container.
+pub fn parse(data: &[u8]) -> Exif {
+    let mut out = Exif::default();
+    let tiff = if data.starts_with(b"\xFF\xD8") {
+        jpeg_exif(data)
+    } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        Some(data)
+    } else if data.starts_with(b"\x89PNG") {
+        png_exif(data)
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        webp_exif(data)
+    } else if data.len() >= 12 && &data[4..8] == b"ftyp" {
+        heic_exif(data, &mut out)
+    } else {
+        None
+    };
+    if let Some(tiff) = tiff {
+        parse_tiff(tiff, &mut out);
+    }
+    if let Some(xmp) = find_xmp(data) {
+        parse_xmp(xmp, &mut out);
+    }
+    out
+}
+
+// ── Containers ──────────────────────────────────────────────────────────
+
+/// APP1 segment starting with `Exif\0\0`.
+fn jpeg_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+        // Start of scan / end of image: no more metadata segments
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let body = data.get(pos + 4..pos + 2 + len)?;
+        if marker == 0xE1 && body.starts_with(b"Exif\0\0") {
+            return Some(&body[6..]);
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+/// `eXIf` chunk (raw TIFF).
+fn png_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut pos = 8;
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes(data[pos..pos + 4].try_into().ok()?) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let body = data.get(pos + 8..pos + 8 + len)?;
+        match kind {
+            b"eXIf" => return Some(body),
+            b"IDAT" | b"IEND" => return None,
+            _ => {}
+        }
+        pos += 12 + len;
+    }
+    None
+}
+
+/// RIFF `EXIF` chunk; some writers keep the JPEG-style `Exif\0\0` prefix.
+fn webp_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let len = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().ok()?) as usize;
+        let body = data.get(pos + 8..pos + 8 + len)?;
+        if &data[pos..pos + 4] == b"EXIF" {
+            return Some(body.strip_prefix(b"Exif\0\0").unwrap_or(body));
+        }
+        pos += 8 + len + (len & 1);
+    }
+    None
+}
+
+/// ISO-BMFF box: (type, body).
+fn boxes(data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let (header, size) = match size {
+            0 => (8, data.len() - pos),
+            1 => match data.get(pos + 8..pos + 16) {
+                Some(b) => (16, u64::from_be_bytes(b.try_into().unwrap()) as usize),
+                None => break,
+            },
+            n => (8, n),
+        };
+        let Some(body) = data.get(pos + header..pos + size) else {
+            break;
+        };
+        out.push((kind, body));
+        pos += size;
+    }
+    out
+}
+
+fn child<'a>(body: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+    boxes(body)
+        .into_iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, b)| b)
+}
+
+/// Big-endian unsigned integer of `n` bytes (0, 2, 4 or 8) at `*pos`.
+fn read_be(data: &[u8], pos: &mut usize, n: usize) -> Option<u64> {
+    let bytes = data.get(*pos..*pos + n)?;
+    *pos += n;
+    Some(bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64))
+}
+
+/// HEIC/HEIF: the `Exif` item located through `meta/iinf` + `meta/iloc`.
+/// Also records the image size from the largest `ispe` property.
+fn heic_exif<'a>(data: &'a [u8], out: &mut Exif) -> Option<&'a [u8]> {
+    let meta = child(data, b"meta")?.get(4..)?;
+
+    if let Some(ipco) = child(meta, b"iprp").and_then(|p| child(p, b"ipco")) {
+        for (kind, body) in boxes(ipco) {
+            if kind == b"ispe" && body.len() >= 12 {
+                let w = u32::from_be_bytes(body[4..8].try_into().unwrap());
+                let h = u32::from_be_bytes(body[8..12].try_into().unwrap());
+                if w as u64 * h as u64
+                    > out.width.unwrap_or(0) as u64 * out.height.unwrap_or(0) as u64
+                {
+                    out.width = Some(w);
+                    out.height = Some(h);
+                }
+            }
+        }
+    }
+
+    // iinf → item id of type "Exif"
+    let iinf = child(meta, b"iinf")?;
+    let skip = if iinf.first() == Some(&0) { 6 } else { 8 };
+    let exif_id = boxes(iinf.get(skip..)?).into_iter().find_map(|(kind, b)| {
+        if kind != b"infe" || b.len() < 4 {
+            return None;
+        }
+        let mut pos = 4;
+        let id = match b[0] {
+            2 => read_be(b, &mut pos, 2)?,
+            3 => read_be(b, &mut pos, 4)?,
+            _ => return None,
+        };
+        pos += 2; // protection index
+        (b.get(pos..pos + 4)? == b"Exif").then_some(id)
+    })?;
+
+    // iloc → first extent of that item
+    let iloc = child(meta, b"iloc")?;
+    let version = *iloc.first()?;
+    let (off_size, len_size) = ((iloc.get(4)? >> 4) as usize, (iloc[4] & 0xF) as usize);
+    let (base_size, index_size) = ((iloc.get(5)? >> 4) as usize, (iloc[5] & 0xF) as usize);
+    let mut pos = 6;
+    let count = read_be(iloc, &mut pos, if version < 2 { 2 } else { 4 })?;
+    for _ in 0..count {
+        let id = read_be(iloc, &mut pos, if version < 2 { 2 } else { 4 })?;
+        if version >= 1 {
+            pos += 2; // construction method
+        }
+        pos += 2; // data reference index
+        let base = read_be(iloc, &mut pos, base_size)?;
+        let extents = read_be(iloc, &mut pos, 2)?;
+        let mut first = None;
+        for _ in 0..extents {
+            if version >= 1 && index_size > 0 {
+                pos += index_size;
+            }
+            let off = read_be(iloc, &mut pos, off_size)?;
+            let len = read_be(iloc, &mut pos, len_size)?;
+            first.get_or_insert((base + off, len));
+        }
+        if id == exif_id {
+            let (off, len) = first?;
+            let item = data.get(off as usize..(off + len) as usize)?;
+            // Item starts with the offset of the TIFF header within it
+            let tiff_off = u32::from_be_bytes(item.get(..4)?.try_into().ok()?) as usize;
+            return item.get(4 + tiff_off..);
+        }
+    }
+    None
+}
+
+// ── TIFF / IFD ──────────────────────────────────────────────────────────
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    le: bool,
+}
+
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    /// Offset of the value: inline in the entry or pointed to.
+    at: usize,
+}
+
+impl<'a> Tiff<'a> {
+    fn u16(&self, at: usize) -> Option<u16> {
+        let b: [u8; 2] = self.data.get(at..at + 2)?.try_into().ok()?;
+        Some(if self.le {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let b: [u8; 4] = self.data.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.le {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    fn entries(&self, offset: usize) -> Vec<Entry> {
+        let Some(n) = self.u16(offset) else {
+            return Vec::new();
+        };
+        (0..n as usize)
+            .filter_map(|i| {
+                let e = offset + 2 + i * 12;
+                let kind = self.u16(e + 2)?;
+                let count = self.u32(e + 4)?;
+                let unit = match kind {
+                    1 | 2 | 6 | 7 => 1,
+                    3 | 8 => 2,
+                    4 | 9 => 4,
+                    5 | 10 => 8,
+                    _ => return None,
+                };
+                let at = if unit * count as usize <= 4 {
+                    e + 8
+                } else {
+                    self.u32(e + 8)? as usize
+                };
+                Some(Entry {
+                    tag: self.u16(e)?,
+                    kind,
+                    count,
+                    at,
+                })
+            })
+            .collect()
+    }
+
+    fn ascii(&self, e: &Entry) -> Option<String> {
+        let raw = self.data.get(e.at..e.at + e.count as usize)?;
+        let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+        let s = String::from_utf8_lossy(&raw[..end]).trim().to_string();
+        (!s.is_empty()).then_some(s)
+    }
+
+    /// Integer value `i` of a BYTE/SHORT/LONG entry.
+    fn uint(&self, e: &Entry, i: usize) -> Option<u32> {
+        match e.kind {
+            1 | 7 => self.data.get(e.at + i).map(|&b| b as u32),
+            3 => self.u16(e.at + i * 2).map(u32::from),
+            4 => self.u32(e.at + i * 4),
+            _ => None,
+        }
+    }
+
+    /// Rational value `i` of a RATIONAL/SRATIONAL entry.
+    fn rational(&self, e: &Entry, i: usize) -> Option<f64> {
+        let at = e.at + i * 8;
+        let (n, d) = (self.u32(at)?, self.u32(at + 4)?);
+        let (n, d) = if e.kind == 10 {
+            (n as i32 as f64, d as i32 as f64)
+        } else {
+            (n as f64, d as f64)
+        };
+        (d != 0.0).then_some(n / d)
+    }
+}
+
+fn parse_tiff(data: &[u8], out: &mut Exif) {
+    let le = match data.get(..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return,
+    };
+    let t = Tiff { data, le };
+    let Some(ifd0) = t.u32(4) else {
+        return;
+    };
+    let (mut exif_ifd, mut gps_ifd) = (None, None);
+    for e in t.entries(ifd0 as usize) {
+        match e.tag {
+            0x010F => out.make = t.ascii(&e),
+            0x0110 => out.model = t.ascii(&e),
+            0x0112 => out.orientation = t.uint(&e, 0).map(|v| v as u16),
+            0x0131 => out.software = t.ascii(&e),
+            0x0132 if out.taken_at.is_none() => out.taken_at = t.ascii(&e).map(exif_date),
+            0x8769 => exif_ifd = t.uint(&e, 0),
+            0x8825 => gps_ifd = t.uint(&e, 0),
+            _ => {}
+        }
+    }
+    if let Some(off) = exif_ifd {
+        for e in t.entries(off as usize) {
+            match e.tag {
+                0x829A => out.exposure_time = t.rational(&e, 0),
+                0x829D => out.f_number = t.rational(&e, 0),
+                0x8827 => out.iso = t.uint(&e, 0),
+                // DateTimeOriginal wins over IFD0 DateTime (last edit)
+                0x9003 => out.taken_at = t.ascii(&e).map(exif_date).or(out.taken_at.take()),
+                0x920A => out.focal_length = t.rational(&e, 0),
+                0xA002 => out.width = out.width.or(t.uint(&e, 0)),
+                0xA003 => out.height = out.height.or(t.uint(&e, 0)),
+                0xA405 => out.focal_length_35mm = t.uint(&e, 0).filter(|&v| v > 0),
+                0xA434 => out.lens = t.ascii(&e),
+                _ => {}
+            }
+        }
+    }
+    if let Some(off) = gps_ifd {
+        let (mut lat_ref, mut lon_ref, mut alt_ref) = (None, None, 0);
+        let (mut lat, mut lon, mut alt) = (None, None, None);
+        for e in t.entries(off as usize) {
+            match e.tag {
+                1 => lat_ref = t.ascii(&e),
+                2 => lat = dms(&t, &e),
+                3 => lon_ref = t.ascii(&e),
+                4 => lon = dms(&t, &e),
+                5 => alt_ref = t.uint(&e, 0).unwrap_or(0),
+                6 => alt = t.rational(&e, 0),
+                _ => {}
+            }
+        }
+        if let (Some(lat), Some(lon)) = (lat, lon) {
+            let sign =
+                |r: &Option<String>, neg: &str| if r.as_deref() == Some(neg) { -1.0 } else { 1.0 };
+            out.gps_lat = Some(lat * sign(&lat_ref, "S"));
+            out.gps_lon = Some(lon * sign(&lon_ref, "W"));
+            out.gps_alt = alt.map(|a| if alt_ref == 1 { -a } else { a });
+        }
+    }
+}
+
+/// Degrees/minutes/seconds rationals → decimal degrees.
+fn dms(t: &Tiff, e: &Entry) -> Option<f64> {
+    if e.count < 3 {
+        return None;
+    }
+    Some(t.rational(e, 0)? + t.rational(e, 1)? / 60.0 + t.rational(e, 2)? / 3600.0)
+}
+
+/// `2024:05:01 12:30:00` → `2024-05-01 12:30:00`.
+fn exif_date(s: String) -> String {
+    if s.len() >= 10 && s.as_bytes()[4] == b':' && s.as_bytes()[7] == b':' {
+        format!("{}-{}-{}", &s[..4], &s[5..7], &s[8..])
+    } else {
+        s
+    }
+}
+
+// ── XMP ─────────────────────────────────────────────────────────────────
+
+/// The XMP packet, found by its `x:xmpmeta` element wherever the container
+/// keeps it (uncompressed).
+fn find_xmp(data: &[u8]) -> Option<&str> {
+    let start = find(data, b"<x:xmpmeta")?;
+    let end = find(&data[start..], b"</x:xmpmeta>")? + start + b"</x:xmpmeta>".len();
+    std::str::from_utf8(&data[start..end]).ok()
+}
+
+fn find(hay: &[u8], needle: &[u8]) -> Option<usize> {
+    hay.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Fill fields EXIF didn't provide from XMP properties.
+fn parse_xmp(xml: &str, out: &mut Exif) {
+    let get = |names: &[&str]| names.iter().find_map(|n| xmp_value(xml, n));
+    if out.make.is_none() {
+        out.make = get(&["tiff:Make"]);
+    }
+    if out.model.is_none() {
+        out.model = get(&["tiff:Model"]);
+    }
+    if out.lens.is_none() {
+        out.lens = get(&["exifEX:LensModel", "aux:Lens"]);
+    }
+    if out.software.is_none() {
+        out.software = get(&["xmp:CreatorTool", "tiff:Software"]);
+    }
+    if out.exposure_time.is_none() {
+        out.exposure_time = get(&["exif:ExposureTime"]).and_then(|v| xmp_number(&v));
+    }
+    if out.f_number.is_none() {
+        out.f_number = get(&["exif:FNumber"]).and_then(|v| xmp_number(&v));
+    }
+    if out.iso.is_none() {
+        out.iso = get(&["exif:ISOSpeedRatings", "exifEX:PhotographicSensitivity"])
+            .and_then(|v| v.parse().ok());
+    }
+    if out.focal_length.is_none() {
+        out.focal_length = get(&["exif:FocalLength"]).and_then(|v| xmp_number(&v));
+    }
+    if out.taken_at.is_none() {
+        out.taken_at = get(&[
+            "exif:DateTimeOriginal",
+            "xmp:CreateDate",
+            "photoshop:DateCreated",
+        ])
+        .map(|v| xmp_date(&v));
+    }
+    if out.orientation.is_none() {
+        out.orientation = get(&["tiff:Orientation"]).and_then(|v| v.parse().ok());
+    }
+    if out.gps_lat.is_none() {
+        let lat = get(&["exif:GPSLatitude"]).and_then(|v| xmp_coord(&v));
+        let lon = get(&["exif:GPSLongitude"]).and_then(|v| xmp_coord(&v));
+        if let (Some(lat), Some(lon)) = (lat, lon) {
+            out.gps_lat = Some(lat);
+            out.gps_lon = Some(lon);
+        }
+    }
+}
+
+/// Value of an XMP property, written either as an attribute (`name="v"`) or
+/// as an element, taking the first `rdf:li` of a list.
+fn xmp_value(xml: &str, name: &str) -> Option<String> {
+    let attr = format!("{}=\"", name);
+    if let Some(i) = xml.find(&attr) {
+        let rest = &xml[i + attr.len()..];
+        return Some(rest[..rest.find('"')?].trim().to_string()).filter(|v| !v.is_empty());
+    }
+    let open = format!("<{}>", name);
+    let i = xml.find(&open)? + open.len();
+    let rest = &xml[i..];
+    let inner = &rest[..rest.find(&format!("</{}>", name))?];
+    let inner = match inner.find("<rdf:li") {
+        Some(li) => {
+            let body = &inner[li..];
+            let start = body.find('>')? + 1;
+            &body[start..body.find("</rdf:li>")?]
+        }
+        None => inner,
+    };
+    Some(inner.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// `28/10` or `2.8`.
+fn xmp_number(v: &str) -> Option<f64> {
+    match v.split_once('/') {
+        Some((n, d)) => {
+            let d: f64 = d.trim().parse().ok()?;
+            (d != 0.0).then_some(n.trim().parse::<f64>().ok()? / d)
+        }
+        None => v.trim().parse().ok(),
+    }
+}
+
+/// `51,30.5N` (degrees, decimal minutes, hemisphere) → decimal degrees.
+fn xmp_coord(v: &str) -> Option<f64> {
+    let v = v.trim();
+    let hemi = v.chars().last()?;
+    let sign = match hemi {
+        'N' | 'E' => 1.0,
+        'S' | 'W' => -1.0,
+        _ => return None,
+    };
+    let parts: Vec<f64> = v[..v.len() - 1]
+        .split(',')
+        .map(|p| p.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    let deg = match parts.as_slice() {
+        [d, m] => d + m / 60.0,
+        [d, m, s] => d + m / 60.0 + s / 3600.0,
+        _ => return None,
+    };
+    Some(sign * deg)
+}
+
+/// ISO 8601 (`2024-05-01T12:30:00+02:00`) → `2024-05-01 12:30:00`.
+fn xmp_date(v: &str) -> String {
+    let v = v.replacen('T', " ", 1);
+    v.get(..19).map(str::to_string).unwrap_or(v)
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// IFD entry for the test builder: tag, type, count, value bytes.
+    type E = (u16, u16, u32, Vec<u8>);
+
+    /// Build a TIFF block with IFD0, an Exif IFD and a GPS IFD. Entries with
+    /// tag 0x8769 / 0x8825 get the sub-IFD offsets filled in.
+    fn tiff(le: bool, ifds: [Vec<E>; 3]) -> Vec<u8> {
+        let w16 = |v: u16| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        let w32 = |v: u32| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        let ifd_size = |n: usize| 2 + 12 * n + 4;
+        let mut offsets = [8usize; 3];
+        for i in 1..3 {
+            offsets[i] = offsets[i - 1] + ifd_size(ifds[i - 1].len());
+        }
+        let mut extra = offsets[2] + ifd_size(ifds[2].len());
+        let mut out = Vec::new();
+        out.extend_from_slice(if le { b"II" } else { b"MM" });
+        out.extend_from_slice(&w16(42));
+        out.extend_from_slice(&w32(8));
+        let mut tail = Vec::new();
+        for ifd in &ifds {
+            out.extend_from_slice(&w16(ifd.len() as u16));
+            for (tag, kind, count, value) in ifd {
+                let value = match tag {
+                    0x8769 => w32(offsets[1] as u32).to_vec(),
+                    0x8825 => w32(offsets[2] as u32).to_vec(),
+                    _ => value.clone(),
+                };
+                out.extend_from_slice(&w16(*tag));
+                out.extend_from_slice(&w16(*kind));
+                out.extend_from_slice(&w32(*count));
+                if value.len() <= 4 {
+                    let mut v = value.clone();
+                    v.resize(4, 0);
+                    out.extend_from_slice(&v);
+                } else {
+                    out.extend_from_slice(&w32(extra as u32));
+                    extra += value.len();
+                    tail.extend_from_slice(&value);
+                }
+            }
+            out.extend_from_slice(&w32(0));
+        }
+        out.extend_from_slice(&tail);
+        out
+    }
+
+    fn camera_tiff(le: bool) -> Vec<u8> {
+        let w16 = |v: u16| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        let w32 = |v: u32| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        let ascii = |s: &str| {
+            let mut v = s.as_bytes().to_vec();
+            v.push(0);
+            v
+        };
+        let rat = |pairs: &[(u32, u32)]| {
+            let mut v = Vec::new();
+            for (n, d) in pairs {
+                v.extend_from_slice(&w32(*n));
+                v.extend_from_slice(&w32(*d));
+            }
+            v
+        };
+        let a = |tag: u16, s: &str| (tag, 2u16, s.len() as u32 + 1, ascii(s));
+        tiff(
+            le,
+            [
+                vec![
+                    a(0x010F, "Canon"),
+                    a(0x0110, "Canon EOS R5"),
+                    (0x0112, 3, 1, w16(6).to_vec()),
+                    a(0x0132, "2024:06:01 08:00:00"),
+                    (0x8769, 4, 1, vec![]),
+                    (0x8825, 4, 1, vec![]),
+                ],
+                vec![
+                    (0x829A, 5, 1, rat(&[(1, 125)])),
+                    (0x829D, 5, 1, rat(&[(28, 10)])),
+                    (0x8827, 3, 1, w16(400).to_vec()),
+                    a(0x9003, "2024:05:01 12:30:45"),
+                    (0x920A, 5, 1, rat(&[(50, 1)])),
+                    (0xA405, 3, 1, w16(75).to_vec()),
+                    a(0xA434, "RF50mm F1.8 STM"),
+                ],
+                vec![
+                    a(1, "N"),
+                    (2, 5, 3, rat(&[(51, 1), (30, 1), (36, 1)])),
+                    a(3, "W"),
+                    (4, 5, 3, rat(&[(0, 1), (7, 1), (39, 1)])),
+                    (5, 1, 1, vec![0]),
+                    (6, 5, 1, rat(&[(115, 10)])),
+                ],
+            ],
+        )
+    }
+
+    fn assert_camera(e: &Exif) {
+        assert_eq!(e.make.as_deref(), Some("Canon"));
+        assert_eq!(e.model.as_deref(), Some("Canon EOS R5"));
+        assert_eq!(e.lens.as_deref(), Some("RF50mm F1.8 STM"));
+        assert_eq!(e.exposure_time, Some(1.0 / 125.0));
+        assert_eq!(e.f_number, Some(2.8));
+        assert_eq!(e.iso, Some(400));
+        assert_eq!(e.focal_length, Some(50.0));
+        assert_eq!(e.focal_length_35mm, Some(75));
+        assert_eq!(e.taken_at.as_deref(), Some("2024-05-01 12:30:45"));
+        assert_eq!(e.orientation, Some(6));
+        assert!((e.gps_lat.unwrap() - 51.51).abs() < 1e-9);
+        assert!((e.gps_lon.unwrap() + 0.1275).abs() < 1e-9);
+        assert_eq!(e.gps_alt, Some(11.5));
+    }
+
+    #[test]
+    fn tiff_little_and_big_endian() {
+        assert_camera(&parse(&camera_tiff(true)));
+        assert_camera(&parse(&camera_tiff(false)));
+    }
+
+    #[test]
+    fn jpeg_app1() {
+        let exif = camera_tiff(false);
+        let mut jpeg = vec![0xFF, 0xD8];
+        // An APP0 segment before the EXIF one
+        jpeg.extend_from_slice(&[0xFF, 0xE0, 0x00, 0x06, b'J', b'F', b'I', b'F']);
+        jpeg.extend_from_slice(&[0xFF, 0xE1]);
+        jpeg.extend_from_slice(&((exif.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&exif);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9]);
+        assert_camera(&parse(&jpeg));
+    }
+
+    #[test]
+    fn webp_chunk() {
+        let exif = camera_tiff(true);
+        let mut riff = b"RIFF\0\0\0\0WEBP".to_vec();
+        riff.extend_from_slice(b"VP8X");
+        riff.extend_from_slice(&10u32.to_le_bytes());
+        riff.extend_from_slice(&[0; 10]);
+        riff.extend_from_slice(b"EXIF");
+        riff.extend_from_slice(&(exif.len() as u32).to_le_bytes());
+        riff.extend_from_slice(&exif);
+        assert_camera(&parse(&riff));
+    }
+
+    #[test]
+    fn png_exif_chunk() {
+        let exif = camera_tiff(false);
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend_from_slice(&(exif.len() as u32).to_be_bytes());
+        png.extend_from_slice(b"eXIf");
+        png.extend_from_slice(&exif);
+        png.extend_from_slice(&[0; 4]);
+        assert_camera(&parse(&png));
+    }
+
+    fn bmff(kind: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut b = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        b.extend_from_slice(kind);
+        b.extend_from_slice(body);
+        b
+    }
+
+    #[test]
+    fn heic_exif_item() {
+        let exif = camera_tiff(false);
+        let mut item = 0u32.to_be_bytes().to_vec();
+        item.extend_from_slice(&exif);
+
+        let infe = |id: u16, kind: &[u8]| {
+            let mut b = vec![2, 0, 0, 0];
+            b.extend_from_slice(&id.to_be_bytes());
+            b.extend_from_slice(&[0, 0]);
+            b.extend_from_slice(kind);
+            b.push(0);
+            bmff(b"infe", &b)
+        };
+        let mut iinf = vec![0, 0, 0, 0, 0, 2];
+        iinf.extend(infe(1, b"hvc1"));
+        iinf.extend(infe(7, b"Exif"));
+
+        let mut ispe = vec![0; 4];
+        ispe.extend_from_slice(&4032u32.to_be_bytes());
+        ispe.extend_from_slice(&3024u32.to_be_bytes());
+        let iprp = bmff(b"iprp", &bmff(b"ipco", &bmff(b"ispe", &ispe)));
+
+        // iloc v0, offset/length 4 bytes, no base offset; offset patched below
+        let iloc_body = |offset: u32| {
+            let mut b = vec![0, 0, 0, 0, 0x44, 0x00];
+            b.extend_from_slice(&1u16.to_be_bytes());
+            b.extend_from_slice(&7u16.to_be_bytes());
+            b.extend_from_slice(&0u16.to_be_bytes());
+            b.extend_from_slice(&1u16.to_be_bytes());
+            b.extend_from_slice(&offset.to_be_bytes());
+            b.extend_from_slice(&(item.len() as u32).to_be_bytes());
+            b
+        };
+        let file = |offset: u32| {
+            let mut meta = vec![0, 0, 0, 0];
+            meta.extend(bmff(b"iinf", &iinf));
+            meta.extend(iprp.clone());
+            meta.extend(bmff(b"iloc", &iloc_body(offset)));
+            let mut f = bmff(b"ftyp", b"heicmif1");
+            f.extend(bmff(b"meta", &meta));
+            f
+        };
+        let head = file(0).len() as u32 + 8;
+        let mut data = file(head);
+        data.extend(bmff(b"mdat", &item));
+
+        let e = parse(&data);
+        assert_camera(&e);
+        assert_eq!((e.width, e.height), (Some(4032), Some(3024)));
+    }
+
+    #[test]
+    fn xmp_fills_missing_fields() {
+        let xml = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF><rdf:Description
+            tiff:Make="FUJIFILM" tiff:Model="X-T5" exif:FNumber="56/10"
+            exif:DateTimeOriginal="2023-09-10T17:05:12+02:00"
+            exif:GPSLatitude="48,51.4N" exif:GPSLongitude="2,21.05E">
+            <exif:ISOSpeedRatings><rdf:Seq><rdf:li>800</rdf:li></rdf:Seq></exif:ISOSpeedRatings>
+            <aux:Lens>XF23mmF1.4 R</aux:Lens>
+            </rdf:Description></rdf:RDF></x:xmpmeta>"#;
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        let body = [b"http://ns.adobe.com/xap/1.0/\0".as_slice(), xml.as_bytes()].concat();
+        jpeg.extend_from_slice(&((body.len() + 2) as u16).to_be_bytes());
+        jpeg.extend_from_slice(&body);
+
+        let e = parse(&jpeg);
+        assert_eq!(e.make.as_deref(), Some("FUJIFILM"));
+        assert_eq!(e.model.as_deref(), Some("X-T5"));
+        assert_eq!(e.lens.as_deref(), Some("XF23mmF1.4 R"));
+        assert_eq!(e.f_number, Some(5.6));
+        assert_eq!(e.iso, Some(800));
+        assert_eq!(e.taken_at.as_deref(), Some("2023-09-10 17:05:12"));
+        assert!((e.gps_lat.unwrap() - (48.0 + 51.4 / 60.0)).abs() < 1e-9);
+        assert!((e.gps_lon.unwrap() - (2.0 + 21.05 / 60.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn exif_wins_over_xmp() {
+        let mut data = camera_tiff(true);
+        data.extend_from_slice(br#"<x:xmpmeta tiff:Model="Other"></x:xmpmeta>"#);
+        assert_eq!(parse(&data).model.as_deref(), Some("Canon EOS R5"));
+    }
+
+    #[test]
+    fn no_metadata_is_empty() {
+        assert_eq!(parse(b"\xFF\xD8\xFF\xD9"), Exif::default());
+        assert_eq!(parse(b"not an image"), Exif::default());
+        assert_eq!(Exif::default().to_json(), "{}");
+    }
+
+    #[test]
+    fn truncated_data_does_not_panic() {
+        let full = camera_tiff(true);
+        for len in 0..full.len() {
+            parse(&full[..len]);
+        }
+    }
+
+    #[test]
+    fn json_keys() {
+        let v: Value = serde_json::from_str(&parse(&camera_tiff(true)).to_json()).unwrap();
+        assert_eq!(v["model"], "Canon EOS R5");
+        assert_eq!(v["iso"], 400);
+        assert_eq!(v["orientation"], 6);
+        assert_eq!(v["gps"]["alt"], 11.5);
+        assert!(v.get("software").is_none());
+    }
+
+    #[test]
+    fn xmp_helpers() {
+        assert_eq!(xmp_number("1/250"), Some(0.004));
+        assert_eq!(xmp_number("2.8"), Some(2.8));
+        assert_eq!(xmp_number("1/0"), None);
+        assert_eq!(xmp_coord("10,30S"), Some(-10.5));
+        assert_eq!(xmp_coord("10,30"), None);
+        assert_eq!(xmp_date("2024-01-02T03:04:05.123Z"), "2024-01-02 03:04:05");
+        assert_eq!(
+            exif_date("2024:01:02 03:04:05".into()),
+            "2024-01-02 03:04:05"
+        );
+    }
+}
@@ -0,0 +1,568 @@
+//! Thumbnail grid (`g`) and filmstrip (`v`).
+//!
+//! Both follow the viewer cursor, so tag/like keys act on the highlighted
+//! cell. Thumbnails come from the thumbnail store (or a shrunken decode of
+//! the image while the thumb layer hasn't reached it) and live in their own
+//! `TextureCache`, shared by the grid and the strip.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::PathBuf;
+
+use imgui::{Condition, ImColor32, WindowFlags};
+
+use crate::db::{Db, FileEntry};
+use crate::preload::{Preloader, TexInfo, TextureCache};
+use crate::selection::Selection;
+use crate::statusbar::BAR_HEIGHT;
+
+/// Cell pitch in pixels (thumbnail box plus padding).
+pub const CELL: f32 = 168.0;
+const PAD: f32 = 6.0;
+/// Height of the filmstrip; its cells are square.
+pub const STRIP_H: f32 = 96.0;
+const STRIP_PAD: f32 = 4.0;
+/// Thumbnail textures kept on the GPU.
+pub const TEXTURES: usize = 256;
+/// Thumbnail decodes running at once.
+const MAX_IN_FLIGHT: usize = 8;
+
+const CELL_BG: ImColor32 = ImColor32::from_rgba(38, 38, 38, 255);
+const CELL_FG: ImColor32 = ImColor32::from_rgba(128, 128, 128, 255);
+const BADGE_BG: ImColor32 = ImColor32::from_rgba(0, 0, 0, 170);
+const BADGE_FG: ImColor32 = ImColor32::from_rgba(235, 235, 235, 255);
+
+const GRID_FLAGS: WindowFlags = WindowFlags::NO_TITLE_BAR
+    .union(WindowFlags::NO_RESIZE)
+    .union(WindowFlags::NO_MOVE)
+    .union(WindowFlags::NO_SCROLLBAR)
+    .union(WindowFlags::NO_SCROLL_WITH_MOUSE)
+    .union(WindowFlags::NO_COLLAPSE)
+    .union(WindowFlags::NO_SAVED_SETTINGS)
+    .union(WindowFlags::NO_NAV)
+    .union(WindowFlags::NO_BRING_TO_FRONT_ON_FOCUS);
+
+/// Cursor movement within the grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Move {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// What the user did with the mouse this frame.
+#[derive(Debug, PartialEq)]
+pub enum GridAction {
+    None,
+    /// Single click: move the cursor to this index.
+    Select(usize),
+    /// Double click: open this index in the full view.
+    Open(usize),
+}
+
+/// Grid geometry for a given window size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    pub cols: usize,
+    /// Rows that fit completely.
+    pub rows: usize,
+    /// Left margin that centers the columns.
+    pub x0: f32,
+}
+
+pub fn layout(display_w: f32, display_h: f32) -> Layout {
+    let cols = ((display_w / CELL) as usize).max(1);
+    let rows = (((display_h - BAR_HEIGHT) / CELL) as usize).max(1);
+    Layout {
+        cols,
+        rows,
+        x0: ((display_w - cols as f32 * CELL) / 2.0).max(0.0),
+    }
+}
+
+/// New cursor after a move. Left/right wrap across rows; down from the row
+/// above a short last row lands on the last file.
+pub fn step(cursor: usize, len: usize, cols: usize, dir: Move) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    let last = len - 1;
+    match dir {
+        Move::Left => cursor.saturating_sub(1),
+        Move::Right => (cursor + 1).min(last),
+        Move::Up if cursor >= cols => cursor - cols,
+        Move::Down if cursor / cols < last / cols => (cursor + cols).min(last),
+        Move::Up | Move::Down => cursor,
+    }
+}
+
+/// Fit a `w`×`h` image inside a `box_w`×`box_h` box, keeping its aspect.
+/// Returns the drawn size.
+pub fn fit(w: u32, h: u32, box_w: f32, box_h: f32) -> [f32; 2] {
+    if w == 0 || h == 0 {
+        return [box_w, box_h];
+    }
+    let scale = (box_w / w as f32).min(box_h / h as f32);
+    [w as f32 * scale, h as f32 * scale]
+}
+
+// ── Thumbnail textures ──────────────────────────────────────────────────
+
+/// Thumbnail textures keyed by file path, decoded in the background.
+pub struct ThumbTextures {
+    cache: TextureCache,
+    loader: Preloader,
+    root: PathBuf,
+    /// Thumbnails being decoded.
+    loading: HashSet<String>,
+    /// Files without a thumbnail (decode failed, or a video not thumbed yet).
+    failed: HashSet<String>,
+}
+
+impl ThumbTextures {
+    pub fn new(root: PathBuf) -> Self {
+        ThumbTextures {
+            cache: TextureCache::new(TEXTURES),
+            loader: Preloader::new(),
+            root,
+            loading: HashSet::new(),
+            failed: HashSet::new(),
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<TexInfo> {
+        self.cache.get(path)
+    }
+
+    /// Upload finished thumbnails and start decoding missing ones in `range`.
+    pub fn load(&mut self, db: &Db, files: &[FileEntry], range: Range<usize>) {
+        let loading: Vec<String> = self.loading.iter().cloned().collect();
+        for path in loading {
+            if let Some(img) = self.loader.try_take(&path) {
+                self.cache.upload(&path, img);
+                self.loading.remove(&path);
+            } else if !self.loader.is_pending(&path) {
+                self.loading.remove(&path);
+                self.failed.insert(path);
+            }
+        }
+
+        let end = range.end.min(files.len());
+        for file in &files[range.start.min(end)..end] {
+            if self.loader.in_flight() >= MAX_IN_FLIGHT {
+                break;
+            }
+            if self.cache.has(&file.path)
+                || self.loading.contains(&file.path)
+                || self.failed.contains(&file.path)
+            {
+                continue;
+            }
+            let stored = db
+                .file_hash(file.id)
+                .map(|h| crate::thumbs::path_for(&self.root, &h));
+            self.loader.schedule_thumb(file.path.clone(), stored);
+            self.loading.insert(file.path.clone());
+        }
+    }
+
+    /// Let files that had no thumbnail try again (the thumb layer may have
+    /// caught up since).
+    pub fn retry_failed(&mut self) {
+        self.failed.clear();
+    }
+}
+
+/// How a cell is marked besides its thumbnail.
+struct CellMarks<'a> {
+    /// Digit-key badge text, bottom left.
+    badge: &'a str,
+    is_cursor: bool,
+    selected: bool,
+}
+
+/// Draw one thumbnail cell: background, the thumbnail (or a placeholder
+/// once loading failed), like heart, selection tick, badge and the cursor
+/// frame.
+fn draw_cell(
+    ui: &imgui::Ui,
+    draw_list: &imgui::DrawListMut,
+    thumbs: &ThumbTextures,
+    file: &FileEntry,
+    min: [f32; 2],
+    size: f32,
+    marks: CellMarks,
+) {
+    let theme = crate::statusbar::theme();
+    let [x, y] = min;
+    let max = [x + size, y + size];
+    draw_list.add_rect(min, max, CELL_BG).filled(true).build();
+    if let Some(tex) = thumbs.get(&file.path) {
+        let [w, h] = fit(tex.width, tex.height, size, size);
+        let ix = x + (size - w) / 2.0;
+        let iy = y + (size - h) / 2.0;
+        draw_list
+            .add_image(
+                imgui::TextureId::new(tex.gl_id as usize),
+                [ix, iy],
+                [ix + w, iy + h],
+            )
+            .build();
+    } else if thumbs.failed.contains(&file.path) {
+        let label = if crate::is_video(&file.path) {
+            "▶".to_string()
+        } else {
+            crate::ext_of(&file.path).to_uppercase()
+        };
+        let text = ui.calc_text_size(&label);
+        draw_list.add_text(
+            [x + (size - text[0]) / 2.0, y + (size - text[1]) / 2.0],
+            CELL_FG,
+            &label,
+        );
+    }
+
+    if file.liked {
+        let text = ui.calc_text_size("♥");
+        draw_list.add_text([max[0] - text[0] - 4.0, y + 2.0], theme.accent, "♥");
+    }
+    if marks.selected {
+        draw_list
+            .add_rect(min, max, theme.selected)
+            .thickness(2.0)
+            .build();
+        let text = ui.calc_text_size("✓");
+        draw_list
+            .add_rect(min, [x + text[0] + 4.0, y + text[1] + 2.0], theme.selected)
+            .filled(true)
+            .build();
+        draw_list.add_text([x + 2.0, y + 1.0], BADGE_FG, "✓");
+    }
+    let badge = marks.badge;
+    if !badge.is_empty() {
+        let text = ui.calc_text_size(badge);
+        let at = [x + 2.0, max[1] - text[1] - 2.0];
+        draw_list
+            .add_rect(
+                [at[0] - 1.0, at[1]],
+                [at[0] + text[0] + 1.0, at[1] + text[1]],
+                BADGE_BG,
+            )
+            .filled(true)
+            .build();
+        draw_list.add_text(at, BADGE_FG, badge);
+    }
+    if marks.is_cursor {
+        draw_list
+            .add_rect(
+                [x - 2.0, y - 2.0],
+                [max[0] + 2.0, max[1] + 2.0],
+                theme.accent,
+            )
+            .thickness(2.0)
+            .build();
+    }
+}
+
+fn hit(mouse: [f32; 2], min: [f32; 2], size: f32) -> bool {
+    mouse[0] >= min[0] && mouse[0] < min[0] + size && mouse[1] >= min[1] && mouse[1] < min[1] + size
+}
+
+// ── Grid ────────────────────────────────────────────────────────────────
+
+/// Grid state while it is open.
+pub struct GridView {
+    /// First visible row.
+    pub top: usize,
+    /// Cursor the scroll position was last adjusted for.
+    seen_cursor: Option<usize>,
+}
+
+impl GridView {
+    pub fn new() -> Self {
+        GridView {
+            top: 0,
+            seen_cursor: None,
+        }
+    }
+
+    /// Scroll so the cursor row is fully visible.
+    pub fn ensure_visible(&mut self, cursor: usize, layout: Layout) {
+        let row = cursor / layout.cols;
+        if row < self.top {
+            self.top = row;
+        } else if row >= self.top + layout.rows {
+            self.top = row + 1 - layout.rows;
+        }
+    }
+
+    /// Scroll by `delta` rows, without leaving blank rows at the bottom.
+    pub fn scroll_by(&mut self, delta: i64, len: usize, layout: Layout) {
+        let total_rows = len.div_ceil(layout.cols);
+        let max_top = total_rows.saturating_sub(layout.rows);
+        self.top = (self.top as i64 + delta).clamp(0, max_top as i64) as usize;
+    }
+
+    /// Indices drawn this frame (including a partly visible last row).
+    pub fn visible(&self, len: usize, layout: Layout) -> Range<usize> {
+        let start = (self.top * layout.cols).min(len);
+        let end = ((self.top + layout.rows + 1) * layout.cols).min(len);
+        start..end
+    }
+}
+
+/// Draw the grid over the content area. Keeps the cursor in view when it
+/// moved since the last frame; the mouse wheel scrolls.
+#[allow(clippy::too_many_arguments)]
+pub fn draw(
+    ui: &imgui::Ui,
+    view: &mut GridView,
+    files: &[FileEntry],
+    cursor: usize,
+    selection: &Selection,
+    thumbs: &ThumbTextures,
+    display_w: f32,
+    display_h: f32,
+) -> GridAction {
+    let lay = layout(display_w, display_h);
+    if view.seen_cursor != Some(cursor) {
+        view.ensure_visible(cursor, lay);
+        view.seen_cursor = Some(cursor);
+    }
+    let mut action = GridAction::None;
+
+    if let Some(_win) = ui
+        .window("##grid")
+        .position([0.0, BAR_HEIGHT], Condition::Always)
+        .size([display_w, display_h - BAR_HEIGHT], Condition::Always)
+        .bg_alpha(1.0)
+        .flags(GRID_FLAGS)
+        .begin()
+    {
+        let wheel = ui.io().mouse_wheel;
+        if wheel != 0.0 && ui.is_window_hovered() {
+            view.scroll_by(-wheel.signum() as i64, files.len(), lay);
+        }
+
+        let draw_list = ui.get_window_draw_list();
+        let mouse = ui.io().mouse_pos;
+        let clicked = ui.is_window_hovered() && ui.is_mouse_clicked(imgui::MouseButton::Left);
+        let double = ui.is_window_hovered() && ui.is_mouse_double_clicked(imgui::MouseButton::Left);
+        let size = CELL - PAD * 2.0;
+
+        for i in view.visible(files.len(), lay) {
+            let col = i % lay.cols;
+            let row = i / lay.cols - view.top;
+            let min = [
+                lay.x0 + col as f32 * CELL + PAD,
+                BAR_HEIGHT + row as f32 * CELL + PAD,
+            ];
+            let marks = CellMarks {
+                badge: "",
+                is_cursor: i == cursor,
+                selected: selection.contains(files[i].id),
+            };
+            draw_cell(ui, &draw_list, thumbs, &files[i], min, size, marks);
+
+            if hit(mouse, min, size) {
+                if double {
+                    action = GridAction::Open(i);
+                } else if clicked {
+                    action = GridAction::Select(i);
+                }
+            }
+        }
+    }
+
+    action
+}
+
+// ── Filmstrip ───────────────────────────────────────────────────────────
+
+/// Left edge of cell `index` in a filmstrip centered on `cursor`.
+pub fn strip_x(index: usize, cursor: usize, display_w: f32) -> f32 {
+    (display_w - STRIP_H) / 2.0 + (index as f32 - cursor as f32) * STRIP_H
+}
+
+/// Filmstrip state: digit-key badges per file id, cached because they take
+/// a query each. Call `forget_badges` after tags change.
+pub struct Filmstrip {
+    badges: HashMap<i64, String>,
+}
+
+impl Filmstrip {
+    pub fn new() -> Self {
+        Filmstrip {
+            badges: HashMap::new(),
+        }
+    }
+
+    pub fn forget_badges(&mut self) {
+        self.badges.clear();
+    }
+
+    /// Digit keys (2-8) whose tag the file carries, e.g. `"35"`.
+    fn badge(&mut self, db: &Db, slots: &[(u8, String)], file_id: i64) -> &str {
+        self.badges.entry(file_id).or_insert_with(|| {
+            let tags = db.file_tags(file_id);
+            slot_badge(slots, &tags)
+        })
+    }
+}
+
+fn slot_badge(slots: &[(u8, String)], tags: &[String]) -> String {
+    slots
+        .iter()
+        .filter(|(_, name)| tags.contains(name))
+        .map(|(slot, _)| char::from(b'0' + slot))
+        .collect()
+}
+
+/// Draw the filmstrip along the bottom of the window: the files in `range`
+/// around the cursor, centered on it. Returns the index of a clicked cell.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_filmstrip(
+    ui: &imgui::Ui,
+    strip: &mut Filmstrip,
+    db: &Db,
+    files: &[FileEntry],
+    cursor: usize,
+    range: Range<usize>,
+    selection: &Selection,
+    thumbs: &ThumbTextures,
+    display_w: f32,
+    display_h: f32,
+) -> Option<usize> {
+    let mut clicked_at = None;
+    let top = display_h - STRIP_H;
+
+    if let Some(_win) = ui
+        .window("##filmstrip")
+        .position([0.0, top], Condition::Always)
+        .size([display_w, STRIP_H], Condition::Always)
+        .bg_alpha(0.9)
+        .flags(GRID_FLAGS)
+        .begin()
+    {
+        let draw_list = ui.get_window_draw_list();
+        let mouse = ui.io().mouse_pos;
+        let clicked = ui.is_window_hovered() && ui.is_mouse_clicked(imgui::MouseButton::Left);
+        let size = STRIP_H - STRIP_PAD * 2.0;
+        let range = range.start..range.end.min(files.len());
+        let uncached = range
+            .clone()
+            .any(|i| !strip.badges.contains_key(&files[i].id));
+        let slots = if uncached { db.slot_tags() } else { Vec::new() };
+
+        for i in range {
+            let x = strip_x(i, cursor, display_w);
+            if x + STRIP_H < 0.0 || x > display_w {
+                continue;
+            }
+            let min = [x + STRIP_PAD, top + STRIP_PAD];
+            let marks = CellMarks {
+                badge: strip.badge(db, &slots, files[i].id),
+                is_cursor: i == cursor,
+                selected: selection.contains(files[i].id),
+            };
+            draw_cell(ui, &draw_list, thumbs, &files[i], min, size, marks);
+            if clicked && hit(mouse, min, size) {
+                clicked_at = Some(i);
+            }
+        }
+    }
+
+    clicked_at
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_centers_columns() {
+        let lay = layout(1280.0, 720.0);
+        assert_eq!(lay.cols, 7);
+        assert_eq!(lay.rows, 4);
+        assert_eq!(lay.x0, (1280.0 - 7.0 * CELL) / 2.0);
+        // Never fewer than one cell, even in a tiny window
+        let tiny = layout(50.0, 30.0);
+        assert_eq!((tiny.cols, tiny.rows, tiny.x0), (1, 1, 0.0));
+    }
+
+    #[test]
+    fn step_moves_and_clamps() {
+        // 10 files in 4 columns:
+        //  0 1 2 3
+        //  4 5 6 7
+        //  8 9
+        assert_eq!(step(5, 10, 4, Move::Left), 4);
+        assert_eq!(step(4, 10, 4, Move::Left), 3); // wraps to previous row
+        assert_eq!(step(0, 10, 4, Move::Left), 0);
+        assert_eq!(step(9, 10, 4, Move::Right), 9);
+        assert_eq!(step(3, 10, 4, Move::Right), 4);
+        assert_eq!(step(2, 10, 4, Move::Up), 2);
+        assert_eq!(step(6, 10, 4, Move::Up), 2);
+        assert_eq!(step(1, 10, 4, Move::Down), 5);
+        assert_eq!(step(6, 10, 4, Move::Down), 9); // short last row
+        assert_eq!(step(9, 10, 4, Move::Down), 9);
+        assert_eq!(step(0, 0, 4, Move::Down), 0);
+    }
+
+    #[test]
+    fn scrolling_keeps_cursor_visible() {
+        let lay = Layout {
+            cols: 4,
+            rows: 3,
+            x0: 0.0,
+        };
+        let mut view = GridView::new();
+        view.ensure_visible(13, lay); // row 3
+        assert_eq!(view.top, 1);
+        view.ensure_visible(2, lay);
+        assert_eq!(view.top, 0);
+        assert_eq!(view.visible(30, lay), 0..16);
+
+        view.scroll_by(10, 30, lay); // 8 rows total, 3 visible
+        assert_eq!(view.top, 5);
+        assert_eq!(view.visible(30, lay), 20..30);
+        view.scroll_by(-10, 30, lay);
+        assert_eq!(view.top, 0);
+        view.scroll_by(1, 5, lay); // everything already fits
+        assert_eq!(view.top, 0);
+    }
+
+    #[test]
+    fn strip_centers_cursor() {
+        let w = 1000.0;
+        assert_eq!(strip_x(5, 5, w), (w - STRIP_H) / 2.0);
+        assert_eq!(strip_x(6, 5, w), (w - STRIP_H) / 2.0 + STRIP_H);
+        assert_eq!(strip_x(3, 5, w), (w - STRIP_H) / 2.0 - 2.0 * STRIP_H);
+    }
+
+    #[test]
+    fn slot_badge_lists_digit_keys_in_order() {
+        let slots = vec![
+            (2, "c2".to_string()),
+            (3, "portfolio".to_string()),
+            (5, "c5".to_string()),
+        ];
+        let tags = vec![
+            "c5".to_string(),
+            "like".to_string(),
+            "portfolio".to_string(),
+        ];
+        assert_eq!(slot_badge(&slots, &tags), "35");
+        assert_eq!(slot_badge(&slots, &[]), "");
+    }
+
+    #[test]
+    fn fit_keeps_aspect() {
+        assert_eq!(fit(256, 128, 156.0, 156.0), [156.0, 78.0]);
+        assert_eq!(fit(100, 200, 156.0, 156.0), [78.0, 156.0]);
+        assert_eq!(fit(0, 0, 10.0, 10.0), [10.0, 10.0]);
+    }
+}
@@ -1,8 +1,8 @@
 //! Background job engine with independent metadata layers.
 //!
-//! Layers: Hash, Exif (more to come: xattr, tiny_thumb).
-//! Workers process missing layers lazily, with resource throttling
-//! and permanent-failure debounce.
+//! Layers: Hash, Exif, VideoProbe, AiBasic, PHash, Thumb, and the optional
+//! FullHash (more to come: xattr). Workers process missing layers lazily, with resource
+//! throttling and permanent-failure debounce.
 
 use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
@@ -17,7 +17,16 @@ use crate::db::Db;
 pub enum Layer {
     Hash,
     Exif,
+    /// Container probe of videos: duration, bitrate, codecs, size, GOP.
+    VideoProbe,
     AiBasic,
+    /// Perceptual hash (dHash) of images, for near-duplicate search.
+    PHash,
+    /// Thumbnail in the on-disk store (`thumbs.rs`), keyed by content hash.
+    Thumb,
+    /// Full-content SHA-512 of fingerprinted (`fp:`) files. Opt-in: it
+    /// reads every byte of large videos.
+    FullHash,
 }
 
 impl Layer {
@@ -25,12 +34,24 @@ impl Layer {
         match self {
             Layer::Hash => "hash",
             Layer::Exif => "exif",
+            Layer::VideoProbe => "video_probe",
             Layer::AiBasic => "ai_basic",
+            Layer::PHash => "phash",
+            Layer::Thumb => "thumb",
+            Layer::FullHash => "full_hash",
         }
     }
 }
 
-const LAYERS: &[Layer] = &[Layer::Hash, Layer::Exif, Layer::AiBasic];
+const LAYERS: &[Layer] = &[
+    Layer::Hash,
+    Layer::Exif,
+    Layer::VideoProbe,
+    Layer::AiBasic,
+    Layer::PHash,
+    Layer::Thumb,
+    Layer::FullHash,
+];
 
 // ── Stats (shared with UI via Arc) ──────────────────────────────────────
 
@@ -39,6 +60,8 @@ pub struct JobStats {
     pub failed: AtomicU64,
     pub active: AtomicU32,
     pub turbo: AtomicBool,
+    /// Run the FullHash layer (off by default).
+    pub full_hash: AtomicBool,
     last_error: Mutex<String>,
     // Rate tracking
     rate_snapshot: AtomicU64,
@@ -53,6 +76,7 @@ impl JobStats {
             failed: AtomicU64::new(0),
             active: AtomicU32::new(0),
             turbo: AtomicBool::new(false),
+            full_hash: AtomicBool::new(false),
             last_error: Mutex::new(String::new()),
             rate_snapshot: AtomicU64::new(0),
             rate_time: Mutex::new(Instant::now()),
@@ -197,7 +221,7 @@ fn worker_loop(db: Db, stats: Arc<JobStats>, quit: Arc<AtomicBool>, worker_id: u
         }
 
         // Find next work item
-        let work = find_work(&db);
+        let work = find_work(&db, stats.full_hash.load(Ordering::Relaxed));
 
         if let Some((file_id, layer, path)) = work {
             stats.active.fetch_add(1, Ordering::Relaxed);
@@ -234,12 +258,17 @@ fn worker_loop(db: Db, stats: Arc<JobStats>, quit: Arc<AtomicBool>, worker_id: u
     }
 }
 
-fn find_work(db: &Db) -> Option<(i64, Layer, String)> {
+fn find_work(db: &Db, full_hash: bool) -> Option<(i64, Layer, String)> {
     for layer in LAYERS {
         let result = match layer {
             Layer::Hash => db.next_missing_hash(),
             Layer::Exif => db.next_missing_exif(),
+            Layer::VideoProbe => db.next_missing_video_probe(),
             Layer::AiBasic => db.next_missing_pnginfo(),
+            Layer::PHash => db.next_missing_phash(),
+            Layer::Thumb => db.next_missing_thumb(),
+            Layer::FullHash if full_hash => db.next_missing_full_hash(),
+            Layer::FullHash => None,
         };
         if let Some((file_id, path)) = result {
             return Some((file_id, *layer, path));
@@ -254,7 +283,11 @@ fn process_layer(db: &Db, file_id: i64, layer: Layer, path: &str) -> Result<(),
     match layer {
         Layer::Hash => process_hash(db, file_id, path),
         Layer::Exif => process_exif(db, file_id, path),
+        Layer::VideoProbe => process_video_probe(db, file_id, path),
         Layer::AiBasic => process_ai_basic(db, file_id, path),
+        Layer::PHash => process_phash(db, file_id, path),
+        Layer::Thumb => process_thumb(db, file_id, path, &crate::thumbs::dir()),
+        Layer::FullHash => process_full_hash(db, file_id, path),
     }
 }
 
@@ -264,9 +297,6 @@ const FAST_HASH_THRESHOLD: u64 = 2 * 1024 * 1024;
 const FINGERPRINT_CHUNK: usize = 64 * 1024;
 
 fn process_hash(db: &Db, file_id: i64, path: &str) -> Result<(), String> {
-    use sha2::{Digest, Sha512};
-    use std::io::{Read, Seek, SeekFrom};
-
     // Try xattr cache first (instant on Linux)
     #[cfg(unix)]
     {
@@ -282,33 +312,9 @@ fn process_hash(db: &Db, file_id: i64, path: &str) -> Result<(), String> {
     let file_size = file.metadata().map_err(|e| e.to_string())?.len();
 
     let hash = if file_size > FAST_HASH_THRESHOLD {
-        // Fingerprint: head + tail + size
-        let mut hasher = Sha512::new();
-        let mut head = vec![0u8; FINGERPRINT_CHUNK.min(file_size as usize)];
-        file.read_exact(&mut head).map_err(|e| e.to_string())?;
-        hasher.update(&head);
-
-        if file_size > FINGERPRINT_CHUNK as u64 * 2 {
-            file.seek(SeekFrom::End(-(FINGERPRINT_CHUNK as i64)))
-                .map_err(|e| e.to_string())?;
-            let mut tail = vec![0u8; FINGERPRINT_CHUNK];
-            file.read_exact(&mut tail).map_err(|e| e.to_string())?;
-            hasher.update(&tail);
-        }
-        hasher.update(file_size.to_le_bytes());
-        format!("fp:{:x}", hasher.finalize())
+        fingerprint(&mut file, file_size)?
     } else {
-        // Full SHA-512
-        let mut hasher = Sha512::new();
-        let mut buf = [0u8; 65536];
-        loop {
-            let n = file.read(&mut buf).map_err(|e| e.to_string())?;
-            if n == 0 {
-                break;
-            }
-            hasher.update(&buf[..n]);
-        }
-        format!("{:x}", hasher.finalize())
+        sha512_stream(&mut file)?
     };
 
     // Cache in xattr (ignore errors)
@@ -319,6 +325,48 @@ fn process_hash(db: &Db, file_id: i64, path: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// `fp:` fingerprint: SHA-512 over the first and last 64 KB plus the size.
+fn fingerprint(file: &mut std::fs::File, file_size: u64) -> Result<String, String> {
+    use sha2::{Digest, Sha512};
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut hasher = Sha512::new();
+    let mut head = vec![0u8; FINGERPRINT_CHUNK.min(file_size as usize)];
+    file.read_exact(&mut head).map_err(|e| e.to_string())?;
+    hasher.update(&head);
+
+    if file_size > FINGERPRINT_CHUNK as u64 * 2 {
+        file.seek(SeekFrom::End(-(FINGERPRINT_CHUNK as i64)))
+            .map_err(|e| e.to_string())?;
+        let mut tail = vec![0u8; FINGERPRINT_CHUNK];
+        file.read_exact(&mut tail).map_err(|e| e.to_string())?;
+        hasher.update(&tail);
+    }
+    hasher.update(file_size.to_le_bytes());
+    Ok(format!("fp:{:x}", hasher.finalize()))
+}
+
+/// Full-content SHA-512 of a file, streamed in 64 KB blocks. Same format as
+/// `process_hash` uses for small files (hex, no `fp:` prefix).
+pub fn full_sha512(path: &str) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    sha512_stream(&mut file)
+}
+
+fn sha512_stream(reader: &mut impl std::io::Read) -> Result<String, String> {
+    use sha2::{Digest, Sha512};
+    let mut hasher = Sha512::new();
+    let mut buf = [0u8; 65536];
+    loop {
+        let n = reader.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 #[cfg(unix)]
 fn xattr_get(path: &str, name: &str) -> Result<Option<Vec<u8>>, ()> {
     use std::ffi::CString;
@@ -362,10 +410,93 @@ fn xattr_set(path: &str, name: &str, value: &[u8]) {
     }
 }
 
+// ── Thumbnail layer ─────────────────────────────────────────────────────
+
+pub fn process_thumb(
+    db: &Db,
+    file_id: i64,
+    path: &str,
+    root: &std::path::Path,
+) -> Result<(), String> {
+    let hash = db.file_hash(file_id).ok_or("file not hashed")?;
+    let img = if crate::is_video(path) {
+        crate::thumbs::from_video(path)?
+    } else {
+        crate::thumbs::from_image(path)?
+    };
+    crate::thumbs::write(root, &hash, &img)?;
+    db.meta_set_thumb_ready(file_id);
+    Ok(())
+}
+
+// ── Full hash layer ─────────────────────────────────────────────────────
+
+/// Stored in `files.full_hash`, next to the fingerprint in `hash_sha512`, so
+/// the file keeps its `meta` row while duplicates and `lv verify` get an
+/// exact answer.
+fn process_full_hash(db: &Db, file_id: i64, path: &str) -> Result<(), String> {
+    let hash = full_sha512(path)?;
+    db.file_set_full_hash(file_id, &hash);
+    Ok(())
+}
+
+// ── Verify ──────────────────────────────────────────────────────────────
+
+/// Result of re-hashing one file for `lv verify`.
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Ok,
+    /// Size or mtime changed since the last scan: an edit, not bit-rot.
+    Modified,
+    /// Same size and mtime as when hashed, but different bytes.
+    Corrupt,
+    Missing,
+    Error(String),
+}
+
+/// Re-hash a file and compare with what the library stored. Uses the full
+/// hash when there is one; an `fp:` file without it only gets its
+/// fingerprint re-checked. Never reads the xattr cache.
+pub fn verify_file(stored: &crate::db::StoredHash) -> Verdict {
+    let md = match std::fs::metadata(&stored.path) {
+        Ok(md) => md,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Verdict::Missing,
+        Err(e) => return Verdict::Error(e.to_string()),
+    };
+    // Compare instants: rows the watcher wrote may hold epoch seconds
+    let mtime = md
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    let stored_mtime = stored
+        .modified_at
+        .as_deref()
+        .and_then(crate::scanner::mtime_secs);
+    if stored.size != Some(md.len() as i64) || stored_mtime != mtime {
+        return Verdict::Modified;
+    }
+    let (expected, actual) = match &stored.full_hash {
+        Some(full) => (full, full_sha512(&stored.path)),
+        None if stored.hash.starts_with("fp:") => (
+            &stored.hash,
+            std::fs::File::open(&stored.path)
+                .map_err(|e| e.to_string())
+                .and_then(|mut f| fingerprint(&mut f, md.len())),
+        ),
+        None => (&stored.hash, full_sha512(&stored.path)),
+    };
+    match actual {
+        Ok(h) if h == *expected => Verdict::Ok,
+        Ok(_) => Verdict::Corrupt,
+        Err(e) => Verdict::Error(e),
+    }
+}
+
 // ── Exif layer ──────────────────────────────────────────────────────────
 
 fn process_exif(db: &Db, file_id: i64, path: &str) -> Result<(), String> {
-    let dims = image::image_dimensions(path).map_err(|e| e.to_string())?;
+    let exif = crate::exif::extract(path)?;
     let ext = path.rsplit('.').next().unwrap_or("").to_lowercase();
     let format = match ext.as_str() {
         "jpg" | "jpeg" => "JPEG",
@@ -374,9 +505,30 @@ fn process_exif(db: &Db, file_id: i64, path: &str) -> Result<(), String> {
         "gif" => "GIF",
         "bmp" => "BMP",
         "tiff" | "tif" => "TIFF",
+        "heic" | "heif" => "HEIF",
         _ => "Unknown",
     };
+    // The image crate can't open HEIF; its size comes from the container
+    let dims = match image::image_dimensions(path) {
+        Ok(dims) => dims,
+        Err(e) => match (exif.width, exif.height) {
+            (Some(w), Some(h)) => (w, h),
+            _ => return Err(e.to_string()),
+        },
+    };
     db.meta_set_dimensions(file_id, dims.0, dims.1, format);
+    db.meta_set_exif_json(file_id, &exif.to_json());
+    Ok(())
+}
+
+// ── Video probe layer ───────────────────────────────────────────────────
+
+fn process_video_probe(db: &Db, file_id: i64, path: &str) -> Result<(), String> {
+    let info = crate::probe::probe(path)?;
+    if info.duration_ms.is_none() {
+        return Err("no duration in container".into());
+    }
+    db.meta_set_video(file_id, &info);
     Ok(())
 }
 
@@ -398,6 +550,47 @@ fn process_ai_basic(db: &Db, file_id: i64, path: &str) -> Result<(), String> {
     Ok(())
 }
 
+// ── Perceptual hash layer ───────────────────────────────────────────────
+
+fn process_phash(db: &Db, file_id: i64, path: &str) -> Result<(), String> {
+    let img = image::open(path).map_err(|e| e.to_string())?;
+    db.meta_set_phash(file_id, dhash(&img));
+    Ok(())
+}
+
+/// 64-bit difference hash: shrink to 9×8 grey and set one bit per
+/// horizontal neighbour pair where the left pixel is brighter. Resizes and
+/// re-encodes keep the hash; near-identical images differ in a few bits.
+pub fn dhash(img: &image::DynamicImage) -> u64 {
+    let small = img
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | (left > right) as u64;
+        }
+    }
+    hash
+}
+
+/// Default Hamming distance for "similar" (out of 64 bits).
+pub const SIMILAR_DISTANCE: u32 = 10;
+
+/// Perceptual hash of a library file, computed now (and stored) when the
+/// PHash layer hasn't reached it yet.
+pub fn phash_of(db: &Db, file_id: i64, path: &str) -> Result<u64, String> {
+    if let Some(h) = db.file_phash(file_id) {
+        return Ok(h);
+    }
+    let img = image::open(path).map_err(|e| e.to_string())?;
+    let h = dhash(&img);
+    db.meta_set_phash(file_id, h);
+    Ok(h)
+}
+
 // ── Tests ───────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -408,6 +601,281 @@ mod tests {
     fn layer_names() {
         assert_eq!(Layer::Hash.name(), "hash");
         assert_eq!(Layer::Exif.name(), "exif");
+        assert_eq!(Layer::VideoProbe.name(), "video_probe");
+        assert_eq!(Layer::FullHash.name(), "full_hash");
+        assert_eq!(Layer::PHash.name(), "phash");
+        assert_eq!(Layer::Thumb.name(), "thumb");
+    }
+
+    /// Horizontal gradient with a bright square whose position is set by `shift`.
+    fn test_image(w: u32, h: u32, shift: u32) -> image::DynamicImage {
+        image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(w, h, |x, y| {
+            let v = (x * 255 / w) as u8;
+            let in_square = x >= w / 4 + shift && x < w / 2 + shift && y >= h / 4 && y < h / 2;
+            if in_square {
+                image::Rgb([255, 255, 255])
+            } else {
+                image::Rgb([v, v / 2, 255 - v])
+            }
+        }))
+    }
+
+    #[test]
+    fn dhash_survives_resize() {
+        let big = test_image(640, 480, 0);
+        let small = big.resize_exact(160, 120, image::imageops::FilterType::Triangle);
+        let d = (dhash(&big) ^ dhash(&small)).count_ones();
+        assert!(d <= 4, "resized copy is {} bits away", d);
+    }
+
+    #[test]
+    fn dhash_separates_different_images() {
+        let a = test_image(640, 480, 0);
+        let b = image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(640, 480, |x, y| {
+            let v = ((x ^ y) & 0xff) as u8;
+            image::Rgb([v, v, v])
+        }));
+        let d = (dhash(&a) ^ dhash(&b)).count_ones();
+        assert!(d > 10, "unrelated images only {} bits apart", d);
+    }
+
+    #[test]
+    fn phash_layer_and_similar_query() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_string_lossy().to_string();
+        let mut ids = Vec::new();
+        for (name, img) in [
+            ("a.png", test_image(320, 240, 0)),
+            ("a_small.png", test_image(160, 120, 0)),
+            ("noise.png", {
+                image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(320, 240, |x, y| {
+                    let v = ((x * 7 + y * 13) % 256) as u8;
+                    image::Rgb([v, 255 - v, v])
+                }))
+            }),
+        ] {
+            let path = tmp.path().join(name);
+            img.save(&path).unwrap();
+            let path = path.to_string_lossy().to_string();
+            let id = db.file_insert(&path, &dir, name, None, None).unwrap();
+            db.file_set_hash_meta(id, name);
+            ids.push(id);
+        }
+        while let Some((id, layer, path)) = find_work(&db, false) {
+            if layer == Layer::PHash {
+                process_layer(&db, id, layer, &path).unwrap();
+            } else {
+                db.record_job_fail(id, layer.name(), "skipped in test");
+            }
+        }
+
+        let h = db.file_phash(ids[0]).unwrap();
+        let near: Vec<i64> = db
+            .similar_files(h, 6)
+            .into_iter()
+            .map(|(f, _)| f.id)
+            .collect();
+        assert_eq!(near, vec![ids[0], ids[1]]);
+    }
+
+    #[test]
+    fn phash_of_computes_when_missing() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.png");
+        test_image(64, 48, 0).save(&path).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let id = db
+            .file_insert(&path, &tmp.path().to_string_lossy(), "a.png", None, None)
+            .unwrap();
+        db.file_set_hash_meta(id, "h");
+        assert!(db.file_phash(id).is_none());
+
+        let h = phash_of(&db, id, &path).unwrap();
+        assert_eq!(db.file_phash(id), Some(h));
+    }
+
+    #[test]
+    fn exif_layer_stores_dimensions_and_json() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.png");
+        test_image(64, 48, 0).save(&path).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let id = db
+            .file_insert(&path, &tmp.path().to_string_lossy(), "a.png", None, None)
+            .unwrap();
+        db.file_set_hash_meta(id, "h");
+
+        process_layer(&db, id, Layer::Exif, &path).unwrap();
+        let meta = db.get_file_metadata(id).unwrap();
+        assert_eq!((meta.width, meta.height), (Some(64), Some(48)));
+        assert_eq!(meta.exif_json.as_deref(), Some("{}"));
+        assert!(db.next_missing_exif().is_none());
+    }
+
+    #[test]
+    fn thumb_layer_writes_store_and_marks_ready() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.png");
+        test_image(640, 480, 0).save(&path).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let id = db
+            .file_insert(&path, &tmp.path().to_string_lossy(), "a.png", None, None)
+            .unwrap();
+        db.file_set_hash_meta(id, "abcd");
+        assert_eq!(db.next_missing_thumb().map(|(i, _)| i), Some(id));
+
+        let root = tmp.path().join("thumbs");
+        process_thumb(&db, id, &path, &root).unwrap();
+        let thumb = image::open(crate::thumbs::path_for(&root, "abcd")).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (256, 192));
+        assert!(db.next_missing_thumb().is_none());
+    }
+
+    fn test_db() -> Db {
+        let db = Db::open_memory();
+        db.ensure_schema();
+        db.ensure_jobs_schema();
+        db
+    }
+
+    /// Library state of `path` as if it had just been hashed with `hash`.
+    fn stored(path: &std::path::Path, hash: &str, full: Option<String>) -> crate::db::StoredHash {
+        let md = std::fs::metadata(path).unwrap();
+        crate::db::StoredHash {
+            path: path.to_string_lossy().to_string(),
+            size: Some(md.len() as i64),
+            modified_at: crate::scanner::mtime_stamp(&md),
+            hash: hash.to_string(),
+            full_hash: full,
+        }
+    }
+
+    /// Overwrite `path` with same-size bytes and put the mtime back, like
+    /// silent corruption on disk.
+    fn corrupt(path: &std::path::Path, bytes: &[u8]) {
+        let mtime = std::fs::metadata(path).unwrap().modified().unwrap();
+        std::fs::write(path, bytes).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    #[test]
+    fn full_hash_layer_is_opt_in() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("clip.mp4");
+        std::fs::write(&path, b"video bytes").unwrap();
+        let path = path.to_string_lossy().to_string();
+        let id = db
+            .file_insert(&path, &tmp.path().to_string_lossy(), "clip.mp4", None, None)
+            .unwrap();
+        db.file_set_hash_meta(id, "fp:abc");
+        db.record_job_fail(id, "video_probe", "not a real video");
+        db.record_job_fail(id, "thumb", "not a real video");
+
+        assert!(find_work(&db, false).is_none());
+        let (wid, layer, wpath) = find_work(&db, true).unwrap();
+        assert_eq!((wid, layer), (id, Layer::FullHash));
+
+        process_layer(&db, wid, layer, &wpath).unwrap();
+        assert!(find_work(&db, true).is_none());
+        let rows = db.stored_hashes(None);
+        assert_eq!(rows[0].full_hash, Some(full_sha512(&path).unwrap()));
+    }
+
+    #[test]
+    fn full_sha512_matches_small_file_hash() {
+        let db = test_db();
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.png");
+        std::fs::write(&path, b"pixels").unwrap();
+        let path = path.to_string_lossy().to_string();
+        let id = db
+            .file_insert(&path, &tmp.path().to_string_lossy(), "a.png", None, None)
+            .unwrap();
+        // Bypass the xattr cache so the hash really is computed
+        let mut f = std::fs::File::open(&path).unwrap();
+        let hash = sha512_stream(&mut f).unwrap();
+        db.file_set_hash_meta(id, &hash);
+        assert_eq!(full_sha512(&path).unwrap(), hash);
+        assert!(!hash.starts_with("fp:"));
+    }
+
+    #[test]
+    fn verify_ok_and_corrupt() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.png");
+        std::fs::write(&path, b"original").unwrap();
+        let hash = full_sha512(&path.to_string_lossy()).unwrap();
+        let s = stored(&path, &hash, None);
+        assert_eq!(verify_file(&s), Verdict::Ok);
+
+        corrupt(&path, b"originaL");
+        assert_eq!(verify_file(&s), Verdict::Corrupt);
+    }
+
+    #[test]
+    fn verify_prefers_full_hash() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("clip.mp4");
+        std::fs::write(&path, b"head-middle-tail").unwrap();
+        let full = full_sha512(&path.to_string_lossy()).unwrap();
+        let s = stored(&path, "fp:whatever", Some(full));
+        assert_eq!(verify_file(&s), Verdict::Ok);
+
+        corrupt(&path, b"head-MIDDLE-tail");
+        assert_eq!(verify_file(&s), Verdict::Corrupt);
+    }
+
+    #[test]
+    fn verify_fingerprint_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("clip.mp4");
+        let mut bytes = vec![7u8; FINGERPRINT_CHUNK * 3];
+        std::fs::write(&path, &bytes).unwrap();
+        let mut f = std::fs::File::open(&path).unwrap();
+        let fp = fingerprint(&mut f, bytes.len() as u64).unwrap();
+        let s = stored(&path, &fp, None);
+        assert_eq!(verify_file(&s), Verdict::Ok);
+
+        // The middle isn't covered by the fingerprint...
+        bytes[FINGERPRINT_CHUNK + 10] = 0;
+        corrupt(&path, &bytes);
+        assert_eq!(verify_file(&s), Verdict::Ok);
+        // ...the head is
+        bytes[0] = 0;
+        corrupt(&path, &bytes);
+        assert_eq!(verify_file(&s), Verdict::Corrupt);
+    }
+
+    #[test]
+    fn verify_modified_and_missing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.png");
+        std::fs::write(&path, b"original").unwrap();
+        let hash = full_sha512(&path.to_string_lossy()).unwrap();
+        let s = stored(&path, &hash, None);
+
+        // An mtime the watcher stored as epoch seconds still gets re-hashed
+        let mut epoch = stored(&path, &hash, None);
+        let md = std::fs::metadata(&path).unwrap();
+        let secs = md.modified().unwrap().duration_since(std::time::UNIX_EPOCH);
+        epoch.modified_at = Some(secs.unwrap().as_secs().to_string());
+        assert_eq!(verify_file(&epoch), Verdict::Ok);
+
+        std::fs::write(&path, b"edited, longer").unwrap();
+        assert_eq!(verify_file(&s), Verdict::Modified);
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(verify_file(&s), Verdict::Missing);
     }
 
     #[test]
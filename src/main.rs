@@ -9,12 +9,26 @@ const GIT_HASH: &str = env!("GIT_HASH");
 
 mod aimeta;
 mod cli;
+mod config;
 mod db;
+mod dest;
+mod dupes;
+mod exif;
+mod grid;
 mod jobs;
 mod preload;
+mod probe;
 mod quad;
+mod query;
+mod rename;
 mod scanner;
+mod selection;
+mod slideshow;
 mod statusbar;
+mod thumbs;
+mod trash;
+mod tree;
+mod undo;
 mod watcher;
 
 use std::path::PathBuf;
@@ -25,12 +39,12 @@ use std::time::Instant;
 use clap::{Parser, Subcommand};
 
 use sdl2::event::Event;
-use sdl2::keyboard::{Keycode, Mod};
 use sdl2::video::GLProfile;
 
 use libmpv2::Mpv;
 
-use db::{Db, FileEntry};
+use config::Action;
+use db::{Db, FileEntry, SortOrder};
 use preload::TextureCache;
 
 const IMAGE_EXTS: &[&str] = &[
@@ -97,7 +111,7 @@ fn handle_drop(
             db.dir_track(&parent_str, false);
             scanner::discover(db, parent);
             // Mark as temporary
-            for f in &db.files_by_dir(&parent_str) {
+            for f in &db.files_by_dir(&parent_str, SortOrder::Path) {
                 db.set_temporary(f.id, true);
             }
             eprintln!("drop: tracked (temp) {}", parent_str);
@@ -107,7 +121,7 @@ fn handle_drop(
 
         // Exit collection mode, switch to dir mode
         *collection_mode = None;
-        let new_files = db.files_by_dir(&parent_str);
+        let new_files = dir_files(db, &parent_str);
         if new_files.is_empty() {
             eprintln!("drop: no files in {}", parent_str);
             return false;
@@ -128,7 +142,7 @@ fn handle_drop(
         if !db.dir_is_tracked(&dir_str) && !db.dir_is_covered(&dir_str) {
             db.dir_track(&dir_str, false);
             scanner::discover(db, &path);
-            for f in &db.files_by_dir(&dir_str) {
+            for f in &db.files_by_dir(&dir_str, SortOrder::Path) {
                 db.set_temporary(f.id, true);
             }
             eprintln!("drop: tracked (temp) {}", dir_str);
@@ -137,7 +151,7 @@ fn handle_drop(
         }
 
         *collection_mode = None;
-        let new_files = db.files_by_dir(&dir_str);
+        let new_files = dir_files(db, &dir_str);
         if new_files.is_empty() {
             eprintln!("drop: no media files in {}", dir_str);
             return false;
@@ -423,7 +437,101 @@ enum Commands {
     /// Show library statistics
     Status,
     /// Run headless job worker until done
-    Worker,
+    Worker {
+        /// Also hash large files in full (slow; confirms duplicates and
+        /// gives `verify` full coverage)
+        #[arg(long)]
+        full_hash: bool,
+    },
+    /// Re-hash library files and report corrupted ones (bit-rot)
+    Verify { path: Option<PathBuf> },
+    /// Search the library (e.g. `tag:c3 liked ext:png width>2000 prompt:"red car"`)
+    Search {
+        /// Query terms (joined with spaces)
+        #[arg(required = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+    /// List duplicate files with their sizes and reclaimable space
+    Dupes,
+    /// List images that look like FILE (perceptual hash)
+    Similar {
+        path: PathBuf,
+        /// Maximum Hamming distance (0-64 bits)
+        #[arg(long, short, default_value_t = jobs::SIMILAR_DISTANCE)]
+        distance: u32,
+    },
+    /// Show thumbnail store usage, regenerate thumbnails or prune orphans
+    Thumbs {
+        /// Delete all thumbnails and generate them again
+        #[arg(long)]
+        rebuild: bool,
+        /// Remove thumbnails no library file uses any more
+        #[arg(long)]
+        gc: bool,
+    },
+    /// Add, remove or list named tags
+    Tag {
+        #[command(subcommand)]
+        action: TagCommands,
+    },
+    /// List or set the directories Shift+1-9 move (or copy) files to
+    Dest {
+        slot: Option<u8>,
+        path: Option<PathBuf>,
+        /// Copy files there instead of moving them
+        #[arg(long)]
+        copy: bool,
+        /// Unbind the key
+        #[arg(long, conflicts_with = "path")]
+        clear: bool,
+    },
+    /// Show, print or check the config file (keys, tunables, theme)
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+    /// Undo the last tag, like, trash, move or rename (viewer or CLI)
+    Undo {
+        /// Redo the last undone action instead
+        #[arg(long)]
+        redo: bool,
+        /// Show recent actions, newest first
+        #[arg(long, conflicts_with = "redo")]
+        list: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum TagCommands {
+    /// Add a tag to files
+    Add {
+        tag: String,
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Remove a tag from files
+    Remove {
+        tag: String,
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// List all tags with file counts, or the tags on one file
+    List { path: Option<PathBuf> },
+    /// Show or rename the tags on digit keys 2-8 (e.g. `lv tag slot 3 portfolio`)
+    Slot {
+        slot: Option<u8>,
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommands {
+    /// Print where the config file is read from
+    Path,
+    /// Print the effective config, defaults included
+    Dump,
+    /// Report problems in the config file
+    Check,
 }
 
 fn main() {
@@ -443,12 +551,39 @@ fn main() {
             Commands::Unwatch { path } => cli::unwatch(&lv_db, &path),
             Commands::Scan { path } => cli::scan(&lv_db, path.as_deref()),
             Commands::Status => cli::status(&lv_db),
-            Commands::Worker => cli::worker(&lv_db),
+            Commands::Worker { full_hash } => cli::worker(&lv_db, full_hash),
+            Commands::Verify { path } => cli::verify(&lv_db, path.as_deref()),
+            Commands::Search { query } => cli::search(&lv_db, &query.join(" ")),
+            Commands::Dupes => cli::dupes(&lv_db),
+            Commands::Similar { path, distance } => cli::similar(&lv_db, &path, distance),
+            Commands::Thumbs { rebuild, gc } => cli::thumbs(&lv_db, rebuild, gc),
+            Commands::Tag { action } => match action {
+                TagCommands::Add { tag, paths } => cli::tag_set(&lv_db, &tag, &paths, true),
+                TagCommands::Remove { tag, paths } => cli::tag_set(&lv_db, &tag, &paths, false),
+                TagCommands::List { path } => cli::tag_list(&lv_db, path.as_deref()),
+                TagCommands::Slot { slot, name } => cli::tag_slot(&lv_db, slot, name.as_deref()),
+            },
+            Commands::Dest {
+                slot,
+                path,
+                copy,
+                clear,
+            } => cli::dest(&lv_db, slot, path.as_deref(), copy, clear),
+            Commands::Undo { redo, list } => cli::undo(&lv_db, redo, list),
+            Commands::Config { action } => match action {
+                ConfigCommands::Path => println!("{}", config::path().display()),
+                ConfigCommands::Dump => print!("{}", config::load().0.dump()),
+                ConfigCommands::Check => cli::config_check(),
+            },
         }
         return;
     }
 
     // ── GUI mode ─────────────────────────────────────────────────────────
+    let (mut cfg, config_errors) = config::load();
+    for e in &config_errors {
+        eprintln!("config: {}", e);
+    }
     let total_files = lv_db.file_count();
     let total_dirs = lv_db.dir_count();
     eprintln!("lv.db: {} files in {} dirs", total_files, total_dirs);
@@ -477,6 +612,10 @@ fn main() {
 
     // ── Background job engine ────────────────────────────────────────────
     let mut job_engine = jobs::JobEngine::start(lv_db.clone());
+    // Full-content hashing of large files is opt-in (reads every byte)
+    if std::env::var_os("LV_FULL_HASH").is_some() {
+        job_engine.stats.full_hash.store(true, Ordering::Relaxed);
+    }
 
     // ── Filesystem watcher ──────────────────────────────────────────────
     let (fs_watcher, fs_rx) = watcher::FsWatcher::start(lv_db.clone());
@@ -494,7 +633,7 @@ fn main() {
             if already_tracked {
                 // File is in an already-tracked dir → open in dir mode, no temporary flag
                 scanner::discover(&lv_db, parent);
-                let f = lv_db.files_by_dir(&parent_str);
+                let f = dir_files(&lv_db, &parent_str);
                 let clean = clean_path(&path.to_string_lossy());
                 let idx = f.iter().position(|e| e.path == clean).unwrap_or(0);
                 eprintln!("open (tracked): {}", clean);
@@ -509,27 +648,27 @@ fn main() {
                     count,
                     parent_str
                 );
-                for f in &lv_db.files_by_dir(&parent_str) {
+                for f in &lv_db.files_by_dir(&parent_str, SortOrder::Path) {
                     lv_db.set_temporary(f.id, true);
                 }
                 collection_mode = Some(1);
                 let clean = clean_path(&path.to_string_lossy());
-                let all = lv_db.files_by_collection(1);
+                let all = collection_files(&lv_db, 1);
                 let idx = all.iter().position(|f| f.path == clean).unwrap_or(0);
                 (all, parent_str, idx)
             }
         } else if path.is_dir() {
             let dir_str = clean_path(&path.to_string_lossy());
-            let f = lv_db.files_by_dir(&dir_str);
+            let f = dir_files(&lv_db, &dir_str);
             (f, dir_str, 0)
         } else {
             let dir = p.to_string_lossy().to_string();
-            let f = lv_db.files_by_dir(&dir);
+            let f = dir_files(&lv_db, &dir);
             (f, dir, 0)
         }
     } else {
         let dir = lv_db.first_dir().unwrap_or_default();
-        let f = lv_db.files_by_dir(&dir);
+        let f = dir_files(&lv_db, &dir);
         (f, dir, 0)
     };
     if files.is_empty() {
@@ -598,7 +737,7 @@ fn main() {
     let mut imgui_ctx = imgui::Context::create();
     imgui_ctx.set_ini_filename(None);
     statusbar::add_font(&mut imgui_ctx);
-    statusbar::apply_theme(&mut imgui_ctx);
+    statusbar::apply_theme(&mut imgui_ctx, &cfg.theme);
 
     let mut imgui_platform = imgui_sdl2_support::SdlPlatform::new(&mut imgui_ctx);
     let gl = unsafe { glow::Context::from_loader_function(|s| video.gl_get_proc_address(s) as _) };
@@ -656,8 +795,10 @@ fn main() {
     window.gl_make_current(&_gl_ctx).unwrap();
 
     // ── Texture cache + preloader ───────────────────────────────────────
-    let mut tex_cache = TextureCache::new(20);
+    let mut tex_cache = TextureCache::new(cfg.textures);
     let preloader = preload::Preloader::new();
+    // Grid/filmstrip thumbnails get their own cache so they don't evict full images
+    let mut thumb_tex = grid::ThumbTextures::new(thumbs::dir());
 
     // ── Spawn mpv render thread ─────────────────────────────────────────
     let (init_w, init_h) = window.drawable_size();
@@ -704,6 +845,34 @@ fn main() {
     const VIDEO_DEBOUNCE_MS: u128 = 150;
     let mut pending_video: Option<(String, Instant)> = None;
     let mut error_message: Option<(String, String)> = None; // (error, filename)
+    let mut search: Option<SearchView> = None;
+    let mut search_prompt: Option<String> = None; // `/` input open while Some
+    let mut search_error: Option<String> = None;
+    let mut dupes: Option<DupesView> = None; // `d` duplicate groups
+    let mut tag_editor: Option<(i64, statusbar::TagEditor)> = None; // (file id, state)
+
+    // Listing key and the sort order the status bar shows for it
+    let mut sort_shown: (String, SortOrder) = (String::new(), SortOrder::default());
+    // short status-bar feedback; config problems show on the first frame
+    let mut flash: Option<Flash> = match config_errors.as_slice() {
+        [] => None,
+        [e] => Some(Flash::error(format!("config: {}", e))),
+        [e, rest @ ..] => Some(Flash::error(format!(
+            "config: {} (+{} more, lv config check)",
+            e,
+            rest.len()
+        ))),
+    };
+    let mut grid: Option<grid::GridView> = None; // `g` thumbnail grid
+    let mut show_strip = false; // `v` filmstrip under the image
+    let mut strip = grid::Filmstrip::new();
+    let mut selection = selection::Selection::new(); // batch targets (`a`)
+    let mut export_prompt: Option<String> = None; // `e` destination input
+    let mut export_error: Option<String> = None;
+    let mut rename_prompt: Option<String> = None; // F2 name or template input
+    let mut rename_error: Option<String> = None;
+    let mut slideshow: Option<slideshow::Slideshow> = None; // F5
+    let mut tree_panel: Option<tree::TreePanel> = None; // Tab directory tree
 
     // Slow frame tracking: aggregate stats over 10s windows
     #[cfg(debug_assertions)]
@@ -738,25 +907,28 @@ fn main() {
                     );
                     let old_id = files.get(cursor).map(|f| f.id);
                     let old_len = files.len();
-                    if let Some(c) = collection_mode {
-                        let new_files = lv_db.files_by_collection(c);
-                        files = new_files;
-                        cursor = old_id
-                            .and_then(|id| files.iter().position(|f| f.id == id))
-                            .unwrap_or(cursor.min(files.len().saturating_sub(1)));
-                    } else if dir == &current_dir {
-                        // In dir mode, refresh if the changed dir is the current one
-                        let new_files = lv_db.files_by_dir(&current_dir);
-                        files = new_files;
-                        cursor = old_id
-                            .and_then(|id| files.iter().position(|f| f.id == id))
-                            .unwrap_or(cursor.min(files.len().saturating_sub(1)));
+                    // In dir mode, refresh only if the changed dir is the current one
+                    if dupes.is_some()
+                        || search.is_some()
+                        || collection_mode.is_some()
+                        || dir == &current_dir
+                    {
+                        reload_files(
+                            &lv_db,
+                            &mut dupes,
+                            &search,
+                            collection_mode,
+                            &current_dir,
+                            &mut files,
+                            &mut cursor,
+                        );
                     } else {
                         eprintln!(
                             "main:   SKIP — event dir != current_dir ({} != {})",
                             dir, current_dir
                         );
                     }
+                    selection.prune(&lv_db);
                     let new_id = files.get(cursor).map(|f| f.id);
                     let new_len = files.len();
                     eprintln!(
@@ -805,33 +977,24 @@ fn main() {
                     keymod,
                     ..
                 } if !imgui_ctx.io().want_capture_keyboard => {
-                    let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+                    let Some(action) = cfg.keys.feed(config::Chord::new(key, keymod)) else {
+                        continue;
+                    };
 
                     // ── Ctrl+0-9: switch collection view ────────────
-                    let col_key = match key {
-                        Keycode::Num0 | Keycode::Kp0 if ctrl => Some(0u8),
-                        Keycode::Num1 | Keycode::Kp1 if ctrl => Some(1),
-                        Keycode::Num2 | Keycode::Kp2 if ctrl => Some(2),
-                        Keycode::Num3 | Keycode::Kp3 if ctrl => Some(3),
-                        Keycode::Num4 | Keycode::Kp4 if ctrl => Some(4),
-                        Keycode::Num5 | Keycode::Kp5 if ctrl => Some(5),
-                        Keycode::Num6 | Keycode::Kp6 if ctrl => Some(6),
-                        Keycode::Num7 | Keycode::Kp7 if ctrl => Some(7),
-                        Keycode::Num8 | Keycode::Kp8 if ctrl => Some(8),
-                        Keycode::Num9 | Keycode::Kp9 if ctrl => Some(9),
-                        _ => None,
-                    };
-                    if let Some(c) = col_key {
+                    if let Action::Collection(c) = action {
+                        search = None;
+                        dupes = None;
                         let new_mode = Some(c);
                         if collection_mode == new_mode {
                             // Toggle off → back to dir mode
                             collection_mode = None;
-                            files = lv_db.files_by_dir(&current_dir);
+                            files = dir_files(&lv_db, &current_dir);
                             cursor = 0;
                             eprintln!("collection: off (dir: {})", current_dir);
                         } else {
                             collection_mode = new_mode;
-                            files = lv_db.files_by_collection(c);
+                            files = collection_files(&lv_db, c);
                             cursor = 0;
                             eprintln!("collection: {} ({} files)", c, files.len());
                         }
@@ -839,55 +1002,366 @@ fn main() {
                         continue;
                     }
 
-                    // ── 2-8: toggle collection tag on current file ──
-                    let tag_key = match key {
-                        Keycode::Num2 | Keycode::Kp2 if !ctrl => Some(2u8),
-                        Keycode::Num3 | Keycode::Kp3 if !ctrl => Some(3),
-                        Keycode::Num4 | Keycode::Kp4 if !ctrl => Some(4),
-                        Keycode::Num5 | Keycode::Kp5 if !ctrl => Some(5),
-                        Keycode::Num6 | Keycode::Kp6 if !ctrl => Some(6),
-                        Keycode::Num7 | Keycode::Kp7 if !ctrl => Some(7),
-                        Keycode::Num8 | Keycode::Kp8 if !ctrl => Some(8),
-                        _ => None,
-                    };
-                    if let Some(c) = tag_key {
-                        if let Some(file) = files.get(cursor) {
-                            let now_in = lv_db.toggle_collection(file.id, c);
-                            eprintln!(
-                                "{} {} c{}",
-                                if now_in { "+" } else { "-" },
-                                file.filename,
-                                c
-                            );
+                    // ── Shift+1-9: move/copy to destination slot ────
+                    if let Action::Dest(n) = action {
+                        let Some(slot) = lv_db.dest_slot(n) else {
+                            flash = Some(Flash::error(format!(
+                                "Shift+{} has no destination (lv dest {} DIR)",
+                                n, n
+                            )));
+                            continue;
+                        };
+                        let targets = selection.targets(&lv_db, &files, cursor);
+                        if targets.is_empty() {
+                            continue;
+                        }
+                        match dest::send(&lv_db, &slot, &targets) {
+                            Ok(msg) => {
+                                flash = Some(Flash::info(msg));
+                                // Moved files usually leave the view
+                                if !slot.copy {
+                                    selection.clear();
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!("dest {}: {}", n, e);
+                                flash = Some(Flash::error(format!("can't send: {}", e)));
+                            }
                         }
+                        reload_files(
+                            &lv_db,
+                            &mut dupes,
+                            &search,
+                            collection_mode,
+                            &current_dir,
+                            &mut files,
+                            &mut cursor,
+                        );
+                        needs_display = true;
+                        cached_meta_file_id = -1;
+                        update_title(&window, &files, cursor, &current_dir);
                         continue;
                     }
 
-                    // ── 9: toggle like (= collection 9) ────────────
-                    if matches!(key, Keycode::Num9 | Keycode::Kp9) && !ctrl {
-                        if cursor < files.len() {
-                            let file_id = files[cursor].id;
-                            let liked = lv_db.toggle_like(file_id);
-                            files[cursor].liked = liked;
-                            eprintln!(
-                                "{} {} ♥",
-                                if liked { "+" } else { "-" },
-                                files[cursor].filename
-                            );
+                    // ── 2-8: toggle collection tag on current file ──
+                    if let Action::Tag(c) = action {
+                        let targets = selection.targets(&lv_db, &files, cursor);
+                        if !targets.is_empty() {
+                            flash = Some(tag_targets(&lv_db, &targets, &lv_db.slot_tag(c)));
+                            cached_meta_file_id = -1;
+                            strip.forget_badges();
                         }
                         continue;
                     }
 
-                    match key {
+                    match action {
+                        // ── g: thumbnail grid ───────────────────────────
+                        Action::Grid => {
+                            if grid.take().is_some() {
+                                needs_display = true;
+                            } else {
+                                // The grid covers the content area: stop
+                                // playback, show the cursor again on exit
+                                if using_mpv {
+                                    unsafe {
+                                        mpv_stop_async(mpv_handle);
+                                    }
+                                    using_mpv = false;
+                                    mpv_shared.has_frame.store(false, Ordering::Release);
+                                }
+                                pending_video = None;
+                                pending_cold_load = None;
+                                error_message = None;
+                                thumb_tex.retry_failed();
+                                grid = Some(grid::GridView::new());
+                            }
+                        }
+
+                        // ── v: toggle filmstrip ─────────────────────────
+                        Action::Filmstrip => {
+                            show_strip = !show_strip;
+                            if show_strip {
+                                thumb_tex.retry_failed();
+                            }
+                        }
+
+                        // ── Tab: directory tree ─────────────────────────
+                        Action::Tree => {
+                            tree_panel = match tree_panel {
+                                Some(_) => None,
+                                None => Some(tree::TreePanel::new()),
+                            };
+                        }
+                        Action::Back | Action::Open if grid.is_some() => {
+                            grid = None;
+                            needs_display = true;
+                        }
+                        Action::Next
+                        | Action::Prev
+                        | Action::NextDir
+                        | Action::PrevDir
+                        | Action::SeekBack
+                        | Action::SeekForward
+                        | Action::VolumeUp
+                        | Action::VolumeDown
+                            if grid.is_some() =>
+                        {
+                            let dir = match action {
+                                Action::PrevDir | Action::SeekBack => grid::Move::Left,
+                                Action::Next | Action::VolumeDown => grid::Move::Down,
+                                Action::Prev | Action::VolumeUp => grid::Move::Up,
+                                _ => grid::Move::Right,
+                            };
+                            let (w, h) = window.drawable_size();
+                            let cols = grid::layout(w as f32, h as f32).cols;
+                            cursor = grid::step(cursor, files.len(), cols, dir);
+                            update_title(&window, &files, cursor, &current_dir);
+                        }
+
+                        // ── a: mark, A: range, Ctrl+A: all, Ctrl+I: invert ──
+                        Action::SelectAll => {
+                            selection.select_all(&files);
+                            flash = Some(Flash::info(format!("✓{}", selection.len())));
+                        }
+                        Action::SelectRange => {
+                            let n = selection.select_range(&files, cursor);
+                            flash = Some(Flash::info(format!("+✓{}", n)));
+                        }
+                        Action::Select => {
+                            if let Some(file) = files.get(cursor) {
+                                let on = selection.toggle(file.id);
+                                flash = Some(Flash::info(if on { "+✓" } else { "-✓" }.to_string()));
+                            }
+                        }
+                        Action::InvertSelection => {
+                            selection.invert(&files);
+                            flash = Some(Flash::info(format!("✓{}", selection.len())));
+                        }
+                        Action::Back if !selection.is_empty() => {
+                            selection.clear();
+                            flash = Some(Flash::info("selection cleared".into()));
+                        }
+
+                        // ── Delete: trash file / selection ──────────────
+                        Action::Trash => {
+                            let targets = selection.targets(&lv_db, &files, cursor);
+                            if !targets.is_empty() {
+                                flash = Some(trash_targets(&lv_db, &targets));
+                                selection.prune(&lv_db);
+                                reload_files(
+                                    &lv_db,
+                                    &mut dupes,
+                                    &search,
+                                    collection_mode,
+                                    &current_dir,
+                                    &mut files,
+                                    &mut cursor,
+                                );
+                                needs_display = true;
+                                cached_meta_file_id = -1;
+                                update_title(&window, &files, cursor, &current_dir);
+                            }
+                        }
+
+                        // ── z: restore trashed, Ctrl+Z / Ctrl+Shift+Z: undo / redo
+                        Action::Restore | Action::Undo | Action::Redo => {
+                            let result = match action {
+                                Action::Restore => undo::undo_trash(&lv_db),
+                                Action::Redo => undo::redo(&lv_db),
+                                _ => undo::undo(&lv_db),
+                            };
+                            let verb = if action == Action::Redo {
+                                "redo"
+                            } else {
+                                "undo"
+                            };
+                            let ids = match result {
+                                Ok(step) => {
+                                    eprintln!("{}: {}", verb, step.label);
+                                    flash = Some(Flash::info(format!("{}: {}", verb, step.label)));
+                                    step.ids
+                                }
+                                Err(e) => {
+                                    flash = Some(Flash::error(e));
+                                    Vec::new()
+                                }
+                            };
+                            selection.prune(&lv_db);
+                            reload_files(
+                                &lv_db,
+                                &mut dupes,
+                                &search,
+                                collection_mode,
+                                &current_dir,
+                                &mut files,
+                                &mut cursor,
+                            );
+                            if let Some(i) = ids
+                                .first()
+                                .and_then(|id| files.iter().position(|f| f.id == *id))
+                            {
+                                cursor = i;
+                            }
+                            needs_display = true;
+                            cached_meta_file_id = -1;
+                            strip.forget_badges();
+                            update_title(&window, &files, cursor, &current_dir);
+                        }
+
+                        // ── e: export selection (dir or .m3u) ───────────
+                        Action::Export if cursor < files.len() => {
+                            export_prompt = Some(String::new());
+                            export_error = None;
+                        }
+
+                        // ── F2: rename file, or selection by template ───
+                        Action::Rename if cursor < files.len() => {
+                            rename_prompt = Some(if selection.is_empty() {
+                                files[cursor].filename.clone()
+                            } else {
+                                rename::DEFAULT_TEMPLATE.to_string()
+                            });
+                            rename_error = None;
+                        }
+
                         // ── Quit ─────────────────────────────────────────
-                        Keycode::Q | Keycode::Escape => running = false,
+                        Action::Back if search.is_some() => {
+                            if let Some(view) = search.take() {
+                                exit_search(
+                                    &lv_db,
+                                    view,
+                                    &mut files,
+                                    &current_dir,
+                                    &mut cursor,
+                                    collection_mode,
+                                );
+                                needs_display = true;
+                                cached_meta_file_id = -1;
+                            }
+                        }
+                        Action::Back if dupes.is_some() => {
+                            if let Some(view) = dupes.take() {
+                                exit_dupes(
+                                    &lv_db,
+                                    view,
+                                    &mut files,
+                                    &current_dir,
+                                    &mut cursor,
+                                    collection_mode,
+                                );
+                                needs_display = true;
+                                cached_meta_file_id = -1;
+                            }
+                        }
+                        Action::Quit | Action::Back => running = false,
+
+                        // ── /: search prompt ────────────────────────────
+                        Action::Search if dupes.is_none() => {
+                            search_prompt =
+                                Some(search.as_ref().map(|s| s.prompt_text()).unwrap_or_default());
+                            search_error = None;
+                        }
+
+                        // ── s: show similar images ──────────────────────
+                        Action::Similar if dupes.is_none() => {
+                            match enter_similar(&lv_db, &mut files, &mut cursor, &mut search) {
+                                Ok(n) => {
+                                    flash = Some(Flash::info(format!("{} similar", n)));
+                                    cached_meta_file_id = -1;
+                                }
+                                Err(e) => flash = Some(Flash::error(e)),
+                            }
+                        }
+
+                        // ── F: flatten the directory subtree into one list ──
+                        Action::Flatten
+                            if search.as_ref().is_some_and(SearchView::follows_sort) =>
+                        {
+                            if let Some(view) = search.take() {
+                                exit_search(
+                                    &lv_db,
+                                    view,
+                                    &mut files,
+                                    &current_dir,
+                                    &mut cursor,
+                                    collection_mode,
+                                );
+                                needs_display = true;
+                                cached_meta_file_id = -1;
+                            }
+                        }
+                        Action::Flatten
+                            if search.is_none() && dupes.is_none() && collection_mode.is_none() =>
+                        {
+                            match enter_flatten(
+                                &lv_db,
+                                &current_dir,
+                                &mut files,
+                                &mut cursor,
+                                &mut search,
+                            ) {
+                                Ok(n) => flash = Some(Flash::info(format!("{} files", n))),
+                                Err(e) => flash = Some(Flash::error(e)),
+                            }
+                        }
+                        Action::Flatten => {
+                            flash = Some(Flash::error("flatten works on a directory".into()));
+                        }
+
+                        // ── t: tag editor ───────────────────────────────
+                        Action::Tags if cursor < files.len() => {
+                            let file_id = files[cursor].id;
+                            tag_editor = Some((file_id, load_tag_editor(&lv_db, file_id)));
+                        }
+
+                        // ── d: step through duplicate groups ────────────
+                        Action::Dupes => {
+                            if let Some(view) = dupes.take() {
+                                exit_dupes(
+                                    &lv_db,
+                                    view,
+                                    &mut files,
+                                    &current_dir,
+                                    &mut cursor,
+                                    collection_mode,
+                                );
+                            } else if let Err(e) = enter_dupes(
+                                &lv_db,
+                                &mut files,
+                                &mut cursor,
+                                &mut search,
+                                &mut dupes,
+                            ) {
+                                flash = Some(Flash::info(e));
+                            }
+                            needs_display = true;
+                            cached_meta_file_id = -1;
+                        }
+
+                        // ── x: keep this copy, trash the other dupes ────
+                        Action::KeepDupe if dupes.is_some() => {
+                            let old_id = files.get(cursor).map(|f| f.id);
+                            flash = keep_dupe(
+                                &lv_db,
+                                &mut dupes,
+                                &mut files,
+                                &current_dir,
+                                &mut cursor,
+                                collection_mode,
+                            )
+                            .or(flash);
+                            if files.get(cursor).map(|f| f.id) != old_id {
+                                needs_display = true;
+                            }
+                            cached_meta_file_id = -1;
+                        }
 
                         // ── j/k: next/prev in current dir ───────────────
-                        Keycode::J => {
+                        Action::Next => {
                             if cursor + 1 < files.len() {
                                 cursor += 1;
                                 needs_display = true;
-                            } else {
+                            } else if search.is_none() && dupes.is_none() {
                                 // End of dir → try next dir
                                 if let Some(dir) = lv_db.navigate_dir(&current_dir, 1) {
                                     switch_dir(
@@ -902,11 +1376,11 @@ fn main() {
                                 }
                             }
                         }
-                        Keycode::K => {
+                        Action::Prev => {
                             if cursor > 0 {
                                 cursor -= 1;
                                 needs_display = true;
-                            } else {
+                            } else if search.is_none() && dupes.is_none() {
                                 // Start of dir → try prev dir
                                 if let Some(dir) = lv_db.navigate_dir(&current_dir, -1) {
                                     switch_dir(
@@ -922,8 +1396,17 @@ fn main() {
                             }
                         }
 
-                        // ── h/l: prev/next directory ────────────────────
-                        Keycode::L => {
+                        // ── h/l: prev/next directory (dupe group) ───────
+                        Action::NextDir if dupes.is_some() => {
+                            if let Some(view) = dupes.as_mut() {
+                                if view.walker.next(&lv_db) {
+                                    show_dupe_group(view, &mut files, &mut cursor);
+                                    cursor = 0;
+                                    needs_display = true;
+                                }
+                            }
+                        }
+                        Action::NextDir if search.is_none() => {
                             if let Some(dir) = lv_db.navigate_dir(&current_dir, 1) {
                                 switch_dir(
                                     &lv_db,
@@ -936,11 +1419,19 @@ fn main() {
                                 needs_display = true;
                             }
                         }
-                        Keycode::H => {
+                        Action::PrevDir => {
                             if cursor > 0 {
                                 // Go to first file in current directory
                                 cursor = 0;
                                 needs_display = true;
+                            } else if let Some(view) = dupes.as_mut() {
+                                if view.walker.prev() {
+                                    show_dupe_group(view, &mut files, &mut cursor);
+                                    cursor = 0;
+                                    needs_display = true;
+                                }
+                            } else if search.is_some() {
+                                // Search results span dirs; nothing before them
                             } else if let Some(dir) = lv_db.navigate_dir(&current_dir, -1) {
                                 switch_dir(
                                     &lv_db,
@@ -955,69 +1446,61 @@ fn main() {
                         }
 
                         // ── u: random file (collection-aware) ────────────
-                        Keycode::U => {
-                            let file = if let Some(c) = collection_mode {
-                                lv_db.random_in_collection(c)
-                            } else {
-                                lv_db.random_file()
-                            };
-                            if let Some(file) = file {
-                                if collection_mode.is_some() {
-                                    // In collection mode, just find cursor position
-                                    if let Some(idx) = files.iter().position(|f| f.id == file.id) {
-                                        cursor = idx;
-                                    }
-                                } else {
-                                    jump_to(
-                                        &lv_db,
-                                        file,
-                                        &mut files,
-                                        &mut current_dir,
-                                        &mut cursor,
-                                    );
-                                }
-                                needs_display = true;
-                            }
+                        Action::Random => {
+                            needs_display |= go_random(
+                                &lv_db,
+                                search.is_some() || dupes.is_some(),
+                                collection_mode,
+                                &mut files,
+                                &mut current_dir,
+                                &mut cursor,
+                            );
                         }
 
                         // ── n: newest file ──────────────────────────────
-                        Keycode::N => {
+                        Action::Newest => {
                             if let Some(file) = lv_db.newest_file() {
+                                leave_view_for(&mut search, &file, &files);
+                                leave_view_for(&mut dupes, &file, &files);
                                 jump_to(&lv_db, file, &mut files, &mut current_dir, &mut cursor);
                                 needs_display = true;
                             }
                         }
 
                         // ── m: random favourite ─────────────────────────
-                        Keycode::M => {
+                        Action::RandomFav => {
                             if let Some(file) = lv_db.random_fav() {
+                                leave_view_for(&mut search, &file, &files);
+                                leave_view_for(&mut dupes, &file, &files);
                                 jump_to(&lv_db, file, &mut files, &mut current_dir, &mut cursor);
                                 needs_display = true;
                             }
                         }
 
                         // ── b: latest favourite ─────────────────────────
-                        Keycode::B => {
+                        Action::LatestFav => {
                             if let Some(file) = lv_db.latest_fav() {
+                                leave_view_for(&mut search, &file, &files);
+                                leave_view_for(&mut dupes, &file, &files);
                                 jump_to(&lv_db, file, &mut files, &mut current_dir, &mut cursor);
                                 needs_display = true;
                             }
                         }
 
                         // ── y: toggle like ──────────────────────────────
-                        Keycode::Y => {
-                            if cursor < files.len() {
-                                let file_id = files[cursor].id;
-                                let liked = lv_db.toggle_like(file_id);
-                                files[cursor].liked = liked;
-                                let sym = if liked { "♥" } else { "♡" };
-                                eprintln!("{} {}", sym, files[cursor].filename);
-                                update_title(&window, &files, cursor, &current_dir);
-                            }
+                        Action::Like if !selection.is_empty() => {
+                            let targets = selection.targets(&lv_db, &files, cursor);
+                            flash = Some(like_targets(&lv_db, &targets, &mut files));
+                            cached_meta_file_id = -1;
+                        }
+                        Action::Like if cursor < files.len() => {
+                            flash = Some(toggle_like_at(&lv_db, &mut files[cursor]));
+                            cached_meta_file_id = -1;
+                            update_title(&window, &files, cursor, &current_dir);
                         }
 
                         // ── f: toggle fullscreen ────────────────────────
-                        Keycode::F => {
+                        Action::Fullscreen => {
                             use sdl2::video::FullscreenType;
                             let current = window.fullscreen_state();
                             let next = if current == FullscreenType::Off {
@@ -1031,7 +1514,7 @@ fn main() {
                         }
 
                         // ── i: toggle info sidebar ───────────────────
-                        Keycode::I => {
+                        Action::Info => {
                             show_info = !show_info;
                             if show_info {
                                 cached_meta_file_id = -1;
@@ -1040,33 +1523,25 @@ fn main() {
                         }
 
                         // ── info panel scrolling ─────────────────────
-                        Keycode::PageUp => {
-                            if show_info {
-                                info_scroll_y = (info_scroll_y - 200.0).max(0.0);
-                                info_scroll = Some(info_scroll_y);
-                            }
+                        Action::InfoPageUp if show_info => {
+                            info_scroll_y = (info_scroll_y - 200.0).max(0.0);
+                            info_scroll = Some(info_scroll_y);
                         }
-                        Keycode::PageDown => {
-                            if show_info {
-                                info_scroll_y += 200.0;
-                                info_scroll = Some(info_scroll_y);
-                            }
+                        Action::InfoPageDown if show_info => {
+                            info_scroll_y += 200.0;
+                            info_scroll = Some(info_scroll_y);
                         }
-                        Keycode::Home => {
-                            if show_info {
-                                info_scroll_y = 0.0;
-                                info_scroll = Some(0.0);
-                            }
+                        Action::InfoTop if show_info => {
+                            info_scroll_y = 0.0;
+                            info_scroll = Some(0.0);
                         }
-                        Keycode::End => {
-                            if show_info {
-                                info_scroll_y = f32::MAX;
-                                info_scroll = Some(f32::MAX);
-                            }
+                        Action::InfoBottom if show_info => {
+                            info_scroll_y = f32::MAX;
+                            info_scroll = Some(f32::MAX);
                         }
 
                         // ── -: toggle turbo mode ─────────────────────
-                        Keycode::Minus => {
+                        Action::Turbo => {
                             let stats = &job_engine.stats;
                             let was = stats.turbo.load(Ordering::Relaxed);
                             stats.turbo.store(!was, Ordering::Relaxed);
@@ -1074,69 +1549,150 @@ fn main() {
                         }
 
                         // ── r: refresh current directory ───────────────
-                        Keycode::R => {
+                        Action::Refresh if dupes.is_some() => {
+                            refresh_dupes(
+                                &lv_db,
+                                &mut dupes,
+                                &mut files,
+                                &current_dir,
+                                &mut cursor,
+                                collection_mode,
+                            );
+                            selection.prune(&lv_db);
+                            needs_display = true;
+                            cached_meta_file_id = -1;
+                        }
+                        Action::Refresh => {
                             let old_id = files.get(cursor).map(|f| f.id);
-                            files = lv_db.files_by_dir(&current_dir);
+                            files = match search {
+                                Some(ref s) => s.load(&lv_db),
+                                None => dir_files(&lv_db, &current_dir),
+                            };
                             if files.is_empty() {
                                 cursor = 0;
                             } else if let Some(oid) = old_id {
                                 cursor = files.iter().position(|f| f.id == oid).unwrap_or(0);
                             }
+                            selection.prune(&lv_db);
                             needs_display = true;
                             cached_meta_file_id = -1;
                             eprintln!("refresh: {} ({} files)", current_dir, files.len());
                         }
 
+                        // ── o / O: cycle sort order (remembered per listing) ──
+                        Action::Sort | Action::SortBack
+                            if dupes.is_none()
+                                && search.as_ref().is_none_or(SearchView::follows_sort) =>
+                        {
+                            let key = listing_key(collection_mode, &current_dir);
+                            let sort = lv_db.sort_for(&key).cycle(action == Action::SortBack);
+                            match lv_db.set_sort_for(&key, sort) {
+                                Ok(()) => {
+                                    flash = Some(Flash::info(format!("sort: {}", sort.name())));
+                                    sort_shown = (key, sort);
+                                }
+                                Err(e) => flash = Some(Flash::error(format!("can't sort: {}", e))),
+                            }
+                            reload_files(
+                                &lv_db,
+                                &mut dupes,
+                                &search,
+                                collection_mode,
+                                &current_dir,
+                                &mut files,
+                                &mut cursor,
+                            );
+                            update_title(&window, &files, cursor, &current_dir);
+                        }
+
+                        // ── F5: slideshow, Shift+F5 mode, Shift+Space pause, [ ] interval ──
+                        Action::Slideshow => {
+                            slideshow = match slideshow {
+                                Some(_) => None,
+                                None => {
+                                    let mut show = slideshow::Slideshow::new(
+                                        cfg.slideshow_mode,
+                                        cfg.slideshow_interval,
+                                    );
+                                    show.shown(using_mpv && error_message.is_none());
+                                    Some(show)
+                                }
+                            };
+                            sync_slideshow(&mpv, &mut slideshow, using_mpv);
+                            flash = Some(Flash::info(match &slideshow {
+                                Some(show) => show.label(),
+                                None => "slideshow off".into(),
+                            }));
+                        }
+                        Action::SlideshowMode
+                        | Action::SlideshowPause
+                        | Action::SlideshowFaster
+                        | Action::SlideshowSlower => {
+                            if let Some(show) = slideshow.as_mut() {
+                                match action {
+                                    Action::SlideshowMode => show.cycle_mode(),
+                                    Action::SlideshowPause => show.toggle_pause(),
+                                    _ => show.step_interval(action == Action::SlideshowSlower),
+                                }
+                                flash = Some(Flash::info(show.label()));
+                            }
+                            sync_slideshow(&mpv, &mut slideshow, using_mpv);
+                        }
+
                         // ── c: copy path to clipboard ───────────────────
-                        Keycode::C => {
-                            if let Some(file) = files.get(cursor) {
+                        Action::CopyPath => {
+                            let targets = selection.targets(&lv_db, &files, cursor);
+                            if !targets.is_empty() {
+                                let text = targets
+                                    .iter()
+                                    .map(|f| f.path.as_str())
+                                    .collect::<Vec<_>>()
+                                    .join("\n");
                                 if let Ok(clipboard) = sdl.video().map(|v| v.clipboard()) {
-                                    clipboard.set_clipboard_text(&file.path).ok();
-                                    eprintln!("copied: {}", file.path);
+                                    clipboard.set_clipboard_text(&text).ok();
+                                    eprintln!("copied: {}", text);
+                                    if targets.len() > 1 {
+                                        flash = Some(Flash::info(format!(
+                                            "copied {} paths",
+                                            targets.len()
+                                        )));
+                                    }
                                 }
                             }
                         }
 
                         // ── space: pause video ──────────────────────────
-                        Keycode::Space => {
-                            if using_mpv {
-                                mpv.command("cycle", &["pause"]).ok();
-                            }
+                        Action::Pause if using_mpv => {
+                            mpv.command("cycle", &["pause"]).ok();
                         }
 
                         // ── video seek / volume ─────────────────────────
-                        Keycode::Left => {
-                            if using_mpv {
-                                mpv.command("seek", &["-5"]).ok();
-                            }
+                        Action::SeekBack if using_mpv => {
+                            mpv.command("seek", &[&format!("-{}", cfg.seek_back)]).ok();
                         }
-                        Keycode::Right => {
-                            if using_mpv {
-                                mpv.command("seek", &["15"]).ok();
-                            }
+                        Action::SeekForward if using_mpv => {
+                            mpv.command("seek", &[&cfg.seek_forward.to_string()]).ok();
                         }
-                        Keycode::Up => {
-                            if using_mpv {
-                                volume = (volume + 5).min(150);
-                                mpv.set_property("volume", volume).ok();
-                            }
+                        Action::VolumeUp if using_mpv => {
+                            volume = (volume + cfg.volume_step).min(150);
+                            mpv.set_property("volume", volume).ok();
                         }
-                        Keycode::Down => {
-                            if using_mpv {
-                                volume = (volume - 5).max(0);
-                                mpv.set_property("volume", volume).ok();
-                            }
+                        Action::VolumeDown if using_mpv => {
+                            volume = (volume - cfg.volume_step).max(0);
+                            mpv.set_property("volume", volume).ok();
                         }
 
                         // ── p: print timing report ──────────────────────
                         #[cfg(debug_assertions)]
-                        Keycode::P => print_report(&timings),
+                        Action::Report => print_report(&timings),
 
                         _ => {}
                     }
                 }
                 Event::DropFile { filename, .. } => {
                     let dropped = std::path::PathBuf::from(&filename);
+                    search = None;
+                    dupes = None;
                     if handle_drop(
                         &lv_db,
                         &dropped,
@@ -1164,6 +1720,35 @@ fn main() {
         let _t_events = _t1.elapsed();
         let _t2 = Instant::now();
 
+        // ── Slideshow: next file when the interval is up or the video ended ──
+        let prompt_open = tag_editor.is_some()
+            || search_prompt.is_some()
+            || export_prompt.is_some()
+            || rename_prompt.is_some();
+        if let Some(show) = slideshow.as_mut() {
+            if show.due() && grid.is_none() && !prompt_open {
+                match show.next(&files, cursor) {
+                    Some(slideshow::Step::Index(i)) => {
+                        cursor = i;
+                        needs_display = true;
+                    }
+                    Some(slideshow::Step::Random) => {
+                        needs_display |= go_random(
+                            &lv_db,
+                            search.is_some() || dupes.is_some(),
+                            collection_mode,
+                            &mut files,
+                            &mut current_dir,
+                            &mut cursor,
+                        );
+                    }
+                    None => {}
+                }
+                // Nothing shown: try again after another interval
+                show.shown(false);
+            }
+        }
+
         // ── Check for completed async cold decode ─────────────────────
         if let Some(ref cold_path) = pending_cold_load.clone() {
             if let Some(decoded) = preloader.try_take(cold_path) {
@@ -1183,7 +1768,8 @@ fn main() {
         }
 
         // ── Display current file ────────────────────────────────────────
-        if needs_display {
+        // (deferred while the grid is open)
+        if needs_display && grid.is_none() {
             needs_display = false;
 
             if let Some(file) = files.get(cursor) {
@@ -1255,7 +1841,7 @@ fn main() {
                         });
                     }
 
-                    schedule_preload(&preloader, &tex_cache, &files, cursor);
+                    schedule_preload(&preloader, &tex_cache, &files, cursor, cfg.preload);
                 } else if is_video(path) {
                     error_message = None;
                     // Stop current mpv playback (async) so we don't
@@ -1282,6 +1868,9 @@ fn main() {
                 }
 
                 update_title(&window, &files, cursor, &current_dir);
+                if let Some(show) = slideshow.as_mut() {
+                    show.shown(error_message.is_none() && is_video(path));
+                }
 
                 // Deferred: record view after display work is done
                 lv_db.record_view(file.id);
@@ -1350,27 +1939,42 @@ fn main() {
                     libmpv2_sys::mpv_event_id_MPV_EVENT_PLAYBACK_RESTART => {
                         video_has_frame = true;
                     }
-                    libmpv2_sys::mpv_event_id_MPV_EVENT_END_FILE => {
+                    libmpv2_sys::mpv_event_id_MPV_EVENT_END_FILE => unsafe {
                         video_has_frame = false;
-                    }
+                        // Played to the end (or failed to): the slideshow moves on
+                        let end = (*ev).data as *const libmpv2_sys::mpv_event_end_file;
+                        if !end.is_null()
+                            && ((*end).reason
+                                == libmpv2_sys::mpv_end_file_reason_MPV_END_FILE_REASON_EOF
+                                || (*end).reason
+                                    == libmpv2_sys::mpv_end_file_reason_MPV_END_FILE_REASON_ERROR)
+                        {
+                            if let Some(show) = slideshow.as_mut() {
+                                show.video_ended();
+                            }
+                        }
+                    },
                     libmpv2_sys::mpv_event_id_MPV_EVENT_PROPERTY_CHANGE => unsafe {
                         let prop = (*ev).data as *const libmpv2_sys::mpv_event_property;
                         if !prop.is_null() {
                             match (*ev).reply_userdata {
-                                OBS_TIME_POS => {
-                                    if (*prop).format == libmpv2_sys::mpv_format_MPV_FORMAT_DOUBLE {
-                                        video_pos = *((*prop).data as *const f64);
-                                    }
+                                OBS_TIME_POS
+                                    if (*prop).format
+                                        == libmpv2_sys::mpv_format_MPV_FORMAT_DOUBLE =>
+                                {
+                                    video_pos = *((*prop).data as *const f64);
                                 }
-                                OBS_DURATION => {
-                                    if (*prop).format == libmpv2_sys::mpv_format_MPV_FORMAT_DOUBLE {
-                                        video_duration = *((*prop).data as *const f64);
-                                    }
+                                OBS_DURATION
+                                    if (*prop).format
+                                        == libmpv2_sys::mpv_format_MPV_FORMAT_DOUBLE =>
+                                {
+                                    video_duration = *((*prop).data as *const f64);
                                 }
-                                OBS_PAUSE => {
-                                    if (*prop).format == libmpv2_sys::mpv_format_MPV_FORMAT_FLAG {
-                                        video_paused = *((*prop).data as *const i32) != 0;
-                                    }
+                                OBS_PAUSE
+                                    if (*prop).format
+                                        == libmpv2_sys::mpv_format_MPV_FORMAT_FLAG =>
+                                {
+                                    video_paused = *((*prop).data as *const i32) != 0;
                                 }
                                 _ => {}
                             }
@@ -1408,6 +2012,13 @@ fn main() {
         // Content area excludes the status bar at the top
         let bar_h = statusbar::BAR_HEIGHT as u32;
         let content_h = h.saturating_sub(bar_h);
+        // The filmstrip takes the bottom of the content area
+        let strip_h = if show_strip && grid.is_none() {
+            (grid::STRIP_H as u32).min(content_h)
+        } else {
+            0
+        };
+        let view_h = content_h - strip_h;
         unsafe {
             gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
             // Clear the full window first (including bar area)
@@ -1415,22 +2026,23 @@ fn main() {
             gl::ClearColor(0.05, 0.05, 0.05, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
             // Set viewport to content area below the status bar
-            // GL origin is bottom-left, so y=0 is correct (bar is at top)
-            gl::Viewport(0, 0, w as i32, content_h as i32);
+            // GL origin is bottom-left, so y=strip_h is just above the strip
+            gl::Viewport(0, strip_h as i32, w as i32, view_h as i32);
         }
         let mpv_display_tex = mpv_shared.display_tex.load(Ordering::Acquire);
         if using_mpv && video_has_frame && mpv_display_tex != 0 {
             // Blit texture produced by mpv render thread (sub-1ms)
-            quad_renderer.draw_video(mpv_display_tex, w, h, w, content_h);
-        } else if !using_mpv {
+            quad_renderer.draw_video(mpv_display_tex, w, h, w, view_h);
+        } else if !using_mpv && grid.is_none() {
             if let Some(file) = files.get(cursor) {
                 if let Some(tex_info) = tex_cache.get(&file.path) {
                     quad_renderer.draw(
                         tex_info.gl_id,
                         tex_info.width,
                         tex_info.height,
+                        tex_info.orientation,
                         w,
-                        content_h,
+                        view_h,
                     );
                 }
             }
@@ -1444,11 +2056,65 @@ fn main() {
         let _t7 = Instant::now();
 
         // ── ImGui overlay ────────────────────────────────────────────────
+        if let Some(ref g) = grid {
+            let layout = grid::layout(w as f32, h as f32);
+            thumb_tex.load(&lv_db, &files, g.visible(files.len(), layout));
+        } else if show_strip {
+            thumb_tex.load(
+                &lv_db,
+                &files,
+                preload_window(cursor, files.len(), cfg.preload),
+            );
+        }
         imgui_platform.prepare_frame(&mut imgui_ctx, &window, &event_pump);
         let ui = imgui_ctx.new_frame();
 
+        // Thumbnail grid (toggle with 'g')
+        if let Some(ref mut g) = grid {
+            match grid::draw(
+                ui, g, &files, cursor, &selection, &thumb_tex, w as f32, h as f32,
+            ) {
+                grid::GridAction::Select(i) => {
+                    cursor = i;
+                    update_title(&window, &files, cursor, &current_dir);
+                }
+                grid::GridAction::Open(i) => {
+                    cursor = i;
+                    grid = None;
+                    needs_display = true;
+                }
+                grid::GridAction::None => {}
+            }
+        } else if show_strip {
+            // Filmstrip (toggle with 'v'): the preload window around the cursor
+            if let Some(i) = grid::draw_filmstrip(
+                ui,
+                &mut strip,
+                &lv_db,
+                &files,
+                cursor,
+                preload_window(cursor, files.len(), cfg.preload),
+                &selection,
+                &thumb_tex,
+                w as f32,
+                h as f32,
+            ) {
+                if i != cursor {
+                    cursor = i;
+                    needs_display = true;
+                }
+            }
+        }
+
         if let Some(file) = files.get(cursor) {
             let is_turbo = job_engine.stats.turbo.load(Ordering::Relaxed);
+            let search_label = search.as_ref().map(|s| s.label());
+            let slideshow_label = slideshow.as_ref().map(|s| s.label());
+            let subdir = search.as_ref().and_then(|s| s.subdir(file));
+            let key = listing_key(collection_mode, &current_dir);
+            if sort_shown.0 != key {
+                sort_shown = (key.clone(), lv_db.sort_for(&key));
+            }
             let info = statusbar::StatusInfo {
                 index: cursor + 1,
                 total: files.len(),
@@ -1460,6 +2126,17 @@ fn main() {
                 video_duration,
                 volume,
                 turbo: is_turbo,
+                search: search_label.as_deref(),
+                subdir: subdir.as_deref(),
+                dupes: dupes.as_ref().map(|d| d.walker.position()),
+                flash: flash
+                    .as_ref()
+                    .filter(|f| f.at.elapsed().as_millis() < FLASH_MS)
+                    .map(|f| (f.text.as_str(), f.error)),
+                selected: selection.len(),
+                sort: (dupes.is_none() && search.as_ref().is_none_or(SearchView::follows_sort))
+                    .then(|| sort_shown.1.name()),
+                slideshow: slideshow_label.as_deref(),
             };
             let win_action = statusbar::draw_status_bar(ui, &info, w as f32, h as f32);
             match win_action {
@@ -1527,11 +2204,168 @@ fn main() {
             }
         }
 
+        // Directory tree (toggle with Tab); a click opens the directory
+        if let Some(panel) = tree_panel.as_mut() {
+            panel.refresh(&lv_db);
+            let bottom = h as f32 - if show_strip { grid::STRIP_H } else { 0.0 };
+            let in_view = search.is_some() || dupes.is_some() || collection_mode.is_some();
+            let picked = tree::draw_tree_panel(ui, panel, &current_dir, w as f32, bottom)
+                .filter(|dir| *dir != current_dir || in_view);
+            if let Some(dir) = picked {
+                if switch_dir(
+                    &lv_db,
+                    &dir,
+                    &mut files,
+                    &mut current_dir,
+                    &mut cursor,
+                    "first",
+                ) {
+                    search = None;
+                    dupes = None;
+                    collection_mode = None;
+                    needs_display = true;
+                }
+            }
+        }
+
         if let Some((ref err, ref fname)) = error_message {
             statusbar::draw_error_overlay(ui, err, fname, w as f32, h as f32);
         } else if (using_mpv && !video_has_frame) || pending_cold_load.is_some() {
             statusbar::draw_spinner(ui, w as f32, h as f32, start_time.elapsed().as_secs_f32());
         }
+
+        // Tag editor (open with 't')
+        if let Some((file_id, ref mut ed)) = tag_editor {
+            let action = statusbar::draw_tag_editor(ui, ed, w as f32, h as f32);
+            let result = match action {
+                statusbar::TagAction::None => None,
+                statusbar::TagAction::Close => {
+                    tag_editor = None;
+                    None
+                }
+                statusbar::TagAction::Add(name) => {
+                    Some(set_tag_undoable(&lv_db, file_id, name, true))
+                }
+                statusbar::TagAction::Remove(name) => {
+                    Some(set_tag_undoable(&lv_db, file_id, name, false))
+                }
+                statusbar::TagAction::RenameSlot(slot, name) => {
+                    Some(lv_db.set_slot_tag(slot, &name))
+                }
+            };
+            if let (Some(result), Some((_, ref mut ed))) = (result, &mut tag_editor) {
+                reload_tag_editor(&lv_db, file_id, ed);
+                ed.error = result.err();
+                if let Some(f) = files.iter_mut().find(|f| f.id == file_id) {
+                    f.liked = ed.file_tags.iter().any(|t| t == "like");
+                }
+                cached_meta_file_id = -1;
+                strip.forget_badges();
+            }
+        }
+
+        // Search prompt (open with '/')
+        if let Some(ref mut buf) = search_prompt {
+            match statusbar::draw_search_prompt(ui, buf, search_error.as_deref(), w as f32) {
+                statusbar::PromptAction::Submit => {
+                    let text = buf.trim().to_string();
+                    let result = if text.is_empty() {
+                        // Empty query closes the search, like Esc in the viewer
+                        if let Some(view) = search.take() {
+                            exit_search(
+                                &lv_db,
+                                view,
+                                &mut files,
+                                &current_dir,
+                                &mut cursor,
+                                collection_mode,
+                            );
+                        }
+                        Ok(files.len())
+                    } else {
+                        enter_search(&lv_db, &text, &mut files, &mut cursor, &mut search)
+                    };
+                    match result {
+                        Ok(_) => {
+                            search_prompt = None;
+                            search_error = None;
+                            needs_display = true;
+                            cached_meta_file_id = -1;
+                        }
+                        Err(e) => search_error = Some(e),
+                    }
+                }
+                statusbar::PromptAction::Cancel => {
+                    search_prompt = None;
+                    search_error = None;
+                }
+                statusbar::PromptAction::None => {}
+            }
+        }
+
+        // Export prompt (open with 'e')
+        if let Some(ref mut buf) = export_prompt {
+            match statusbar::draw_prompt(
+                ui,
+                "export to",
+                "~/picks/  or  ~/picks.m3u",
+                buf,
+                export_error.as_deref(),
+                w as f32,
+            ) {
+                statusbar::PromptAction::Submit => {
+                    let dest = selection::expand_home(buf.trim());
+                    let targets = selection.targets(&lv_db, &files, cursor);
+                    match selection::export(&dest, &targets) {
+                        Ok(msg) => {
+                            eprintln!("export: {}", msg);
+                            flash = Some(Flash::info(msg));
+                            export_prompt = None;
+                        }
+                        Err(e) => export_error = Some(e),
+                    }
+                }
+                statusbar::PromptAction::Cancel => export_prompt = None,
+                statusbar::PromptAction::None => {}
+            }
+        }
+        // Rename prompt (open with F2)
+        if let Some(ref mut buf) = rename_prompt {
+            match statusbar::draw_prompt(
+                ui,
+                "rename",
+                "new name, or a template like {date}_{model}_{seq}.{ext}",
+                buf,
+                rename_error.as_deref(),
+                w as f32,
+            ) {
+                statusbar::PromptAction::Submit => {
+                    let targets = selection.targets(&lv_db, &files, cursor);
+                    match rename::plan(&lv_db, &targets, buf).and_then(|p| rename::apply(&lv_db, p))
+                    {
+                        Ok(n) => {
+                            flash = Some(Flash::info(format!("renamed {} (Ctrl+Z: undo)", n)));
+                            rename_prompt = None;
+                            reload_files(
+                                &lv_db,
+                                &mut dupes,
+                                &search,
+                                collection_mode,
+                                &current_dir,
+                                &mut files,
+                                &mut cursor,
+                            );
+                            needs_display = true;
+                            cached_meta_file_id = -1;
+                            update_title(&window, &files, cursor, &current_dir);
+                        }
+                        Err(e) => rename_error = Some(e),
+                    }
+                }
+                statusbar::PromptAction::Cancel => rename_prompt = None,
+                statusbar::PromptAction::None => {}
+            }
+        }
         let draw_data = imgui_ctx.render();
         imgui_renderer.render(draw_data).ok();
 
@@ -1605,58 +2439,680 @@ fn main() {
     // The process is exiting anyway, the OS will reclaim all resources.
     std::mem::forget(mpv);
 
-    #[cfg(debug_assertions)]
-    if !timings.is_empty() {
-        print_report(&timings);
+    #[cfg(debug_assertions)]
+    if !timings.is_empty() {
+        print_report(&timings);
+    }
+}
+
+// ── Helpers ─────────────────────────────────────────────────────────────
+
+/// Open `dir` at its first or last file. Returns false, changing nothing,
+/// if it has no files.
+fn switch_dir(
+    db: &Db,
+    dir: &str,
+    files: &mut Vec<FileEntry>,
+    current_dir: &mut String,
+    cursor: &mut usize,
+    pos: &str, // "first" or "last"
+) -> bool {
+    let new_files = dir_files(db, dir);
+    if new_files.is_empty() {
+        return false;
+    }
+    eprintln!("dir: {} ({} files)", dir, new_files.len());
+    *files = new_files;
+    *current_dir = dir.to_string();
+    *cursor = if pos == "last" {
+        files.len().saturating_sub(1)
+    } else {
+        0
+    };
+    true
+}
+
+/// Let mpv report the end of each video while a slideshow runs (with
+/// `keep-open` it would hold the last frame instead). A video that already
+/// ended before the show started or resumed counts as done.
+fn sync_slideshow(mpv: &Mpv, slideshow: &mut Option<slideshow::Slideshow>, using_mpv: bool) {
+    let running = slideshow.as_ref().is_some_and(|s| !s.paused);
+    mpv.set_property("keep-open", if running { "no" } else { "yes" })
+        .ok();
+    if let Some(show) = slideshow.as_mut().filter(|_| running && using_mpv) {
+        if mpv.get_property::<bool>("eof-reached").unwrap_or(false) {
+            show.video_ended();
+        }
+    }
+}
+
+/// `u`: a random file of the listing while searching or stepping through
+/// duplicates (`in_view`), else of the open collection or the whole
+/// library. Returns whether the cursor moved.
+fn go_random(
+    db: &Db,
+    in_view: bool,
+    collection_mode: Option<u8>,
+    files: &mut Vec<FileEntry>,
+    current_dir: &mut String,
+    cursor: &mut usize,
+) -> bool {
+    if in_view {
+        *cursor = random_index(files.len().max(1));
+        return true;
+    }
+    let file = match collection_mode {
+        Some(c) => db.random_in_collection(c),
+        None => db.random_file(),
+    };
+    let Some(file) = file else {
+        return false;
+    };
+    if collection_mode.is_some() {
+        // In collection mode, just find cursor position
+        if let Some(idx) = files.iter().position(|f| f.id == file.id) {
+            *cursor = idx;
+        }
+    } else {
+        jump_to(db, file, files, current_dir, cursor);
+    }
+    true
+}
+
+fn jump_to(
+    db: &Db,
+    file: FileEntry,
+    files: &mut Vec<FileEntry>,
+    current_dir: &mut String,
+    cursor: &mut usize,
+) {
+    // Check if file is in current dir
+    if let Some(idx) = files.iter().position(|f| f.id == file.id) {
+        *cursor = idx;
+        return;
+    }
+    // Load the file's directory
+    let new_files = dir_files(db, &file.dir);
+    if new_files.is_empty() {
+        return;
+    }
+    let idx = new_files.iter().position(|f| f.id == file.id).unwrap_or(0);
+    eprintln!("jump → {} ({} files)", file.dir, new_files.len());
+    *files = new_files;
+    *current_dir = file.dir;
+    *cursor = idx;
+}
+
+/// Key a listing's sort order is remembered under (`Db::sort_for`).
+fn listing_key(collection_mode: Option<u8>, dir: &str) -> String {
+    match collection_mode {
+        Some(c) => format!("collection:{}", c),
+        None => dir.to_string(),
+    }
+}
+
+/// Files of `dir` in its remembered sort order.
+fn dir_files(db: &Db, dir: &str) -> Vec<FileEntry> {
+    db.files_by_dir(dir, db.sort_for(dir))
+}
+
+/// Files of collection `c` in its remembered sort order.
+fn collection_files(db: &Db, c: u8) -> Vec<FileEntry> {
+    db.files_by_collection(c, db.sort_for(&listing_key(Some(c), "")))
+}
+
+/// How long a status-bar flash message stays visible.
+const FLASH_MS: u128 = 2500;
+
+/// Short feedback shown in the status bar (e.g. "+portfolio", or why a tag
+/// could not be applied).
+struct Flash {
+    text: String,
+    error: bool,
+    at: Instant,
+}
+
+impl Flash {
+    fn info(text: String) -> Self {
+        Flash {
+            text,
+            error: false,
+            at: Instant::now(),
+        }
+    }
+
+    fn error(text: String) -> Self {
+        Flash {
+            text,
+            error: true,
+            at: Instant::now(),
+        }
+    }
+}
+
+/// Toggle like on `file` (9 / y keys) as one undo step and describe the
+/// outcome.
+fn toggle_like_at(db: &Db, file: &mut FileEntry) -> Flash {
+    let on = !db.file_tags(file.id).iter().any(|t| t == "like");
+    let label = if on { "+♥" } else { "-♥" };
+    let change = undo::Change::Like {
+        on,
+        files: vec![file.id],
+    };
+    match undo::run(db, label, change) {
+        Ok(_) => {
+            file.liked = on;
+            eprintln!("{} {}", if on { "♥" } else { "♡" }, file.filename);
+            Flash::info(label.to_string())
+        }
+        Err(e) => {
+            eprintln!("like: {}: {}", file.filename, e);
+            Flash::error(format!("can't like: {}", e))
+        }
+    }
+}
+
+/// `+tag`, or `+tag ×N` for a batch.
+fn batch_label(sign: &str, name: &str, n: usize) -> String {
+    if n == 1 {
+        format!("{}{}", sign, name)
+    } else {
+        format!("{}{} ×{}", sign, name, n)
+    }
+}
+
+/// Toggle `tag` on a batch as one undo step: added to all unless every file
+/// already has it, in which case it is removed from all.
+fn tag_targets(db: &Db, targets: &[FileEntry], tag: &str) -> Flash {
+    let has: Vec<bool> = targets
+        .iter()
+        .map(|f| db.file_tags(f.id).iter().any(|t| t == tag))
+        .collect();
+    let on = !has.iter().all(|&h| h);
+    let changed = targets
+        .iter()
+        .zip(&has)
+        .filter(|(_, &h)| h != on)
+        .map(|(f, _)| f.id)
+        .collect();
+    let sign = if on { "+" } else { "-" };
+    let label = batch_label(sign, tag, targets.len());
+    let change = undo::Change::Tag {
+        tag: tag.to_string(),
+        on,
+        files: changed,
+    };
+    match undo::run(db, &label, change) {
+        Ok(_) => {
+            eprintln!("{} {} on {} files", sign, tag, targets.len());
+            Flash::info(label)
+        }
+        Err(e) => {
+            eprintln!("tag: {}: {}", tag, e);
+            Flash::error(format!("can't tag {}: {}", tag, e))
+        }
+    }
+}
+
+/// Toggle `like` on a batch as one undo step (like all unless all are
+/// liked) and update the matching entries in `files`.
+fn like_targets(db: &Db, targets: &[FileEntry], files: &mut [FileEntry]) -> Flash {
+    let on = !targets.iter().all(|f| f.liked);
+    let changed = targets
+        .iter()
+        .filter(|f| f.liked != on)
+        .map(|f| f.id)
+        .collect();
+    let label = batch_label(if on { "+♥" } else { "-♥" }, "", targets.len());
+    let result = undo::run(db, &label, undo::Change::Like { on, files: changed });
+    let ids: Vec<i64> = targets.iter().map(|t| t.id).collect();
+    for t in db.files_by_ids(&ids) {
+        if let Some(f) = files.iter_mut().find(|f| f.id == t.id) {
+            f.liked = t.liked;
+        }
+    }
+    match result {
+        Ok(_) => Flash::info(label),
+        Err(e) => Flash::error(format!("can't like: {}", e)),
+    }
+}
+
+/// Add or remove one tag from the tag editor as an undo step.
+fn set_tag_undoable(db: &Db, file_id: i64, tag: String, on: bool) -> Result<(), String> {
+    if db.file_tags(file_id).contains(&tag) == on {
+        return Ok(());
+    }
+    let label = format!("{}{}", if on { "+" } else { "-" }, tag);
+    let files = vec![file_id];
+    undo::run(db, &label, undo::Change::Tag { tag, on, files }).map(|_| ())
+}
+
+/// Load the current tag state of `file_id` into a fresh tag editor.
+fn load_tag_editor(db: &Db, file_id: i64) -> statusbar::TagEditor {
+    statusbar::TagEditor::new(db.file_tags(file_id), db.all_tags(), db.slot_tags())
+}
+
+/// Refresh an open tag editor after a change, keeping its input state.
+fn reload_tag_editor(db: &Db, file_id: i64, ed: &mut statusbar::TagEditor) {
+    ed.file_tags = db.file_tags(file_id);
+    ed.all_tags = db.all_tags();
+    ed.slots = db.slot_tags();
+}
+
+/// Search results shown as the browsing list (`/` prompt, `s` similar,
+/// `F` flatten).
+/// `current_dir` and `collection_mode` stay untouched while searching, so Esc
+/// can rebuild the previous list; `return_file_id` restores the cursor.
+struct SearchView {
+    /// Query text, or the source file name of a similar-images list.
+    text: String,
+    source: SearchSource,
+    return_file_id: Option<i64>,
+}
+
+enum SearchSource {
+    Query(query::Query),
+    /// Images within `SIMILAR_DISTANCE` of `phash`, source file first.
+    Similar {
+        file_id: i64,
+        phash: u64,
+    },
+    /// Every file in `dir` and below, in `dir`'s sort order.
+    Under {
+        dir: String,
+    },
+}
+
+impl SearchView {
+    /// Re-run the search (watcher events, `r`).
+    fn load(&self, db: &Db) -> Vec<FileEntry> {
+        match self.source {
+            SearchSource::Query(ref q) => db.query_files(q),
+            SearchSource::Similar { file_id, phash } => similar_list(db, file_id, phash),
+            SearchSource::Under { ref dir } => db.files_under(dir, db.sort_for(dir)),
+        }
+    }
+
+    /// Whether `o` re-sorts the list (flattened directories only; other
+    /// results keep their own order).
+    fn follows_sort(&self) -> bool {
+        matches!(self.source, SearchSource::Under { .. })
+    }
+
+    /// `file`'s directory relative to a flattened root.
+    fn subdir(&self, file: &FileEntry) -> Option<String> {
+        let SearchSource::Under { ref dir } = self.source else {
+            return None;
+        };
+        let rest = file.dir.strip_prefix(dir.as_str())?;
+        let rest = rest.trim_start_matches(['/', '\\']);
+        Some(if rest.is_empty() { "." } else { rest }.to_string())
+    }
+
+    /// Status bar label: `/query` or `~file.png`.
+    fn label(&self) -> String {
+        match self.source {
+            SearchSource::Query(_) => format!("/{}", self.text),
+            SearchSource::Similar { .. } => format!("~{}", self.text),
+            SearchSource::Under { .. } => format!("{}/**", self.text),
+        }
+    }
+
+    /// Text the `/` prompt opens with.
+    fn prompt_text(&self) -> String {
+        match self.source {
+            SearchSource::Query(_) => self.text.clone(),
+            SearchSource::Similar { .. } | SearchSource::Under { .. } => String::new(),
+        }
+    }
+}
+
+/// Run a search and swap `files` for its results. Keeps the original return
+/// point when refining an already-active search. Returns the match count;
+/// leaves everything untouched on a parse error or an empty result.
+fn enter_search(
+    db: &Db,
+    text: &str,
+    files: &mut Vec<FileEntry>,
+    cursor: &mut usize,
+    search: &mut Option<SearchView>,
+) -> Result<usize, String> {
+    let query = query::Query::parse(text)?;
+    let results = db.query_files(&query);
+    if results.is_empty() {
+        return Err("no matches".into());
+    }
+    let return_file_id = match search.take() {
+        Some(prev) => prev.return_file_id,
+        None => files.get(*cursor).map(|f| f.id),
+    };
+    eprintln!("search: {} ({} files)", text, results.len());
+    *files = results;
+    *cursor = 0;
+    *search = Some(SearchView {
+        text: text.to_string(),
+        source: SearchSource::Query(query),
+        return_file_id,
+    });
+    Ok(files.len())
+}
+
+/// The source file, then the images that look like it, closest first.
+fn similar_list(db: &Db, file_id: i64, phash: u64) -> Vec<FileEntry> {
+    let mut found = db.similar_files(phash, jobs::SIMILAR_DISTANCE);
+    found.sort_by_key(|(f, d)| (*d, f.id != file_id));
+    found.into_iter().map(|(f, _)| f).collect()
+}
+
+/// `s`: show the images similar to the current one as the browsing list,
+/// like a search. Returns the number of similar images; leaves everything
+/// untouched when there are none.
+fn enter_similar(
+    db: &Db,
+    files: &mut Vec<FileEntry>,
+    cursor: &mut usize,
+    search: &mut Option<SearchView>,
+) -> Result<usize, String> {
+    let file = files.get(*cursor).ok_or("no file")?;
+    if !is_image(&file.path) {
+        return Err("not an image".into());
+    }
+    let phash = jobs::phash_of(db, file.id, &file.path)?;
+    let results = similar_list(db, file.id, phash);
+    let count = results.iter().filter(|f| f.id != file.id).count();
+    if count == 0 {
+        return Err("no similar images".into());
+    }
+    let (file_id, text) = (file.id, file.filename.clone());
+    let return_file_id = match search.take() {
+        Some(prev) => prev.return_file_id,
+        None => Some(file_id),
+    };
+    eprintln!("similar: {} ({} files)", text, count);
+    *files = results;
+    *cursor = files.iter().position(|f| f.id == file_id).unwrap_or(0);
+    *search = Some(SearchView {
+        text,
+        source: SearchSource::Similar { file_id, phash },
+        return_file_id,
+    });
+    Ok(count)
+}
+
+/// `F`: list every file in `current_dir` and its subdirectories, like a
+/// search, with the cursor staying on the current file. Returns the file
+/// count; leaves everything untouched when there are none.
+fn enter_flatten(
+    db: &Db,
+    current_dir: &str,
+    files: &mut Vec<FileEntry>,
+    cursor: &mut usize,
+    search: &mut Option<SearchView>,
+) -> Result<usize, String> {
+    let source = SearchSource::Under {
+        dir: current_dir.to_string(),
+    };
+    let view = SearchView {
+        text: std::path::Path::new(current_dir)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| current_dir.to_string()),
+        source,
+        return_file_id: files.get(*cursor).map(|f| f.id),
+    };
+    let results = view.load(db);
+    if results.is_empty() {
+        return Err("no files".into());
+    }
+    eprintln!("flatten: {} ({} files)", current_dir, results.len());
+    *files = results;
+    *cursor = view
+        .return_file_id
+        .and_then(|id| files.iter().position(|f| f.id == id))
+        .unwrap_or(0);
+    *search = Some(view);
+    Ok(files.len())
+}
+
+/// Leave the search results and reload the directory or collection view it
+/// was opened from, with the cursor back on the file that was showing.
+fn exit_search(
+    db: &Db,
+    view: SearchView,
+    files: &mut Vec<FileEntry>,
+    current_dir: &str,
+    cursor: &mut usize,
+    collection_mode: Option<u8>,
+) {
+    restore_list(
+        db,
+        view.return_file_id,
+        files,
+        current_dir,
+        cursor,
+        collection_mode,
+    );
+    eprintln!("search: off ({} files)", files.len());
+}
+
+/// Reload the directory or collection list a search/dupes view replaced,
+/// with the cursor on `return_file_id` when it is still there.
+fn restore_list(
+    db: &Db,
+    return_file_id: Option<i64>,
+    files: &mut Vec<FileEntry>,
+    current_dir: &str,
+    cursor: &mut usize,
+    collection_mode: Option<u8>,
+) {
+    *files = match collection_mode {
+        Some(c) => collection_files(db, c),
+        None => dir_files(db, current_dir),
+    };
+    *cursor = return_file_id
+        .and_then(|id| files.iter().position(|f| f.id == id))
+        .unwrap_or(0);
+}
+
+/// Drop a search or duplicates view when a jump (n/m/b) targets a file
+/// outside its list, so `jump_to` falls back to the file's directory.
+fn leave_view_for<T>(view: &mut Option<T>, target: &FileEntry, files: &[FileEntry]) {
+    if view.is_some() && !files.iter().any(|f| f.id == target.id) {
+        *view = None;
     }
 }
 
-// ── Helpers ─────────────────────────────────────────────────────────────
+/// Duplicate groups shown one at a time as the browsing list (`d`). Like
+/// `SearchView`, the dir/collection state is left alone so Esc can rebuild it.
+struct DupesView {
+    walker: dupes::DupeWalker,
+    return_file_id: Option<i64>,
+    /// File `x` was pressed on; a second press within `FLASH_MS` trashes the
+    /// other copies.
+    keep_armed: Option<(i64, Instant)>,
+}
 
-fn switch_dir(
+/// Start stepping through duplicate groups with the first one as the list.
+/// An active search is replaced, keeping its return point. Leaves everything
+/// untouched when the library has no duplicates.
+fn enter_dupes(
     db: &Db,
-    dir: &str,
     files: &mut Vec<FileEntry>,
-    current_dir: &mut String,
     cursor: &mut usize,
-    pos: &str, // "first" or "last"
+    search: &mut Option<SearchView>,
+    dupes: &mut Option<DupesView>,
+) -> Result<usize, String> {
+    let walker = dupes::DupeWalker::new(db).ok_or("no duplicates")?;
+    let return_file_id = match search.take() {
+        Some(s) => s.return_file_id,
+        None => files.get(*cursor).map(|f| f.id),
+    };
+    let view = DupesView {
+        walker,
+        return_file_id,
+        keep_armed: None,
+    };
+    show_dupe_group(&view, files, cursor);
+    eprintln!("dupes: on ({} groups)", view.walker.position().1);
+    *dupes = Some(view);
+    Ok(files.len())
+}
+
+/// Put the walker's current group in `files`, keeping the cursor on the same
+/// file when it is still part of the group.
+fn show_dupe_group(view: &DupesView, files: &mut Vec<FileEntry>, cursor: &mut usize) {
+    let old_id = files.get(*cursor).map(|f| f.id);
+    *files = view.walker.current().files.clone();
+    *cursor = old_id
+        .and_then(|id| files.iter().position(|f| f.id == id))
+        .unwrap_or(0);
+}
+
+/// Leave dupes mode and go back to the list it was opened from.
+fn exit_dupes(
+    db: &Db,
+    view: DupesView,
+    files: &mut Vec<FileEntry>,
+    current_dir: &str,
+    cursor: &mut usize,
+    collection_mode: Option<u8>,
 ) {
-    let new_files = db.files_by_dir(dir);
-    if new_files.is_empty() {
+    restore_list(
+        db,
+        view.return_file_id,
+        files,
+        current_dir,
+        cursor,
+        collection_mode,
+    );
+    eprintln!("dupes: off ({} files)", files.len());
+}
+
+/// Re-check the shown group after files may have left the library (watcher,
+/// `r`, a failed keep). Leaves dupes mode once no groups remain.
+fn refresh_dupes(
+    db: &Db,
+    dupes: &mut Option<DupesView>,
+    files: &mut Vec<FileEntry>,
+    current_dir: &str,
+    cursor: &mut usize,
+    collection_mode: Option<u8>,
+) {
+    let Some(view) = dupes.as_mut() else {
         return;
-    }
-    eprintln!("dir: {} ({} files)", dir, new_files.len());
-    *files = new_files;
-    *current_dir = dir.to_string();
-    *cursor = if pos == "last" {
-        files.len().saturating_sub(1)
-    } else {
-        0
     };
+    if view.walker.prune(db) {
+        show_dupe_group(view, files, cursor);
+    } else if let Some(view) = dupes.take() {
+        exit_dupes(db, view, files, current_dir, cursor, collection_mode);
+    }
 }
 
-fn jump_to(
+/// `x` in dupes mode: keep the file under the cursor and trash the other
+/// copies, then move on to the next group. Needs a second press to confirm.
+fn keep_dupe(
     db: &Db,
-    file: FileEntry,
+    dupes: &mut Option<DupesView>,
+    files: &mut Vec<FileEntry>,
+    current_dir: &str,
+    cursor: &mut usize,
+    collection_mode: Option<u8>,
+) -> Option<Flash> {
+    let view = dupes.as_mut()?;
+    let keep_id = files.get(*cursor)?.id;
+    let armed = matches!(view.keep_armed,
+        Some((id, at)) if id == keep_id && at.elapsed().as_millis() < FLASH_MS);
+    if !armed {
+        view.keep_armed = Some((keep_id, Instant::now()));
+        let others = view.walker.current().files.len() - 1;
+        return Some(Flash::info(format!(
+            "x again: keep this, trash {} other {}",
+            others,
+            if others == 1 { "copy" } else { "copies" }
+        )));
+    }
+    view.keep_armed = None;
+    match dupes::keep_only(db, view.walker.current(), keep_id) {
+        Ok(n) => {
+            let flash = Flash::info(format!("kept 1, trashed {}", n));
+            if view.walker.resolve(db) {
+                show_dupe_group(view, files, cursor);
+                *cursor = 0;
+            } else if let Some(view) = dupes.take() {
+                exit_dupes(db, view, files, current_dir, cursor, collection_mode);
+                return Some(Flash::info(format!("trashed {}, no duplicates left", n)));
+            }
+            Some(flash)
+        }
+        Err(e) => {
+            eprintln!("dupes: keep failed: {}", e);
+            refresh_dupes(db, dupes, files, current_dir, cursor, collection_mode);
+            Some(Flash::error(format!("can't trash: {}", e)))
+        }
+    }
+}
+
+/// Reload the list for the active view after files changed: dupe groups,
+/// search results, collection or current dir. The cursor stays on the same
+/// file, or at the same index (clamped) when that file is gone.
+fn reload_files(
+    db: &Db,
+    dupes: &mut Option<DupesView>,
+    search: &Option<SearchView>,
+    collection_mode: Option<u8>,
+    current_dir: &str,
     files: &mut Vec<FileEntry>,
-    current_dir: &mut String,
     cursor: &mut usize,
 ) {
-    // Check if file is in current dir
-    if let Some(idx) = files.iter().position(|f| f.id == file.id) {
-        *cursor = idx;
+    if dupes.is_some() {
+        refresh_dupes(db, dupes, files, current_dir, cursor, collection_mode);
         return;
     }
-    // Load the file's directory
-    let new_files = db.files_by_dir(&file.dir);
-    if new_files.is_empty() {
-        return;
+    let old_id = files.get(*cursor).map(|f| f.id);
+    *files = if let Some(s) = search {
+        s.load(db)
+    } else if let Some(c) = collection_mode {
+        collection_files(db, c)
+    } else {
+        dir_files(db, current_dir)
+    };
+    *cursor = old_id
+        .and_then(|id| files.iter().position(|f| f.id == id))
+        .unwrap_or((*cursor).min(files.len().saturating_sub(1)));
+}
+
+/// `Delete`: move `targets` to the trash and drop them from the library, as
+/// one undo step. Stops at the first file that can't be trashed.
+fn trash_targets(db: &Db, targets: &[FileEntry]) -> Flash {
+    let paths: Vec<String> = targets.iter().map(|f| f.path.clone()).collect();
+    let label = match paths.len() {
+        1 => "trash".to_string(),
+        n => format!("trash ×{}", n),
+    };
+    match undo::run(db, &label, undo::Change::Trash { paths }) {
+        Ok(1) => Flash::info("trashed (z: undo)".to_string()),
+        Ok(n) => Flash::info(format!("trashed {} (z: undo)", n)),
+        Err(e) => {
+            eprintln!("trash: {}", e);
+            Flash::error(format!("can't trash {}", e))
+        }
     }
-    let idx = new_files.iter().position(|f| f.id == file.id).unwrap_or(0);
-    eprintln!("jump → {} ({} files)", file.dir, new_files.len());
-    *files = new_files;
-    *current_dir = file.dir;
-    *cursor = idx;
+}
+
+/// Pseudo-random index in `0..len` (len > 0), for `u` within search results.
+fn random_index(len: usize) -> usize {
+    use std::hash::BuildHasher;
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    (std::collections::hash_map::RandomState::new().hash_one(seed) % len as u64) as usize
+}
+
+/// Files around the cursor (±`radius`, `[cache] preload`) that are preloaded
+/// and shown in the filmstrip.
+fn preload_window(cursor: usize, len: usize, radius: usize) -> std::ops::Range<usize> {
+    cursor.saturating_sub(radius)..(cursor + radius + 1).min(len)
 }
 
 fn schedule_preload(
@@ -1664,10 +3120,10 @@ fn schedule_preload(
     cache: &TextureCache,
     files: &[FileEntry],
     cursor: usize,
+    radius: usize,
 ) {
-    let start = cursor.saturating_sub(10);
-    let end = (cursor + 11).min(files.len());
-    for (i, file) in files.iter().enumerate().take(end).skip(start) {
+    let window = preload_window(cursor, files.len(), radius);
+    for (i, file) in files.iter().enumerate().take(window.end).skip(window.start) {
         if i == cursor {
             continue;
         }
@@ -1783,6 +3239,13 @@ fn print_report(timings: &[TimingEntry]) {
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::assertions_on_constants,
+    clippy::len_zero,
+    clippy::neg_cmp_op_on_partial_ord,
+    clippy::nonminimal_bool,
+    clippy::unnecessary_literal_unwrap
+)]
 mod tests {
     use super::*;
 
@@ -2087,7 +3550,7 @@ mod tests {
         db.dir_track(&dir_str, true);
         scanner::discover(&db, dir.path());
 
-        let mut files = db.files_by_dir(&dir_str);
+        let mut files = db.files_by_dir(&dir_str, SortOrder::Path);
         let mut current_dir = dir_str.clone();
         let mut cursor = 0usize;
         let mut col = None;
@@ -2521,7 +3984,7 @@ mod tests {
 
         // Simulate the watcher refresh logic from the main loop
         let old_id = files.get(cursor).map(|f| f.id);
-        let new_files = db.files_by_dir(&current_dir);
+        let new_files = db.files_by_dir(&current_dir, SortOrder::Path);
         files = new_files;
         cursor = old_id
             .and_then(|id| files.iter().position(|f| f.id == id))
@@ -2575,7 +4038,7 @@ mod tests {
         db.remove_file_by_path(&bbb_path);
 
         // Refresh
-        let new_files = db.files_by_dir(&current_dir);
+        let new_files = db.files_by_dir(&current_dir, SortOrder::Path);
         files = new_files;
         cursor = old_id
             .and_then(|id| files.iter().position(|f| f.id == id))
@@ -2601,7 +4064,7 @@ mod tests {
         current_dir: &str,
     ) -> bool {
         let old_id = files.get(*cursor).map(|f| f.id);
-        let new_files = db.files_by_dir(current_dir);
+        let new_files = db.files_by_dir(current_dir, SortOrder::Path);
         *files = new_files;
         let fallback = (*cursor).min(files.len().saturating_sub(1));
         *cursor = old_id
@@ -3312,7 +4775,7 @@ mod tests {
 
         // Jump to b.png (in dir_b)
         let dir_b_str = clean_path(&dir_b.path().to_string_lossy());
-        let b_files = db.files_by_dir(&dir_b_str);
+        let b_files = db.files_by_dir(&dir_b_str, SortOrder::Path);
         let target = FileEntry {
             id: b_files[0].id,
             path: b_files[0].path.clone(),
@@ -3638,7 +5101,7 @@ mod tests {
         scanner::rescan(&db, dir.path());
 
         // Refresh file list
-        let new_files = db.files_by_dir(&current_dir);
+        let new_files = db.files_by_dir(&current_dir, SortOrder::Path);
         let old_id = files.get(cursor).map(|f| f.id);
         files = new_files;
         cursor = old_id
@@ -3929,7 +5392,7 @@ mod tests {
         scanner::discover(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let mut files = db.files_by_dir(&dir_str);
+        let mut files = db.files_by_dir(&dir_str, SortOrder::Path);
         let mut current_dir = dir_str.clone();
         let mut cursor = 0usize;
 
@@ -4018,24 +5481,24 @@ mod tests {
         scanner::discover(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let mut files = db.files_by_dir(&dir_str);
+        let mut files = db.files_by_dir(&dir_str, SortOrder::Path);
         let mut cursor = files.len() - 1; // cursor at last file (index 4)
         assert_eq!(cursor, 4);
 
-        // toggle_like needs meta_id, so assign hashes first
+        // Assign hashes first so likes land on the content
         for (i, f) in files.iter().enumerate() {
             db.file_set_hash_meta(f.id, &format!("hash_{}", i));
         }
         // Re-fetch so meta_id is populated
-        files = db.files_by_dir(&dir_str);
+        files = db.files_by_dir(&dir_str, SortOrder::Path);
         cursor = files.len() - 1;
 
         // Like only 2 files
-        db.toggle_like(files[0].id);
-        db.toggle_like(files[1].id);
+        db.set_like(files[0].id, true).unwrap();
+        db.set_like(files[1].id, true).unwrap();
 
         // Switch to "likes" collection (collection 9 = like tag)
-        let liked_files = db.files_by_collection(9);
+        let liked_files = db.files_by_collection(9, SortOrder::Path);
         assert_eq!(liked_files.len(), 2);
 
         // Simulate collection switch with cursor clamping
@@ -4079,23 +5542,23 @@ mod tests {
         // dirs() queries files table — should now contain our dir
         assert!(db.dirs().iter().any(|d| d == &dir_str));
 
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
         assert_eq!(files.len(), 4);
 
-        // 4. Assign hashes so toggle_like works (needs meta_id)
+        // 4. Assign hashes so likes land on the content
         for (i, f) in files.iter().enumerate() {
             db.file_set_hash_meta(f.id, &format!("hash_{}", i));
         }
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
 
         // 5. View and like photo1 (find by name, not index — order is alphabetical)
         let photo1 = files.iter().find(|f| f.filename == "photo1.jpg").unwrap();
         let photo1_id = photo1.id;
         db.record_view(photo1_id);
-        db.toggle_like(photo1_id);
+        db.set_like(photo1_id, true).unwrap();
 
         // 6. Verify like (collection 9 = like tag)
-        let liked = db.files_by_collection(9);
+        let liked = db.files_by_collection(9, SortOrder::Path);
         assert_eq!(liked.len(), 1);
         assert_eq!(liked[0].id, photo1_id);
 
@@ -4104,12 +5567,12 @@ mod tests {
         std::fs::remove_file(dir.path().join("clip.mkv")).unwrap();
         db.remove_file_by_id(clip.id);
 
-        let files_after = db.files_by_dir(&dir_str);
+        let files_after = db.files_by_dir(&dir_str, SortOrder::Path);
         assert_eq!(files_after.len(), 3);
         assert!(!files_after.iter().any(|f| f.filename == "clip.mkv"));
 
         // Like should still work (photo1 was not deleted)
-        let liked_after = db.files_by_collection(9);
+        let liked_after = db.files_by_collection(9, SortOrder::Path);
         assert_eq!(
             liked_after.len(),
             1,
@@ -4121,11 +5584,11 @@ mod tests {
         db.remove_file_by_id(photo1_id);
 
         // Liked collection should now be empty
-        let liked_final = db.files_by_collection(9);
+        let liked_final = db.files_by_collection(9, SortOrder::Path);
         assert_eq!(liked_final.len(), 0);
 
         // 9. Final state
-        assert_eq!(db.files_by_dir(&dir_str).len(), 2);
+        assert_eq!(db.files_by_dir(&dir_str, SortOrder::Path).len(), 2);
     }
 
     // ── Multi-dir workflow integration tests ────────────────────────────
@@ -4153,7 +5616,7 @@ mod tests {
 
         // Start in first dir
         let mut current_dir = dirs[0].clone();
-        let mut files = db.files_by_dir(&current_dir);
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
         let mut cursor = 0usize;
         assert_eq!(files.len(), 2);
 
@@ -4226,7 +5689,7 @@ mod tests {
         scanner::discover(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let mut files = db.files_by_dir(&dir_str);
+        let mut files = db.files_by_dir(&dir_str, SortOrder::Path);
         let mut current_dir = dir_str.clone();
         let mut cursor = 0usize;
 
@@ -4248,7 +5711,7 @@ mod tests {
         scanner::discover(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let mut files = db.files_by_dir(&dir_str);
+        let mut files = db.files_by_dir(&dir_str, SortOrder::Path);
         let mut cursor = 0usize;
         assert_eq!(files.len(), 1);
 
@@ -4305,7 +5768,7 @@ mod tests {
         assert_eq!(files.len(), 5);
 
         // Delete some files from A while we're about to switch
-        let a_files = db.files_by_dir(&dir_a_str);
+        let a_files = db.files_by_dir(&dir_a_str, SortOrder::Path);
         for f in a_files.iter().take(3) {
             db.remove_file_by_id(f.id);
         }
@@ -4348,7 +5811,7 @@ mod tests {
         scanner::discover(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let mut files = db.files_by_dir(&dir_str);
+        let mut files = db.files_by_dir(&dir_str, SortOrder::Path);
         let mut current_dir = dir_str.clone();
         let mut cursor = 0usize;
 
@@ -4365,14 +5828,14 @@ mod tests {
         assert_eq!(files[cursor].filename, "new.jpg");
 
         // Like a file, then use b key (latest fav)
-        // Need hash first for toggle_like
-        let files_fresh = db.files_by_dir(&dir_str);
+        // Hash first so the like lands on the content
+        let files_fresh = db.files_by_dir(&dir_str, SortOrder::Path);
         let f = files_fresh
             .iter()
             .find(|f| f.filename == "old.jpg")
             .unwrap();
         db.file_set_hash_meta(f.id, "hash_old");
-        db.toggle_like(f.id);
+        db.set_like(f.id, true).unwrap();
 
         let latest = db.latest_fav().unwrap();
         assert_eq!(latest.filename, "old.jpg");
@@ -4400,21 +5863,21 @@ mod tests {
 
         let dir_a_str = clean_path(&dir_a.path().canonicalize().unwrap().to_string_lossy());
 
-        let mut files = db.files_by_dir(&dir_a_str);
+        let mut files = db.files_by_dir(&dir_a_str, SortOrder::Path);
         let mut cursor = 0usize;
 
         // Hash all files and like some
-        let all_files = db.files_by_collection(0);
+        let all_files = db.files_by_collection(0, SortOrder::Path);
         for (i, f) in all_files.iter().enumerate() {
             db.file_set_hash_meta(f.id, &format!("hash_{}", i));
         }
         // Re-fetch after hash
-        let all_files = db.files_by_collection(0);
-        db.toggle_like(all_files[0].id); // a1.jpg
-        db.toggle_like(all_files[3].id); // b1.jpg
+        let all_files = db.files_by_collection(0, SortOrder::Path);
+        db.set_like(all_files[0].id, true).unwrap(); // a1.jpg
+        db.set_like(all_files[3].id, true).unwrap(); // b1.jpg
 
         // Switch to likes collection (collection 9)
-        let liked = db.files_by_collection(9);
+        let liked = db.files_by_collection(9, SortOrder::Path);
         assert_eq!(liked.len(), 2);
 
         // Simulate collection mode switch
@@ -4434,7 +5897,7 @@ mod tests {
         assert!(files[cursor].liked);
 
         // Switch back to dir mode
-        files = db.files_by_dir(&dir_a_str);
+        files = db.files_by_dir(&dir_a_str, SortOrder::Path);
         let _cursor = 0;
         assert_eq!(files.len(), 3);
     }
@@ -4451,7 +5914,7 @@ mod tests {
         scanner::discover(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let mut files = db.files_by_dir(&dir_str);
+        let mut files = db.files_by_dir(&dir_str, SortOrder::Path);
         let mut cursor = 0usize;
         assert_eq!(files.len(), 2);
 
@@ -4486,7 +5949,7 @@ mod tests {
         scanner::discover(&db, dir_a.path());
 
         let dir_a_str = clean_path(&dir_a.path().canonicalize().unwrap().to_string_lossy());
-        let mut files = db.files_by_dir(&dir_a_str);
+        let mut files = db.files_by_dir(&dir_a_str, SortOrder::Path);
         let mut current_dir = dir_a_str.clone();
         let mut cursor = 0usize;
         assert_eq!(files.len(), 1);
@@ -4541,7 +6004,7 @@ mod tests {
         scanner::discover(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let mut files = db.files_by_dir(&dir_str);
+        let mut files = db.files_by_dir(&dir_str, SortOrder::Path);
         let mut cursor = 0usize;
 
         // Simulate slideshow: advance cursor
@@ -4658,7 +6121,7 @@ mod tests {
         scanner::discover(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let mut files = db.files_by_dir(&dir_str);
+        let mut files = db.files_by_dir(&dir_str, SortOrder::Path);
         let mut cursor = 5usize; // middle of list
         let viewing_id = files[cursor].id;
 
@@ -4757,7 +6220,7 @@ mod tests {
         scanner::discover(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
         assert_eq!(files.len(), 1);
         // File path should start with the clean dir string
         assert!(
@@ -4809,7 +6272,7 @@ mod tests {
     fn empty_library_files_by_dir_returns_empty() {
         let db = Db::open_memory();
         db.ensure_schema();
-        let files = db.files_by_dir("");
+        let files = db.files_by_dir("", SortOrder::Path);
         assert!(files.is_empty());
     }
 
@@ -4928,4 +6391,496 @@ mod tests {
         assert!(db.navigate_dir("", 1).is_none());
         assert!(db.navigate_dir("", -1).is_none());
     }
+
+    // ── Search view (/ prompt) ──────────────────────────────────────────
+
+    fn setup_search_dirs() -> (Db, tempfile::TempDir, tempfile::TempDir) {
+        let db = Db::open_memory();
+        db.ensure_schema();
+        let dir_a = tempfile::tempdir().unwrap();
+        let dir_b = tempfile::tempdir().unwrap();
+        for d in [&dir_a, &dir_b] {
+            std::fs::write(d.path().join("cat.png"), b"img").unwrap();
+            std::fs::write(d.path().join("dog.jpg"), b"img").unwrap();
+            std::fs::write(d.path().join("clip.mp4"), b"vid").unwrap();
+        }
+        scanner::discover(&db, dir_a.path());
+        scanner::discover(&db, dir_b.path());
+        (db, dir_a, dir_b)
+    }
+
+    #[test]
+    fn search_enter_spans_dirs() {
+        let (db, _a, _b) = setup_search_dirs();
+        let current_dir = db.dirs()[0].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        let mut cursor = 1usize;
+        let mut search = None;
+
+        let n = enter_search(&db, "ext:png", &mut files, &mut cursor, &mut search).unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(cursor, 0);
+        assert!(files.iter().all(|f| f.filename == "cat.png"));
+        let view = search.as_ref().unwrap();
+        assert_eq!(view.text, "ext:png");
+    }
+
+    #[test]
+    fn search_parse_error_leaves_state() {
+        let (db, _a, _b) = setup_search_dirs();
+        let current_dir = db.dirs()[0].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        let mut cursor = 2usize;
+        let mut search = None;
+
+        let err = enter_search(&db, "width>abc", &mut files, &mut cursor, &mut search);
+        assert!(err.is_err());
+        assert_eq!(files.len(), 3);
+        assert_eq!(cursor, 2);
+        assert!(search.is_none());
+    }
+
+    #[test]
+    fn search_no_matches_is_error() {
+        let (db, _a, _b) = setup_search_dirs();
+        let current_dir = db.dirs()[0].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        let mut cursor = 0usize;
+        let mut search = None;
+
+        let err = enter_search(&db, "ext:gif", &mut files, &mut cursor, &mut search);
+        assert_eq!(err, Err("no matches".to_string()));
+        assert_eq!(files.len(), 3);
+        assert!(search.is_none());
+    }
+
+    #[test]
+    fn search_exit_restores_dir_and_cursor() {
+        let (db, _a, _b) = setup_search_dirs();
+        let current_dir = db.dirs()[1].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        let mut cursor = 2usize;
+        let orig_id = files[cursor].id;
+        let mut search = None;
+
+        enter_search(&db, "ext:jpg", &mut files, &mut cursor, &mut search).unwrap();
+        // Refining keeps the original return point
+        enter_search(&db, "dog", &mut files, &mut cursor, &mut search).unwrap();
+        cursor = files.len() - 1;
+
+        exit_search(
+            &db,
+            search.take().unwrap(),
+            &mut files,
+            &current_dir,
+            &mut cursor,
+            None,
+        );
+        assert_eq!(files.len(), 3);
+        assert!(files.iter().all(|f| f.dir == current_dir));
+        assert_eq!(files[cursor].id, orig_id);
+    }
+
+    #[test]
+    fn search_exit_returns_to_collection() {
+        let (db, _a, _b) = setup_search_dirs();
+        for (i, f) in db
+            .files_by_collection(0, SortOrder::Path)
+            .iter()
+            .enumerate()
+        {
+            db.file_set_hash_meta(f.id, &format!("hash_{}", i));
+        }
+        let all = db.files_by_collection(0, SortOrder::Path);
+        let liked = all.iter().find(|f| f.filename == "clip.mp4").unwrap();
+        db.set_like(liked.id, true).unwrap();
+
+        let mut files = db.files_by_collection(9, SortOrder::Path);
+        let mut cursor = 0usize;
+        let mut search = None;
+        enter_search(&db, "ext:png", &mut files, &mut cursor, &mut search).unwrap();
+
+        exit_search(
+            &db,
+            search.take().unwrap(),
+            &mut files,
+            &all[0].dir,
+            &mut cursor,
+            Some(9),
+        );
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[cursor].id, liked.id);
+    }
+
+    #[test]
+    fn search_jump_outside_results_leaves_search() {
+        let (db, _a, _b) = setup_search_dirs();
+        let current_dir = db.dirs()[0].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        let mut cursor = 0usize;
+        let mut search = None;
+        enter_search(&db, "ext:png", &mut files, &mut cursor, &mut search).unwrap();
+
+        let inside = files[1].clone();
+        leave_view_for(&mut search, &inside, &files);
+        assert!(search.is_some());
+
+        let outside = db
+            .files_by_dir(&current_dir, SortOrder::Path)
+            .into_iter()
+            .find(|f| f.filename == "clip.mp4")
+            .unwrap();
+        leave_view_for(&mut search, &outside, &files);
+        assert!(search.is_none());
+    }
+
+    // ── Flatten (`F`) ───────────────────────────────────────────────────
+
+    #[test]
+    fn flatten_lists_subtree_and_exits_back() {
+        let db = Db::open_memory();
+        db.ensure_schema();
+        for path in ["/p/b.jpg", "/p/sub10/a.jpg", "/p/sub9/a.jpg", "/px/c.jpg"] {
+            let (dir, name) = path.rsplit_once('/').unwrap();
+            db.file_insert(path, dir, name, None, None).unwrap();
+        }
+        let paths = |files: &[FileEntry]| files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+        let mut files = db.files_by_dir("/p", SortOrder::Natural);
+        let mut cursor = 0usize;
+        let mut search = None;
+
+        let n = enter_flatten(&db, "/p", &mut files, &mut cursor, &mut search).unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(
+            paths(&files),
+            vec!["/p/b.jpg", "/p/sub9/a.jpg", "/p/sub10/a.jpg"]
+        );
+        assert_eq!(files[cursor].path, "/p/b.jpg");
+        let view = search.as_ref().unwrap();
+        assert!(view.follows_sort());
+        assert_eq!(view.label(), "p/**");
+        assert_eq!(view.subdir(&files[0]).as_deref(), Some("."));
+        assert_eq!(view.subdir(&files[2]).as_deref(), Some("sub10"));
+
+        // `o` on the directory re-sorts the flattened list
+        db.set_sort_for("/p", SortOrder::Path).unwrap();
+        files = view.load(&db);
+        assert_eq!(
+            paths(&files),
+            vec!["/p/b.jpg", "/p/sub10/a.jpg", "/p/sub9/a.jpg"]
+        );
+
+        cursor = 2;
+        exit_search(
+            &db,
+            search.take().unwrap(),
+            &mut files,
+            "/p",
+            &mut cursor,
+            None,
+        );
+        assert_eq!(paths(&files), vec!["/p/b.jpg"]);
+        assert_eq!(cursor, 0);
+
+        let err = enter_flatten(&db, "/q", &mut files, &mut cursor, &mut search);
+        assert_eq!(err, Err("no files".to_string()));
+        assert!(search.is_none());
+    }
+
+    // ── Similar images (`s`) ────────────────────────────────────────
+
+    #[test]
+    fn similar_lists_source_first_and_exits_back() {
+        let db = Db::open_memory();
+        db.ensure_schema();
+        let tmp = tempfile::tempdir().unwrap();
+        let gradient = |w: u32, h: u32| {
+            image::RgbImage::from_fn(w, h, |x, y| {
+                image::Rgb([(x * 255 / w) as u8, (y * 255 / h) as u8, 128])
+            })
+        };
+        gradient(200, 100).save(tmp.path().join("a.png")).unwrap();
+        gradient(100, 50).save(tmp.path().join("b.png")).unwrap();
+        image::RgbImage::from_fn(200, 100, |x, y| {
+            let v = ((x * 7 + y * 13) % 256) as u8;
+            image::Rgb([v, 255 - v, v])
+        })
+        .save(tmp.path().join("c.png"))
+        .unwrap();
+        scanner::discover(&db, tmp.path());
+        let current_dir = db.dirs()[0].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        for f in &files {
+            db.file_set_hash_meta(f.id, &f.filename);
+        }
+        // a.png and c.png as if the PHash layer had run; b.png is hashed on demand
+        for f in [&files[0], &files[2]] {
+            jobs::phash_of(&db, f.id, &f.path).unwrap();
+        }
+
+        // b.png: only a.png looks like it
+        let mut cursor = 1usize;
+        let mut search = None;
+        let n = enter_similar(&db, &mut files, &mut cursor, &mut search).unwrap();
+        assert_eq!(n, 1);
+        let names: Vec<&str> = files.iter().map(|f| f.filename.as_str()).collect();
+        assert_eq!(names, vec!["b.png", "a.png"]);
+        assert_eq!(cursor, 0);
+        let view = search.as_ref().unwrap();
+        assert_eq!(view.label(), "~b.png");
+        assert_eq!(view.prompt_text(), "");
+        assert_eq!(view.load(&db).len(), 2);
+
+        exit_search(
+            &db,
+            search.take().unwrap(),
+            &mut files,
+            &current_dir,
+            &mut cursor,
+            None,
+        );
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[cursor].filename, "b.png");
+
+        // c.png looks like nothing else
+        cursor = 2;
+        assert!(enter_similar(&db, &mut files, &mut cursor, &mut search).is_err());
+        assert!(search.is_none());
+        assert_eq!(files.len(), 3);
+    }
+
+    // ── Dupes mode ──────────────────────────────────────────────────
+
+    /// Same layout as `setup_search_dirs`, hashed so both `cat.png` files
+    /// share content (the other files get unique hashes).
+    fn setup_dupe_dirs() -> (Db, tempfile::TempDir, tempfile::TempDir) {
+        let (db, a, b) = setup_search_dirs();
+        for dir in db.dirs() {
+            for f in db.files_by_dir(&dir, SortOrder::Path) {
+                let hash = if f.filename == "cat.png" {
+                    "cat".to_string()
+                } else {
+                    format!("h{}", f.id)
+                };
+                db.file_set_hash_meta(f.id, &hash);
+            }
+        }
+        (db, a, b)
+    }
+
+    #[test]
+    fn dupes_none_leaves_state() {
+        let (db, _a, _b) = setup_search_dirs();
+        let current_dir = db.dirs()[0].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        let mut cursor = 1usize;
+        let mut search = None;
+        let mut dupes = None;
+        let err = enter_dupes(&db, &mut files, &mut cursor, &mut search, &mut dupes);
+        assert!(err.is_err());
+        assert!(dupes.is_none());
+        assert_eq!(files.len(), 3);
+        assert_eq!(cursor, 1);
+    }
+
+    #[test]
+    fn dupes_show_group_and_exit_restores() {
+        let (db, _a, _b) = setup_dupe_dirs();
+        let current_dir = db.dirs()[0].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        let mut cursor = 2usize;
+        let orig_id = files[cursor].id;
+        let mut search = None;
+        let mut dupes = None;
+
+        let n = enter_dupes(&db, &mut files, &mut cursor, &mut search, &mut dupes).unwrap();
+        assert_eq!(n, 2);
+        assert!(files.iter().all(|f| f.filename == "cat.png"));
+        assert_eq!(dupes.as_ref().unwrap().walker.position(), (1, 1));
+
+        exit_dupes(
+            &db,
+            dupes.take().unwrap(),
+            &mut files,
+            &current_dir,
+            &mut cursor,
+            None,
+        );
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[cursor].id, orig_id);
+    }
+
+    #[test]
+    fn dupes_replace_search_keeping_return_point() {
+        let (db, _a, _b) = setup_dupe_dirs();
+        let current_dir = db.dirs()[0].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        let mut cursor = 1usize;
+        let orig_id = files[cursor].id;
+        let mut search = None;
+        let mut dupes = None;
+        enter_search(&db, "ext:mp4", &mut files, &mut cursor, &mut search).unwrap();
+
+        enter_dupes(&db, &mut files, &mut cursor, &mut search, &mut dupes).unwrap();
+        assert!(search.is_none());
+        assert_eq!(dupes.as_ref().unwrap().return_file_id, Some(orig_id));
+    }
+
+    #[test]
+    fn dupes_refresh_exits_when_copy_removed() {
+        let (db, _a, _b) = setup_dupe_dirs();
+        let current_dir = db.dirs()[0].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        let mut cursor = 0usize;
+        let mut search = None;
+        let mut dupes = None;
+        enter_dupes(&db, &mut files, &mut cursor, &mut search, &mut dupes).unwrap();
+
+        // The watcher removed one copy: a single file is no longer a group
+        db.remove_file_by_path(&files[1].path.clone());
+        refresh_dupes(&db, &mut dupes, &mut files, &current_dir, &mut cursor, None);
+        assert!(dupes.is_none());
+        assert!(files.iter().all(|f| f.dir == current_dir));
+    }
+
+    #[test]
+    fn dupes_keep_needs_second_press() {
+        let (db, _a, _b) = setup_dupe_dirs();
+        let current_dir = db.dirs()[0].clone();
+        let mut files = db.files_by_dir(&current_dir, SortOrder::Path);
+        let mut cursor = 0usize;
+        let mut search = None;
+        let mut dupes = None;
+        enter_dupes(&db, &mut files, &mut cursor, &mut search, &mut dupes).unwrap();
+
+        let flash =
+            keep_dupe(&db, &mut dupes, &mut files, &current_dir, &mut cursor, None).unwrap();
+        assert!(!flash.error);
+        assert!(flash.text.contains("trash 1 other copy"));
+        assert!(dupes.as_ref().unwrap().keep_armed.is_some());
+        assert!(files.iter().all(|f| std::path::Path::new(&f.path).exists()));
+    }
+
+    #[test]
+    fn random_index_in_range() {
+        for len in [1usize, 2, 7] {
+            for _ in 0..20 {
+                assert!(random_index(len) < len);
+            }
+        }
+    }
+
+    // ── Batch actions on a selection ────────────────────────────────────
+
+    #[test]
+    fn batch_like_and_tag_toggle_as_a_group() {
+        let (db, dir) = setup_drop_dir(&["a.jpg", "b.jpg", "c.jpg"]);
+        let d = dir.path().to_string_lossy().to_string();
+        for name in ["a.jpg", "b.jpg", "c.jpg"] {
+            let path = dir.path().join(name).to_string_lossy().to_string();
+            db.file_insert(&path, &d, name, Some(4), None);
+        }
+        let mut files = db.files_by_dir(&d, SortOrder::Path);
+        toggle_like_at(&db, &mut files[0]);
+
+        let mut sel = selection::Selection::new();
+        sel.toggle(files[0].id);
+        sel.toggle(files[1].id);
+        let targets = sel.targets(&db, &files, 2);
+        assert_eq!(targets.len(), 2);
+
+        // One of two liked → like both
+        assert_eq!(like_targets(&db, &targets, &mut files).text, "+♥ ×2");
+        assert!(files[0].liked && files[1].liked && !files[2].liked);
+        let targets = sel.targets(&db, &files, 2);
+        assert_eq!(like_targets(&db, &targets, &mut files).text, "-♥ ×2");
+        assert!(db
+            .files_by_dir(&d, SortOrder::Path)
+            .iter()
+            .all(|f| !f.liked));
+
+        assert_eq!(tag_targets(&db, &targets, "c3").text, "+c3 ×2");
+        assert_eq!(db.file_tags(files[1].id), vec!["c3".to_string()]);
+        assert_eq!(tag_targets(&db, &targets, "c3").text, "-c3 ×2");
+        assert!(db.file_tags(files[0].id).is_empty());
+    }
+
+    // ── Delete to trash + undo ──────────────────────────────────────────
+
+    #[test]
+    fn restore_brings_back_row_and_tags() {
+        let (db, dir) = setup_drop_dir(&["a.jpg", "b.jpg", "c.jpg"]);
+        let mut files = Vec::new();
+        let mut current_dir = String::new();
+        let mut cursor = 0usize;
+        let mut col = None;
+        handle_drop(
+            &db,
+            dir.path(),
+            &mut files,
+            &mut current_dir,
+            &mut cursor,
+            &mut col,
+        );
+        cursor = 1;
+        let b = files[1].clone();
+        db.file_set_hash_meta(b.id, "hash_b");
+        db.set_tag(b.id, "c3", true).unwrap();
+
+        // What `Delete` does, with a stand-in trash dir
+        let bin = tempfile::tempdir().unwrap();
+        let trashed = bin.path().join("b.jpg");
+        let hash = db.file_hash(b.id);
+        assert_eq!(hash.as_deref(), Some("hash_b"));
+        std::fs::rename(&b.path, &trashed).unwrap();
+        db.remove_file_by_path(&b.path);
+        let restore = undo::Change::Restore {
+            files: vec![undo::TrashedFile {
+                original: b.path.clone(),
+                trashed,
+                hash,
+            }],
+        };
+        let mut dupes = None;
+        reload_files(
+            &db,
+            &mut dupes,
+            &None,
+            None,
+            &current_dir,
+            &mut files,
+            &mut cursor,
+        );
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[cursor].filename, "c.jpg"); // same index, next file
+
+        assert_eq!(undo::run(&db, "restore", restore.clone()), Ok(1));
+        assert!(std::path::Path::new(&b.path).exists());
+        let (restored, _, _) = db.file_lookup(&b.path).unwrap();
+        assert_eq!(db.file_tags(restored), vec!["c3".to_string()]);
+        reload_files(
+            &db,
+            &mut dupes,
+            &None,
+            None,
+            &current_dir,
+            &mut files,
+            &mut cursor,
+        );
+        assert_eq!(files.len(), 3);
+
+        // Restoring again fails cleanly: the file is already back
+        assert!(undo::run(&db, "restore", restore).is_err());
+        assert_eq!(db.files_by_dir(&current_dir, SortOrder::Path).len(), 3);
+    }
+
+    // ── Preload window (shared with the filmstrip) ──────────────────────
+
+    #[test]
+    fn preload_window_clamps_to_list() {
+        assert_eq!(preload_window(0, 5, 10), 0..5);
+        assert_eq!(preload_window(15, 100, 10), 5..26);
+        assert_eq!(preload_window(98, 100, 10), 88..100);
+        assert_eq!(preload_window(0, 0, 10), 0..0);
+    }
 }
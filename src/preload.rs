@@ -8,30 +8,65 @@
 //! Background threads only do CPU work (image decode). GL uploads happen on the main thread.
 
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
-use image::GenericImageView;
+use image::{GenericImageView, ImageDecoder};
 
 /// Decoded image: raw RGBA pixels ready for GL upload.
 pub struct DecodedImage {
     pub rgba: Vec<u8>,
     pub width: u32,
     pub height: u32,
+    /// EXIF orientation (1-8) of the pixels as stored; applied when drawing.
+    pub orientation: u8,
 }
 
 impl DecodedImage {
     /// Decode an image file to RGBA. Returns None on failure.
     pub fn from_file(path: &str) -> Option<Self> {
-        let img = image::open(path).ok()?;
+        let mut decoder = image::ImageReader::open(path)
+            .ok()?
+            .with_guessed_format()
+            .ok()?
+            .into_decoder()
+            .ok()?;
+        let orientation = decoder.orientation().map(|o| o.to_exif()).unwrap_or(1);
+        let img = image::DynamicImage::from_decoder(decoder).ok()?;
         let (w, h) = img.dimensions();
         let rgba = img.into_rgba8().into_raw();
         Some(DecodedImage {
             rgba,
             width: w,
             height: h,
+            orientation,
         })
     }
+
+    /// Wrap an already upright image.
+    pub fn from_image(img: image::DynamicImage) -> Self {
+        let (w, h) = img.dimensions();
+        DecodedImage {
+            rgba: img.into_rgba8().into_raw(),
+            width: w,
+            height: h,
+            orientation: 1,
+        }
+    }
+
+    /// Decode a small preview of `path`: its stored thumbnail when there is
+    /// one, otherwise a shrunken copy of the image itself. Videos without a
+    /// stored thumbnail have no preview.
+    pub fn thumbnail(path: &str, stored: Option<&Path>) -> Option<Self> {
+        if let Some(thumb) = stored.filter(|t| t.exists()) {
+            return Self::from_file(&thumb.to_string_lossy());
+        }
+        if !crate::is_image(path) {
+            return None;
+        }
+        crate::thumbs::from_image(path).ok().map(Self::from_image)
+    }
 }
 
 /// Info about a cached GL texture.
@@ -40,6 +75,7 @@ pub struct TexInfo {
     pub gl_id: u32,
     pub width: u32,
     pub height: u32,
+    pub orientation: u8,
 }
 
 /// LRU texture cache — keeps up to `capacity` GL textures on the GPU.
@@ -119,6 +155,7 @@ impl TextureCache {
                 gl_id,
                 width: img.width,
                 height: img.height,
+                orientation: img.orientation,
             },
         );
         self.order.push_back(path.to_string());
@@ -180,8 +217,26 @@ impl Preloader {
         img
     }
 
+    /// Number of decodes running or waiting to be taken.
+    pub fn in_flight(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
     /// Schedule background decode of an image file.
     pub fn schedule(&self, path: String) {
+        self.spawn(path, DecodedImage::from_file);
+    }
+
+    /// Schedule background decode of a thumbnail-sized preview of `path`
+    /// (see `DecodedImage::thumbnail`), keyed by `path`.
+    pub fn schedule_thumb(&self, path: String, stored: Option<PathBuf>) {
+        self.spawn(path, move |p| DecodedImage::thumbnail(p, stored.as_deref()));
+    }
+
+    fn spawn<F>(&self, path: String, decode: F)
+    where
+        F: FnOnce(&str) -> Option<DecodedImage> + Send + 'static,
+    {
         {
             let mut pending = self.pending.lock().unwrap();
             if pending.contains(&path) {
@@ -194,16 +249,9 @@ impl Preloader {
         let ready = self.ready.clone();
 
         thread::spawn(move || {
-            if let Some(img) = DecodedImage::from_file(&path) {
+            if let Some(img) = decode(&path) {
                 // Store in ready map
-                ready.lock().unwrap().insert(
-                    path.clone(),
-                    DecodedImage {
-                        rgba: img.rgba,
-                        width: img.width,
-                        height: img.height,
-                    },
-                );
+                ready.lock().unwrap().insert(path.clone(), img);
             } else {
                 // Failed — remove from pending
                 pending.lock().unwrap().remove(&path);
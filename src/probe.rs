@@ -0,0 +1,850 @@
+//! Headless probing of video containers: MP4/MOV (ISO-BMFF) and
+//! Matroska/WebM (EBML).
+//!
+//! Only container headers are read — the `moov` box, or the Matroska
+//! `Info`/`Tracks` elements plus the first clusters for keyframe spacing —
+//! so probing a multi-gigabyte file takes a handful of small reads.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// What the video layer stores in `meta`.
+#[derive(Default, Debug, PartialEq)]
+pub struct VideoInfo {
+    pub format: &'static str,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub duration_ms: Option<i64>,
+    /// Overall bits per second (file size over duration).
+    pub bitrate: Option<i64>,
+    /// Codec names, video tracks first (`h264`, `aac`).
+    pub codecs: Vec<String>,
+    /// Keyframe interval of the video track, in frames.
+    pub gop_frames: Option<u32>,
+}
+
+/// Largest `moov` box read into memory.
+const MAX_MOOV: u64 = 64 * 1024 * 1024;
+/// How far into a Matroska file clusters are scanned for keyframes.
+const MAX_CLUSTER_SCAN: u64 = 64 * 1024 * 1024;
+/// Keyframe intervals averaged for the GOP length.
+const GOP_SAMPLES: usize = 4;
+
+pub fn probe(path: &str) -> Result<VideoInfo, String> {
+    let mut f = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let size = f.metadata().map_err(|e| e.to_string())?.len();
+    probe_reader(&mut f, size)
+}
+
+fn probe_reader<R: Read + Seek>(r: &mut R, size: u64) -> Result<VideoInfo, String> {
+    let mut head = [0u8; 8];
+    r.read_exact(&mut head).map_err(|_| "file too short")?;
+    r.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+    let mut info = if head[..4] == [0x1A, 0x45, 0xDF, 0xA3] {
+        matroska(r, size)
+    } else if matches!(
+        &head[4..8],
+        b"ftyp" | b"moov" | b"mdat" | b"wide" | b"free" | b"skip"
+    ) {
+        mp4(r, size)
+    } else {
+        return Err("unsupported container".into());
+    }
+    .map_err(|e| e.to_string())?;
+    if let Some(ms) = info.duration_ms.filter(|&ms| ms > 0) {
+        info.bitrate = Some((size as i64).saturating_mul(8000) / ms);
+    }
+    Ok(info)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn be(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64)
+}
+
+/// Mean keyframe distance over the intervals seen; one unfinished GOP
+/// counts only if nothing else was seen.
+#[derive(Default)]
+struct GopCounter {
+    since_key: Option<u32>,
+    intervals: Vec<u32>,
+}
+
+impl GopCounter {
+    fn frame(&mut self, key: bool) {
+        match (key, self.since_key) {
+            (true, Some(n)) => {
+                self.intervals.push(n);
+                self.since_key = Some(1);
+            }
+            (true, None) => self.since_key = Some(1),
+            (false, Some(n)) => self.since_key = Some(n + 1),
+            (false, None) => {}
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.intervals.len() >= GOP_SAMPLES
+    }
+
+    fn result(&self) -> Option<u32> {
+        if self.intervals.is_empty() {
+            return self.since_key;
+        }
+        let sum: u32 = self.intervals.iter().sum();
+        let n = self.intervals.len() as u32;
+        Some((sum + n / 2) / n)
+    }
+}
+
+// ── MP4 / MOV ───────────────────────────────────────────────────────────
+
+fn mp4<R: Read + Seek>(r: &mut R, size: u64) -> io::Result<VideoInfo> {
+    let mut brand = [0u8; 4];
+    let mut pos = 0u64;
+    while size.saturating_sub(pos) >= 8 {
+        r.seek(SeekFrom::Start(pos))?;
+        let mut hdr = [0u8; 8];
+        r.read_exact(&mut hdr)?;
+        let (mut len, mut header) = (be(&hdr[..4]), 8);
+        if len == 1 {
+            let mut large = [0u8; 8];
+            r.read_exact(&mut large)?;
+            len = be(&large);
+            header = 16;
+        } else if len == 0 {
+            len = size - pos;
+        }
+        if len < header {
+            return Err(invalid("bad box size"));
+        }
+        match &hdr[4..8] {
+            b"ftyp" => r.read_exact(&mut brand)?,
+            b"moov" => {
+                if len - header > MAX_MOOV {
+                    return Err(invalid("moov box too large"));
+                }
+                let mut moov = vec![0u8; (len - header) as usize];
+                r.read_exact(&mut moov)?;
+                let mut info = parse_moov(&moov);
+                info.format = if &brand == b"qt  " { "MOV" } else { "MP4" };
+                return Ok(info);
+            }
+            _ => {}
+        }
+        pos = pos
+            .checked_add(len)
+            .ok_or_else(|| invalid("bad box size"))?;
+    }
+    Err(invalid("no moov box"))
+}
+
+/// ISO-BMFF child boxes: (type, body).
+fn boxes(data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let size = be(&data[pos..pos + 4]) as usize;
+        let (header, size) = match size {
+            0 => (8, data.len() - pos),
+            1 if pos + 16 <= data.len() => (16, be(&data[pos + 8..pos + 16]) as usize),
+            n => (8, n),
+        };
+        if size < header {
+            break;
+        }
+        let Some(end) = pos.checked_add(size) else {
+            break;
+        };
+        let Some(body) = data.get(pos + header..end) else {
+            break;
+        };
+        out.push((&data[pos + 4..pos + 8], body));
+        pos = end;
+    }
+    out
+}
+
+fn child<'a>(data: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+    boxes(data)
+        .into_iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, b)| b)
+}
+
+fn parse_moov(moov: &[u8]) -> VideoInfo {
+    let mut info = VideoInfo::default();
+    let (mut video, mut audio) = (Vec::new(), Vec::new());
+    for (kind, body) in boxes(moov) {
+        match kind {
+            b"mvhd" if body.len() >= 32 => {
+                let (scale, duration) = if body[0] == 1 {
+                    (be(&body[20..24]), be(&body[24..32]))
+                } else {
+                    (be(&body[12..16]), be(&body[16..20]))
+                };
+                if scale > 0 && duration > 0 && duration != u32::MAX as u64 {
+                    info.duration_ms = duration.checked_mul(1000).map(|d| (d / scale) as i64);
+                }
+            }
+            b"trak" => {
+                let Some(mdia) = child(body, b"mdia") else {
+                    continue;
+                };
+                let handler = child(mdia, b"hdlr").and_then(|h| h.get(8..12));
+                let stbl = child(mdia, b"minf").and_then(|m| child(m, b"stbl"));
+                let codec = stbl
+                    .and_then(|s| child(s, b"stsd"))
+                    .and_then(|d| d.get(12..16))
+                    .map(mp4_codec);
+                match handler {
+                    Some(b"vide") => {
+                        video.extend(codec);
+                        if info.width.is_none() {
+                            if let Some((w, h)) = child(body, b"tkhd").and_then(tkhd_size) {
+                                info.width = Some(w);
+                                info.height = Some(h);
+                            }
+                            info.gop_frames = stbl.and_then(mp4_gop);
+                        }
+                    }
+                    Some(b"soun") => audio.extend(codec),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    info.codecs = video.into_iter().chain(audio).collect();
+    info.codecs.dedup();
+    info
+}
+
+/// Display size from the track header, swapped for 90°/270° rotation
+/// matrices (phone videos).
+fn tkhd_size(tkhd: &[u8]) -> Option<(u32, u32)> {
+    let base = if *tkhd.first()? == 1 { 36 } else { 24 };
+    let matrix = tkhd.get(base + 16..base + 52)?;
+    let dims = tkhd.get(base + 52..base + 60)?;
+    let (w, h) = ((be(&dims[..4]) >> 16) as u32, (be(&dims[4..]) >> 16) as u32);
+    if w == 0 || h == 0 {
+        return None;
+    }
+    // a == 0 with b != 0: a quarter turn
+    let rotated = be(&matrix[..4]) == 0 && be(&matrix[4..8]) != 0;
+    Some(if rotated { (h, w) } else { (w, h) })
+}
+
+/// Keyframe interval from the sync sample table. No `stss` means every
+/// sample is a keyframe; an unsorted one gives nothing.
+fn mp4_gop(stbl: &[u8]) -> Option<u32> {
+    let Some(stss) = child(stbl, b"stss") else {
+        return Some(1);
+    };
+    let count = be(stss.get(4..8)?) as usize;
+    let sync: Vec<u64> = (0..count)
+        .map_while(|i| stss.get(8 + i * 4..12 + i * 4).map(be))
+        .collect();
+    match sync.as_slice() {
+        [] => None,
+        [_] => child(stbl, b"stsz")
+            .and_then(|s| s.get(8..12))
+            .map(|n| be(n) as u32),
+        _ if sync.windows(2).any(|w| w[1] <= w[0]) => None,
+        [first, .., last] => {
+            let n = sync.len() as u64 - 1;
+            let span = last.checked_sub(*first)?;
+            Some(((span + n / 2) / n) as u32)
+        }
+    }
+}
+
+fn mp4_codec(fourcc: &[u8]) -> String {
+    match fourcc {
+        b"avc1" | b"avc3" => "h264",
+        b"hvc1" | b"hev1" => "hevc",
+        b"av01" => "av1",
+        b"vp08" => "vp8",
+        b"vp09" => "vp9",
+        b"mp4v" => "mpeg4",
+        b"apch" | b"apcn" | b"apcs" | b"apco" | b"ap4h" => "prores",
+        b"jpeg" | b"mjpa" => "mjpeg",
+        b"mp4a" => "aac",
+        b"Opus" => "opus",
+        b"fLaC" => "flac",
+        b"ac-3" => "ac3",
+        b"ec-3" => "eac3",
+        b".mp3" => "mp3",
+        b"lpcm" | b"sowt" | b"twos" => "pcm",
+        other => return String::from_utf8_lossy(other).trim().to_lowercase(),
+    }
+    .to_string()
+}
+
+// ── Matroska / WebM ─────────────────────────────────────────────────────
+
+const EBML: u32 = 0x1A45_DFA3;
+const DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_NUMBER: u32 = 0xD7;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const CLUSTER: u32 = 0x1F43_B675;
+const SIMPLE_BLOCK: u32 = 0xA3;
+const BLOCK_GROUP: u32 = 0xA0;
+const BLOCK: u32 = 0xA1;
+const REFERENCE_BLOCK: u32 = 0xFB;
+
+/// Element ID (marker bits kept, as in the spec) or `None` at a bad byte.
+fn vint_id(first: u8) -> Option<usize> {
+    match first.leading_zeros() {
+        n @ 0..=3 => Some(n as usize + 1),
+        _ => None,
+    }
+}
+
+/// Read an element header: (id, body size or `None` if unknown, header length).
+fn read_header<R: Read>(r: &mut R) -> io::Result<(u32, Option<u64>, u64)> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b[..1])?;
+    let id_len = vint_id(b[0]).ok_or_else(|| invalid("bad element id"))?;
+    r.read_exact(&mut b[1..id_len])?;
+    let id = be(&b[..id_len]) as u32;
+
+    let mut s = [0u8; 8];
+    r.read_exact(&mut s[..1])?;
+    let size_len = s[0].leading_zeros() as usize + 1;
+    if size_len > 8 {
+        return Err(invalid("bad element size"));
+    }
+    r.read_exact(&mut s[1..size_len])?;
+    s[0] &= (0xFFu16 >> size_len) as u8;
+    let value = be(&s[..size_len]);
+    let unknown = value == (1u64 << (7 * size_len)) - 1;
+    Ok((id, (!unknown).then_some(value), (id_len + size_len) as u64))
+}
+
+/// In-memory child elements: (id, body).
+fn elements(mut data: &[u8]) -> Vec<(u32, &[u8])> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let mut cursor = io::Cursor::new(data);
+        let Ok((id, Some(size), header)) = read_header(&mut cursor) else {
+            break;
+        };
+        let Some(body) = data.get(header as usize..(header + size) as usize) else {
+            break;
+        };
+        out.push((id, body));
+        data = &data[(header + size) as usize..];
+    }
+    out
+}
+
+fn ebml_float(b: &[u8]) -> Option<f64> {
+    match b.len() {
+        4 => Some(f32::from_be_bytes(b.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(b.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+fn read_body<R: Read>(r: &mut R, size: u64, cap: u64) -> io::Result<Vec<u8>> {
+    if size > cap {
+        return Err(invalid("element too large"));
+    }
+    let mut body = vec![0u8; size as usize];
+    r.read_exact(&mut body)?;
+    Ok(body)
+}
+
+/// Track number at the start of a (Simple)Block.
+fn block_track(data: &[u8]) -> Option<(u64, usize)> {
+    let len = data.first()?.leading_zeros() as usize + 1;
+    if len > 8 {
+        return None;
+    }
+    let mut v = data.get(..len)?.to_vec();
+    v[0] &= (0xFFu16 >> len) as u8;
+    Some((be(&v), len))
+}
+
+fn matroska<R: Read + Seek>(r: &mut R, size: u64) -> io::Result<VideoInfo> {
+    let (id, hsize, _) = read_header(r)?;
+    if id != EBML {
+        return Err(invalid("not an EBML file"));
+    }
+    let header = read_body(r, hsize.ok_or_else(|| invalid("bad EBML header"))?, 4096)?;
+    let webm = elements(&header)
+        .iter()
+        .any(|(id, b)| *id == DOC_TYPE && *b == b"webm");
+
+    let (id, seg_size, _) = read_header(r)?;
+    if id != SEGMENT {
+        return Err(invalid("no segment"));
+    }
+    let seg_start = r.stream_position()?;
+    let seg_end = seg_size.map_or(size, |s| (seg_start + s).min(size));
+
+    let mut info = VideoInfo {
+        format: if webm { "WebM" } else { "Matroska" },
+        ..Default::default()
+    };
+    let mut scale = 1_000_000u64;
+    let mut duration = None;
+    let mut video_track = None;
+    let (mut video, mut audio) = (Vec::new(), Vec::new());
+    let mut gop = GopCounter::default();
+    let mut cluster_start = None;
+
+    let mut pos = seg_start;
+    while pos < seg_end {
+        r.seek(SeekFrom::Start(pos))?;
+        let Ok((id, body_size, header)) = read_header(r) else {
+            break;
+        };
+        let body = pos + header;
+        // Clusters are walked into (they may have unknown size); their
+        // blocks are read in place.
+        if id == CLUSTER {
+            if video_track.is_none() || gop.done() {
+                break;
+            }
+            let start = *cluster_start.get_or_insert(body);
+            if body - start > MAX_CLUSTER_SCAN {
+                break;
+            }
+            pos = body;
+            continue;
+        }
+        let Some(body_size) = body_size else {
+            break;
+        };
+        match id {
+            INFO => {
+                for (id, b) in elements(&read_body(r, body_size, 1 << 20)?) {
+                    match id {
+                        TIMESTAMP_SCALE => scale = be(b).max(1),
+                        DURATION => duration = ebml_float(b),
+                        _ => {}
+                    }
+                }
+            }
+            TRACKS => {
+                let tracks = read_body(r, body_size, 16 << 20)?;
+                for (id, entry) in elements(&tracks) {
+                    if id != TRACK_ENTRY {
+                        continue;
+                    }
+                    let (mut number, mut kind, mut codec) = (None, 0, None);
+                    let (mut w, mut h) = (None, None);
+                    for (id, b) in elements(entry) {
+                        match id {
+                            TRACK_NUMBER => number = Some(be(b)),
+                            TRACK_TYPE => kind = be(b),
+                            CODEC_ID => codec = Some(mkv_codec(&String::from_utf8_lossy(b))),
+                            VIDEO => {
+                                for (id, b) in elements(b) {
+                                    match id {
+                                        PIXEL_WIDTH => w = Some(be(b) as u32),
+                                        PIXEL_HEIGHT => h = Some(be(b) as u32),
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    match kind {
+                        1 => {
+                            video.extend(codec);
+                            if video_track.is_none() {
+                                video_track = number;
+                                info.width = w;
+                                info.height = h;
+                            }
+                        }
+                        2 => audio.extend(codec),
+                        _ => {}
+                    }
+                }
+            }
+            SIMPLE_BLOCK => {
+                let head = read_body(r, body_size.min(16), 16)?;
+                if let Some((track, len)) = block_track(&head) {
+                    if Some(track) == video_track {
+                        let flags = head.get(len + 2).copied().unwrap_or(0);
+                        gop.frame(flags & 0x80 != 0);
+                    }
+                }
+            }
+            BLOCK_GROUP => {
+                // Keyframes are the blocks without a ReferenceBlock
+                let (mut track, mut key) = (None, true);
+                let mut p = body;
+                while p < body + body_size {
+                    r.seek(SeekFrom::Start(p))?;
+                    let (id, Some(size), header) = read_header(r)? else {
+                        break;
+                    };
+                    match id {
+                        BLOCK => {
+                            let head = read_body(r, size.min(8), 8)?;
+                            track = block_track(&head).map(|(t, _)| t);
+                        }
+                        REFERENCE_BLOCK => key = false,
+                        _ => {}
+                    }
+                    p += header + size;
+                }
+                if track.is_some() && track == video_track {
+                    gop.frame(key);
+                }
+            }
+            _ => {}
+        }
+        pos = body + body_size;
+    }
+
+    if let Some(d) = duration {
+        info.duration_ms = Some((d * scale as f64 / 1_000_000.0) as i64);
+    }
+    info.codecs = video.into_iter().chain(audio).collect();
+    info.codecs.dedup();
+    info.gop_frames = gop.result();
+    Ok(info)
+}
+
+fn mkv_codec(id: &str) -> String {
+    match id {
+        "V_MPEG4/ISO/AVC" => "h264",
+        "V_MPEGH/ISO/HEVC" => "hevc",
+        "V_AV1" => "av1",
+        "V_VP8" => "vp8",
+        "V_VP9" => "vp9",
+        "V_MPEG4/ISO/ASP" | "V_MPEG4/ISO/SP" => "mpeg4",
+        "V_MJPEG" => "mjpeg",
+        "V_PRORES" => "prores",
+        "A_OPUS" => "opus",
+        "A_VORBIS" => "vorbis",
+        "A_FLAC" => "flac",
+        "A_AC3" => "ac3",
+        "A_EAC3" => "eac3",
+        "A_MPEG/L3" => "mp3",
+        "A_DTS" => "dts",
+        "A_TRUEHD" => "truehd",
+        id if id.starts_with("A_AAC") => "aac",
+        id if id.starts_with("A_PCM") => "pcm",
+        other => {
+            return other
+                .split_once('_')
+                .map_or(other, |(_, rest)| rest)
+                .to_lowercase()
+        }
+    }
+    .to_string()
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn bmff(kind: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut b = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        b.extend_from_slice(kind);
+        b.extend_from_slice(body);
+        b
+    }
+
+    fn full(version: u8, body: &[u8]) -> Vec<u8> {
+        let mut b = vec![version, 0, 0, 0];
+        b.extend_from_slice(body);
+        b
+    }
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    fn tkhd(w: u32, h: u32, rotated: bool) -> Vec<u8> {
+        let mut b = vec![0u8; 20 + 16];
+        let matrix: [i32; 9] = if rotated {
+            [0, 0x10000, 0, -0x10000, 0, 0, 0, 0, 0x4000_0000]
+        } else {
+            [0x10000, 0, 0, 0, 0x10000, 0, 0, 0, 0x4000_0000]
+        };
+        b.extend(matrix.iter().flat_map(|v| v.to_be_bytes()));
+        b.extend(u32s(&[w << 16, h << 16]));
+        bmff(b"tkhd", &full(0, &b))
+    }
+
+    fn trak(handler: &[u8], codec: &[u8], tkhd_box: Vec<u8>, stss: Option<&[u32]>) -> Vec<u8> {
+        let mut hdlr = vec![0; 4];
+        hdlr.extend_from_slice(handler);
+        hdlr.extend_from_slice(&[0; 12]);
+        let mut stsd = u32s(&[1]);
+        stsd.extend(bmff(codec, &[0; 8]));
+        let mut stbl = bmff(b"stsd", &full(0, &stsd));
+        stbl.extend(bmff(b"stsz", &full(0, &u32s(&[0, 300]))));
+        if let Some(sync) = stss {
+            let mut body = u32s(&[sync.len() as u32]);
+            body.extend(u32s(sync));
+            stbl.extend(bmff(b"stss", &full(0, &body)));
+        }
+        let minf = bmff(b"minf", &bmff(b"stbl", &stbl));
+        let mut mdia = bmff(b"hdlr", &full(0, &hdlr));
+        mdia.extend(minf);
+        let mut t = tkhd_box;
+        t.extend(bmff(b"mdia", &mdia));
+        bmff(b"trak", &t)
+    }
+
+    fn mp4_file(brand: &[u8], traks: Vec<Vec<u8>>, mdat_len: usize) -> Vec<u8> {
+        // mvhd v0: timescale 1000, duration 12.5 s
+        let mut mvhd = u32s(&[0, 0, 1000, 12_500]);
+        mvhd.extend(vec![0; 80]);
+        let mut moov = bmff(b"mvhd", &full(0, &mvhd));
+        for t in traks {
+            moov.extend(t);
+        }
+        let mut ftyp = brand.to_vec();
+        ftyp.extend_from_slice(&[0, 0, 0, 0]);
+        let mut f = bmff(b"ftyp", &ftyp);
+        // mdat before moov, as most cameras write it
+        f.extend(bmff(b"mdat", &vec![0; mdat_len]));
+        f.extend(bmff(b"moov", &moov));
+        f
+    }
+
+    fn probe_bytes(data: &[u8]) -> Result<VideoInfo, String> {
+        probe_reader(&mut Cursor::new(data), data.len() as u64)
+    }
+
+    #[test]
+    fn mp4_tracks() {
+        let data = mp4_file(
+            b"isom",
+            vec![
+                trak(
+                    b"vide",
+                    b"avc1",
+                    tkhd(1920, 1080, false),
+                    Some(&[1, 31, 61, 91]),
+                ),
+                trak(b"soun", b"mp4a", tkhd(0, 0, false), None),
+            ],
+            10_000,
+        );
+        let info = probe_bytes(&data).unwrap();
+        assert_eq!(info.format, "MP4");
+        assert_eq!((info.width, info.height), (Some(1920), Some(1080)));
+        assert_eq!(info.duration_ms, Some(12_500));
+        assert_eq!(info.codecs, vec!["h264", "aac"]);
+        assert_eq!(info.gop_frames, Some(30));
+        assert_eq!(info.bitrate, Some(data.len() as i64 * 8000 / 12_500));
+    }
+
+    #[test]
+    fn mov_rotated_without_stss() {
+        let data = mp4_file(
+            b"qt  ",
+            vec![trak(b"vide", b"hvc1", tkhd(1920, 1080, true), None)],
+            0,
+        );
+        let info = probe_bytes(&data).unwrap();
+        assert_eq!(info.format, "MOV");
+        assert_eq!((info.width, info.height), (Some(1080), Some(1920)));
+        assert_eq!(info.codecs, vec!["hevc"]);
+        // Intra-only: every sample is a keyframe
+        assert_eq!(info.gop_frames, Some(1));
+    }
+
+    #[test]
+    fn mp4_single_keyframe_is_whole_clip() {
+        let data = mp4_file(
+            b"isom",
+            vec![trak(b"vide", b"av01", tkhd(640, 480, false), Some(&[1]))],
+            0,
+        );
+        assert_eq!(probe_bytes(&data).unwrap().gop_frames, Some(300));
+    }
+
+    #[test]
+    fn mp4_corrupt_sizes_and_unsorted_stss() {
+        // A largesize box running past u64::MAX ends the walk inside moov;
+        // keyframes out of order give no GOP
+        let mut large = u32s(&[1]);
+        large.extend_from_slice(b"free");
+        large.extend((u64::MAX - 4).to_be_bytes());
+        let mut data = mp4_file(
+            b"isom",
+            vec![trak(
+                b"vide",
+                b"avc1",
+                tkhd(640, 480, false),
+                Some(&[31, 61, 1]),
+            )],
+            0,
+        );
+        let at = data.windows(4).rposition(|w| w == b"moov").unwrap() - 4;
+        let moov_len = be(&data[at..at + 4]) as u32 + large.len() as u32;
+        data[at..at + 4].copy_from_slice(&moov_len.to_be_bytes());
+        data.extend(&large);
+        let info = probe_bytes(&data).unwrap();
+        assert_eq!(info.gop_frames, None);
+        assert_eq!(info.codecs, vec!["h264"]);
+
+        // ... and at the top level is an error
+        let mut data = bmff(b"ftyp", b"isom\0\0\0\0");
+        data.extend(&large);
+        data.extend([0; 16]);
+        assert!(probe_bytes(&data).is_err());
+    }
+
+    #[test]
+    fn mp4_without_moov_is_error() {
+        let mut data = bmff(b"ftyp", b"isom\0\0\0\0");
+        data.extend(bmff(b"mdat", &[0; 64]));
+        assert!(probe_bytes(&data).is_err());
+        assert!(probe_bytes(b"plain text file").is_err());
+    }
+
+    /// EBML element with an 8-byte size field.
+    fn el(id: u32, body: &[u8]) -> Vec<u8> {
+        let id_bytes = id.to_be_bytes();
+        let skip = id_bytes.iter().position(|&b| b != 0).unwrap();
+        let mut b = id_bytes[skip..].to_vec();
+        b.push(0x01);
+        b.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        b.extend_from_slice(body);
+        b
+    }
+
+    fn simple_block(track: u8, key: bool) -> Vec<u8> {
+        el(
+            SIMPLE_BLOCK,
+            &[0x80 | track, 0, 0, if key { 0x80 } else { 0 }, 1, 2, 3],
+        )
+    }
+
+    fn block_group(track: u8, key: bool) -> Vec<u8> {
+        let mut b = el(BLOCK, &[0x80 | track, 0, 0, 0, 1, 2, 3]);
+        if !key {
+            b.extend(el(REFERENCE_BLOCK, &[0xFF]));
+        }
+        el(BLOCK_GROUP, &b)
+    }
+
+    fn mkv_file(doc_type: &[u8], unknown_cluster: bool) -> Vec<u8> {
+        let mut info = el(TIMESTAMP_SCALE, &[0x0F, 0x42, 0x40]);
+        info.extend(el(DURATION, &8_000.0f64.to_be_bytes()));
+
+        let video = {
+            let mut v = el(PIXEL_WIDTH, &[0x07, 0x80]);
+            v.extend(el(PIXEL_HEIGHT, &[0x04, 0x38]));
+            v
+        };
+        let mut t1 = el(TRACK_NUMBER, &[1]);
+        t1.extend(el(TRACK_TYPE, &[1]));
+        t1.extend(el(CODEC_ID, b"V_VP9"));
+        t1.extend(el(VIDEO, &video));
+        let mut t2 = el(TRACK_NUMBER, &[2]);
+        t2.extend(el(TRACK_TYPE, &[2]));
+        t2.extend(el(CODEC_ID, b"A_OPUS"));
+        let mut tracks = el(TRACK_ENTRY, &t1);
+        tracks.extend(el(TRACK_ENTRY, &t2));
+
+        // Keyframe every 12 frames; audio blocks in between don't count
+        let mut clusters = Vec::new();
+        for _ in 0..3 {
+            let mut c = el(0xE7, &[0]);
+            for i in 0..12 {
+                if i % 2 == 0 {
+                    c.extend(block_group(1, i == 0));
+                } else {
+                    c.extend(simple_block(1, false));
+                }
+                c.extend(simple_block(2, true));
+            }
+            if unknown_cluster {
+                clusters.extend([0x1F, 0x43, 0xB6, 0x75, 0xFF]);
+                clusters.extend(c);
+            } else {
+                clusters.extend(el(CLUSTER, &c));
+            }
+        }
+        clusters.extend(simple_block(1, true));
+
+        let mut segment = el(INFO, &info);
+        segment.extend(el(TRACKS, &tracks));
+        segment.extend(clusters);
+
+        let mut f = el(EBML, &el(DOC_TYPE, doc_type));
+        f.extend(el(SEGMENT, &segment));
+        f
+    }
+
+    #[test]
+    fn webm_tracks_and_keyframes() {
+        let info = probe_bytes(&mkv_file(b"webm", false)).unwrap();
+        assert_eq!(info.format, "WebM");
+        assert_eq!((info.width, info.height), (Some(1920), Some(1080)));
+        assert_eq!(info.duration_ms, Some(8_000));
+        assert_eq!(info.codecs, vec!["vp9", "opus"]);
+        assert_eq!(info.gop_frames, Some(12));
+    }
+
+    #[test]
+    fn matroska_unknown_size_clusters() {
+        let info = probe_bytes(&mkv_file(b"matroska", true)).unwrap();
+        assert_eq!(info.format, "Matroska");
+        assert_eq!(info.gop_frames, Some(12));
+    }
+
+    #[test]
+    fn truncated_files_do_not_panic() {
+        let mkv = mkv_file(b"webm", false);
+        let mp4 = mp4_file(
+            b"isom",
+            vec![trak(b"vide", b"avc1", tkhd(16, 16, false), Some(&[1, 5]))],
+            0,
+        );
+        for data in [mkv, mp4] {
+            for len in 0..data.len() {
+                let _ = probe_bytes(&data[..len]);
+            }
+        }
+    }
+
+    #[test]
+    fn gop_counter() {
+        let mut g = GopCounter::default();
+        assert_eq!(g.result(), None);
+        for key in [false, true, false, false, true, false, false, false, true] {
+            g.frame(key);
+        }
+        // Intervals 3 and 4 → 3.5, rounded up
+        assert_eq!(g.result(), Some(4));
+    }
+
+    #[test]
+    fn codec_names() {
+        assert_eq!(mp4_codec(b"avc1"), "h264");
+        assert_eq!(mp4_codec(b"xyz "), "xyz");
+        assert_eq!(mkv_codec("A_AAC/MPEG4/LC"), "aac");
+        assert_eq!(mkv_codec("V_THEORA"), "theora");
+    }
+}
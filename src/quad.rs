@@ -1,5 +1,7 @@
 //! Minimal OpenGL quad renderer for displaying image textures.
 //! Draws a textured quad that fits the image within the viewport while preserving aspect ratio.
+//! EXIF orientation is applied in the vertex shader as a UV transform, so rotated photos need
+//! no CPU-side pixel copy.
 
 use std::ffi::CString;
 use std::ptr;
@@ -16,13 +18,13 @@ layout(location = 0) in vec2 aPos;
 layout(location = 1) in vec2 aUV;
 out vec2 vUV;
 uniform vec4 uRect; // x, y, w, h in NDC
-uniform int uFlipY; // 1 = flip vertically (for mpv FBO textures)
+uniform vec3 uUvX;  // displayed UV -> texture U (see uv_transform)
+uniform vec3 uUvY;  // displayed UV -> texture V
 void main() {
     vec2 pos = uRect.xy + aPos * uRect.zw;
     gl_Position = vec4(pos, 0.0, 1.0);
-    vec2 uv = aUV;
-    if (uFlipY != 0) uv.y = 1.0 - uv.y;
-    vUV = uv;
+    vec3 uv = vec3(aUV, 1.0);
+    vUV = vec2(dot(uUvX, uv), dot(uUvY, uv));
 }
 "#;
 
@@ -81,9 +83,24 @@ impl QuadRenderer {
     }
 
     /// Draw a texture fitted within the viewport, preserving aspect ratio.
-    /// `flip_y`: set true for mpv video textures (rendered into FBO with GL origin).
-    pub fn draw(&self, texture: u32, img_w: u32, img_h: u32, viewport_w: u32, viewport_h: u32) {
-        self.draw_inner(texture, img_w, img_h, viewport_w, viewport_h, false);
+    /// `orientation` is the EXIF orientation (1-8) of the stored pixels.
+    pub fn draw(
+        &self,
+        texture: u32,
+        img_w: u32,
+        img_h: u32,
+        orientation: u8,
+        viewport_w: u32,
+        viewport_h: u32,
+    ) {
+        self.draw_inner(
+            texture,
+            img_w,
+            img_h,
+            viewport_w,
+            viewport_h,
+            uv_transform(orientation, false),
+        );
     }
 
     /// Draw a video texture (flipped Y to correct for mpv FBO orientation).
@@ -95,7 +112,14 @@ impl QuadRenderer {
         viewport_w: u32,
         viewport_h: u32,
     ) {
-        self.draw_inner(texture, img_w, img_h, viewport_w, viewport_h, true);
+        self.draw_inner(
+            texture,
+            img_w,
+            img_h,
+            viewport_w,
+            viewport_h,
+            uv_transform(1, true),
+        );
     }
 
     fn draw_inner(
@@ -105,8 +129,14 @@ impl QuadRenderer {
         img_h: u32,
         viewport_w: u32,
         viewport_h: u32,
-        flip_y: bool,
+        uv: UvTransform,
     ) {
+        // Fit the image as displayed: quarter turns swap its sides
+        let (img_w, img_h) = if uv.swaps_axes() {
+            (img_h, img_w)
+        } else {
+            (img_w, img_h)
+        };
         let img_aspect = img_w as f32 / img_h.max(1) as f32;
         let vp_aspect = viewport_w as f32 / viewport_h.max(1) as f32;
 
@@ -129,9 +159,7 @@ impl QuadRenderer {
             let loc = gl::GetUniformLocation(self.program, CString::new("uRect").unwrap().as_ptr());
             gl::Uniform4f(loc, x, y, quad_w, quad_h);
 
-            let flip_loc =
-                gl::GetUniformLocation(self.program, CString::new("uFlipY").unwrap().as_ptr());
-            gl::Uniform1i(flip_loc, flip_y as i32);
+            self.set_uv(uv);
 
             gl::ActiveTexture(gl::TEXTURE0);
             gl::BindTexture(gl::TEXTURE_2D, texture);
@@ -158,6 +186,7 @@ impl QuadRenderer {
 
             let loc = gl::GetUniformLocation(self.program, CString::new("uRect").unwrap().as_ptr());
             gl::Uniform4f(loc, x, y, w, h);
+            self.set_uv(uv_transform(1, false));
 
             gl::ActiveTexture(gl::TEXTURE0);
             gl::BindTexture(gl::TEXTURE_2D, texture);
@@ -174,6 +203,60 @@ impl QuadRenderer {
             gl::Disable(gl::BLEND);
         }
     }
+
+    unsafe fn set_uv(&self, uv: UvTransform) {
+        let x = gl::GetUniformLocation(self.program, CString::new("uUvX").unwrap().as_ptr());
+        gl::Uniform3fv(x, 1, uv.x.as_ptr());
+        let y = gl::GetUniformLocation(self.program, CString::new("uUvY").unwrap().as_ptr());
+        gl::Uniform3fv(y, 1, uv.y.as_ptr());
+    }
+}
+
+/// Affine map from displayed UV (origin top-left) to texture UV: `u = x·(u, v, 1)`,
+/// `v = y·(u, v, 1)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvTransform {
+    x: [f32; 3],
+    y: [f32; 3],
+}
+
+impl UvTransform {
+    /// True when displayed U comes from texture V, i.e. the image is shown a quarter turn
+    /// from how it is stored.
+    pub fn swaps_axes(&self) -> bool {
+        self.x[0] == 0.0
+    }
+
+    #[cfg(test)]
+    fn apply(&self, u: f32, v: f32) -> (f32, f32) {
+        (
+            self.x[0] * u + self.x[1] * v + self.x[2],
+            self.y[0] * u + self.y[1] * v + self.y[2],
+        )
+    }
+}
+
+/// UV transform that displays pixels stored with EXIF `orientation` upright.
+/// Unknown values draw the texture as stored. `flip_y` additionally flips the
+/// texture vertically (mpv renders into its FBO bottom-up).
+pub fn uv_transform(orientation: u8, flip_y: bool) -> UvTransform {
+    #[rustfmt::skip]
+    let (x, y) = match orientation {
+        2 => ([-1.0, 0.0, 1.0], [0.0, 1.0, 0.0]),  // mirrored
+        3 => ([-1.0, 0.0, 1.0], [0.0, -1.0, 1.0]), // rotated 180°
+        4 => ([1.0, 0.0, 0.0], [0.0, -1.0, 1.0]),  // flipped
+        5 => ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0]),   // transposed
+        6 => ([0.0, 1.0, 0.0], [-1.0, 0.0, 1.0]),  // needs 90° clockwise
+        7 => ([0.0, -1.0, 1.0], [-1.0, 0.0, 1.0]), // transversed
+        8 => ([0.0, -1.0, 1.0], [1.0, 0.0, 0.0]),  // needs 90° counter-clockwise
+        _ => ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    };
+    let y = if flip_y {
+        [-y[0], -y[1], 1.0 - y[2]]
+    } else {
+        y
+    };
+    UvTransform { x, y }
 }
 
 impl Drop for QuadRenderer {
@@ -236,3 +319,84 @@ unsafe fn compile_shader(kind: u32, src: &str) -> u32 {
     }
     shader
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Texture UV shown at the displayed top-left, top-right and bottom-left corners.
+    fn corners(orientation: u8) -> [(f32, f32); 3] {
+        let t = uv_transform(orientation, false);
+        [t.apply(0.0, 0.0), t.apply(1.0, 0.0), t.apply(0.0, 1.0)]
+    }
+
+    #[test]
+    fn identity_and_unknown() {
+        for o in [0, 1, 9] {
+            assert_eq!(corners(o), [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+            assert!(!uv_transform(o, false).swaps_axes());
+        }
+    }
+
+    #[test]
+    fn rotations() {
+        // Rotate 90° CW for display: the stored bottom-left ends up top-left
+        assert_eq!(corners(6), [(0.0, 1.0), (0.0, 0.0), (1.0, 1.0)]);
+        assert_eq!(corners(8), [(1.0, 0.0), (1.0, 1.0), (0.0, 0.0)]);
+        assert_eq!(corners(3), [(1.0, 1.0), (0.0, 1.0), (1.0, 0.0)]);
+    }
+
+    #[test]
+    fn flips() {
+        assert_eq!(corners(2), [(1.0, 0.0), (0.0, 0.0), (1.0, 1.0)]);
+        assert_eq!(corners(4), [(0.0, 1.0), (1.0, 1.0), (0.0, 0.0)]);
+        assert_eq!(corners(5), [(0.0, 0.0), (0.0, 1.0), (1.0, 0.0)]);
+        assert_eq!(corners(7), [(1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]);
+    }
+
+    #[test]
+    fn quarter_turns_swap_axes() {
+        for o in 1..=8 {
+            assert_eq!(
+                uv_transform(o, false).swaps_axes(),
+                o >= 5,
+                "orientation {}",
+                o
+            );
+        }
+    }
+
+    #[test]
+    fn matches_image_crate() {
+        // 3×2 image with distinct pixels; compare against apply_orientation
+        let img = image::RgbaImage::from_fn(3, 2, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+        for o in 1..=8u8 {
+            let mut shown = image::DynamicImage::ImageRgba8(img.clone());
+            shown.apply_orientation(image::metadata::Orientation::from_exif(o).unwrap());
+            let shown = shown.into_rgba8();
+            let t = uv_transform(o, false);
+            for (dx, dy, px) in shown.enumerate_pixels() {
+                let u = (dx as f32 + 0.5) / shown.width() as f32;
+                let v = (dy as f32 + 0.5) / shown.height() as f32;
+                let (su, sv) = t.apply(u, v);
+                let sx = (su * img.width() as f32) as u32;
+                let sy = (sv * img.height() as f32) as u32;
+                assert_eq!(
+                    img.get_pixel(sx, sy),
+                    px,
+                    "orientation {} at {},{}",
+                    o,
+                    dx,
+                    dy
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn flip_y_for_video() {
+        let t = uv_transform(1, true);
+        assert_eq!(t.apply(0.0, 0.0), (0.0, 1.0));
+        assert_eq!(t.apply(1.0, 1.0), (1.0, 0.0));
+    }
+}
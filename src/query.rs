@@ -0,0 +1,447 @@
+//! Library search query language.
+//!
+//! A query is a whitespace-separated list of terms, all of which must match:
+//!
+//! ```text
+//! tag:c3 liked ext:png width>2000 model:flux prompt:"red car" dir:~/renders modified>2026-01-01
+//! ```
+//!
+//! Terms compile to a SQL `WHERE` clause over `files f LEFT JOIN meta m`.
+//! Prefix a term with `-` to negate it. Bare words match the filename.
+
+use rusqlite::types::Value;
+
+/// Comparison operator for numeric and date terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn sql(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        }
+    }
+}
+
+/// Numeric columns that can be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Width,
+    Height,
+    Size,
+}
+
+impl Field {
+    fn column(self) -> &'static str {
+        match self {
+            Field::Width => "m.width",
+            Field::Height => "m.height",
+            Field::Size => "f.size",
+        }
+    }
+}
+
+/// A single search term.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    /// `tag:NAME` — file's content carries the tag.
+    Tag(String),
+    /// `liked` — shorthand for `tag:like`.
+    Liked,
+    /// `ext:png` — filename extension (case-insensitive).
+    Ext(String),
+    /// `width>2000`, `size<=10MB`, ...
+    Cmp(Field, Op, i64),
+    /// `model:flux` — substring of the AI model name.
+    Model(String),
+    /// `prompt:"red car"` — substring of the AI prompt.
+    Prompt(String),
+    /// `dir:~/renders` — file lives in this directory or below it.
+    Dir(String),
+    /// `modified>2026-01-01` — compares against `files.modified_at`.
+    Modified(Op, String),
+    /// Bare word — substring of the filename.
+    Name(String),
+}
+
+/// A term, optionally negated with a leading `-`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub negate: bool,
+    pub term: Term,
+}
+
+/// Parsed search query: a conjunction of clauses.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+}
+
+impl Query {
+    /// Parse a query string. Returns a human-readable error on bad input.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut clauses = Vec::new();
+        for token in tokenize(input)? {
+            let (negate, body) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+                _ => (false, token),
+            };
+            clauses.push(Clause {
+                negate,
+                term: parse_term(&body)?,
+            });
+        }
+        Ok(Query { clauses })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// Compile to a SQL boolean expression (for use after `WHERE`) plus bound params.
+    /// The expression refers to `f` (files) and `m` (meta, LEFT JOINed).
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        if self.is_empty() {
+            return ("1".into(), Vec::new());
+        }
+        let mut params = Vec::new();
+        let parts: Vec<String> = self
+            .clauses
+            .iter()
+            .map(|c| {
+                let expr = term_sql(&c.term, &mut params);
+                if c.negate {
+                    format!("NOT COALESCE({}, 0)", expr)
+                } else {
+                    expr
+                }
+            })
+            .collect();
+        (parts.join(" AND "), params)
+    }
+}
+
+/// Split on whitespace, keeping `"quoted strings"` (with the quotes removed) together.
+fn tokenize(input: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut cur = String::new();
+    let mut in_quote = false;
+    let mut has_token = false;
+    for ch in input.chars() {
+        match ch {
+            '"' => {
+                in_quote = !in_quote;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !in_quote => {
+                if has_token {
+                    tokens.push(std::mem::take(&mut cur));
+                    has_token = false;
+                }
+            }
+            c => {
+                cur.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if in_quote {
+        return Err("unterminated quote".into());
+    }
+    if has_token {
+        tokens.push(cur);
+    }
+    Ok(tokens)
+}
+
+fn parse_term(token: &str) -> Result<Term, String> {
+    if token.eq_ignore_ascii_case("liked") {
+        return Ok(Term::Liked);
+    }
+
+    // Split at the first operator character: `key:value`, `key>value`, `key>=value`, ...
+    let Some(pos) = token.find([':', '<', '>', '=']) else {
+        return Ok(Term::Name(token.to_string()));
+    };
+    let key = token[..pos].to_lowercase();
+    let rest = &token[pos..];
+    let (op, value) = if let Some(v) = rest.strip_prefix(">=") {
+        (Op::Ge, v)
+    } else if let Some(v) = rest.strip_prefix("<=") {
+        (Op::Le, v)
+    } else if let Some(v) = rest.strip_prefix('>') {
+        (Op::Gt, v)
+    } else if let Some(v) = rest.strip_prefix('<') {
+        (Op::Lt, v)
+    } else {
+        // ':' or '='
+        (Op::Eq, &rest[1..])
+    };
+    if value.is_empty() {
+        return Err(format!("missing value in `{}`", token));
+    }
+    let is_colon = rest.starts_with(':');
+
+    match key.as_str() {
+        "tag" if is_colon => Ok(Term::Tag(value.to_string())),
+        "ext" if is_colon => Ok(Term::Ext(value.trim_start_matches('.').to_lowercase())),
+        "model" if is_colon => Ok(Term::Model(value.to_string())),
+        "prompt" if is_colon => Ok(Term::Prompt(value.to_string())),
+        "dir" if is_colon => Ok(Term::Dir(expand_dir(value))),
+        "width" | "w" => Ok(Term::Cmp(Field::Width, op, parse_int(value)?)),
+        "height" | "h" => Ok(Term::Cmp(Field::Height, op, parse_int(value)?)),
+        "size" => Ok(Term::Cmp(Field::Size, op, parse_size(value)?)),
+        "modified" | "mtime" => {
+            if !value.chars().all(|c| c.is_ascii_digit() || c == '-') {
+                return Err(format!("bad date `{}` (expected YYYY-MM-DD)", value));
+            }
+            Ok(Term::Modified(op, value.to_string()))
+        }
+        "tag" | "ext" | "model" | "prompt" | "dir" => {
+            Err(format!("`{}` only supports `{}:value`", key, key))
+        }
+        _ => Err(format!("unknown field `{}`", key)),
+    }
+}
+
+fn parse_int(s: &str) -> Result<i64, String> {
+    s.parse::<i64>().map_err(|_| format!("bad number `{}`", s))
+}
+
+/// Parse a size with an optional K/M/G suffix (binary units, `B` optional).
+fn parse_size(s: &str) -> Result<i64, String> {
+    let upper = s.to_uppercase();
+    let trimmed = upper.strip_suffix('B').unwrap_or(&upper);
+    let (num, mul) = match trimmed.chars().last() {
+        Some('K') => (&trimmed[..trimmed.len() - 1], 1024),
+        Some('M') => (&trimmed[..trimmed.len() - 1], 1024 * 1024),
+        Some('G') => (&trimmed[..trimmed.len() - 1], 1024 * 1024 * 1024),
+        _ => (trimmed, 1),
+    };
+    num.parse::<f64>()
+        .map(|n| (n * mul as f64) as i64)
+        .map_err(|_| format!("bad size `{}`", s))
+}
+
+/// Expand a leading `~` to $HOME and drop trailing separators.
+fn expand_dir(s: &str) -> String {
+    let expanded = match s.strip_prefix('~') {
+        Some(rest) => match std::env::var("HOME") {
+            Ok(home) => format!("{}{}", home, rest),
+            Err(_) => s.to_string(),
+        },
+        None => s.to_string(),
+    };
+    let trimmed = expanded.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() {
+        expanded
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Escape `%`, `_` and `\` for a `LIKE ... ESCAPE '\'` pattern.
+pub fn like_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// The file carries the tag named by the SQL expression `name`, on its
+/// content or still pending (not hashed yet).
+fn tagged_sql(name: &str) -> String {
+    format!(
+        "(f.meta_id IN (SELECT mt.meta_id FROM meta_tags mt \
+         JOIN tags t ON t.id = mt.tag_id WHERE t.name = {0}) \
+         OR f.id IN (SELECT pt.file_id FROM pending_tags pt \
+         JOIN tags t ON t.id = pt.tag_id WHERE t.name = {0}))",
+        name
+    )
+}
+
+fn term_sql(term: &Term, params: &mut Vec<Value>) -> String {
+    let mut bind = |v: Value| {
+        params.push(v);
+        format!("?{}", params.len())
+    };
+    match term {
+        Term::Tag(name) => {
+            let p = bind(Value::Text(name.clone()));
+            tagged_sql(&p)
+        }
+        Term::Liked => tagged_sql("'like'"),
+        Term::Ext(ext) => {
+            let p = bind(Value::Text(format!("%.{}", like_escape(ext))));
+            format!("(LOWER(f.filename) LIKE {} ESCAPE '\\')", p)
+        }
+        Term::Cmp(field, op, n) => {
+            let p = bind(Value::Integer(*n));
+            format!("({} {} {})", field.column(), op.sql(), p)
+        }
+        Term::Model(s) => {
+            // pnginfo stores "<prompt>\n\nmodel: <name>"
+            let p = bind(Value::Text(format!("%model: %{}%", like_escape(s))));
+            format!("(m.pnginfo LIKE {} ESCAPE '\\')", p)
+        }
+        Term::Prompt(s) => {
+            let p = bind(Value::Text(format!("%{}%", like_escape(s))));
+            format!("(m.pnginfo LIKE {} ESCAPE '\\')", p)
+        }
+        Term::Dir(d) => {
+            let exact = bind(Value::Text(d.clone()));
+            // `/` keeps its separator through parsing; don't double it
+            let base = d.trim_end_matches(['/', '\\']);
+            let under = bind(Value::Text(format!("{}/%", like_escape(base))));
+            format!("(f.dir = {} OR f.dir LIKE {} ESCAPE '\\')", exact, under)
+        }
+        Term::Modified(Op::Eq, date) => {
+            let p = bind(Value::Text(format!("{}%", like_escape(date))));
+            format!("(f.modified_at LIKE {} ESCAPE '\\')", p)
+        }
+        Term::Modified(op, date) => {
+            // `modified<=2026-01-01` should include the whole day.
+            let bound = if *op == Op::Le || *op == Op::Gt {
+                format!("{}\u{10FFFF}", date)
+            } else {
+                date.clone()
+            };
+            let p = bind(Value::Text(bound));
+            format!("(f.modified_at {} {})", op.sql(), p)
+        }
+        Term::Name(s) => {
+            let p = bind(Value::Text(format!("%{}%", like_escape(s))));
+            format!("(f.filename LIKE {} ESCAPE '\\')", p)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(q: &str) -> Vec<Term> {
+        Query::parse(q)
+            .unwrap()
+            .clauses
+            .into_iter()
+            .map(|c| c.term)
+            .collect()
+    }
+
+    #[test]
+    fn parse_example_query() {
+        let t = terms(
+            r#"tag:c3 liked ext:png width>2000 model:flux prompt:"red car" dir:/renders modified>2026-01-01"#,
+        );
+        assert_eq!(
+            t,
+            vec![
+                Term::Tag("c3".into()),
+                Term::Liked,
+                Term::Ext("png".into()),
+                Term::Cmp(Field::Width, Op::Gt, 2000),
+                Term::Model("flux".into()),
+                Term::Prompt("red car".into()),
+                Term::Dir("/renders".into()),
+                Term::Modified(Op::Gt, "2026-01-01".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_empty_query() {
+        assert!(Query::parse("").unwrap().is_empty());
+        assert!(Query::parse("   ").unwrap().is_empty());
+        assert_eq!(Query::parse("").unwrap().to_sql().0, "1");
+    }
+
+    #[test]
+    fn parse_operators() {
+        assert_eq!(terms("w>=10"), vec![Term::Cmp(Field::Width, Op::Ge, 10)]);
+        assert_eq!(terms("h<=10"), vec![Term::Cmp(Field::Height, Op::Le, 10)]);
+        assert_eq!(terms("width<5"), vec![Term::Cmp(Field::Width, Op::Lt, 5)]);
+        assert_eq!(terms("width=5"), vec![Term::Cmp(Field::Width, Op::Eq, 5)]);
+        assert_eq!(terms("width:5"), vec![Term::Cmp(Field::Width, Op::Eq, 5)]);
+    }
+
+    #[test]
+    fn parse_size_suffixes() {
+        assert_eq!(parse_size("100").unwrap(), 100);
+        assert_eq!(parse_size("2K").unwrap(), 2048);
+        assert_eq!(parse_size("10MB").unwrap(), 10 * 1024 * 1024);
+        assert_eq!(parse_size("1.5g").unwrap(), 1536 * 1024 * 1024);
+        assert!(parse_size("lots").is_err());
+    }
+
+    #[test]
+    fn parse_negation() {
+        let q = Query::parse("-liked -ext:mp4").unwrap();
+        assert!(q.clauses.iter().all(|c| c.negate));
+        assert_eq!(q.clauses[1].term, Term::Ext("mp4".into()));
+        // A lone dash is a filename word, not a negation
+        assert_eq!(terms("-"), vec![Term::Name("-".into())]);
+    }
+
+    #[test]
+    fn parse_bare_words_match_filename() {
+        assert_eq!(
+            terms("ComfyUI final"),
+            vec![Term::Name("ComfyUI".into()), Term::Name("final".into())]
+        );
+    }
+
+    #[test]
+    fn parse_ext_normalized() {
+        assert_eq!(terms("ext:.PNG"), vec![Term::Ext("png".into())]);
+    }
+
+    #[test]
+    fn parse_dir_strips_trailing_slash() {
+        assert_eq!(terms("dir:/a/b/"), vec![Term::Dir("/a/b".into())]);
+        assert_eq!(terms("dir:/"), vec![Term::Dir("/".into())]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Query::parse("bogus:1").is_err());
+        assert!(Query::parse("width>abc").is_err());
+        assert!(Query::parse("tag:").is_err());
+        assert!(Query::parse("tag>c3").is_err());
+        assert!(Query::parse(r#"prompt:"open"#).is_err());
+        assert!(Query::parse("modified>yesterday").is_err());
+    }
+
+    #[test]
+    fn to_sql_binds_params_in_order() {
+        let q = Query::parse("ext:png width>100").unwrap();
+        let (sql, params) = q.to_sql();
+        assert!(sql.contains("?1"));
+        assert!(sql.contains("?2"));
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0], Value::Text("%.png".into()));
+        assert_eq!(params[1], Value::Integer(100));
+    }
+
+    #[test]
+    fn dir_root_matches_everything_below() {
+        let (_, params) = Query::parse("dir:/").unwrap().to_sql();
+        assert_eq!(params[1], Value::Text("/%".into()));
+        let (_, params) = Query::parse("dir:/a/").unwrap().to_sql();
+        assert_eq!(params[1], Value::Text("/a/%".into()));
+    }
+
+    #[test]
+    fn like_escape_special_chars() {
+        assert_eq!(like_escape("100%_a\\b"), "100\\%\\_a\\\\b");
+    }
+}
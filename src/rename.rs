@@ -0,0 +1,345 @@
+//! Renaming from the viewer (F2): one file to a typed name, or a batch by
+//! template such as `{date}_{model}_{seq}.{ext}`.
+//!
+//! Files stay in their directory and keep their `files` row, so likes, tags,
+//! history and the `meta_id` link carry over. Names already taken get a
+//! number (`a.2.jpg`). A batch is one undo step (Ctrl+Z, see `undo.rs`).
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::db::{Db, FileEntry, FileMeta, Rename};
+
+/// Template the batch prompt starts with.
+pub const DEFAULT_TEMPLATE: &str = "{date}_{model}_{seq}.{ext}";
+
+/// Fields a template can use.
+const FIELDS: &[&str] = &[
+    "name", "ext", "seq", "date", "time", "model", "make", "width", "height", "hash",
+];
+
+enum Part {
+    Text(String),
+    Field(String),
+}
+
+/// A parsed rename template: literal text and `{field}`s.
+pub struct Template(Vec<Part>);
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                parts.push(Part::Text(rest[..open].to_string()));
+            }
+            let close = rest[open..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed {{ in {}", s))?;
+            let name = &rest[open + 1..open + close];
+            if !FIELDS.contains(&name) {
+                return Err(format!(
+                    "unknown field {{{}}} (use {})",
+                    name,
+                    FIELDS.join(", ")
+                ));
+            }
+            parts.push(Part::Field(name.to_string()));
+            rest = &rest[open + close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        Ok(Template(parts))
+    }
+
+    /// File name for `meta`, the `seq`th file (1-based) of a batch whose
+    /// numbers are padded to `width` digits.
+    pub fn render(&self, meta: &FileMeta, seq: usize, width: usize) -> String {
+        let mut out = String::new();
+        for part in &self.0 {
+            match part {
+                Part::Text(t) => out.push_str(t),
+                Part::Field(f) => out.push_str(&field(meta, f, seq, width)),
+            }
+        }
+        sanitize(&out)
+    }
+}
+
+/// Whether prompt input is a template rather than a plain name.
+pub fn is_template(input: &str) -> bool {
+    input.contains('{')
+}
+
+/// Value of template field `name`; empty when the file doesn't have it.
+fn field(meta: &FileMeta, name: &str, seq: usize, width: usize) -> String {
+    let exif: Option<serde_json::Value> = meta
+        .exif_json
+        .as_deref()
+        .and_then(|j| serde_json::from_str(j).ok());
+    let exif_text = |key: &str| {
+        exif.as_ref()
+            .and_then(|e| e.get(key))
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+    };
+    // EXIF `YYYY-MM-DD hh:mm:ss`, else the file's `YYYY-MM-DDThh:mm:ssZ`
+    // (parsed, as rows from older watcher builds hold epoch seconds)
+    let stamp = exif_text("taken_at").or_else(|| {
+        meta.modified_at
+            .as_deref()
+            .and_then(crate::scanner::mtime_secs)
+            .map(crate::scanner::iso_lite)
+    });
+    let path = Path::new(&meta.filename);
+    match name {
+        "name" => path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        "ext" => path
+            .extension()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        "seq" => format!("{:0width$}", seq, width = width),
+        "date" => stamp
+            .and_then(|s| s.get(..10).map(str::to_string))
+            .unwrap_or_default(),
+        "time" => stamp
+            .and_then(|s| s.get(11..19).map(|t| t.replace(':', "")))
+            .unwrap_or_default(),
+        // Camera model, else the AI model (`pnginfo` ends in "model: <name>")
+        "model" => exif_text("model")
+            .or_else(|| {
+                meta.pnginfo.as_deref().and_then(|p| {
+                    p.lines()
+                        .rev()
+                        .find_map(|l| l.strip_prefix("model: "))
+                        .map(|m| m.trim().to_string())
+                })
+            })
+            .unwrap_or_default(),
+        "make" => exif_text("make").unwrap_or_default(),
+        "width" => meta.width.map(|v| v.to_string()).unwrap_or_default(),
+        "height" => meta.height.map(|v| v.to_string()).unwrap_or_default(),
+        "hash" => meta
+            .hash_sha512
+            .as_deref()
+            .map(|h| h.trim_start_matches("fp:").chars().take(8).collect())
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Make a rendered name safe: no path separators or control characters,
+/// no surrounding spaces or trailing dots.
+fn sanitize(name: &str) -> String {
+    let clean: String = name
+        .chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    clean.trim().trim_end_matches('.').to_string()
+}
+
+/// Work out new names for `targets`: `input` is a template, or a plain name
+/// for a single file. Files whose name doesn't change are left out; a name
+/// taken on disk or earlier in the batch gets a number.
+pub fn plan(db: &Db, targets: &[FileEntry], input: &str) -> Result<Vec<Rename>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("empty name".into());
+    }
+    let names: Vec<String> = if is_template(input) {
+        let template = Template::parse(input)?;
+        let width = targets.len().to_string().len();
+        targets
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let meta = db
+                    .get_file_metadata(f.id)
+                    .ok_or_else(|| format!("{}: not in library", f.filename))?;
+                Ok(template.render(&meta, i + 1, width))
+            })
+            .collect::<Result<_, String>>()?
+    } else if targets.len() > 1 {
+        return Err("use a template with {seq} to rename several files".into());
+    } else {
+        if input.contains('/') || input.contains('\\') {
+            return Err("name can't contain a path separator".into());
+        }
+        targets.iter().map(|_| input.to_string()).collect()
+    };
+    if let Some(bad) = names.iter().find(|n| matches!(n.as_str(), "" | "." | "..")) {
+        return Err(format!("invalid name '{}'", bad));
+    }
+
+    // Names the batch frees up, and names that stay put
+    let sources: HashSet<&str> = targets.iter().map(|f| f.path.as_str()).collect();
+    let mut taken: HashSet<PathBuf> = targets
+        .iter()
+        .zip(&names)
+        .filter(|(f, name)| f.filename == **name)
+        .map(|(f, _)| PathBuf::from(&f.path))
+        .collect();
+    let mut plan = Vec::new();
+    for (f, name) in targets.iter().zip(&names) {
+        if f.filename == *name {
+            continue;
+        }
+        let dir = Path::new(&f.path).parent().unwrap_or(Path::new(""));
+        let is_free = |p: &Path, taken: &HashSet<PathBuf>| {
+            !taken.contains(p) && (!p.exists() || sources.contains(&*p.to_string_lossy()))
+        };
+        let mut to = dir.join(name);
+        let mut n = 2;
+        while !is_free(&to, &taken) {
+            to = dir.join(crate::trash::numbered(name, n));
+            n += 1;
+        }
+        taken.insert(to.clone());
+        plan.push(Rename {
+            file_id: f.id,
+            from: f.path.clone(),
+            to: to.to_string_lossy().to_string(),
+        });
+    }
+    Ok(plan)
+}
+
+/// Carry out a plan as one undoable step. Nothing changes if any file
+/// can't be renamed. Returns how many files were renamed.
+pub fn apply(db: &Db, plan: Vec<Rename>) -> Result<usize, String> {
+    let label = match plan.len() {
+        1 => "rename".to_string(),
+        n => format!("rename ×{}", n),
+    };
+    crate::undo::run(db, &label, crate::undo::Change::Relocate { files: plan })
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(filename: &str) -> FileMeta {
+        FileMeta {
+            filename: filename.to_string(),
+            path: format!("/a/{}", filename),
+            dir: "/a".into(),
+            size: None,
+            modified_at: Some("2025-03-04T05:06:07Z".into()),
+            hash_sha512: Some("fp:0123456789abcdef".into()),
+            width: Some(1024),
+            height: Some(768),
+            format: None,
+            duration_ms: None,
+            bitrate: None,
+            codecs: None,
+            gop_frames: None,
+            tags: Vec::new(),
+            pnginfo: None,
+            exif_json: None,
+        }
+    }
+
+    #[test]
+    fn template_fields() {
+        let t = Template::parse("{date}_{model}_{seq}.{ext}").unwrap();
+        let mut m = meta("IMG_1.JPG");
+        m.exif_json = Some(r#"{"model":"X-T5","taken_at":"2024-01-02 10:20:30"}"#.into());
+        assert_eq!(t.render(&m, 7, 3), "2024-01-02_X-T5_007.JPG");
+
+        // No EXIF: file date, AI model
+        let mut m = meta("render.png");
+        m.pnginfo = Some("a red car\n\nmodel: flux/dev".into());
+        assert_eq!(t.render(&m, 1, 1), "2025-03-04_flux_dev_1.png");
+
+        let t = Template::parse("{name}-{time}-{width}x{height}-{hash}").unwrap();
+        assert_eq!(
+            t.render(&meta("a.b.jpg"), 1, 1),
+            "a.b-050607-1024x768-01234567"
+        );
+        // Epoch-second mtimes read as dates; unreadable ones leave it empty
+        let t = Template::parse("{date}_{time}_{name}").unwrap();
+        let mut m = meta("a.jpg");
+        m.modified_at = Some("1760000000".into());
+        assert_eq!(t.render(&m, 1, 1), "2025-10-09_085320_a");
+        m.modified_at = Some("yesterday".into());
+        assert_eq!(t.render(&m, 1, 1), "__a");
+
+        // Missing extension leaves no trailing dot
+        assert_eq!(
+            Template::parse("{name}.{ext}")
+                .unwrap()
+                .render(&meta("README"), 1, 1),
+            "README"
+        );
+    }
+
+    #[test]
+    fn template_errors() {
+        assert!(Template::parse("{date").is_err());
+        let err = Template::parse("{date}_{camera}").err().unwrap();
+        assert!(err.contains("{camera}"), "{}", err);
+        assert!(Template::parse("plain}.jpg").is_ok());
+    }
+
+    #[test]
+    fn plan_numbers_taken_names() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg", "c.jpg"], Some("t"));
+        std::fs::write(tmp.path().join("x.jpg"), "other").unwrap();
+        let plan = plan(&db, &files, "x.{ext}").unwrap();
+        let names: Vec<&str> = plan
+            .iter()
+            .map(|r| r.to.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(names, vec!["x.2.jpg", "x.3.jpg", "x.4.jpg"]);
+
+        // Plain names only for one file
+        assert!(super::plan(&db, &files, "y.jpg").is_err());
+        assert!(super::plan(&db, &files[..1], "sub/y.jpg").is_err());
+        assert!(super::plan(&db, &files[..1], "  ").is_err());
+        // Unchanged names are skipped
+        assert!(super::plan(&db, &files[..1], "a.jpg").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rename_keeps_rows_and_undoes() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg"], Some("t"));
+        let p = plan(&db, &files, "photo_{seq}.{ext}").unwrap();
+        assert_eq!(apply(&db, p), Ok(2));
+        assert!(tmp.path().join("photo_1.jpg").exists());
+        assert!(!tmp.path().join("a.jpg").exists());
+        let renamed = db.files_by_ids(&[files[0].id]);
+        assert_eq!(renamed[0].filename, "photo_1.jpg");
+        assert_eq!(renamed[0].dir, files[0].dir);
+        assert_eq!(db.file_tags(files[0].id), vec!["t".to_string()]);
+
+        let step = crate::undo::undo(&db).unwrap();
+        assert_eq!(step.label, "rename ×2");
+        assert_eq!(std::fs::read(tmp.path().join("a.jpg")).unwrap(), b"a.jpg");
+        assert_eq!(db.files_by_ids(&[files[0].id])[0].path, files[0].path);
+    }
+
+    #[test]
+    fn failed_rename_changes_nothing() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg"], Some("t"));
+        std::fs::remove_file(&files[1].path).unwrap();
+        let p = plan(&db, &files, "n{seq}.{ext}").unwrap();
+        assert!(apply(&db, p).is_err());
+        assert!(tmp.path().join("a.jpg").exists());
+        assert!(!tmp.path().join("n1.jpg").exists());
+        assert_eq!(db.files_by_ids(&[files[0].id])[0].path, files[0].path);
+        assert!(db.journal_next(false).is_none());
+    }
+}
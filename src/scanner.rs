@@ -3,7 +3,7 @@
 use std::path::Path;
 use walkdir::WalkDir;
 
-use crate::db::Db;
+use crate::db::{Db, SortOrder};
 
 use crate::clean_path;
 
@@ -51,14 +51,7 @@ pub fn discover(db: &Db, root: &Path) -> usize {
 
         let fmeta = entry.metadata().ok();
         let size = fmeta.as_ref().map(|m| m.len() as i64);
-        let modified_at = fmeta
-            .as_ref()
-            .and_then(|m| m.modified().ok())
-            .and_then(|t| {
-                t.duration_since(std::time::UNIX_EPOCH)
-                    .ok()
-                    .map(|d| iso_lite(d.as_secs()))
-            });
+        let modified_at = fmeta.as_ref().and_then(mtime_stamp);
 
         let path_str = clean_path(&abs.to_string_lossy());
         let mtime_ref = modified_at.as_deref();
@@ -95,7 +88,7 @@ pub fn rescan(db: &Db, root: &Path) -> (usize, usize) {
         .map(|p| clean_path(&p.to_string_lossy()))
         .unwrap_or_else(|_| clean_path(&root.to_string_lossy()));
 
-    let db_files = db.files_by_dir(&canon_dir);
+    let db_files = db.files_by_dir(&canon_dir, SortOrder::Path);
     let mut pruned = 0usize;
     for f in &db_files {
         if !Path::new(&f.path).exists() {
@@ -115,7 +108,15 @@ pub fn rescan(db: &Db, root: &Path) -> (usize, usize) {
     (updated, pruned)
 }
 
-fn iso_lite(epoch_secs: u64) -> String {
+/// `modified_at` as stored in `files` (UTC, second precision).
+pub fn mtime_stamp(meta: &std::fs::Metadata) -> Option<String> {
+    let t = meta.modified().ok()?;
+    let d = t.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(iso_lite(d.as_secs()))
+}
+
+/// `YYYY-MM-DDThh:mm:ssZ` (UTC) for epoch seconds.
+pub fn iso_lite(epoch_secs: u64) -> String {
     let s = epoch_secs;
     let days = s / 86400;
     let time = s % 86400;
@@ -159,6 +160,34 @@ fn iso_lite(epoch_secs: u64) -> String {
     format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", y, mo, d, h, m, sec)
 }
 
+/// Epoch seconds of a stored `modified_at`: the `iso_lite` form, or bare
+/// epoch seconds as older watcher builds wrote them.
+pub fn mtime_secs(stamp: &str) -> Option<u64> {
+    if !stamp.is_empty() && stamp.bytes().all(|b| b.is_ascii_digit()) {
+        return stamp.parse().ok();
+    }
+    let b = stamp.as_bytes();
+    if b.len() != 20 || b[4] != b'-' || b[7] != b'-' || b[10] != b'T' || b[19] != b'Z' {
+        return None;
+    }
+    let num = |r: std::ops::Range<usize>| stamp.get(r)?.parse::<u64>().ok();
+    let (y, mo, d) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (h, m, sec) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if y < 1970 || !(1..=12).contains(&mo) || d == 0 || h > 23 || m > 59 || sec > 59 {
+        return None;
+    }
+    let mut days: u64 = (1970..y as i64)
+        .map(|yy| if is_leap(yy) { 366 } else { 365 })
+        .sum();
+    let feb = if is_leap(y as i64) { 29 } else { 28 };
+    let months = [31, feb, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    if d > months[mo as usize - 1] {
+        return None;
+    }
+    days += months[..mo as usize - 1].iter().sum::<u64>() + d - 1;
+    Some(days * 86400 + h * 3600 + m * 60 + sec)
+}
+
 fn is_leap(y: i64) -> bool {
     (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
 }
@@ -224,6 +253,17 @@ mod tests {
         assert_eq!(iso_lite(1704067199), "2023-12-31T23:59:59Z");
     }
 
+    #[test]
+    fn mtime_secs_reads_both_forms() {
+        for secs in [0, 45045, 1709164800, 1704067199] {
+            assert_eq!(mtime_secs(&iso_lite(secs)), Some(secs));
+        }
+        assert_eq!(mtime_secs("1760000000"), Some(1760000000));
+        assert_eq!(mtime_secs("2023-02-29T00:00:00Z"), None);
+        assert_eq!(mtime_secs("2024-01-01"), None);
+        assert_eq!(mtime_secs(""), None);
+    }
+
     // ── media extension filtering ───────────────────────────────────────
 
     #[test]
@@ -280,7 +320,7 @@ mod tests {
         discover(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
         assert_eq!(files.len(), 1);
         // No file path or dir should contain the Windows extended-length prefix
         for f in &files {
@@ -309,7 +349,7 @@ mod tests {
         assert_eq!(updated, 1);
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        for f in &db.files_by_dir(&dir_str) {
+        for f in &db.files_by_dir(&dir_str, SortOrder::Path) {
             assert!(
                 !f.path.starts_with(r"\\?\"),
                 "path has \\\\?\\ prefix: {}",
@@ -338,7 +378,7 @@ mod tests {
         assert_eq!(pruned, 0);
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
         assert_eq!(files.len(), 1);
         assert_eq!(files[0].filename, "a.jpg");
     }
@@ -355,7 +395,7 @@ mod tests {
         // Initial scan
         rescan(&db, dir.path());
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        assert_eq!(db.files_by_dir(&dir_str).len(), 2);
+        assert_eq!(db.files_by_dir(&dir_str, SortOrder::Path).len(), 2);
 
         // Delete one file from disk
         std::fs::remove_file(dir.path().join("a.jpg")).unwrap();
@@ -363,7 +403,7 @@ mod tests {
         // Rescan should prune it
         let (_updated, pruned) = rescan(&db, dir.path());
         assert_eq!(pruned, 1, "should prune deleted file");
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
         assert_eq!(files.len(), 1);
         assert_eq!(files[0].filename, "b.png");
     }
@@ -378,7 +418,7 @@ mod tests {
 
         rescan(&db, dir.path());
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
         let old_size = db.file_lookup(&files[0].path).unwrap().1;
 
         // Modify the file (make it bigger)
@@ -406,7 +446,7 @@ mod tests {
 
         rescan(&db, dir.path());
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        assert_eq!(db.files_by_dir(&dir_str).len(), 3);
+        assert_eq!(db.files_by_dir(&dir_str, SortOrder::Path).len(), 3);
 
         // Simulate offline changes
         std::fs::remove_file(dir.path().join("delete_me.png")).unwrap();
@@ -421,7 +461,7 @@ mod tests {
         assert!(updated >= 2, "should add new + update changed");
         assert_eq!(pruned, 1, "should prune deleted");
 
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
         assert_eq!(files.len(), 3, "keep + changed + new");
         let names: Vec<&str> = files.iter().map(|f| f.filename.as_str()).collect();
         assert!(names.contains(&"keep.jpg"));
@@ -461,7 +501,7 @@ mod tests {
         assert_eq!(p2, 0, "no files to prune");
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        assert_eq!(db.files_by_dir(&dir_str).len(), 2);
+        assert_eq!(db.files_by_dir(&dir_str, SortOrder::Path).len(), 2);
     }
 
     #[test]
@@ -505,7 +545,7 @@ mod tests {
 
         rescan(&db, dir.path());
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        assert_eq!(db.files_by_dir(&dir_str).len(), 2);
+        assert_eq!(db.files_by_dir(&dir_str, SortOrder::Path).len(), 2);
 
         // Delete all files
         std::fs::remove_file(dir.path().join("a.jpg")).unwrap();
@@ -513,7 +553,7 @@ mod tests {
 
         let (_u, pruned) = rescan(&db, dir.path());
         assert_eq!(pruned, 2);
-        assert!(db.files_by_dir(&dir_str).is_empty());
+        assert!(db.files_by_dir(&dir_str, SortOrder::Path).is_empty());
     }
 
     #[test]
@@ -537,7 +577,7 @@ mod tests {
         rescan(&db, dir.path());
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
         let names: Vec<&str> = files.iter().map(|f| f.filename.as_str()).collect();
 
         // Every file in the list should exist on disk
@@ -615,7 +655,7 @@ mod tests {
         assert_eq!(count, 2);
 
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
         assert_eq!(files.len(), 2);
 
         // Zero-byte file should have size 0
@@ -686,17 +726,17 @@ mod tests {
 
         discover(&db, dir.path());
         let dir_str = clean_path(&dir.path().canonicalize().unwrap().to_string_lossy());
-        assert_eq!(db.files_by_dir(&dir_str).len(), 1);
+        assert_eq!(db.files_by_dir(&dir_str, SortOrder::Path).len(), 1);
 
         // Delete from disk, then prune from DB
         std::fs::remove_file(dir.path().join("a.jpg")).unwrap();
-        let files = db.files_by_dir(&dir_str);
+        let files = db.files_by_dir(&dir_str, SortOrder::Path);
         db.remove_file_by_id(files[0].id);
-        assert!(db.files_by_dir(&dir_str).is_empty());
+        assert!(db.files_by_dir(&dir_str, SortOrder::Path).is_empty());
 
         // discover again — should NOT re-add the deleted file
         let added = discover(&db, dir.path());
         assert_eq!(added, 0);
-        assert!(db.files_by_dir(&dir_str).is_empty());
+        assert!(db.files_by_dir(&dir_str, SortOrder::Path).is_empty());
     }
 }
@@ -0,0 +1,271 @@
+//! Multi-selection: the set of files batch actions apply to.
+//!
+//! Files are kept by id rather than list position, so a selection survives
+//! `r`, watcher refreshes and switching between dirs, collections and
+//! search results. With nothing selected, actions apply to the file under
+//! the cursor as before.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::db::{Db, FileEntry};
+
+#[derive(Default)]
+pub struct Selection {
+    ids: BTreeSet<i64>,
+    /// Last file marked on its own: one end of a range selection.
+    anchor: Option<i64>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn contains(&self, id: i64) -> bool {
+        self.ids.contains(&id)
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.anchor = None;
+    }
+
+    /// Mark or unmark one file. Returns whether it is now selected.
+    pub fn toggle(&mut self, id: i64) -> bool {
+        self.anchor = Some(id);
+        if self.ids.remove(&id) {
+            false
+        } else {
+            self.ids.insert(id);
+            true
+        }
+    }
+
+    /// Select every file between the anchor and the cursor, inclusive. Without
+    /// an anchor in `files` this marks just the cursor. Returns how many files
+    /// the range covers.
+    pub fn select_range(&mut self, files: &[FileEntry], cursor: usize) -> usize {
+        let Some(cur) = files.get(cursor) else {
+            return 0;
+        };
+        let from = self
+            .anchor
+            .and_then(|a| files.iter().position(|f| f.id == a))
+            .unwrap_or(cursor);
+        let (lo, hi) = (from.min(cursor), from.max(cursor));
+        self.ids.extend(files[lo..=hi].iter().map(|f| f.id));
+        self.anchor = Some(cur.id);
+        hi - lo + 1
+    }
+
+    /// Select every file in the list.
+    pub fn select_all(&mut self, files: &[FileEntry]) {
+        self.ids.extend(files.iter().map(|f| f.id));
+    }
+
+    /// Flip the selection state of every file in the list. Selected files
+    /// outside the list stay selected.
+    pub fn invert(&mut self, files: &[FileEntry]) {
+        for f in files {
+            if !self.ids.remove(&f.id) {
+                self.ids.insert(f.id);
+            }
+        }
+    }
+
+    /// Forget files that are no longer in the library.
+    pub fn prune(&mut self, db: &Db) {
+        if self.ids.is_empty() {
+            return;
+        }
+        let ids: Vec<i64> = self.ids.iter().copied().collect();
+        self.ids = db.files_by_ids(&ids).iter().map(|f| f.id).collect();
+        if self.anchor.is_some_and(|a| !self.ids.contains(&a)) {
+            self.anchor = None;
+        }
+    }
+
+    /// Files an action applies to: the selection (by path), or the file
+    /// under the cursor when nothing is selected.
+    pub fn targets(&self, db: &Db, files: &[FileEntry], cursor: usize) -> Vec<FileEntry> {
+        if self.ids.is_empty() {
+            return files.get(cursor).cloned().into_iter().collect();
+        }
+        let ids: Vec<i64> = self.ids.iter().copied().collect();
+        db.files_by_ids(&ids)
+    }
+}
+
+/// Expand a leading `~/` to the home directory.
+pub fn expand_home(input: &str) -> PathBuf {
+    match input.strip_prefix("~/") {
+        Some(rest) => directories::BaseDirs::new()
+            .map(|d| d.home_dir().join(rest))
+            .unwrap_or_else(|| PathBuf::from(input)),
+        None => PathBuf::from(input),
+    }
+}
+
+/// Export `targets` to `dest`: a `.m3u`/`.m3u8` destination gets a playlist
+/// of their paths, anything else is a directory the files are copied into
+/// (created if needed; names already there are skipped). Returns a summary
+/// for the status bar.
+pub fn export(dest: &Path, targets: &[FileEntry]) -> Result<String, String> {
+    if targets.is_empty() {
+        return Err("nothing to export".into());
+    }
+    let is_playlist = dest
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("m3u") || e.eq_ignore_ascii_case("m3u8"));
+    if is_playlist {
+        let mut out = String::from("#EXTM3U\n");
+        for f in targets {
+            out.push_str(&f.path);
+            out.push('\n');
+        }
+        std::fs::write(dest, out).map_err(|e| format!("{}: {}", dest.display(), e))?;
+        return Ok(format!("{} in {}", targets.len(), dest.display()));
+    }
+
+    std::fs::create_dir_all(dest).map_err(|e| format!("{}: {}", dest.display(), e))?;
+    let (mut copied, mut skipped) = (0, 0);
+    for f in targets {
+        let to = dest.join(&f.filename);
+        if to.exists() {
+            skipped += 1;
+            continue;
+        }
+        std::fs::copy(&f.path, &to).map_err(|e| format!("{}: {}", f.filename, e))?;
+        copied += 1;
+    }
+    Ok(if skipped > 0 {
+        format!(
+            "{} copied to {} ({} already there)",
+            copied,
+            dest.display(),
+            skipped
+        )
+    } else {
+        format!("{} copied to {}", copied, dest.display())
+    })
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64, path: &str) -> FileEntry {
+        let (dir, filename) = path.rsplit_once('/').unwrap();
+        FileEntry {
+            id,
+            path: path.to_string(),
+            dir: dir.to_string(),
+            filename: filename.to_string(),
+            meta_id: None,
+            liked: false,
+            temporary: false,
+        }
+    }
+
+    fn list(n: i64) -> Vec<FileEntry> {
+        (1..=n)
+            .map(|i| entry(i, &format!("/a/{}.jpg", i)))
+            .collect()
+    }
+
+    #[test]
+    fn toggle_and_range() {
+        let files = list(6);
+        let mut sel = Selection::new();
+        assert!(sel.toggle(2));
+        // Range from the anchor (id 2, index 1) down to index 4
+        assert_eq!(sel.select_range(&files, 4), 4);
+        assert_eq!(
+            sel.ids.iter().copied().collect::<Vec<_>>(),
+            vec![2, 3, 4, 5]
+        );
+        // Anchor moved to the cursor; extending backwards works too
+        assert_eq!(sel.select_range(&files, 0), 5);
+        assert_eq!(sel.len(), 5);
+        assert!(!sel.toggle(3));
+        assert!(!sel.contains(3));
+    }
+
+    #[test]
+    fn range_without_anchor_marks_cursor() {
+        let files = list(3);
+        let mut sel = Selection::new();
+        assert_eq!(sel.select_range(&files, 2), 1);
+        assert!(sel.contains(3));
+        assert_eq!(sel.select_range(&[], 0), 0);
+    }
+
+    #[test]
+    fn all_and_invert_are_list_scoped() {
+        let files = list(4);
+        let mut sel = Selection::new();
+        sel.toggle(99); // selected in some other view
+        sel.toggle(1);
+        sel.invert(&files);
+        assert_eq!(
+            sel.ids.iter().copied().collect::<Vec<_>>(),
+            vec![2, 3, 4, 99]
+        );
+        sel.select_all(&files);
+        assert_eq!(sel.len(), 5);
+        sel.clear();
+        assert!(sel.is_empty());
+    }
+
+    #[test]
+    fn export_playlist_and_copies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let src = tmp.path().join("src");
+        std::fs::create_dir(&src).unwrap();
+        let mut targets = Vec::new();
+        for (id, name) in [(1, "a.jpg"), (2, "b.jpg")] {
+            let p = src.join(name);
+            std::fs::write(&p, name).unwrap();
+            targets.push(entry(id, &p.to_string_lossy()));
+        }
+
+        let m3u = tmp.path().join("pick.m3u");
+        export(&m3u, &targets).unwrap();
+        let text = std::fs::read_to_string(&m3u).unwrap();
+        assert_eq!(
+            text,
+            format!("#EXTM3U\n{}\n{}\n", targets[0].path, targets[1].path)
+        );
+
+        let out = tmp.path().join("out");
+        std::fs::create_dir(&out).unwrap();
+        std::fs::write(out.join("b.jpg"), "keep").unwrap();
+        let msg = export(&out, &targets).unwrap();
+        assert!(msg.contains("1 copied"), "{}", msg);
+        assert!(msg.contains("1 already there"), "{}", msg);
+        assert_eq!(std::fs::read_to_string(out.join("a.jpg")).unwrap(), "a.jpg");
+        assert_eq!(std::fs::read_to_string(out.join("b.jpg")).unwrap(), "keep");
+        // Originals stay where they were
+        assert!(Path::new(&targets[0].path).exists());
+
+        assert!(export(&out, &[]).is_err());
+    }
+
+    #[test]
+    fn expand_home_only_touches_tilde_prefix() {
+        assert_eq!(expand_home("/tmp/x"), PathBuf::from("/tmp/x"));
+        assert_eq!(expand_home("rel/~/x"), PathBuf::from("rel/~/x"));
+        assert!(!expand_home("~/x").starts_with("~"));
+    }
+}
@@ -0,0 +1,267 @@
+//! Slideshow (F5): step through the listing on a timer, in order, shuffled,
+//! or to random files from the library (or the open collection).
+//!
+//! Images stay up for the interval; a video plays to its end before the
+//! show moves on. Every display restarts the timer, so stepping by hand
+//! while the show runs just shifts it.
+
+use std::time::Instant;
+
+use crate::db::FileEntry;
+
+/// Intervals `[` and `]` step through, in seconds.
+const STEPS: &[f64] = &[
+    1.0, 2.0, 3.0, 5.0, 8.0, 10.0, 15.0, 20.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// The next file of the listing, wrapping at the end.
+    #[default]
+    Sequential,
+    /// Every file of the listing once, in random order, then again.
+    Shuffle,
+    /// A random file from the library, or from the open collection.
+    Random,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Mode::Sequential, Mode::Shuffle, Mode::Random];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Sequential => "sequential",
+            Mode::Shuffle => "shuffle",
+            Mode::Random => "random",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Mode> {
+        Mode::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    fn cycle(self) -> Mode {
+        let i = Mode::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        Mode::ALL[(i + 1) % Mode::ALL.len()]
+    }
+}
+
+/// Where the show goes next.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Step {
+    /// This index of the listing.
+    Index(usize),
+    /// A random file from the library or the open collection.
+    Random,
+}
+
+pub struct Slideshow {
+    pub mode: Mode,
+    /// Seconds an image stays up.
+    pub interval: f64,
+    pub paused: bool,
+    /// When the file at the cursor was shown.
+    shown_at: Instant,
+    /// The file at the cursor is a video still playing.
+    video: bool,
+    /// Ids of the listing not shown yet this round (shuffle).
+    queue: Vec<i64>,
+}
+
+impl Slideshow {
+    pub fn new(mode: Mode, interval: f64) -> Self {
+        Slideshow {
+            mode,
+            interval,
+            paused: false,
+            shown_at: Instant::now(),
+            video: false,
+            queue: Vec::new(),
+        }
+    }
+
+    /// The file at the cursor was just shown; `video` if the show should
+    /// wait for it to end rather than for the interval.
+    pub fn shown(&mut self, video: bool) {
+        self.shown_at = Instant::now();
+        self.video = video;
+    }
+
+    /// The video at the cursor finished or failed to play.
+    pub fn video_ended(&mut self) {
+        self.video = false;
+        let interval = std::time::Duration::from_secs_f64(self.interval);
+        if let Some(t) = Instant::now().checked_sub(interval) {
+            self.shown_at = t;
+        }
+    }
+
+    /// Time to move on.
+    pub fn due(&self) -> bool {
+        !self.paused && !self.video && self.shown_at.elapsed().as_secs_f64() >= self.interval
+    }
+
+    /// Pause or resume. Resuming gives the current image a full interval.
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if !self.paused && !self.video {
+            self.shown_at = Instant::now();
+        }
+    }
+
+    pub fn cycle_mode(&mut self) {
+        self.mode = self.mode.cycle();
+        self.queue.clear();
+    }
+
+    /// Step the interval up (`longer`) or down through `STEPS`.
+    pub fn step_interval(&mut self, longer: bool) {
+        let next = if longer {
+            STEPS.iter().find(|s| **s > self.interval)
+        } else {
+            STEPS.iter().rev().find(|s| **s < self.interval)
+        };
+        if let Some(s) = next {
+            self.interval = *s;
+        }
+    }
+
+    /// Where to go from `cursor` in `files`. `None` when there is nothing
+    /// to show.
+    pub fn next(&mut self, files: &[FileEntry], cursor: usize) -> Option<Step> {
+        if self.mode == Mode::Random {
+            return Some(Step::Random);
+        }
+        if files.is_empty() {
+            return None;
+        }
+        if self.mode == Mode::Sequential {
+            return Some(Step::Index((cursor + 1) % files.len()));
+        }
+        let current = files.get(cursor).map(|f| f.id);
+        // Ids of files removed since the round started are skipped
+        for _ in 0..2 {
+            if self.queue.is_empty() {
+                self.queue = files.iter().map(|f| f.id).collect();
+            }
+            while !self.queue.is_empty() {
+                let mut i = crate::random_index(self.queue.len());
+                // Don't show the same file twice in a row across rounds
+                if Some(self.queue[i]) == current && self.queue.len() > 1 {
+                    i = (i + 1) % self.queue.len();
+                }
+                let id = self.queue.swap_remove(i);
+                if let Some(idx) = files.iter().position(|f| f.id == id) {
+                    return Some(Step::Index(idx));
+                }
+            }
+        }
+        None
+    }
+
+    /// Status-bar text: `slideshow:shuffle 5s`, `||` when paused.
+    pub fn label(&self) -> String {
+        format!(
+            "slideshow:{} {}s{}",
+            self.mode.name(),
+            self.interval,
+            if self.paused { " ||" } else { "" }
+        )
+    }
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(n: i64) -> Vec<FileEntry> {
+        (1..=n)
+            .map(|id| FileEntry {
+                id,
+                path: format!("/p/{}.jpg", id),
+                dir: "/p".into(),
+                filename: format!("{}.jpg", id),
+                meta_id: None,
+                liked: false,
+                temporary: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sequential_wraps() {
+        let files = files(3);
+        let mut show = Slideshow::new(Mode::Sequential, 5.0);
+        assert_eq!(show.next(&files, 0), Some(Step::Index(1)));
+        assert_eq!(show.next(&files, 2), Some(Step::Index(0)));
+        assert_eq!(show.next(&[], 0), None);
+    }
+
+    #[test]
+    fn shuffle_shows_each_file_once_per_round() {
+        let files = files(5);
+        let mut show = Slideshow::new(Mode::Shuffle, 5.0);
+        let mut cursor = 0;
+        let mut seen = Vec::new();
+        for _ in 0..5 {
+            let Some(Step::Index(i)) = show.next(&files, cursor) else {
+                panic!("expected an index");
+            };
+            cursor = i;
+            seen.push(i);
+        }
+        seen.sort();
+        assert_eq!(seen, vec![0, 1, 2, 3, 4]);
+
+        // Next round never starts with the file just shown
+        for _ in 0..20 {
+            show.queue.clear();
+            assert_ne!(show.next(&files, cursor), Some(Step::Index(cursor)));
+        }
+
+        // Removed files are skipped
+        show.queue = vec![42, 3];
+        assert_eq!(show.next(&files, 0), Some(Step::Index(2)));
+
+        let one = &files[..1];
+        assert_eq!(show.next(one, 0), Some(Step::Index(0)));
+    }
+
+    #[test]
+    fn videos_wait_for_their_end() {
+        let mut show = Slideshow::new(Mode::Random, 0.0);
+        assert!(show.due());
+        assert_eq!(show.next(&[], 0), Some(Step::Random));
+
+        show.shown(true);
+        assert!(!show.due());
+        show.video_ended();
+        assert!(show.due());
+
+        show.toggle_pause();
+        assert!(!show.due());
+        assert_eq!(show.label(), "slideshow:random 0s ||");
+        show.toggle_pause();
+        assert!(show.due());
+    }
+
+    #[test]
+    fn interval_steps() {
+        let mut show = Slideshow::new(Mode::Sequential, 4.0);
+        show.step_interval(true);
+        assert_eq!(show.interval, 5.0);
+        show.step_interval(false);
+        show.step_interval(false);
+        assert_eq!(show.interval, 2.0);
+        show.interval = 300.0;
+        show.step_interval(true);
+        assert_eq!(show.interval, 300.0);
+
+        show.cycle_mode();
+        assert_eq!(show.mode, Mode::Shuffle);
+        assert_eq!(Mode::from_name("random"), Some(Mode::Random));
+        assert_eq!(show.label(), "slideshow:shuffle 300s");
+    }
+}
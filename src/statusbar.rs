@@ -52,8 +52,51 @@ pub fn add_font(imgui: &mut imgui::Context) {
     }]);
 }
 
-/// Apply a dark, semi-transparent theme suitable for a media viewer overlay.
-pub fn apply_theme(imgui: &mut imgui::Context) {
+/// Overlay colours (`[theme]` in the config). The defaults are the
+/// constants next to the code that draws with them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub background: [f32; 4],
+    pub text: [f32; 4],
+    pub dim: [f32; 4],
+    pub bright: [f32; 4],
+    pub accent: [f32; 4],
+    pub error: [f32; 4],
+    pub error_dim: [f32; 4],
+    pub label: [f32; 4],
+    pub value: [f32; 4],
+    pub header: [f32; 4],
+    /// Selection marks in the grid and filmstrip.
+    pub selected: [f32; 4],
+}
+
+impl Theme {
+    pub const DEFAULT: Theme = Theme {
+        background: [0.0, 0.0, 0.0, 0.78],
+        text: [0.9, 0.9, 0.9, 1.0],
+        dim: DIM,
+        bright: BRIGHT,
+        accent: ACCENT,
+        error: ERROR_COL,
+        error_dim: ERROR_DIM,
+        label: LABEL_COL,
+        value: VALUE_COL,
+        header: HEADER_COL,
+        selected: [0.43, 0.67, 1.0, 1.0],
+    };
+}
+
+static THEME: std::sync::OnceLock<Theme> = std::sync::OnceLock::new();
+
+/// The theme set by `apply_theme`, or the default before that.
+pub fn theme() -> &'static Theme {
+    THEME.get().unwrap_or(&Theme::DEFAULT)
+}
+
+/// Apply a dark, semi-transparent theme suitable for a media viewer overlay,
+/// with `theme`'s colours. Call once at startup.
+pub fn apply_theme(imgui: &mut imgui::Context, theme: &Theme) {
+    THEME.set(*theme).ok();
     let style = imgui.style_mut();
     style.window_rounding = 0.0;
     style.window_border_size = 0.0;
@@ -62,8 +105,8 @@ pub fn apply_theme(imgui: &mut imgui::Context) {
     style.item_spacing = [4.0, 0.0];
     style.window_min_size = [1.0, 1.0];
 
-    style.colors[imgui::sys::ImGuiCol_WindowBg as usize] = [0.0, 0.0, 0.0, 0.78];
-    style.colors[imgui::sys::ImGuiCol_Text as usize] = [0.9, 0.9, 0.9, 1.0];
+    style.colors[imgui::sys::ImGuiCol_WindowBg as usize] = theme.background;
+    style.colors[imgui::sys::ImGuiCol_Text as usize] = theme.text;
 }
 
 const STATUS_FLAGS: WindowFlags = WindowFlags::NO_TITLE_BAR
@@ -93,6 +136,22 @@ pub struct StatusInfo<'a> {
     pub video_duration: f64,
     pub volume: i64,
     pub turbo: bool,
+    /// Label of an active search result set (`/query`, `~similar.png`).
+    pub search: Option<&'a str>,
+    /// Directory of the current file relative to a flattened root (`2024/jan`,
+    /// `.` for the root itself).
+    pub subdir: Option<&'a str>,
+    /// Duplicate group `(position, total)` while stepping through duplicates.
+    pub dupes: Option<(usize, usize)>,
+    /// Short feedback message `(text, is_error)`, shown left of the index.
+    pub flash: Option<(&'a str, bool)>,
+    /// Number of selected files (0 = no selection).
+    pub selected: usize,
+    /// Sort order of a directory or collection listing (none for search
+    /// results and duplicates, which keep their own order).
+    pub sort: Option<&'a str>,
+    /// Running slideshow (`slideshow:shuffle 5s`).
+    pub slideshow: Option<&'a str>,
 }
 
 /// Truncate a string with middle ellipsis to fit within `max_w` pixels.
@@ -174,7 +233,37 @@ pub fn draw_status_bar(
 
         // Build right side (before buttons): [T] [index/total] + video info
         let turbo_prefix = if info.turbo { "[T] " } else { "" };
-        let index_text = format!("{}[{}/{}]", turbo_prefix, info.index, info.total);
+        let search_prefix = match (info.search, info.dupes) {
+            (Some(label), _) => match info.subdir {
+                Some(sub) => format!("{} {} ", label, sub),
+                None => format!("{} ", label),
+            },
+            (None, Some((n, total))) => format!("dupes {}/{} ", n, total),
+            (None, None) => String::new(),
+        };
+        let selected_prefix = if info.selected > 0 {
+            format!("✓{} ", info.selected)
+        } else {
+            String::new()
+        };
+        let sort_prefix = info
+            .sort
+            .map(|s| format!("sort:{} ", s))
+            .unwrap_or_default();
+        let slideshow_prefix = info
+            .slideshow
+            .map(|s| format!("{} ", s))
+            .unwrap_or_default();
+        let index_text = format!(
+            "{}{}{}{}{}[{}/{}]",
+            turbo_prefix,
+            slideshow_prefix,
+            search_prefix,
+            sort_prefix,
+            selected_prefix,
+            info.index,
+            info.total
+        );
         let right_text = if info.is_video {
             let icon = if info.paused { "||" } else { ">" };
             format!(
@@ -190,6 +279,10 @@ pub fn draw_status_bar(
         };
         let right_w = ui.calc_text_size(&right_text)[0];
         let right_x = buttons_start_x - pad - right_w;
+        let flash_w = info
+            .flash
+            .map(|(msg, _)| ui.calc_text_size(msg)[0] + pad * 3.0)
+            .unwrap_or(0.0);
 
         // Available width for left path + heart
         let heart_w = if info.liked {
@@ -197,7 +290,7 @@ pub fn draw_status_bar(
         } else {
             0.0
         };
-        let left_max = (right_x - pad * 2.0 - heart_w).max(50.0);
+        let left_max = (right_x - flash_w - pad * 2.0 - heart_w).max(50.0);
 
         // Split path into dir + basename
         let clean = crate::clean_path(info.path);
@@ -219,24 +312,37 @@ pub fn draw_status_bar(
         ui.set_cursor_pos([pad, y]);
         if path_w <= left_max {
             if !dir_part.is_empty() {
-                ui.text_colored(DIM, dir_part);
+                ui.text_colored(theme().dim, dir_part);
                 ui.same_line_with_spacing(0.0, 0.0);
             }
-            ui.text_colored(BRIGHT, base_part);
+            ui.text_colored(theme().bright, base_part);
         } else if dir_w > 0.0 && dir_w < left_max * 0.6 {
-            ui.text_colored(DIM, dir_part);
+            ui.text_colored(theme().dim, dir_part);
             ui.same_line_with_spacing(0.0, 0.0);
             let trunc = middle_ellipsis(ui, base_part, left_max - dir_w);
-            ui.text_colored(BRIGHT, trunc);
+            ui.text_colored(theme().bright, trunc);
         } else {
             let trunc = middle_ellipsis(ui, &clean, left_max);
-            ui.text_colored(BRIGHT, trunc);
+            ui.text_colored(theme().bright, trunc);
         }
 
         // Heart after filename
         if info.liked {
             ui.same_line_with_spacing(0.0, 0.0);
-            ui.text_colored(ACCENT, " ♥");
+            ui.text_colored(theme().accent, " ♥");
+        }
+
+        // Flash message just left of the right-side info
+        if let Some((msg, is_error)) = info.flash {
+            ui.set_cursor_pos([right_x - flash_w + pad, y]);
+            ui.text_colored(
+                if is_error {
+                    theme().error
+                } else {
+                    theme().accent
+                },
+                msg,
+            );
         }
 
         // Draw right: video info + [index/total]
@@ -249,13 +355,13 @@ pub fn draw_status_bar(
                 fmt_time(info.video_pos),
                 fmt_time(info.video_duration),
             );
-            ui.text_colored(BRIGHT, &progress);
+            ui.text_colored(theme().bright, &progress);
             ui.same_line();
-            ui.text_colored(DIM, format!("Vol: {}%", info.volume));
+            ui.text_colored(theme().dim, format!("Vol: {}%", info.volume));
             ui.same_line();
-            ui.text_colored(DIM, &index_text);
+            ui.text_colored(theme().dim, &index_text);
         } else {
-            ui.text_colored(DIM, &right_text);
+            ui.text_colored(theme().dim, &right_text);
         }
 
         // ── Window control buttons (— □ ✕) ──────────────────────────
@@ -294,6 +400,310 @@ pub fn draw_status_bar(
     action
 }
 
+// ── Search prompt ────────────────────────────────────────────────────────
+
+/// Outcome of one frame of a text prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptAction {
+    None,
+    Submit,
+    Cancel,
+}
+
+const PROMPT_FLAGS: WindowFlags = WindowFlags::NO_TITLE_BAR
+    .union(WindowFlags::NO_RESIZE)
+    .union(WindowFlags::NO_MOVE)
+    .union(WindowFlags::NO_SCROLLBAR)
+    .union(WindowFlags::NO_SCROLL_WITH_MOUSE)
+    .union(WindowFlags::NO_COLLAPSE)
+    .union(WindowFlags::NO_SAVED_SETTINGS);
+
+/// Draw the `/` search input over the status bar. Keeps keyboard focus while open.
+/// `error` is shown after the input (e.g. a parse error or "no matches").
+pub fn draw_search_prompt(
+    ui: &imgui::Ui,
+    buf: &mut String,
+    error: Option<&str>,
+    display_w: f32,
+) -> PromptAction {
+    draw_prompt(
+        ui,
+        "/",
+        "tag:c3 liked ext:png width>2000 prompt:\"red car\"",
+        buf,
+        error,
+        display_w,
+    )
+}
+
+/// Draw a one-line input over the status bar, introduced by `label`.
+pub fn draw_prompt(
+    ui: &imgui::Ui,
+    label: &str,
+    hint: &str,
+    buf: &mut String,
+    error: Option<&str>,
+    display_w: f32,
+) -> PromptAction {
+    let mut action = PromptAction::None;
+    if let Some(_win) = ui
+        .window("##prompt")
+        .position([0.0, 0.0], Condition::Always)
+        .size([display_w, BAR_HEIGHT], Condition::Always)
+        .bg_alpha(0.95)
+        .flags(PROMPT_FLAGS)
+        .begin()
+    {
+        ui.text_colored(theme().accent, label);
+        ui.same_line();
+        let label_w = ui.calc_text_size(label)[0];
+        let err_w = error.map(|e| ui.calc_text_size(e)[0] + 8.0).unwrap_or(0.0);
+        let _w = ui.push_item_width((display_w - 16.0 - label_w - err_w).max(100.0));
+        if !ui.is_any_item_active() {
+            ui.set_keyboard_focus_here();
+        }
+        if ui
+            .input_text("##query", buf)
+            .hint(hint)
+            .enter_returns_true(true)
+            .build()
+        {
+            action = PromptAction::Submit;
+        }
+        if let Some(e) = error {
+            ui.same_line();
+            ui.text_colored(theme().error, e);
+        }
+        if ui.is_key_pressed(imgui::Key::Escape) {
+            action = PromptAction::Cancel;
+        }
+    }
+    action
+}
+
+// ── Tag editor ───────────────────────────────────────────────────────────
+
+/// State of the `t` tag editor while it is open. `file_tags`, `all_tags` and
+/// `slots` are loaded by the caller and reloaded after every action.
+pub struct TagEditor {
+    pub file_tags: Vec<String>,
+    pub all_tags: Vec<(String, i64)>,
+    pub slots: Vec<(u8, String)>,
+    pub error: Option<String>,
+    input: String,
+    selected: Option<usize>,
+    slot_edit: Option<(u8, String)>,
+}
+
+/// What the user did in the tag editor this frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagAction {
+    None,
+    Add(String),
+    Remove(String),
+    RenameSlot(u8, String),
+    Close,
+}
+
+impl TagEditor {
+    pub fn new(
+        file_tags: Vec<String>,
+        all_tags: Vec<(String, i64)>,
+        slots: Vec<(u8, String)>,
+    ) -> Self {
+        TagEditor {
+            file_tags,
+            all_tags,
+            slots,
+            error: None,
+            input: String::new(),
+            selected: None,
+            slot_edit: None,
+        }
+    }
+}
+
+/// Existing tags matching `input` for autocomplete: prefix matches first,
+/// then substring matches (case-insensitive), skipping tags in `exclude`.
+pub fn tag_suggestions<'a>(
+    all_tags: &'a [(String, i64)],
+    input: &str,
+    exclude: &[String],
+    limit: usize,
+) -> Vec<&'a str> {
+    let needle = input.trim().to_lowercase();
+    let candidates = all_tags
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .filter(|name| !exclude.iter().any(|e| e == name));
+    let (mut prefix, mut inner): (Vec<&str>, Vec<&str>) = (Vec::new(), Vec::new());
+    for name in candidates {
+        let lower = name.to_lowercase();
+        if lower.starts_with(&needle) {
+            prefix.push(name);
+        } else if lower.contains(&needle) {
+            inner.push(name);
+        }
+    }
+    prefix.append(&mut inner);
+    prefix.truncate(limit);
+    prefix
+}
+
+const TAG_EDITOR_FLAGS: WindowFlags = WindowFlags::NO_TITLE_BAR
+    .union(WindowFlags::NO_RESIZE)
+    .union(WindowFlags::NO_MOVE)
+    .union(WindowFlags::NO_COLLAPSE)
+    .union(WindowFlags::NO_SAVED_SETTINGS);
+
+/// Draw the tag editor centered on screen.
+/// Enter adds the typed tag (or the suggestion picked with ↑/↓), clicking a
+/// tag removes it, clicking a digit key renames it. Esc closes.
+pub fn draw_tag_editor(
+    ui: &imgui::Ui,
+    ed: &mut TagEditor,
+    display_w: f32,
+    display_h: f32,
+) -> TagAction {
+    let mut action = TagAction::None;
+    let win_w = 360.0_f32.min(display_w - 20.0);
+    let win_h = 340.0_f32.min(display_h - BAR_HEIGHT * 2.0);
+    if let Some(_win) = ui
+        .window("##tageditor")
+        .position(
+            [(display_w - win_w) / 2.0, (display_h - win_h) / 2.0],
+            Condition::Always,
+        )
+        .size([win_w, win_h], Condition::Always)
+        .bg_alpha(0.95)
+        .flags(TAG_EDITOR_FLAGS)
+        .begin()
+    {
+        ui.text_colored(theme().header, "Tags");
+        ui.separator();
+
+        // Current tags — click to remove
+        if ed.file_tags.is_empty() {
+            ui.text_colored(theme().dim, "(none)");
+        }
+        for (i, tag) in ed.file_tags.iter().enumerate() {
+            if i > 0 {
+                let next_w = ui.calc_text_size(tag)[0] + 24.0;
+                if ui.cursor_pos()[0] + next_w < win_w {
+                    ui.same_line();
+                }
+            }
+            if ui.small_button(format!("{} ×##rm{}", tag, i)) {
+                action = TagAction::Remove(tag.clone());
+            }
+        }
+        ui.spacing();
+
+        // Input with autocomplete
+        let suggestions = tag_suggestions(&ed.all_tags, &ed.input, &ed.file_tags, 8);
+        if ed.slot_edit.is_none() {
+            if ui.is_key_pressed(imgui::Key::DownArrow) && !suggestions.is_empty() {
+                ed.selected = Some(ed.selected.map_or(0, |s| (s + 1) % suggestions.len()));
+            }
+            if ui.is_key_pressed(imgui::Key::UpArrow) {
+                ed.selected = match ed.selected {
+                    Some(0) | None => None,
+                    Some(s) => Some(s - 1),
+                };
+            }
+            if !ui.is_any_item_active() {
+                ui.set_keyboard_focus_here();
+            }
+        }
+        let _w = ui.push_item_width(-1.0);
+        let changed_before = ed.input.clone();
+        if ui
+            .input_text("##tag", &mut ed.input)
+            .hint("add tag… (↑/↓ to pick)")
+            .enter_returns_true(true)
+            .build()
+        {
+            let pick = ed
+                .selected
+                .and_then(|i| suggestions.get(i))
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| ed.input.trim().to_string());
+            if !pick.is_empty() {
+                action = TagAction::Add(pick);
+            }
+            ed.input.clear();
+            ed.selected = None;
+        } else if ed.input != changed_before {
+            ed.selected = None;
+        }
+        for (i, name) in suggestions.iter().enumerate() {
+            let count = ed
+                .all_tags
+                .iter()
+                .find(|(n, _)| n == name)
+                .map_or(0, |(_, c)| *c);
+            let label = format!("{}  ({})##sug{}", name, count, i);
+            if ui
+                .selectable_config(label)
+                .selected(ed.selected == Some(i))
+                .build()
+            {
+                action = TagAction::Add(name.to_string());
+                ed.input.clear();
+                ed.selected = None;
+            }
+        }
+
+        // Digit-key slots — click to rename
+        ui.spacing();
+        ui.text_colored(theme().header, "Keys");
+        ui.separator();
+        for (slot, name) in &ed.slots {
+            let on = ed.file_tags.contains(name);
+            ui.text_colored(
+                if on { theme().accent } else { theme().dim },
+                format!("{}", slot),
+            );
+            ui.same_line();
+            match ed.slot_edit {
+                Some((s, ref mut buf)) if s == *slot => {
+                    ui.set_keyboard_focus_here();
+                    if ui
+                        .input_text(format!("##slot{}", slot), buf)
+                        .enter_returns_true(true)
+                        .build()
+                    {
+                        action = TagAction::RenameSlot(s, buf.trim().to_string());
+                        ed.slot_edit = None;
+                    }
+                }
+                _ => {
+                    if ui.selectable(format!("{}##slot{}", name, slot)) {
+                        ed.slot_edit = Some((*slot, name.clone()));
+                    }
+                }
+            }
+        }
+        ui.text_colored(theme().dim, "9");
+        ui.same_line();
+        ui.text_colored(theme().dim, "like");
+
+        if let Some(ref e) = ed.error {
+            ui.spacing();
+            ui.text_colored(theme().error, e);
+        }
+
+        if ui.is_key_pressed(imgui::Key::Escape) {
+            if ed.slot_edit.is_some() {
+                ed.slot_edit = None;
+            } else {
+                action = TagAction::Close;
+            }
+        }
+    }
+    action
+}
+
 /// Draw a circular spinner in the center of the screen (shown while video loads).
 pub fn draw_spinner(ui: &imgui::Ui, display_w: f32, display_h: f32, time_secs: f32) {
     let draw_list = ui.get_foreground_draw_list();
@@ -370,17 +780,17 @@ pub fn draw_error_overlay(
         let icon = "\u{26A0}"; // ⚠
         let icon_w = ui.calc_text_size(icon)[0];
         ui.set_cursor_pos([(display_w - icon_w) / 2.0, start_y]);
-        ui.text_colored(ERROR_COL, icon);
+        ui.text_colored(theme().error, icon);
 
         // Error message
         let err_w = ui.calc_text_size(error)[0];
         ui.set_cursor_pos([(display_w - err_w) / 2.0, start_y + line_h]);
-        ui.text_colored(ERROR_COL, error);
+        ui.text_colored(theme().error, error);
 
         // Filename
         let fname_w = ui.calc_text_size(filename)[0];
         ui.set_cursor_pos([(display_w - fname_w) / 2.0, start_y + line_h * 2.0]);
-        ui.text_colored(ERROR_DIM, filename);
+        ui.text_colored(theme().error_dim, filename);
     }
 }
 
@@ -424,7 +834,7 @@ pub fn draw_info_panel(
         if let Some(sy) = scroll_req {
             ui.set_scroll_y(sy);
         }
-        ui.text_colored(HEADER_COL, "Info");
+        ui.text_colored(theme().header, "Info");
         ui.separator();
         ui.spacing();
 
@@ -454,25 +864,62 @@ pub fn draw_info_panel(
         if let Some(ref c) = meta.codecs {
             rows.push(("Codecs", c.clone()));
         }
-        if !meta.tags.is_empty() {
-            rows.push(("Tags", meta.tags.join(", ")));
+        if let Some(gop) = meta.gop_frames {
+            let every = match gop {
+                1 => "every frame".to_string(),
+                n => format!("every {} frames", n),
+            };
+            rows.push(("Keyframes", every));
+        }
+        if let Some(ref json) = meta.exif_json {
+            rows.extend(camera_rows(json));
         }
 
         for (label, value) in &rows {
-            ui.text_colored(LABEL_COL, label);
+            ui.text_colored(theme().label, label);
             ui.same_line_with_pos(label_w);
             // Wrap long values
             let avail = panel_w - label_w - 16.0;
             if ui.calc_text_size(value)[0] > avail && value.len() > 40 {
                 // Show wrapped
-                ui.text_colored(VALUE_COL, &value[..40.min(value.len())]);
+                ui.text_colored(theme().value, &value[..40.min(value.len())]);
                 let rest = &value[40.min(value.len())..];
                 if !rest.is_empty() {
                     ui.set_cursor_pos([label_w, ui.cursor_pos()[1]]);
-                    ui.text_colored(VALUE_COL, rest);
+                    ui.text_colored(theme().value, rest);
                 }
             } else {
-                ui.text_colored(VALUE_COL, value);
+                ui.text_colored(theme().value, value);
+            }
+        }
+
+        // Tags, wrapped onto as many lines as needed
+        if !meta.tags.is_empty() {
+            ui.spacing();
+            ui.text_colored(theme().label, "Tags");
+            ui.same_line_with_pos(label_w);
+            for (i, tag) in meta.tags.iter().enumerate() {
+                let label = if tag == "like" {
+                    "♥ like"
+                } else {
+                    tag.as_str()
+                };
+                if i > 0 {
+                    let w = ui.calc_text_size(label)[0] + 12.0;
+                    if ui.cursor_pos()[0] + w < panel_w - 8.0 {
+                        ui.same_line_with_spacing(0.0, 12.0);
+                    } else {
+                        ui.set_cursor_pos([label_w, ui.cursor_pos()[1]]);
+                    }
+                }
+                ui.text_colored(
+                    if tag == "like" {
+                        theme().accent
+                    } else {
+                        theme().value
+                    },
+                    label,
+                );
             }
         }
 
@@ -481,20 +928,20 @@ pub fn draw_info_panel(
             ui.spacing();
             ui.separator();
             ui.spacing();
-            ui.text_colored(LABEL_COL, "SHA-512");
+            ui.text_colored(theme().label, "SHA-512");
             // Show hash in two lines of 32 chars
             let h = hash.as_str();
             if h.len() > 32 {
-                ui.text_colored(DIM, &h[..32]);
-                ui.text_colored(DIM, &h[32..64.min(h.len())]);
+                ui.text_colored(theme().dim, &h[..32]);
+                ui.text_colored(theme().dim, &h[32..64.min(h.len())]);
                 if h.len() > 64 {
-                    ui.text_colored(DIM, &h[64..96.min(h.len())]);
+                    ui.text_colored(theme().dim, &h[64..96.min(h.len())]);
                     if h.len() > 96 {
-                        ui.text_colored(DIM, &h[96..]);
+                        ui.text_colored(theme().dim, &h[96..]);
                     }
                 }
             } else {
-                ui.text_colored(DIM, h);
+                ui.text_colored(theme().dim, h);
             }
         }
 
@@ -503,7 +950,7 @@ pub fn draw_info_panel(
             ui.spacing();
             ui.separator();
             ui.spacing();
-            ui.text_colored(HEADER_COL, "AI");
+            ui.text_colored(theme().header, "AI");
             ui.text_wrapped(info);
         }
 
@@ -511,14 +958,14 @@ pub fn draw_info_panel(
         ui.spacing();
         ui.separator();
         ui.spacing();
-        ui.text_colored(LABEL_COL, "Path");
+        ui.text_colored(theme().label, "Path");
         ui.text_wrapped(crate::clean_path(&meta.path));
     }
 
     panel_w
 }
 
-fn format_size(bytes: i64) -> String {
+pub fn format_size(bytes: i64) -> String {
     if bytes < 1024 {
         format!("{} B", bytes)
     } else if bytes < 1024 * 1024 {
@@ -541,6 +988,74 @@ fn format_duration(ms: i64) -> String {
     }
 }
 
+/// Info panel rows from `meta.exif_json`, skipping what the camera didn't
+/// record.
+fn camera_rows(json: &str) -> Vec<(&'static str, String)> {
+    let Ok(v) = serde_json::from_str::<serde_json::Value>(json) else {
+        return Vec::new();
+    };
+    let text = |k: &str| v[k].as_str().map(str::to_string);
+    let num = |k: &str| v[k].as_f64();
+    let mut rows = Vec::new();
+
+    // Model names usually repeat the make ("Canon" / "Canon EOS R5")
+    let camera = match (text("make"), text("model")) {
+        (Some(make), Some(model)) if model.to_lowercase().starts_with(&make.to_lowercase()) => {
+            Some(model)
+        }
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model),
+    };
+    if let Some(c) = camera {
+        rows.push(("Camera", c));
+    }
+    if let Some(lens) = text("lens") {
+        rows.push(("Lens", lens));
+    }
+    if let Some(t) = num("exposure_time").filter(|&t| t > 0.0) {
+        rows.push(("Exposure", format_exposure(t)));
+    }
+    if let Some(f) = num("f_number") {
+        rows.push(("Aperture", format!("f/{}", trim_float(f, 1))));
+    }
+    if let Some(iso) = v["iso"].as_u64() {
+        rows.push(("ISO", iso.to_string()));
+    }
+    if let Some(fl) = num("focal_length") {
+        let mut s = format!("{} mm", trim_float(fl, 1));
+        if let Some(eq) = v["focal_length_35mm"].as_u64() {
+            s.push_str(&format!(" ({} mm eq.)", eq));
+        }
+        rows.push(("Focal", s));
+    }
+    if let Some(t) = text("taken_at") {
+        rows.push(("Taken", t));
+    }
+    if let (Some(lat), Some(lon)) = (v["gps"]["lat"].as_f64(), v["gps"]["lon"].as_f64()) {
+        rows.push(("GPS", format!("{:.5}, {:.5}", lat, lon)));
+    }
+    rows
+}
+
+/// Shutter speed: `1/125 s` below a second, `2.5 s` above.
+fn format_exposure(t: f64) -> String {
+    if t < 1.0 {
+        format!("1/{} s", (1.0 / t).round())
+    } else {
+        format!("{} s", trim_float(t, 1))
+    }
+}
+
+/// `2.80` → `2.8`, `50.0` → `50`.
+fn trim_float(v: f64, decimals: usize) -> String {
+    let s = format!("{:.*}", decimals, v);
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        s
+    }
+}
+
 fn fmt_size(bytes: i64) -> String {
     const KB: i64 = 1024;
     const MB: i64 = 1024 * 1024;
@@ -556,20 +1071,19 @@ fn fmt_size(bytes: i64) -> String {
     }
 }
 
-fn collection_name(c: Option<u8>) -> &'static str {
+/// Header for the stats section. `slot_tag` is the tag on digit key 2-8;
+/// it is shown unless it still has its default `cN` name.
+fn collection_name(c: Option<u8>, slot_tag: Option<&str>) -> String {
     match c {
-        None => "Library",
-        Some(0) => "C0 Permanent",
-        Some(1) => "C1 Temporary",
-        Some(2) => "C2",
-        Some(3) => "C3",
-        Some(4) => "C4",
-        Some(5) => "C5",
-        Some(6) => "C6",
-        Some(7) => "C7",
-        Some(8) => "C8",
-        Some(9) => "C9 Favorites",
-        _ => "Collection",
+        None => "Library".into(),
+        Some(0) => "C0 Permanent".into(),
+        Some(1) => "C1 Temporary".into(),
+        Some(n @ 2..=8) => match slot_tag {
+            Some(tag) if tag != format!("c{n}") => format!("C{n} {tag}"),
+            _ => format!("C{n}"),
+        },
+        Some(9) => "C9 Favorites".into(),
+        _ => "Collection".into(),
     }
 }
 
@@ -624,12 +1138,18 @@ pub fn draw_stats_section(
         };
 
         // Collection
-        ui.text_colored(HEADER_COL, collection_name(collection_mode));
+        let slot_tag = collection_mode
+            .filter(|c| (2..=8).contains(c))
+            .map(|c| db.slot_tag(c));
+        ui.text_colored(
+            theme().header,
+            collection_name(collection_mode, slot_tag.as_deref()),
+        );
         ui.separator();
         if let Some(c) = collection_mode {
             let (col_count, col_size) = db.collection_count_size(c);
             ui.text_colored(
-                DIM,
+                theme().dim,
                 format!(
                     "{} files  {}  (of {} total)",
                     col_count,
@@ -639,7 +1159,7 @@ pub fn draw_stats_section(
             );
         } else {
             ui.text_colored(
-                DIM,
+                theme().dim,
                 format!("{} files  {} dirs", cs.total_files, cs.total_dirs),
             );
         }
@@ -654,11 +1174,11 @@ pub fn draw_stats_section(
             0
         };
         ui.text_colored(
-            DIM,
+            theme().dim,
             format!("# {}/{}  {}%", cs.hashed, cs.total_files, pct_hash),
         );
         ui.text_colored(
-            DIM,
+            theme().dim,
             format!("E {}/{}  {}%", cs.with_exif, cs.total_files, pct_exif),
         );
 
@@ -666,10 +1186,10 @@ pub fn draw_stats_section(
 
         // Jobs
         let mode = if turbo { "Turbo" } else { "Lazy" };
-        ui.text_colored(HEADER_COL, format!("Jobs [{}]", mode));
+        ui.text_colored(theme().header, format!("Jobs [{}]", mode));
         ui.separator();
         ui.text_colored(
-            DIM,
+            theme().dim,
             format!(
                 "{}/min  ok:{}  err:{}  run:{}",
                 rpm_str, done, failed, active
@@ -702,6 +1222,7 @@ pub fn fmt_time(secs: f64) -> String {
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
     use super::*;
 
@@ -809,15 +1330,50 @@ mod tests {
 
     #[test]
     fn collection_name_values() {
-        assert_eq!(collection_name(None), "Library");
-        assert_eq!(collection_name(Some(0)), "C0 Permanent");
-        assert_eq!(collection_name(Some(1)), "C1 Temporary");
-        assert_eq!(collection_name(Some(9)), "C9 Favorites");
+        assert_eq!(collection_name(None, None), "Library");
+        assert_eq!(collection_name(Some(0), None), "C0 Permanent");
+        assert_eq!(collection_name(Some(1), None), "C1 Temporary");
+        assert_eq!(collection_name(Some(9), None), "C9 Favorites");
         for c in 2..=8 {
-            assert_eq!(collection_name(Some(c)), format!("C{c}"));
+            assert_eq!(collection_name(Some(c), None), format!("C{c}"));
         }
-        assert_eq!(collection_name(Some(10)), "Collection");
-        assert_eq!(collection_name(Some(255)), "Collection");
+        assert_eq!(collection_name(Some(10), None), "Collection");
+        assert_eq!(collection_name(Some(255), None), "Collection");
+    }
+
+    #[test]
+    fn collection_name_shows_renamed_slot() {
+        assert_eq!(collection_name(Some(3), Some("c3")), "C3");
+        assert_eq!(collection_name(Some(3), Some("portfolio")), "C3 portfolio");
+        assert_eq!(collection_name(Some(9), Some("x")), "C9 Favorites");
+    }
+
+    // ── tag_suggestions ─────────────────────────────────────────────────
+
+    fn all_tags(names: &[&str]) -> Vec<(String, i64)> {
+        names.iter().map(|n| (n.to_string(), 1)).collect()
+    }
+
+    #[test]
+    fn tag_suggestions_prefix_before_substring() {
+        let all = all_tags(&["reject", "portfolio", "sport", "Portrait"]);
+        assert_eq!(
+            tag_suggestions(&all, "port", &[], 8),
+            vec!["portfolio", "Portrait", "sport"]
+        );
+    }
+
+    #[test]
+    fn tag_suggestions_skips_applied_and_limits() {
+        let all = all_tags(&["a1", "a2", "a3", "a4"]);
+        let applied = vec!["a2".to_string()];
+        assert_eq!(tag_suggestions(&all, "a", &applied, 2), vec!["a1", "a3"]);
+    }
+
+    #[test]
+    fn tag_suggestions_empty_input_lists_all() {
+        let all = all_tags(&["like", "c3"]);
+        assert_eq!(tag_suggestions(&all, "  ", &[], 8), vec!["like", "c3"]);
     }
 
     // ── error overlay constants ─────────────────────────────────────────
@@ -913,6 +1469,40 @@ mod tests {
         assert_eq!(format_duration(3599000), "59:59");
     }
 
+    #[test]
+    fn camera_rows_from_exif_json() {
+        let json = r#"{"make":"Canon","model":"Canon EOS R5","lens":"RF50mm F1.8 STM",
+            "exposure_time":0.008,"f_number":2.8,"iso":400,"focal_length":50.0,
+            "focal_length_35mm":75,"taken_at":"2024-05-01 12:30:45",
+            "gps":{"lat":51.51,"lon":-0.1275}}"#;
+        assert_eq!(
+            camera_rows(json),
+            vec![
+                ("Camera", "Canon EOS R5".to_string()),
+                ("Lens", "RF50mm F1.8 STM".to_string()),
+                ("Exposure", "1/125 s".to_string()),
+                ("Aperture", "f/2.8".to_string()),
+                ("ISO", "400".to_string()),
+                ("Focal", "50 mm (75 mm eq.)".to_string()),
+                ("Taken", "2024-05-01 12:30:45".to_string()),
+                ("GPS", "51.51000, -0.12750".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn camera_rows_partial_and_empty() {
+        assert!(camera_rows("{}").is_empty());
+        assert!(camera_rows("not json").is_empty());
+        assert_eq!(
+            camera_rows(r#"{"make":"FUJIFILM","model":"X-T5","exposure_time":2.5}"#),
+            vec![
+                ("Camera", "FUJIFILM X-T5".to_string()),
+                ("Exposure", "2.5 s".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn format_duration_hours() {
         assert_eq!(format_duration(3600000), "1:00:00");
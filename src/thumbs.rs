@@ -0,0 +1,297 @@
+//! On-disk thumbnail store, content-addressed by `hash_sha512`.
+//!
+//! Thumbnails are JPEGs at most `THUMB_SIZE` pixels on their long side,
+//! stored as `<root>/<first two hash chars>/<hash>.jpg` under the cache
+//! directory (or `$LV_THUMB_DIR`). Copies of a file share one thumbnail, and
+//! a thumbnail stays valid for as long as some file has that hash.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use image::{DynamicImage, ImageDecoder};
+
+/// Long side of a thumbnail, in pixels.
+pub const THUMB_SIZE: u32 = 256;
+const JPEG_QUALITY: u8 = 85;
+/// Give up on a video frame after this long.
+const VIDEO_TIMEOUT_SECS: f64 = 30.0;
+
+/// Root of the thumbnail store.
+pub fn dir() -> PathBuf {
+    if let Ok(p) = std::env::var("LV_THUMB_DIR") {
+        return PathBuf::from(p);
+    }
+    if let Some(dirs) = directories::ProjectDirs::from("dev", "lv", "lv") {
+        dirs.cache_dir().join("thumbs")
+    } else {
+        PathBuf::from("thumbs")
+    }
+}
+
+/// Where the thumbnail for `hash` lives. `fp:` hashes keep their prefix as
+/// `fp_` so the name is valid everywhere.
+pub fn path_for(root: &Path, hash: &str) -> PathBuf {
+    let name = hash.replace(':', "_");
+    let shard = hash.trim_start_matches("fp:").get(..2).unwrap_or("00");
+    root.join(shard).join(format!("{}.jpg", name))
+}
+
+/// Hash a store file was written for (inverse of `path_for`).
+fn hash_of(file: &Path) -> Option<String> {
+    let stem = file.file_stem()?.to_str()?;
+    if file.extension()? != "jpg" {
+        return None;
+    }
+    Some(match stem.strip_prefix("fp_") {
+        Some(rest) => format!("fp:{}", rest),
+        None => stem.to_string(),
+    })
+}
+
+/// Shrink to fit `THUMB_SIZE`; smaller images are kept as they are.
+fn shrink(img: DynamicImage) -> DynamicImage {
+    if img.width().max(img.height()) > THUMB_SIZE {
+        img.thumbnail(THUMB_SIZE, THUMB_SIZE)
+    } else {
+        img
+    }
+}
+
+/// Decode an image file, upright, scaled to thumbnail size.
+pub fn from_image(path: &str) -> Result<DynamicImage, String> {
+    let mut decoder = image::ImageReader::open(path)
+        .map_err(|e| e.to_string())?
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .into_decoder()
+        .map_err(|e| e.to_string())?;
+    let orientation = decoder.orientation().ok();
+    let mut img = DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+    if let Some(o) = orientation {
+        img.apply_orientation(o);
+    }
+    Ok(shrink(img))
+}
+
+/// Grab a frame a quarter into a video with a headless mpv (`vo=image`),
+/// scaled to thumbnail size.
+pub fn from_video(path: &str) -> Result<DynamicImage, String> {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    static SEQ: AtomicU32 = AtomicU32::new(0);
+
+    let out = std::env::temp_dir().join(format!(
+        "lv-thumb-{}-{}",
+        std::process::id(),
+        SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&out).map_err(|e| e.to_string())?;
+    let result = grab_frame(path, &out)
+        .and_then(|frame| image::open(&frame).map(shrink).map_err(|e| e.to_string()));
+    std::fs::remove_dir_all(&out).ok();
+    result
+}
+
+fn grab_frame(path: &str, out: &Path) -> Result<PathBuf, String> {
+    use libmpv2::events::Event;
+
+    let outdir = out.to_string_lossy().to_string();
+    let mut mpv = libmpv2::Mpv::with_initializer(|init| {
+        init.set_property("config", "no")?;
+        init.set_property("terminal", "no")?;
+        init.set_property("load-scripts", "no")?;
+        init.set_property("vo", "image")?;
+        init.set_property("vo-image-format", "png")?;
+        init.set_property("vo-image-outdir", outdir.as_str())?;
+        init.set_property("ao", "null")?;
+        init.set_property("aid", "no")?;
+        init.set_property("sid", "no")?;
+        init.set_property("hwdec", "no")?;
+        init.set_property("start", "25%")?;
+        init.set_property("frames", "1")?;
+        Ok(())
+    })
+    .map_err(|e| e.to_string())?;
+    mpv.command("loadfile", &[path])
+        .map_err(|e| e.to_string())?;
+
+    let deadline =
+        std::time::Instant::now() + std::time::Duration::from_secs_f64(VIDEO_TIMEOUT_SECS);
+    loop {
+        let left = deadline.saturating_duration_since(std::time::Instant::now());
+        if left.is_zero() {
+            return Err("timed out grabbing video frame".into());
+        }
+        match mpv.wait_event(left.as_secs_f64()) {
+            Some(Ok(Event::EndFile(_))) | Some(Ok(Event::Shutdown)) => break,
+            Some(Err(e)) => return Err(e.to_string()),
+            _ => {}
+        }
+    }
+    drop(mpv);
+
+    std::fs::read_dir(out)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|e| e.path())
+        .min()
+        .ok_or_else(|| "mpv wrote no frame".to_string())
+}
+
+/// Write `img` as the thumbnail for `hash`. The file appears atomically.
+pub fn write(root: &Path, hash: &str, img: &DynamicImage) -> Result<PathBuf, String> {
+    let dest = path_for(root, hash);
+    let parent = dest.parent().unwrap_or(root);
+    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    let tmp = parent.join(format!(
+        ".{}.{}.tmp",
+        hash.replace(':', "_"),
+        std::process::id()
+    ));
+    let file = std::fs::File::create(&tmp).map_err(|e| e.to_string())?;
+    let mut w = std::io::BufWriter::new(file);
+    let enc = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut w, JPEG_QUALITY);
+    let written = img
+        .to_rgb8()
+        .write_with_encoder(enc)
+        .map_err(|e| e.to_string())
+        .and_then(|_| std::io::Write::flush(&mut w).map_err(|e| e.to_string()));
+    drop(w);
+    if let Err(e) = written.and_then(|_| std::fs::rename(&tmp, &dest).map_err(|e| e.to_string())) {
+        std::fs::remove_file(&tmp).ok();
+        return Err(e);
+    }
+    Ok(dest)
+}
+
+/// Remove thumbnails whose hash is not in `keep`, and empty shard dirs.
+/// Returns (files removed, bytes freed).
+pub fn gc(root: &Path, keep: &HashSet<String>) -> (usize, u64) {
+    let (mut removed, mut freed) = (0, 0);
+    let Ok(shards) = std::fs::read_dir(root) else {
+        return (0, 0);
+    };
+    for shard in shards.flatten().map(|e| e.path()).filter(|p| p.is_dir()) {
+        for file in std::fs::read_dir(&shard).into_iter().flatten().flatten() {
+            let path = file.path();
+            let orphan = match hash_of(&path) {
+                Some(hash) => !keep.contains(&hash),
+                // Leftover temp files from an interrupted write
+                None => path.extension().is_some_and(|e| e == "tmp"),
+            };
+            if orphan {
+                let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+                if std::fs::remove_file(&path).is_ok() {
+                    removed += 1;
+                    freed += size;
+                }
+            }
+        }
+        // Only succeeds when empty
+        std::fs::remove_dir(&shard).ok();
+    }
+    (removed, freed)
+}
+
+/// Number of thumbnails and their total size.
+pub fn usage(root: &Path) -> (usize, u64) {
+    let mut count = 0;
+    let mut bytes = 0;
+    for shard in std::fs::read_dir(root).into_iter().flatten().flatten() {
+        for file in std::fs::read_dir(shard.path())
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            if hash_of(&file.path()).is_some() {
+                count += 1;
+                bytes += file.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+        }
+    }
+    (count, bytes)
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_layout() {
+        let root = Path::new("/cache/thumbs");
+        assert_eq!(
+            path_for(root, "abcdef"),
+            PathBuf::from("/cache/thumbs/ab/abcdef.jpg")
+        );
+        assert_eq!(
+            path_for(root, "fp:1234"),
+            PathBuf::from("/cache/thumbs/12/fp_1234.jpg")
+        );
+        assert_eq!(
+            hash_of(&path_for(root, "fp:1234")).as_deref(),
+            Some("fp:1234")
+        );
+        assert_eq!(
+            hash_of(&path_for(root, "abcdef")).as_deref(),
+            Some("abcdef")
+        );
+        assert_eq!(hash_of(Path::new("/x/ab/.1.tmp")), None);
+    }
+
+    #[test]
+    fn image_thumbnail_is_bounded_and_written() {
+        let tmp = tempfile::tempdir().unwrap();
+        let src = tmp.path().join("wide.png");
+        image::RgbImage::from_pixel(1000, 500, image::Rgb([200, 10, 10]))
+            .save(&src)
+            .unwrap();
+        let thumb = from_image(&src.to_string_lossy()).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (256, 128));
+
+        let root = tmp.path().join("thumbs");
+        let dest = write(&root, "abc123", &thumb).unwrap();
+        assert_eq!(dest, root.join("ab/abc123.jpg"));
+        let back = image::open(&dest).unwrap();
+        assert_eq!((back.width(), back.height()), (256, 128));
+        // No temp file left behind
+        assert_eq!(std::fs::read_dir(root.join("ab")).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn small_images_are_not_upscaled() {
+        let tmp = tempfile::tempdir().unwrap();
+        let src = tmp.path().join("icon.png");
+        image::RgbImage::new(32, 16).save(&src).unwrap();
+        let thumb = from_image(&src.to_string_lossy()).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (32, 16));
+    }
+
+    #[test]
+    fn gc_removes_orphans_and_empty_shards() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let img = DynamicImage::new_rgb8(8, 8);
+        for hash in ["aa11", "aa22", "bb33", "fp:cc44"] {
+            write(root, hash, &img).unwrap();
+        }
+        std::fs::write(root.join("aa/.99.tmp"), b"partial").unwrap();
+        assert_eq!(usage(root).0, 4);
+
+        let keep: HashSet<String> = ["aa11".to_string(), "fp:cc44".to_string()].into();
+        let (removed, freed) = gc(root, &keep);
+        assert_eq!(removed, 3);
+        assert!(freed > 0);
+        assert!(path_for(root, "aa11").exists());
+        assert!(path_for(root, "fp:cc44").exists());
+        assert!(!path_for(root, "aa22").exists());
+        assert!(!root.join("bb").exists());
+        assert_eq!(usage(root).0, 2);
+    }
+
+    #[test]
+    fn gc_on_missing_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(gc(&tmp.path().join("none"), &HashSet::new()), (0, 0));
+    }
+}
@@ -0,0 +1,267 @@
+//! Move files to the freedesktop.org Trash instead of deleting them.
+//!
+//! Files go to the home trash (`$XDG_DATA_HOME/Trash`, usually
+//! `~/.local/share/Trash`) with a `.trashinfo` record, so file managers can
+//! show and restore them. A file on another filesystem can't be renamed
+//! into the home trash; it goes to `$topdir/.Trash-$uid` on its own mount.
+
+use std::path::{Path, PathBuf};
+
+/// Move `path` to the trash. Returns where the file now lives.
+#[cfg(unix)]
+pub fn trash(path: &Path) -> Result<PathBuf, String> {
+    let path = std::path::absolute(path).map_err(|e| e.to_string())?;
+    let home = directories::BaseDirs::new()
+        .map(|d| d.data_dir().join("Trash"))
+        .ok_or("no home directory")?;
+    match trash_into(&path, &home) {
+        Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+            let top = mount_top(&path).map_err(|e| e.to_string())?;
+            let uid = unsafe { libc::getuid() };
+            trash_into(&path, &top.join(format!(".Trash-{}", uid)))
+        }
+        r => r,
+    }
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(not(unix))]
+pub fn trash(_path: &Path) -> Result<PathBuf, String> {
+    Err("trash is not supported on this platform".into())
+}
+
+/// Put a trashed file back at `original` and drop its `.trashinfo`. Refuses
+/// to overwrite a file that has appeared there since.
+pub fn restore(trashed: &Path, original: &Path) -> Result<(), String> {
+    if original.symlink_metadata().is_ok() {
+        return Err(format!("{} already exists", original.display()));
+    }
+    if let Some(parent) = original.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::rename(trashed, original).map_err(|e| e.to_string())?;
+    let name = trashed.file_name().unwrap_or_default().to_string_lossy();
+    if let Some(trash_dir) = trashed.parent().and_then(Path::parent) {
+        std::fs::remove_file(trash_dir.join("info").join(format!("{}.trashinfo", name))).ok();
+    }
+    Ok(())
+}
+
+/// Move `path` into `trash_dir/files`, writing `trash_dir/info/NAME.trashinfo`
+/// first. The info file is created exclusively, which reserves the name; a
+/// taken name gets a counter (`a.jpg` → `a.2.jpg`).
+#[cfg(unix)]
+fn trash_into(path: &Path, trash_dir: &Path) -> std::io::Result<PathBuf> {
+    use std::io::Write;
+    use std::os::unix::fs::DirBuilderExt;
+
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    for dir in [&files_dir, &info_dir] {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+    }
+    let name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "no file name"))?
+        .to_string_lossy()
+        .to_string();
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(path),
+        deletion_date()
+    );
+
+    let mut n = 1u32;
+    loop {
+        let candidate = if n == 1 {
+            name.clone()
+        } else {
+            numbered(&name, n)
+        };
+        n += 1;
+        let dest = files_dir.join(&candidate);
+        if dest.symlink_metadata().is_ok() {
+            continue;
+        }
+        let info_path = info_dir.join(format!("{}.trashinfo", candidate));
+        let mut f = match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+        let moved = f
+            .write_all(info.as_bytes())
+            .and_then(|_| std::fs::rename(path, &dest));
+        if let Err(e) = moved {
+            std::fs::remove_file(&info_path).ok();
+            return Err(e);
+        }
+        return Ok(dest);
+    }
+}
+
+/// `a.jpg` → `a.N.jpg`; names without an extension get `.N` appended.
+pub(crate) fn numbered(name: &str, n: u32) -> String {
+    match name.rfind('.') {
+        Some(i) if i > 0 => format!("{}.{}{}", &name[..i], n, &name[i..]),
+        _ => format!("{}.{}", name, n),
+    }
+}
+
+/// Percent-encode a path for the `Path=` key (RFC 2396, `/` kept).
+#[cfg(unix)]
+fn encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    let mut out = String::new();
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// Local time as `YYYY-MM-DDThh:mm:ss`, the `DeletionDate=` format.
+#[cfg(unix)]
+fn deletion_date() -> String {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::localtime_r(&now, &mut tm);
+    }
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+/// Topmost ancestor of `path` on the same device: its mount point.
+#[cfg(unix)]
+fn mount_top(path: &Path) -> std::io::Result<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+    let dev = path.symlink_metadata()?.dev();
+    let mut top = path.parent().unwrap_or(path).to_path_buf();
+    while let Some(parent) = top.parent() {
+        match parent.metadata() {
+            Ok(m) if m.dev() == dev => top = parent.to_path_buf(),
+            _ => break,
+        }
+    }
+    Ok(top)
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trash_into_moves_file_and_writes_info() {
+        let src = tempfile::tempdir().unwrap();
+        let bin = tempfile::tempdir().unwrap();
+        let file = src.path().join("a b.jpg");
+        std::fs::write(&file, b"x").unwrap();
+
+        let dest = trash_into(&file, bin.path()).unwrap();
+        assert!(!file.exists());
+        assert_eq!(dest, bin.path().join("files/a b.jpg"));
+        assert_eq!(std::fs::read(&dest).unwrap(), b"x");
+
+        let info = std::fs::read_to_string(bin.path().join("info/a b.jpg.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains(&format!("Path={}/a%20b.jpg\n", src.path().display())));
+        assert!(info.contains("DeletionDate="));
+    }
+
+    #[test]
+    fn trash_into_numbers_taken_names() {
+        let src = tempfile::tempdir().unwrap();
+        let bin = tempfile::tempdir().unwrap();
+        for content in [b"1", b"2", b"3"] {
+            let file = src.path().join("a.jpg");
+            std::fs::write(&file, content).unwrap();
+            trash_into(&file, bin.path()).unwrap();
+        }
+        assert_eq!(std::fs::read(bin.path().join("files/a.jpg")).unwrap(), b"1");
+        assert_eq!(
+            std::fs::read(bin.path().join("files/a.2.jpg")).unwrap(),
+            b"2"
+        );
+        assert_eq!(
+            std::fs::read(bin.path().join("files/a.3.jpg")).unwrap(),
+            b"3"
+        );
+        assert!(bin.path().join("info/a.3.jpg.trashinfo").exists());
+    }
+
+    #[test]
+    fn restore_puts_file_back_and_drops_info() {
+        let src = tempfile::tempdir().unwrap();
+        let bin = tempfile::tempdir().unwrap();
+        let file = src.path().join("sub/a.jpg");
+        std::fs::create_dir(src.path().join("sub")).unwrap();
+        std::fs::write(&file, b"x").unwrap();
+        let dest = trash_into(&file, bin.path()).unwrap();
+        std::fs::remove_dir(src.path().join("sub")).unwrap();
+
+        restore(&dest, &file).unwrap();
+        assert_eq!(std::fs::read(&file).unwrap(), b"x");
+        assert!(!dest.exists());
+        assert!(!bin.path().join("info/a.jpg.trashinfo").exists());
+
+        // A new file at the old path is never overwritten
+        let dest = trash_into(&file, bin.path()).unwrap();
+        std::fs::write(&file, b"new").unwrap();
+        assert!(restore(&dest, &file).is_err());
+        assert_eq!(std::fs::read(&file).unwrap(), b"new");
+        assert!(dest.exists());
+    }
+
+    #[test]
+    fn trash_into_missing_file_leaves_no_info() {
+        let src = tempfile::tempdir().unwrap();
+        let bin = tempfile::tempdir().unwrap();
+        assert!(trash_into(&src.path().join("gone.jpg"), bin.path()).is_err());
+        assert!(!bin.path().join("info/gone.jpg.trashinfo").exists());
+    }
+
+    #[test]
+    fn numbered_names() {
+        assert_eq!(numbered("a.jpg", 2), "a.2.jpg");
+        assert_eq!(numbered("a.tar.gz", 3), "a.tar.3.gz");
+        assert_eq!(numbered("README", 2), "README.2");
+        assert_eq!(numbered(".hidden", 2), ".hidden.2");
+    }
+
+    #[test]
+    fn encode_path_escapes_bytes() {
+        assert_eq!(
+            encode_path(Path::new("/a/b c/ü.jpg")),
+            "/a/b%20c/%C3%BC.jpg"
+        );
+        assert_eq!(encode_path(Path::new("/x/100%.png")), "/x/100%25.png");
+    }
+
+    #[test]
+    fn deletion_date_format() {
+        let d = deletion_date();
+        assert_eq!(d.len(), 19);
+        assert_eq!(&d[4..5], "-");
+        assert_eq!(&d[10..11], "T");
+    }
+}
@@ -0,0 +1,293 @@
+//! Directory tree panel (Tab): tracked roots and their subdirectories with
+//! file, like and hashing counts. Clicking a directory opens it.
+//!
+//! Counts include subdirectories. The tree opens down to the current
+//! directory whenever it changes, and is rebuilt every few seconds while
+//! shown so hashing progress moves.
+
+use std::time::{Duration, Instant};
+
+use imgui::{Condition, TreeNodeFlags, WindowFlags};
+
+use crate::db::{natural_cmp, Db, DirCounts};
+use crate::statusbar::BAR_HEIGHT;
+
+/// How often the counts are reloaded while the panel is open.
+const REFRESH: Duration = Duration::from_secs(2);
+
+const PANEL_FLAGS: WindowFlags = WindowFlags::NO_TITLE_BAR
+    .union(WindowFlags::NO_RESIZE)
+    .union(WindowFlags::NO_MOVE)
+    .union(WindowFlags::NO_COLLAPSE)
+    .union(WindowFlags::NO_SAVED_SETTINGS)
+    .union(WindowFlags::NO_FOCUS_ON_APPEARING)
+    .union(WindowFlags::NO_NAV);
+
+/// A directory and the totals of everything below it.
+#[derive(Debug, PartialEq)]
+pub struct Node {
+    pub path: String,
+    pub name: String,
+    pub files: i64,
+    pub liked: i64,
+    pub hashed: i64,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    fn new(path: &str, name: &str) -> Self {
+        Node {
+            path: path.to_string(),
+            name: name.to_string(),
+            files: 0,
+            liked: 0,
+            hashed: 0,
+            children: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, c: &DirCounts) {
+        self.files += c.files;
+        self.liked += c.liked;
+        self.hashed += c.hashed;
+    }
+
+    fn sort(&mut self) {
+        self.children.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+        for child in &mut self.children {
+            child.sort();
+        }
+    }
+
+    /// `name  12 ♥3 40%`: files, likes if any, hashing progress until done.
+    pub fn label(&self) -> String {
+        let mut label = format!("{}  {}", self.name, self.files);
+        if self.liked > 0 {
+            label.push_str(&format!(" ♥{}", self.liked));
+        }
+        if self.hashed < self.files {
+            label.push_str(&format!(" {}%", self.hashed * 100 / self.files));
+        }
+        label
+    }
+}
+
+/// `dir` is strictly below `root`. Returns the part after the separator.
+fn below<'a>(dir: &'a str, root: &str) -> Option<&'a str> {
+    let base = root.trim_end_matches(['/', '\\']);
+    dir.strip_prefix(base)?
+        .strip_prefix(['/', '\\'])
+        .filter(|rest| !rest.is_empty())
+}
+
+/// The tree of `roots` with `counts` filed under them. Roots inside another
+/// root appear in its subtree; directories outside every root are left out.
+pub fn build(roots: &[String], counts: &[DirCounts]) -> Vec<Node> {
+    let mut tops: Vec<Node> = roots
+        .iter()
+        .filter(|r| !roots.iter().any(|other| below(r, other).is_some()))
+        .map(|r| Node::new(r, r))
+        .collect();
+    tops.sort_by(|a, b| natural_cmp(&a.path, &b.path));
+    tops.dedup_by(|a, b| a.path == b.path);
+
+    for c in counts {
+        let Some(top) = tops
+            .iter_mut()
+            .find(|t| t.path == c.dir || below(&c.dir, &t.path).is_some())
+        else {
+            continue;
+        };
+        top.add(c);
+        let Some(rest) = below(&c.dir, &top.path) else {
+            continue;
+        };
+        let mut end = c.dir.len() - rest.len();
+        let mut node = top;
+        for name in rest.split(['/', '\\']) {
+            end += name.len();
+            let i = match node.children.iter().position(|n| n.name == name) {
+                Some(i) => i,
+                None => {
+                    node.children.push(Node::new(&c.dir[..end], name));
+                    node.children.len() - 1
+                }
+            };
+            end += 1;
+            node = &mut node.children[i];
+            node.add(c);
+        }
+    }
+    for top in &mut tops {
+        top.sort();
+    }
+    tops
+}
+
+/// Panel state, kept while it's open.
+pub struct TreePanel {
+    roots: Vec<Node>,
+    loaded_at: Option<Instant>,
+    /// Directory the tree was last opened down to.
+    revealed: String,
+}
+
+impl TreePanel {
+    pub fn new() -> Self {
+        TreePanel {
+            roots: Vec::new(),
+            loaded_at: None,
+            revealed: String::new(),
+        }
+    }
+
+    /// Rebuild from the library if the counts are older than `REFRESH`.
+    pub fn refresh(&mut self, db: &Db) {
+        if self.loaded_at.is_some_and(|t| t.elapsed() < REFRESH) {
+            return;
+        }
+        let roots: Vec<String> = db.tracked_list().into_iter().map(|(p, _, _)| p).collect();
+        self.roots = build(&roots, &db.dir_counts());
+        self.loaded_at = Some(Instant::now());
+    }
+}
+
+/// Draw the panel on the left, between the status bar and `bottom`.
+/// Returns the directory clicked this frame.
+pub fn draw_tree_panel(
+    ui: &imgui::Ui,
+    panel: &mut TreePanel,
+    current_dir: &str,
+    display_w: f32,
+    bottom: f32,
+) -> Option<String> {
+    let panel_w = 300.0_f32.min(display_w * 0.35);
+    let reveal = panel.revealed != current_dir;
+    let mut picked = None;
+
+    if let Some(_win) = ui
+        .window("##dirtree")
+        .position([0.0, BAR_HEIGHT], Condition::Always)
+        .size([panel_w, bottom - BAR_HEIGHT], Condition::Always)
+        .bg_alpha(0.88)
+        .flags(PANEL_FLAGS)
+        .begin()
+    {
+        ui.text_colored(crate::statusbar::theme().header, "Directories");
+        ui.separator();
+        if panel.roots.is_empty() {
+            ui.text_colored(
+                crate::statusbar::theme().dim,
+                "nothing tracked (lv track DIR)",
+            );
+        }
+        for node in &panel.roots {
+            draw_node(ui, node, current_dir, reveal, &mut picked);
+        }
+    }
+    panel.revealed = current_dir.to_string();
+    picked
+}
+
+fn draw_node(
+    ui: &imgui::Ui,
+    node: &Node,
+    current_dir: &str,
+    reveal: bool,
+    picked: &mut Option<String>,
+) {
+    let current = node.path == current_dir;
+    let mut flags = TreeNodeFlags::OPEN_ON_ARROW
+        | TreeNodeFlags::OPEN_ON_DOUBLE_CLICK
+        | TreeNodeFlags::SPAN_AVAIL_WIDTH;
+    if node.children.is_empty() {
+        flags |= TreeNodeFlags::LEAF;
+    }
+    if current {
+        flags |= TreeNodeFlags::SELECTED;
+    }
+    let label = node.label();
+    let mut tree_node = ui
+        .tree_node_config(node.path.as_str())
+        .label::<&str, &str>(&label)
+        .flags(flags);
+    if reveal && below(current_dir, &node.path).is_some() {
+        tree_node = tree_node.opened(true, Condition::Always);
+    }
+    let token = tree_node.push();
+    if ui.is_item_clicked() && !ui.is_item_toggled_open() {
+        *picked = Some(node.path.clone());
+    }
+    if current && reveal {
+        ui.set_scroll_here_y_with_ratio(0.5);
+    }
+    if token.is_some() {
+        for child in &node.children {
+            draw_node(ui, child, current_dir, reveal, picked);
+        }
+    }
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(dir: &str, files: i64, liked: i64, hashed: i64) -> DirCounts {
+        DirCounts {
+            dir: dir.into(),
+            files,
+            liked,
+            hashed,
+        }
+    }
+
+    #[test]
+    fn builds_nested_tree_with_totals() {
+        let roots = vec!["/p".to_string(), "/p/b/sub".to_string(), "/q".to_string()];
+        let tree = build(
+            &roots,
+            &[
+                counts("/p", 1, 0, 1),
+                counts("/p/b/sub", 2, 1, 2),
+                counts("/p/a10", 4, 0, 4),
+                counts("/p/a9", 3, 2, 1),
+                counts("/other", 5, 0, 5),
+            ],
+        );
+        assert_eq!(tree.len(), 2);
+        let p = &tree[0];
+        assert_eq!((p.files, p.liked, p.hashed), (10, 3, 8));
+        let names: Vec<&str> = p.children.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["a9", "a10", "b"]);
+
+        // `b` has no files of its own but shows up on the way to `sub`
+        let b = &p.children[2];
+        assert_eq!((b.path.as_str(), b.files), ("/p/b", 2));
+        assert_eq!(b.children[0].path, "/p/b/sub");
+        assert!(b.children[0].children.is_empty());
+
+        assert_eq!(tree[1].path, "/q");
+        assert_eq!(tree[1].files, 0);
+    }
+
+    #[test]
+    fn below_handles_separators() {
+        assert_eq!(below("/p/a/b", "/p"), Some("a/b"));
+        assert_eq!(below("/p/a", "/p/"), Some("a"));
+        assert_eq!(below("/a", "/"), Some("a"));
+        assert_eq!(below(r"C:\p\a", r"C:\p"), Some("a"));
+        assert_eq!(below("/pa", "/p"), None);
+        assert_eq!(below("/p", "/p"), None);
+    }
+
+    #[test]
+    fn labels() {
+        let mut node = Node::new("/p/a", "a");
+        node.add(&counts("/p/a", 10, 0, 10));
+        assert_eq!(node.label(), "a  10");
+        node.add(&counts("/p/a/b", 10, 3, 0));
+        assert_eq!(node.label(), "a  20 ♥3 50%");
+    }
+}
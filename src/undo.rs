@@ -0,0 +1,821 @@
+//! Undo and redo for library changes (Ctrl+Z / Ctrl+Shift+Z, `lv undo`).
+//!
+//! Tags, likes, trashing, moves, copies and renames all go through `run`,
+//! which applies a `Change` and journals it together with its inverse in
+//! the `journal` table. Undo applies the inverse, redo the change again.
+//! Applying a change always yields a fresh inverse, because some can't be
+//! known in advance: a file trashed a second time lands at a new trash path.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::db::{Db, Rename};
+
+/// A file moved to the trash, with its content hash so restoring it can
+/// reattach tags and metadata.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrashedFile {
+    pub original: String,
+    pub trashed: PathBuf,
+    pub hash: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// Add (`on`) or remove `tag` on files.
+    Tag {
+        tag: String,
+        on: bool,
+        files: Vec<i64>,
+    },
+    /// Like or unlike files.
+    Like { on: bool, files: Vec<i64> },
+    /// Move files (by path) to the trash and drop them from the library.
+    Trash { paths: Vec<String> },
+    /// Put trashed files back and register them again.
+    Restore { files: Vec<TrashedFile> },
+    /// Move or rename files, keeping their rows. All or none.
+    Relocate { files: Vec<Rename> },
+    /// Copy each `(from, to)` and register the copy.
+    Copy { files: Vec<(String, String)> },
+    /// Trash copies made by `Copy` (the `to` side).
+    Uncopy { files: Vec<(String, String)> },
+}
+
+impl Change {
+    /// Number of files the change covers.
+    pub fn len(&self) -> usize {
+        match self {
+            Change::Tag { files, .. } | Change::Like { files, .. } => files.len(),
+            Change::Trash { paths } => paths.len(),
+            Change::Restore { files } => files.len(),
+            Change::Relocate { files } => files.len(),
+            Change::Copy { files } | Change::Uncopy { files } => files.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The change that undoes this one, where that needs nothing from
+    /// applying it (everything but `Trash`).
+    fn reverse(&self) -> Option<Change> {
+        Some(match self {
+            Change::Tag { tag, on, files } => Change::Tag {
+                tag: tag.clone(),
+                on: !on,
+                files: files.clone(),
+            },
+            Change::Like { on, files } => Change::Like {
+                on: !on,
+                files: files.clone(),
+            },
+            Change::Trash { .. } => return None,
+            Change::Restore { files } => Change::Trash {
+                paths: files.iter().map(|t| t.original.clone()).collect(),
+            },
+            Change::Relocate { files } => Change::Relocate {
+                files: files.iter().rev().map(swap).collect(),
+            },
+            Change::Copy { files } => Change::Uncopy {
+                files: files.clone(),
+            },
+            Change::Uncopy { files } => Change::Copy {
+                files: files.clone(),
+            },
+        })
+    }
+
+    /// Library files the change touched (for putting the cursor on them).
+    fn file_ids(&self, db: &Db) -> Vec<i64> {
+        let lookup = |path: &str| db.file_lookup(path).map(|(id, _, _)| id);
+        match self {
+            Change::Tag { files, .. } | Change::Like { files, .. } => files.clone(),
+            Change::Trash { paths } => paths.iter().filter_map(|p| lookup(p)).collect(),
+            Change::Restore { .. } => Vec::new(),
+            Change::Relocate { files } => files.iter().map(|r| r.file_id).collect(),
+            Change::Copy { files } => files.iter().filter_map(|(f, _)| lookup(f)).collect(),
+            Change::Uncopy { files } => files.iter().filter_map(|(_, t)| lookup(t)).collect(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let pairs = |files: &[(String, String)]| -> Vec<Value> {
+            files.iter().map(|(f, t)| json!([f, t])).collect()
+        };
+        match self {
+            Change::Tag { tag, on, files } => {
+                json!({ "op": "tag", "tag": tag, "on": on, "files": files })
+            }
+            Change::Like { on, files } => json!({ "op": "like", "on": on, "files": files }),
+            Change::Trash { paths } => json!({ "op": "trash", "paths": paths }),
+            Change::Restore { files } => {
+                let files: Vec<Value> = files
+                    .iter()
+                    .map(|t| {
+                        json!({
+                            "original": t.original,
+                            "trashed": t.trashed.to_string_lossy(),
+                            "hash": t.hash,
+                        })
+                    })
+                    .collect();
+                json!({ "op": "restore", "files": files })
+            }
+            Change::Relocate { files } => {
+                let files: Vec<Value> = files
+                    .iter()
+                    .map(|r| json!({ "id": r.file_id, "from": r.from, "to": r.to }))
+                    .collect();
+                json!({ "op": "relocate", "files": files })
+            }
+            Change::Copy { files } => json!({ "op": "copy", "files": pairs(files) }),
+            Change::Uncopy { files } => json!({ "op": "uncopy", "files": pairs(files) }),
+        }
+        .to_string()
+    }
+
+    fn from_json(s: &str) -> Result<Change, String> {
+        let v: Value = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        let text = |v: &Value| v.as_str().map(str::to_string);
+        let list = |key: &str| v[key].as_array().cloned().unwrap_or_default();
+        let ids = |key: &str| list(key).iter().filter_map(Value::as_i64).collect();
+        let pairs = |key: &str| {
+            list(key)
+                .iter()
+                .filter_map(|p| Some((text(&p[0])?, text(&p[1])?)))
+                .collect()
+        };
+        let on = v["on"].as_bool().unwrap_or(false);
+        Ok(match v["op"].as_str().unwrap_or_default() {
+            "tag" => Change::Tag {
+                tag: text(&v["tag"]).ok_or("tag change without a tag")?,
+                on,
+                files: ids("files"),
+            },
+            "like" => Change::Like {
+                on,
+                files: ids("files"),
+            },
+            "trash" => Change::Trash {
+                paths: list("paths").iter().filter_map(text).collect(),
+            },
+            "restore" => Change::Restore {
+                files: list("files")
+                    .iter()
+                    .filter_map(|t| {
+                        Some(TrashedFile {
+                            original: text(&t["original"])?,
+                            trashed: PathBuf::from(text(&t["trashed"])?),
+                            hash: text(&t["hash"]),
+                        })
+                    })
+                    .collect(),
+            },
+            "relocate" => Change::Relocate {
+                files: list("files")
+                    .iter()
+                    .filter_map(|r| {
+                        Some(Rename {
+                            file_id: r["id"].as_i64()?,
+                            from: text(&r["from"])?,
+                            to: text(&r["to"])?,
+                        })
+                    })
+                    .collect(),
+            },
+            "copy" => Change::Copy {
+                files: pairs("files"),
+            },
+            "uncopy" => Change::Uncopy {
+                files: pairs("files"),
+            },
+            op => return Err(format!("unknown journal change '{}'", op)),
+        })
+    }
+}
+
+fn swap(r: &Rename) -> Rename {
+    Rename {
+        file_id: r.file_id,
+        from: r.to.clone(),
+        to: r.from.clone(),
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+// ── Files on disk ───────────────────────────────────────────────────────
+
+/// Rename `from` to `to`, copying and removing when they are on different
+/// filesystems.
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            std::fs::copy(from, to)?;
+            std::fs::remove_file(from).inspect_err(|_| {
+                std::fs::remove_file(to).ok();
+            })
+        }
+        r => r,
+    }
+}
+
+/// Move each `from` to `to` on disk, all or none. Every file is parked on
+/// a temporary name first, so a batch can swap or cycle names. Existing
+/// files are never overwritten.
+fn relocate_on_disk(moves: &[Rename]) -> Result<(), String> {
+    let temps: Vec<PathBuf> = moves
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            Path::new(&m.from).with_file_name(format!(".lv-move-{}-{}", std::process::id(), i))
+        })
+        .collect();
+    let undo = |parked: &[usize], placed: &[usize]| {
+        for &i in placed.iter().rev() {
+            move_file(Path::new(&moves[i].to), &temps[i]).ok();
+        }
+        for &i in parked.iter().rev() {
+            std::fs::rename(&temps[i], &moves[i].from).ok();
+        }
+    };
+
+    let mut parked = Vec::new();
+    for (i, m) in moves.iter().enumerate() {
+        if let Err(e) = std::fs::rename(&m.from, &temps[i]) {
+            undo(&parked, &[]);
+            return Err(format!("{}: {}", file_name(&m.from), e));
+        }
+        parked.push(i);
+    }
+    let mut placed = Vec::new();
+    for (i, m) in moves.iter().enumerate() {
+        let to = Path::new(&m.to);
+        let result = if to.exists() {
+            Err("already exists".to_string())
+        } else {
+            to.parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| move_file(&temps[i], to))
+                .map_err(|e| e.to_string())
+        };
+        if let Err(e) = result {
+            undo(&parked, &placed);
+            return Err(format!("{}: {}", m.to, e));
+        }
+        placed.push(i);
+    }
+    Ok(())
+}
+
+/// Copy `from` to `to` (never overwriting) and register the copy under the
+/// original's hash, so it shares its tags.
+fn copy_file(db: &Db, from: &str, to: &str) -> Result<(), String> {
+    let dest = Path::new(to);
+    if dest.exists() {
+        return Err(format!("{}: already exists", to));
+    }
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::copy(from, dest).map_err(|e| format!("{}: {}", file_name(from), e))?;
+    let md = std::fs::metadata(dest).ok();
+    let dir = dest
+        .parent()
+        .map(|d| d.to_string_lossy())
+        .unwrap_or_default();
+    db.file_insert(
+        to,
+        &dir,
+        file_name(to),
+        md.as_ref().map(|m| m.len() as i64),
+        md.as_ref().and_then(crate::scanner::mtime_stamp).as_deref(),
+    );
+    let source = db.file_lookup(from).and_then(|(id, _, _)| db.file_hash(id));
+    if let (Some((id, _, _)), Some(hash)) = (db.file_lookup(to), source) {
+        db.file_set_hash_meta(id, &hash);
+    }
+    Ok(())
+}
+
+/// Trash `path` and drop it from the library.
+fn trash_file(db: &Db, path: &str) -> Result<TrashedFile, String> {
+    let hash = db.file_lookup(path).and_then(|(id, _, _)| db.file_hash(id));
+    let trashed =
+        crate::trash::trash(Path::new(path)).map_err(|e| format!("{}: {}", file_name(path), e))?;
+    db.remove_file_by_path(path);
+    eprintln!("trash: {}", path);
+    Ok(TrashedFile {
+        original: path.to_string(),
+        trashed,
+        hash,
+    })
+}
+
+/// Put a trashed file back and register it, relinking it to its metadata
+/// by hash.
+fn restore_file(db: &Db, t: &TrashedFile) -> Result<(), String> {
+    let path = Path::new(&t.original);
+    crate::trash::restore(&t.trashed, path)
+        .map_err(|e| format!("{}: {}", file_name(&t.original), e))?;
+    let md = std::fs::metadata(path).ok();
+    let dir = crate::clean_path(&path.parent().unwrap_or(path).to_string_lossy());
+    db.file_insert(
+        &t.original,
+        &dir,
+        file_name(&t.original),
+        md.as_ref().map(|m| m.len() as i64),
+        md.as_ref().and_then(crate::scanner::mtime_stamp).as_deref(),
+    );
+    if let (Some((id, _, _)), Some(hash)) = (db.file_lookup(&t.original), &t.hash) {
+        db.file_set_hash_meta(id, hash);
+    }
+    eprintln!("untrash: {}", t.original);
+    Ok(())
+}
+
+// ── Applying changes ────────────────────────────────────────────────────
+
+/// Apply `change`. Returns the change that undoes what was done, and the
+/// first error. Tags, likes and restores carry on past errors; trashing
+/// and copying stop at the first one; relocating is all or none.
+fn apply(db: &Db, change: &Change) -> (Change, Option<String>) {
+    let mut err = None;
+    let inverse = match change {
+        Change::Tag { tag, on, files } => Change::Tag {
+            tag: tag.clone(),
+            on: !on,
+            files: files
+                .iter()
+                .copied()
+                .filter(|&id| match db.set_tag(id, tag, *on) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        err.get_or_insert(e);
+                        false
+                    }
+                })
+                .collect(),
+        },
+        Change::Like { on, files } => Change::Like {
+            on: !on,
+            files: files
+                .iter()
+                .copied()
+                .filter(|&id| match db.set_like(id, *on) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        err.get_or_insert(e);
+                        false
+                    }
+                })
+                .collect(),
+        },
+        Change::Trash { paths } => {
+            let mut files = Vec::new();
+            for path in paths {
+                match trash_file(db, path) {
+                    Ok(t) => files.push(t),
+                    Err(e) => {
+                        err = Some(e);
+                        break;
+                    }
+                }
+            }
+            Change::Restore { files }
+        }
+        Change::Restore { files } => Change::Trash {
+            paths: files
+                .iter()
+                .filter(|t| match restore_file(db, t) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        err.get_or_insert(e);
+                        false
+                    }
+                })
+                .map(|t| t.original.clone())
+                .collect(),
+        },
+        Change::Relocate { files } => {
+            let back: Vec<Rename> = files.iter().rev().map(swap).collect();
+            let mut moved = false;
+            let result = db.relocate_files(files, || {
+                relocate_on_disk(files)?;
+                moved = true;
+                Ok(())
+            });
+            if result.is_err() && moved {
+                relocate_on_disk(&back).ok();
+            }
+            match result {
+                Ok(()) => {
+                    for r in files {
+                        eprintln!("move: {} → {}", r.from, r.to);
+                    }
+                    Change::Relocate { files: back }
+                }
+                Err(e) => {
+                    err = Some(e);
+                    Change::Relocate { files: Vec::new() }
+                }
+            }
+        }
+        Change::Copy { files } => {
+            let mut done = Vec::new();
+            for (from, to) in files {
+                match copy_file(db, from, to) {
+                    Ok(()) => done.push((from.clone(), to.clone())),
+                    Err(e) => {
+                        err = Some(e);
+                        break;
+                    }
+                }
+            }
+            Change::Uncopy { files: done }
+        }
+        Change::Uncopy { files } => {
+            let mut done = Vec::new();
+            for (from, to) in files {
+                match trash_file(db, to) {
+                    Ok(_) => done.push((from.clone(), to.clone())),
+                    Err(e) => {
+                        err = Some(e);
+                        break;
+                    }
+                }
+            }
+            Change::Copy { files: done }
+        }
+    };
+    (inverse, err)
+}
+
+/// Apply `change` and journal it under `label`. When it stops partway, the
+/// part that was done is journalled and the error returned. Returns how
+/// many files changed.
+pub fn run(db: &Db, label: &str, change: Change) -> Result<usize, String> {
+    if change.is_empty() {
+        return Ok(0);
+    }
+    let (inverse, err) = apply(db, &change);
+    if !inverse.is_empty() {
+        let forward = inverse.reverse().unwrap_or(change);
+        if let Err(e) = db.journal_push(label, &forward.to_json(), &inverse.to_json()) {
+            eprintln!("journal: {}", e);
+        }
+    }
+    match err {
+        Some(e) => Err(e),
+        None => Ok(inverse.len()),
+    }
+}
+
+/// An undone or redone journal entry.
+pub struct Step {
+    pub label: String,
+    /// Files it brought back or changed.
+    pub ids: Vec<i64>,
+}
+
+/// Undo the newest journalled action.
+pub fn undo(db: &Db) -> Result<Step, String> {
+    step(db, false)
+}
+
+/// Redo the oldest undone action.
+pub fn redo(db: &Db) -> Result<Step, String> {
+    step(db, true)
+}
+
+/// Undo the newest action if it was trashing files (`z`).
+pub fn undo_trash(db: &Db) -> Result<Step, String> {
+    let is_trash = db
+        .journal_next(false)
+        .and_then(|e| Change::from_json(&e.forward).ok())
+        .is_some_and(|c| matches!(c, Change::Trash { .. }));
+    if !is_trash {
+        return Err("nothing to restore".into());
+    }
+    undo(db)
+}
+
+fn step(db: &Db, redo: bool) -> Result<Step, String> {
+    let entry = db.journal_next(redo).ok_or(if redo {
+        "nothing to redo"
+    } else {
+        "nothing to undo"
+    })?;
+    let change = Change::from_json(if redo { &entry.forward } else { &entry.inverse })?;
+    let (back, err) = apply(db, &change);
+    if back.is_empty() && !change.is_empty() {
+        return Err(format!(
+            "can't {} {}: {}",
+            if redo { "redo" } else { "undo" },
+            entry.label,
+            err.unwrap_or_default()
+        ));
+    }
+    // `back` reverses what was just applied; the other side is the applied
+    // change, narrowed to what succeeded where that's known
+    let done = back.reverse().unwrap_or(change);
+    let (forward, inverse) = if redo { (&done, &back) } else { (&back, &done) };
+    db.journal_update(entry.id, !redo, &forward.to_json(), &inverse.to_json())?;
+    let ids = if redo {
+        done.file_ids(db)
+    } else {
+        back.file_ids(db)
+    };
+    match err {
+        Some(e) => Err(format!("{}: {}", entry.label, e)),
+        None => Ok(Step {
+            label: entry.label,
+            ids,
+        }),
+    }
+}
+
+// ── Tests ───────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::SortOrder;
+
+    fn mv(id: i64, from: &Path, to: &Path) -> Rename {
+        Rename {
+            file_id: id,
+            from: from.to_string_lossy().to_string(),
+            to: to.to_string_lossy().to_string(),
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let changes = [
+            Change::Tag {
+                tag: "c3".into(),
+                on: true,
+                files: vec![1, 2],
+            },
+            Change::Like {
+                on: false,
+                files: vec![3],
+            },
+            Change::Trash {
+                paths: vec!["/a/x.jpg".into()],
+            },
+            Change::Restore {
+                files: vec![TrashedFile {
+                    original: "/a/x.jpg".into(),
+                    trashed: PathBuf::from("/t/files/x.jpg"),
+                    hash: None,
+                }],
+            },
+            Change::Relocate {
+                files: vec![Rename {
+                    file_id: 4,
+                    from: "/a/x.jpg".into(),
+                    to: "/b/x.jpg".into(),
+                }],
+            },
+            Change::Copy {
+                files: vec![("/a/x.jpg".into(), "/b/x.jpg".into())],
+            },
+        ];
+        for c in changes {
+            assert_eq!(Change::from_json(&c.to_json()).unwrap(), c);
+        }
+        assert!(Change::from_json(r#"{"op":"explode"}"#).is_err());
+    }
+
+    #[test]
+    fn tag_undo_only_touches_changed_files() {
+        let (db, _tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg"], None);
+        db.set_tag(files[0].id, "c3", true).unwrap();
+        // `a` already has it: only `b` is journalled
+        let change = Change::Tag {
+            tag: "c3".into(),
+            on: true,
+            files: vec![files[1].id],
+        };
+        assert_eq!(run(&db, "+c3", change), Ok(1));
+
+        let step = undo(&db).unwrap();
+        assert_eq!((step.label.as_str(), step.ids), ("+c3", vec![files[1].id]));
+        assert_eq!(db.file_tags(files[0].id), vec!["c3".to_string()]);
+        assert!(db.file_tags(files[1].id).is_empty());
+        assert!(undo(&db).is_err());
+
+        redo(&db).unwrap();
+        assert_eq!(db.file_tags(files[1].id), vec!["c3".to_string()]);
+        assert!(redo(&db).is_err());
+        undo(&db).unwrap();
+        assert!(db.file_tags(files[1].id).is_empty());
+    }
+
+    #[test]
+    fn like_undo_redo() {
+        let (db, _tmp, files) = Db::open_with_files(&["a.jpg"], None);
+        let like = Change::Like {
+            on: true,
+            files: vec![files[0].id],
+        };
+        run(&db, "+♥", like).unwrap();
+        assert!(db.files_by_ids(&[files[0].id])[0].liked);
+        undo(&db).unwrap();
+        assert!(!db.files_by_ids(&[files[0].id])[0].liked);
+        redo(&db).unwrap();
+        assert!(db.files_by_ids(&[files[0].id])[0].liked);
+    }
+
+    #[test]
+    fn undo_trash_only_undoes_trash() {
+        let (db, _tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg"], None);
+        let b = &files[1];
+        let bin = tempfile::tempdir().unwrap();
+        let trashed = bin.path().join("b.jpg");
+        std::fs::rename(&b.path, &trashed).unwrap();
+        db.remove_file_by_path(&b.path);
+        let forward = Change::Trash {
+            paths: vec![b.path.clone()],
+        };
+        let inverse = Change::Restore {
+            files: vec![TrashedFile {
+                original: b.path.clone(),
+                trashed,
+                hash: Some("hash_b.jpg".into()),
+            }],
+        };
+        db.journal_push("trash", &forward.to_json(), &inverse.to_json())
+            .unwrap();
+
+        // A tag after the trash hides it from `z`
+        let tag = Change::Tag {
+            tag: "c3".into(),
+            on: true,
+            files: vec![files[0].id],
+        };
+        run(&db, "+c3", tag).unwrap();
+        assert!(undo_trash(&db).is_err());
+        undo(&db).unwrap();
+
+        let step = undo_trash(&db).unwrap();
+        assert_eq!(step.label, "trash");
+        assert!(Path::new(&b.path).exists());
+        assert_eq!(db.files_by_dir(&files[0].dir, SortOrder::Path).len(), 2);
+    }
+
+    #[test]
+    fn restore_brings_back_row_and_tags() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg"], None);
+        let b = &files[1];
+        db.set_tag(b.id, "c3", true).unwrap();
+
+        // What trashing does, with a stand-in trash dir
+        let bin = tempfile::tempdir().unwrap();
+        let trashed = bin.path().join("b.jpg");
+        let hash = db.file_hash(b.id);
+        std::fs::rename(&b.path, &trashed).unwrap();
+        db.remove_file_by_path(&b.path);
+        let restore = Change::Restore {
+            files: vec![TrashedFile {
+                original: b.path.clone(),
+                trashed,
+                hash,
+            }],
+        };
+        let (inverse, err) = apply(&db, &restore);
+        assert_eq!(err, None);
+        assert_eq!(
+            inverse,
+            Change::Trash {
+                paths: vec![b.path.clone()]
+            }
+        );
+        assert_eq!(std::fs::read(tmp.path().join("b.jpg")).unwrap(), b"b.jpg");
+        let (id, _, _) = db.file_lookup(&b.path).unwrap();
+        assert_eq!(db.file_tags(id), vec!["c3".to_string()]);
+        assert_eq!(inverse.file_ids(&db), vec![id]);
+
+        // A second restore finds its file gone from the trash
+        let (inverse, err) = apply(&db, &restore);
+        assert!(inverse.is_empty());
+        assert!(err.is_some());
+    }
+
+    #[test]
+    fn relocate_swaps_and_undoes() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg"], None);
+        let (a, b) = (tmp.path().join("a.jpg"), tmp.path().join("b.jpg"));
+        let swap = Change::Relocate {
+            files: vec![mv(files[0].id, &a, &b), mv(files[1].id, &b, &a)],
+        };
+        run(&db, "rename ×2", swap).unwrap();
+        assert_eq!(std::fs::read(&a).unwrap(), b"b.jpg");
+        assert_eq!(db.files_by_ids(&[files[0].id])[0].filename, "b.jpg");
+        undo(&db).unwrap();
+        assert_eq!(std::fs::read(&a).unwrap(), b"a.jpg");
+        assert_eq!(db.files_by_ids(&[files[0].id])[0].filename, "a.jpg");
+
+        // Into a new dir and back
+        let sub = tmp.path().join("sub/a.jpg");
+        let change = Change::Relocate {
+            files: vec![mv(files[0].id, &a, &sub)],
+        };
+        run(&db, "move", change).unwrap();
+        assert!(sub.exists() && !a.exists());
+        let step = undo(&db).unwrap();
+        assert_eq!(step.ids, vec![files[0].id]);
+        assert!(a.exists() && !sub.exists());
+    }
+
+    #[test]
+    fn relocate_with_watcher_running() {
+        use std::time::{Duration, Instant};
+
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg"], Some("c3"));
+        let dir = tmp.path().to_string_lossy().to_string();
+        db.dir_track(&dir, false);
+        let (watcher, rx) = crate::watcher::FsWatcher::start(db.clone());
+        watcher.watch_dir(&dir);
+        std::thread::sleep(Duration::from_millis(300));
+        // Let the watcher handle every event of the move before checking
+        let settle = || {
+            let deadline = Instant::now() + Duration::from_secs(3);
+            while Instant::now() < deadline && rx.recv_timeout(Duration::from_millis(500)).is_ok() {
+            }
+        };
+        let check = |paths: [&Path; 2]| {
+            assert_eq!(db.file_count(), 2);
+            for (f, path) in files.iter().zip(paths) {
+                let row = &db.files_by_ids(&[f.id])[0];
+                assert_eq!(row.path, path.to_string_lossy());
+                assert_eq!(db.file_hash(f.id), Some(format!("hash_{}", f.filename)));
+                assert_eq!(db.file_tags(f.id), vec!["c3".to_string()]);
+            }
+        };
+
+        let (a, b) = (tmp.path().join("a.jpg"), tmp.path().join("b.jpg"));
+        let (x, y) = (tmp.path().join("x.jpg"), tmp.path().join("y.jpg"));
+        let change = Change::Relocate {
+            files: vec![mv(files[0].id, &a, &x), mv(files[1].id, &b, &y)],
+        };
+        run(&db, "rename ×2", change).unwrap();
+        settle();
+        check([&x, &y]);
+
+        undo(&db).unwrap();
+        settle();
+        check([&a, &b]);
+        drop(watcher);
+    }
+
+    #[test]
+    fn failed_relocate_changes_nothing() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg", "b.jpg"], None);
+        let (a, b) = (tmp.path().join("a.jpg"), tmp.path().join("b.jpg"));
+        std::fs::remove_file(&b).unwrap();
+        let change = Change::Relocate {
+            files: vec![
+                mv(files[0].id, &a, &tmp.path().join("n1.jpg")),
+                mv(files[1].id, &b, &tmp.path().join("n2.jpg")),
+            ],
+        };
+        assert!(run(&db, "rename ×2", change).is_err());
+        assert!(a.exists());
+        assert!(!tmp.path().join("n1.jpg").exists());
+        assert_eq!(db.files_by_ids(&[files[0].id])[0].path, files[0].path);
+        assert!(db.journal_next(false).is_none());
+
+        // Never onto an existing file
+        std::fs::write(&b, "new").unwrap();
+        let change = Change::Relocate {
+            files: vec![mv(files[0].id, &a, &b)],
+        };
+        assert!(run(&db, "rename", change).is_err());
+        assert_eq!(std::fs::read(&b).unwrap(), b"new");
+        assert!(a.exists());
+    }
+
+    #[test]
+    fn copy_registers_copy_with_hash() {
+        let (db, tmp, files) = Db::open_with_files(&["a.jpg"], None);
+        db.set_tag(files[0].id, "t", true).unwrap();
+        let to = tmp.path().join("out/a.jpg").to_string_lossy().to_string();
+        let change = Change::Copy {
+            files: vec![(files[0].path.clone(), to.clone())],
+        };
+        assert_eq!(run(&db, "copy", change.clone()), Ok(1));
+        let (id, _, _) = db.file_lookup(&to).unwrap();
+        assert_ne!(id, files[0].id);
+        assert_eq!(db.file_tags(id), vec!["t".to_string()]);
+        // Never overwrites
+        assert!(run(&db, "copy", change).is_err());
+    }
+}
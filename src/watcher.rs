@@ -229,17 +229,16 @@ pub(crate) fn handle_event(db: &Db, tx: &mpsc::Sender<FsEvent>, event: notify::E
             eprintln!("watcher:   skip non-media {}", path_str);
             continue;
         }
-        if is_remove && !has_media_ext(&path_str) {
+        // A rename reports its old path as a Modify; that file is gone too
+        let gone = is_remove || !path.exists();
+        if gone && !has_media_ext(&path_str) {
             eprintln!("watcher:   skip non-media remove {}", path_str);
             continue;
         }
-        eprintln!(
-            "watcher:   processing {} (is_remove={})",
-            path_str, is_remove
-        );
+        eprintln!("watcher:   processing {} (gone={})", path_str, gone);
 
         match event.kind {
-            EventKind::Create(_) | EventKind::Modify(_) => {
+            EventKind::Create(_) | EventKind::Modify(_) if !gone => {
                 // Insert or update the file in DB
                 let abs = match path.canonicalize() {
                     Ok(p) => p,
@@ -260,11 +259,7 @@ pub(crate) fn handle_event(db: &Db, tx: &mpsc::Sender<FsEvent>, event: notify::E
 
                 let meta = std::fs::metadata(&abs).ok();
                 let size = meta.as_ref().map(|m| m.len() as i64);
-                let mtime = meta.as_ref().and_then(|m| m.modified().ok()).and_then(|t| {
-                    t.duration_since(std::time::UNIX_EPOCH)
-                        .ok()
-                        .map(|d| format!("{}", d.as_secs()))
-                });
+                let mtime = meta.as_ref().and_then(crate::scanner::mtime_stamp);
                 let mtime_ref = mtime.as_deref();
 
                 if let Some((file_id, db_size, db_mtime)) = db.file_lookup(&abs_str) {
@@ -280,7 +275,7 @@ pub(crate) fn handle_event(db: &Db, tx: &mpsc::Sender<FsEvent>, event: notify::E
 
                 tx.send(FsEvent::Changed(dir)).ok();
             }
-            EventKind::Remove(_) => {
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
                 // File no longer exists so we can't canonicalize.
                 // path_str is already clean_path'd above.
                 if db.file_lookup(&path_str).is_some() {
@@ -320,6 +315,7 @@ fn dedup_nested(dirs: &[(String, bool)]) -> Vec<(String, bool)> {
 }
 
 #[cfg(test)]
+#[allow(clippy::collapsible_match, clippy::single_match)]
 mod tests {
     use super::*;
 
@@ -698,6 +694,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn handle_event_create_stores_iso_mtime() {
+        // Date terms must match files the watcher added, not just scanned ones
+        use crate::db::Db;
+        use crate::query::Query;
+
+        let db = Db::open_memory();
+        db.ensure_schema();
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("photo.jpg");
+        std::fs::write(&file_path, b"x").unwrap();
+
+        let (tx, _rx) = mpsc::channel();
+        let event = make_event(
+            EventKind::Create(notify::event::CreateKind::File),
+            vec![file_path.clone()],
+        );
+        handle_event(&db, &tx, event);
+
+        let md = std::fs::metadata(&file_path).unwrap();
+        let stamp = crate::scanner::mtime_stamp(&md).unwrap();
+        let abs = crate::clean_path(&file_path.canonicalize().unwrap().to_string_lossy());
+        let (_, _, stored) = db.file_lookup(&abs).unwrap();
+        assert_eq!(stored.as_deref(), Some(stamp.as_str()));
+
+        for q in [
+            format!("modified:{}", &stamp[..10]),
+            "modified>2000-01-01".into(),
+        ] {
+            let found = db.query_files(&Query::parse(&q).unwrap());
+            assert_eq!(found.len(), 1, "{}", q);
+        }
+    }
+
     #[test]
     fn handle_event_remove_with_win_prefix_mismatch() {
         // Simulate Windows: DB stores "C:\Users\test\photo.jpg" (clean_path),